@@ -0,0 +1,66 @@
+//! Compares decoding a multiplexed log stream with `TtyFrameDecoder` against the copying decoder
+//! of `containers-api`.
+//!
+//! Run with `cargo bench --bench tty`.
+use bytes::Bytes;
+use containers_api::conn::tty;
+use futures::{executor::block_on, stream, TryStreamExt};
+use stackify_docker_api::conn::TtyFrameDecoder;
+use std::time::{Duration, Instant};
+
+const FRAMES: usize = 100_000;
+const FRAME_LEN: usize = 120;
+const CHUNK_LEN: usize = 32 * 1024;
+const ROUNDS: u32 = 10;
+
+fn chunks() -> Vec<Bytes> {
+    let mut data = Vec::with_capacity(FRAMES * (FRAME_LEN + 8));
+    for i in 0..FRAMES {
+        data.extend_from_slice(&[1 + (i % 2) as u8, 0, 0, 0]);
+        data.extend_from_slice(&(FRAME_LEN as u32).to_be_bytes());
+        data.resize(data.len() + FRAME_LEN, b'a' + (i % 26) as u8);
+    }
+    data.chunks(CHUNK_LEN).map(Bytes::copy_from_slice).collect()
+}
+
+fn bench(name: &str, chunks: &[Bytes], decode: impl Fn(Vec<Bytes>) -> usize) {
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let input = chunks.to_vec();
+        let start = Instant::now();
+        let bytes = decode(input);
+        total += start.elapsed();
+        assert_eq!(bytes, FRAMES * FRAME_LEN);
+    }
+    let per_round = total / ROUNDS;
+    println!(
+        "{name:<16} {per_round:>10.2?}/round {:>8.0} MiB/s",
+        (FRAMES * FRAME_LEN) as f64 / per_round.as_secs_f64() / (1024.0 * 1024.0)
+    );
+}
+
+fn main() {
+    let chunks = chunks();
+
+    bench("TtyFrameDecoder", &chunks, |input| {
+        let mut decoder = TtyFrameDecoder::new();
+        let mut bytes = 0;
+        for chunk in input {
+            decoder.push(chunk);
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                bytes += frame.len();
+            }
+        }
+        bytes
+    });
+
+    bench("tty::decode", &chunks, |input| {
+        let stream = stream::iter(input.into_iter().map(Ok));
+        block_on(
+            tty::decode(stream)
+                .map_ok(|chunk| chunk.len())
+                .try_fold(0, |acc, len| async move { Ok(acc + len) }),
+        )
+        .unwrap()
+    });
+}
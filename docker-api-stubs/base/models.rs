@@ -10,4 +10,39 @@ use serde_json::Value;
 
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
+/// Timestamp returned by the engine API. It is a `chrono::DateTime<Utc>` with the `chrono`
+/// feature, a `time::OffsetDateTime` with only the `time` feature and the RFC 3339 string
+/// otherwise.
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+/// Timestamp returned by the engine API. It is a `chrono::DateTime<Utc>` with the `chrono`
+/// feature, a `time::OffsetDateTime` with only the `time` feature and the RFC 3339 string
+/// otherwise.
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+pub type Timestamp = time::OffsetDateTime;
+/// Timestamp returned by the engine API. It is a `chrono::DateTime<Utc>` with the `chrono`
+/// feature, a `time::OffsetDateTime` with only the `time` feature and the RFC 3339 string
+/// otherwise.
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+pub type Timestamp = String;
+
+/// Serializes optional [`Timestamp`](Timestamp)s as RFC 3339 strings.
+mod timestamp {
+    #[cfg(all(feature = "time", not(feature = "chrono")))]
+    pub use time::serde::rfc3339::option::{deserialize, serialize};
+
+    #[cfg(not(all(feature = "time", not(feature = "chrono"))))]
+    pub fn serialize<S: serde::Serializer>(
+        timestamp: &Option<super::Timestamp>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(timestamp, serializer)
+    }
+
+    #[cfg(not(all(feature = "time", not(feature = "chrono"))))]
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<super::Timestamp>, D::Error> {
+        serde::Deserialize::deserialize(deserializer)
+    }
+}
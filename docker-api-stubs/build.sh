@@ -3,7 +3,7 @@
 set -ex
 
 DOCKER_SWAGGER_URL="https://docs.docker.com/engine/api"
-DOCKER_API_VERSION="v1.43"
+DOCKER_API_VERSION="${DOCKER_API_VERSION:-v1.43}"
 DOCKER_SPEC_FILE="${DOCKER_API_VERSION}.yaml"
 DOCKER_FULL_URL="${DOCKER_SWAGGER_URL}/${DOCKER_SPEC_FILE}"
 RUSTGEN="https://github.com/vv9k/swagger-rustgen.git"
@@ -24,6 +24,13 @@ cd $BASE_DIR
 
 cat base/models.rs > lib/src/models.rs
 
+cat > lib/src/lib.rs <<EOF
+/// Version of the Docker engine API spec the models were generated from.
+pub const API_VERSION: &str = "${DOCKER_API_VERSION#v}";
+
+pub mod models;
+EOF
+
 $BUILD_DIR/swagger-rustgen/target/release/swagger-gen generate models $BUILD_DIR/$DOCKER_SPEC_FILE >> lib/src/models.rs
 
 cd lib
@@ -33,4 +40,7 @@ cargo fmt
 # Fix for https://github.com/vv9k/docker-api-rs/pull/29
 sed -r -i 's/(PortMap = HashMap<String, )(Vec<PortBinding>)/\1Option<\2>/g' src/models.rs
 
+# Timestamps are chrono or time types depending on the enabled feature
+sed -r -i 's/^( *)pub (\w+): Option<DateTime<Utc>>,/\1#[serde(default, with = "timestamp")]\n\1pub \2: Option<Timestamp>,/' src/models.rs
+
 cargo fmt
@@ -1 +1,4 @@
+/// Version of the Docker engine API spec the models were generated from.
+pub const API_VERSION: &str = "1.43";
+
 pub mod models;
@@ -10,7 +10,42 @@ use serde_json::Value;
 
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
+/// Timestamp returned by the engine API. It is a `chrono::DateTime<Utc>` with the `chrono`
+/// feature, a `time::OffsetDateTime` with only the `time` feature and the RFC 3339 string
+/// otherwise.
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+/// Timestamp returned by the engine API. It is a `chrono::DateTime<Utc>` with the `chrono`
+/// feature, a `time::OffsetDateTime` with only the `time` feature and the RFC 3339 string
+/// otherwise.
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+pub type Timestamp = time::OffsetDateTime;
+/// Timestamp returned by the engine API. It is a `chrono::DateTime<Utc>` with the `chrono`
+/// feature, a `time::OffsetDateTime` with only the `time` feature and the RFC 3339 string
+/// otherwise.
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+pub type Timestamp = String;
+
+/// Serializes optional [`Timestamp`](Timestamp)s as RFC 3339 strings.
+mod timestamp {
+    #[cfg(all(feature = "time", not(feature = "chrono")))]
+    pub use time::serde::rfc3339::option::{deserialize, serialize};
+
+    #[cfg(not(all(feature = "time", not(feature = "chrono"))))]
+    pub fn serialize<S: serde::Serializer>(
+        timestamp: &Option<super::Timestamp>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(timestamp, serializer)
+    }
+
+    #[cfg(not(all(feature = "time", not(feature = "chrono"))))]
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<super::Timestamp>, D::Error> {
+        serde::Deserialize::deserialize(deserializer)
+    }
+}
 
 fn deserialize_nonoptional_vec<
     'de,
@@ -63,7 +98,8 @@ pub struct BuildCache {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the build cache was created in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "Description")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Description of the build-step that produced the build cache.
@@ -80,7 +116,8 @@ pub struct BuildCache {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the build cache was last used in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub last_used_at: Option<Timestamp>,
     #[serde(rename = "Parent")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// ID of the parent build cache record.
@@ -192,7 +229,8 @@ pub struct ClusterInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the swarm was initialised in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "DataPathPort")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// DataPathPort specifies the data path port number for data traffic.
@@ -225,7 +263,8 @@ pub struct ClusterInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the swarm was last updated in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -236,7 +275,8 @@ pub struct ClusterInfo {
 pub struct ClusterVolume {
     #[serde(rename = "CreatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "ID")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// The Swarm ID of this volume. Because cluster volumes are Swarm
@@ -256,7 +296,8 @@ pub struct ClusterVolume {
     pub spec: Option<ClusterVolumeSpec>,
     #[serde(rename = "UpdatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -615,7 +656,8 @@ pub struct ComponentVersion {
 pub struct Config {
     #[serde(rename = "CreatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "ID")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -623,7 +665,8 @@ pub struct Config {
     pub spec: Option<ConfigSpec>,
     #[serde(rename = "UpdatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -2159,7 +2202,8 @@ pub struct HealthcheckResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which this check ended in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub end: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub end: Option<Timestamp>,
     #[serde(rename = "ExitCode")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// ExitCode meanings:
@@ -2177,7 +2221,8 @@ pub struct HealthcheckResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which this check started in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub start: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub start: Option<Timestamp>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -2964,7 +3009,8 @@ pub struct ImageInspectMetadataInlineItem {
     ///
     /// This information is only available if the image was tagged locally,
     /// and omitted otherwise.
-    pub last_tag_time: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub last_tag_time: Option<Timestamp>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -3486,7 +3532,8 @@ pub struct Network {
     pub containers: Option<HashMap<String, NetworkContainer>>,
     #[serde(rename = "Created")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created: Option<Timestamp>,
     #[serde(rename = "Driver")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub driver: Option<String>,
@@ -3804,7 +3851,8 @@ pub struct Node {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the node was added to the swarm in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "Description")]
     pub description: Option<NodeDescription>,
     #[serde(rename = "ID")]
@@ -3820,7 +3868,8 @@ pub struct Node {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the node was last updated in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -4861,7 +4910,8 @@ pub struct Runtime {
 pub struct Secret {
     #[serde(rename = "CreatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "ID")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -4869,7 +4919,8 @@ pub struct Secret {
     pub spec: Option<SecretSpec>,
     #[serde(rename = "UpdatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -4929,7 +4980,8 @@ pub struct SecretSpec {
 pub struct Service {
     #[serde(rename = "CreatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "Endpoint")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint: Option<ServiceEndpointInlineItem>,
@@ -4956,7 +5008,8 @@ pub struct Service {
     pub update_status: Option<ServiceUpdateStatusInlineItem>,
     #[serde(rename = "UpdatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -5273,7 +5326,8 @@ pub struct ServiceJobStatusInlineItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// The last time, as observed by the server, that this job was
     /// started.
-    pub last_execution: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub last_execution: Option<Timestamp>,
 }
 
 /// no error
@@ -5838,13 +5892,15 @@ pub struct ServiceUpdateResponse {
 pub struct ServiceUpdateStatusInlineItem {
     #[serde(rename = "CompletedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub completed_at: Option<Timestamp>,
     #[serde(rename = "Message")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
     #[serde(rename = "StartedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub started_at: Option<Timestamp>,
     #[serde(rename = "State")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<String>,
@@ -5884,7 +5940,8 @@ pub struct Swarm {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the swarm was initialised in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "DataPathPort")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// DataPathPort specifies the data path port number for data traffic.
@@ -5919,7 +5976,8 @@ pub struct Swarm {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date and time at which the swarm was last updated in
     /// [RFC 3339](https://www.ietf.org/rfc/rfc3339.txt) format with nano-seconds.
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -6888,7 +6946,8 @@ pub struct Task {
     pub assigned_generic_resources: Option<GenericResources>,
     #[serde(rename = "CreatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "DesiredState")]
     pub desired_state: Option<String>,
     #[serde(rename = "ID")]
@@ -6923,7 +6982,8 @@ pub struct Task {
     pub status: Option<TaskStatusInlineItem>,
     #[serde(rename = "UpdatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub updated_at: Option<Timestamp>,
     #[serde(rename = "Version")]
     pub version: Option<ObjectVersion>,
 }
@@ -7645,7 +7705,8 @@ pub struct TaskStatusInlineItem {
     pub state: Option<String>,
     #[serde(rename = "Timestamp")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub timestamp: Option<Timestamp>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -7701,7 +7762,8 @@ pub struct Volume {
     #[serde(rename = "CreatedAt")]
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Date/Time the volume was created.
-    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamp")]
+    pub created_at: Option<Timestamp>,
     #[serde(rename = "Driver")]
     /// Name of the volume driver used by the volume.
     pub driver: String,
@@ -48,7 +48,7 @@ enum Cmd {
         pause: Option<bool>,
         #[arg(long)]
         /// Dockerfile instructions to apply while committing
-        changes: Option<String>,
+        changes: Vec<String>,
     },
     /// Create a new container.
     Create {
@@ -206,7 +206,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             if let Some(pause) = pause {
                 opts = opts.pause(pause)
             }
-            if let Some(changes) = changes {
+            if !changes.is_empty() {
                 opts = opts.changes(changes)
             }
             match docker
@@ -34,8 +34,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     match opts.subcmd {
         Cmd::Inspect { container, cmd } => {
-            use stackify_docker_api::opts::ExecCreateOpts;
             use futures::StreamExt;
+            use stackify_docker_api::opts::ExecCreateOpts;
 
             // Create Opts with specified command
             let opts = ExecCreateOpts::builder()
@@ -34,7 +34,15 @@ enum Cmd {
         network: String,
     },
     List,
-    Prune,
+    /// Delete unused networks.
+    Prune {
+        #[arg(long)]
+        /// Only prune networks created before this duration or timestamp, for example `24h`.
+        until: Option<String>,
+        #[arg(long)]
+        /// Only prune networks with this label, either `key` or `key=val`.
+        label: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -105,8 +113,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }),
             Err(e) => eprintln!("Error: {e}"),
         },
-        Cmd::Prune => {
-            match docker.networks().prune(&Default::default()).await {
+        Cmd::Prune { until, label } => {
+            use stackify_docker_api::opts::{NetworkPruneFilter, NetworkPruneOpts};
+            let mut filters = vec![];
+            if let Some(until) = until {
+                filters.push(NetworkPruneFilter::Until(until));
+            }
+            if let Some(label) = label {
+                filters.push(match label.split_once('=') {
+                    Some((key, val)) => NetworkPruneFilter::Label(key.into(), val.into()),
+                    None => NetworkPruneFilter::LabelKey(label),
+                });
+            }
+            let opts = NetworkPruneOpts::builder().filter(filters).build();
+            match docker.networks().prune(&opts).await {
                 Ok(info) => println!("{info:#?}"),
                 Err(e) => eprintln!("Error: {e}"),
             };
@@ -33,6 +33,9 @@ enum Cmd {
         #[arg(long)]
         stderr: bool,
     },
+    Ps {
+        service: String,
+    },
 }
 
 #[cfg(feature = "swarm")]
@@ -75,8 +78,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             stdout,
             stderr,
         } => {
-            use stackify_docker_api::opts::LogsOpts;
             use futures::StreamExt;
+            use stackify_docker_api::opts::LogsOpts;
 
             let service = docker.services().get(&service);
             let logs_stream =
@@ -97,6 +100,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .collect::<Vec<_>>();
             print!("{}", String::from_utf8_lossy(&logs));
         }
+        Cmd::Ps { service } => match docker.services().get(&service).ps().await {
+            Ok(tasks) => {
+                for task in tasks {
+                    println!(
+                        "{}\t{}\t{}\t{:?}\t{:?}\t{}",
+                        task.task_id,
+                        task.name,
+                        task.node.unwrap_or_default(),
+                        task.status.desired_state,
+                        task.status.state,
+                        task.status.error.unwrap_or_default(),
+                    )
+                }
+            }
+            Err(e) => eprintln!("Error: {e}"),
+        },
     }
 
     Ok(())
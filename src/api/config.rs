@@ -1,6 +1,7 @@
 //! Configs are application configurations that can be used by services.
 //! Swarm mode must be enabled for these endpoints to work.
 
+use crate::endpoint::Endpoint;
 use crate::{
     conn::{Headers, Payload},
     models,
@@ -12,11 +13,28 @@ impl_api_ty!(Config => name);
 
 impl Config {
     impl_api_ep! { cfg: Config, resp
-        Inspect -> &format!("/configs/{}", cfg.name), models::Config
-        Delete -> &format!("/configs/{}", cfg.name), ()
+        Inspect -> Endpoint::new("/configs").segment(&cfg.name), models::Config
+        Delete -> Endpoint::new("/configs").segment(&cfg.name), ()
     }
 
-    // TODO: add Config::update
+    api_doc! { Config => Update
+    |
+    /// Update this config. Only the labels of a config can be changed, the rest of the `spec`
+    /// must match the current spec. `version` is the current version of the config object as
+    /// returned by [`Config::inspect`](Config::inspect).
+    pub async fn update(&self, spec: &models::ConfigSpec, version: u64) -> Result<()> {
+        self.docker
+            .post(
+                &Endpoint::new("/configs")
+                    .segment(&self.name)
+                    .path("update")
+                    .param("version", version),
+                Payload::Json(serde_json::to_string(spec)?),
+                Headers::none(),
+            )
+            .await
+            .map(|_| ())
+    }}
 }
 
 impl Configs {
@@ -29,8 +47,8 @@ impl Configs {
     |
     /// Create a new config.
     pub async fn create(&self, opts: &ConfigCreateOpts) -> Result<Config> {
-        use serde::Deserialize;
-        #[derive(Deserialize)]
+        use serde::{Deserialize, Serialize};
+        #[derive(Deserialize, Serialize)]
         struct ConfigCreateResponse {
             #[serde(rename = "Id")]
             pub id: String,
@@ -1,31 +1,40 @@
 //! Create and manage containers.
 use crate::opts::{
     ContainerCommitOpts, ContainerCreateOpts, ContainerListOpts, ContainerPruneOpts,
-    ContainerRemoveOpts, ContainerRestartOpts, ContainerStopOpts, ExecStartOpts,
+    ContainerRemoveOpts, ContainerRestartOpts, ContainerStopOpts, ExecStartOpts, Signal,
 };
 use crate::{models, stream};
 
-use std::{io, path::Path, str};
+use std::{path::Path, str};
 
-use futures_util::{Stream, TryStreamExt};
+use bytes::Bytes;
+use futures_util::{AsyncRead, AsyncWrite, Stream, TryStreamExt};
 use hyper::Body;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     api::Exec,
-    conn::{tty, Headers, Payload},
+    conn::{tty, BoundedStream, Headers, Payload, TtyChunk},
+    endpoint::Endpoint,
     opts::ExecCreateOpts,
     Error, Result,
 };
-use containers_api::url::{append_query, construct_ep, encoded_pair};
+use base64::{engine::general_purpose, Engine};
 
-impl_api_ty!(Container => id);
+impl_api_ty!(Container => id: crate::ContainerId);
 
 impl Container {
     impl_api_ep! {container: Container, resp
-        Inspect -> &format!("/containers/{}/json", container.id), models::ContainerInspect200Response
-        Logs -> &format!("/containers/{}/logs", container.id), ()
-        DeleteWithOpts -> &format!("/containers/{}", container.id), String, delete
+        Inspect -> Endpoint::new("/containers").segment(&container.id).path("json"), models::ContainerInspect200Response
+        Logs -> Endpoint::new("/containers").segment(&container.id).path("logs"), ()
+        DeleteWithOpts -> Endpoint::new("/containers").segment(&container.id), String, delete
+    }
+
+    fn archive_endpoint(&self, path: &Path) -> Endpoint {
+        Endpoint::new("/containers")
+            .segment(&self.id)
+            .path("archive")
+            .param("path", path.to_string_lossy())
     }
 
     api_doc! { Container => Top
@@ -33,10 +42,10 @@ impl Container {
     /// Returns a `top` view of information about the container process.
     /// On Unix systems, this is done by running the ps command. This endpoint is not supported on Windows.
     pub async fn top(&self, psargs: Option<&str>) -> Result<models::ContainerTop200Response> {
-        let mut ep = format!("/containers/{}/top", self.id);
-        if let Some(ref args) = psargs {
-            append_query(&mut ep, encoded_pair("ps_args", args));
-        }
+        let ep = Endpoint::new("/containers")
+            .segment(&self.id)
+            .path("top")
+            .param_opt("ps_args", psargs);
         self.docker.get_json(&ep).await
     }}
 
@@ -52,22 +61,47 @@ impl Container {
         let is_tty = inspect.config.and_then(|c| c.tty).unwrap_or_default();
         stream::attach(
             self.docker.clone(),
-            format!(
-                "/containers/{}/attach?stream=1&stdout=1&stderr=1&stdin=1",
-                self.id
-            ),
+            Endpoint::new("/containers")
+                .segment(&self.id)
+                .path("attach")
+                .param("stream", 1)
+                .param("stdout", 1)
+                .param("stderr", 1)
+                .param("stdin", 1)
+                .into(),
             Payload::empty(),
             is_tty,
         )
         .await
     }}
 
+    api_doc! { Container => Attach
+    |
+    /// Same as [`Container::attach`](Container::attach) but reads the output of the container
+    /// in the background into a buffer of `capacity` chunks, returned together with the writer
+    /// to stdin.
+    ///
+    /// Once the buffer is full the oldest chunks are dropped and reported with
+    /// [`Buffered::Lagged`](crate::conn::Buffered::Lagged). Unlike reading the multiplexer
+    /// directly, a consumer that falls behind doesn't block the output of the container.
+    pub async fn attach_buffered(
+        &self,
+        capacity: usize,
+    ) -> Result<(BoundedStream<TtyChunk>, impl AsyncWrite + Send)> {
+        let (reader, writer) = self.attach().await?.split();
+        let reader = reader.map_err(Error::Error);
+        Ok((
+            BoundedStream::forward(self.docker.runtime(), capacity, reader),
+            writer,
+        ))
+    }}
+
     api_doc! { Container => Changes
     |
     /// Returns a set of changes made to the container instance.
     pub async fn changes(&self) -> Result<Option<models::ContainerChanges200Response>> {
         self.docker
-            .get_json(&format!("/containers/{}/changes", self.id))
+            .get_json(&Endpoint::new("/containers").segment(&self.id).path("changes"))
             .await
     }}
 
@@ -76,7 +110,7 @@ impl Container {
     /// Exports the current docker container into a tarball.
     pub fn export(&self) -> impl Stream<Item = Result<Vec<u8>>> + '_ {
         self.docker
-            .get_stream(format!("/containers/{}/export", self.id))
+            .get_stream(Endpoint::new("/containers").segment(&self.id).path("export"))
             .map_ok(|c| c.to_vec())
     }}
 
@@ -84,23 +118,26 @@ impl Container {
     |
     /// Returns a stream of stats specific to this container instance.
     pub fn stats(&self) -> impl Stream<Item = Result<serde_json::Value>> + Unpin + '_ {
-        let codec = asynchronous_codec::LinesCodec {};
+        let stream = self
+            .docker
+            .get_stream(Endpoint::new("/containers").segment(&self.id).path("stats"));
 
-        let reader = Box::pin(
-            self.docker
-                .get_stream(format!("/containers/{}/stats", self.id))
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
-        )
-        .into_async_read();
+        #[cfg(feature = "fast-json")]
+        let stats = crate::json::decode(stream);
+        #[cfg(not(feature = "fast-json"))]
+        let stats = {
+            let codec = asynchronous_codec::LinesCodec {};
+            let reader = Box::pin(stream.map_err(std::io::Error::other)).into_async_read();
 
-        Box::pin(
             asynchronous_codec::FramedRead::new(reader, codec)
-                .map_err(Error::IO)
+                .map_err(|e| Error::IO(e).unwrap_stream_error())
                 .and_then(|s: String| async move {
                     log::trace!("{}", s);
                     serde_json::from_str(&s).map_err(Error::SerdeJsonError)
-                }),
-        )
+                })
+        };
+
+        Box::pin(stats)
     }}
 
     api_doc! { Container => Start
@@ -109,7 +146,7 @@ impl Container {
     pub async fn start(&self) -> Result<()> {
         self.docker
             .post_string(
-                &format!("/containers/{}/start", self.id),
+                &Endpoint::new("/containers").segment(&self.id).path("start"),
                 Payload::empty(),
                 Headers::none(),
             )
@@ -120,9 +157,19 @@ impl Container {
     api_doc! { Container => Stop
     |
     /// Stop the container instance.
+    ///
+    /// The timeout of the client doesn't apply to this call as the daemon answers once the
+    /// container stopped.
     pub async fn stop(&self, opts: &ContainerStopOpts) -> Result<()> {
-        let ep = construct_ep(format!("/containers/{}/stop", self.id), opts.serialize());
         self.docker
+            .require_params(opts.params.keys(), ContainerStopOpts::PARAM_VERSIONS)?;
+        let ep = Endpoint::new("/containers")
+            .segment(&self.id)
+            .path("stop")
+            .query(opts.serialize());
+        self.docker
+            .clone()
+            .with_timeout(None)
             .post_string(&ep, Payload::empty(), Headers::none())
             .await
             .map(|_| ())
@@ -131,9 +178,19 @@ impl Container {
     api_doc! { Container => Restart
     |
     /// Restart the container instance.
+    ///
+    /// The timeout of the client doesn't apply to this call as the daemon answers once the
+    /// container restarted.
     pub async fn restart(&self, opts: &ContainerRestartOpts) -> Result<()> {
-        let ep = construct_ep(format!("/containers/{}/restart", self.id), opts.serialize());
         self.docker
+            .require_params(opts.params.keys(), ContainerRestartOpts::PARAM_VERSIONS)?;
+        let ep = Endpoint::new("/containers")
+            .segment(&self.id)
+            .path("restart")
+            .query(opts.serialize());
+        self.docker
+            .clone()
+            .with_timeout(None)
             .post_string(&ep, Payload::empty(), Headers::none())
             .await
             .map(|_| ())
@@ -142,12 +199,17 @@ impl Container {
     api_doc! { Container => Kill
     |
     /// Kill the container instance.
-    pub async fn kill(&self, signal: Option<&str>) -> Result<()> {
-        let mut ep = format!("/containers/{}/kill", self.id);
-        if let Some(sig) = signal {
-            append_query(&mut ep, encoded_pair("signal", sig));
-        }
+    ///
+    /// The timeout of the client doesn't apply to this call as the daemon answers once the
+    /// signal was delivered.
+    pub async fn kill(&self, signal: Option<Signal>) -> Result<()> {
+        let ep = Endpoint::new("/containers")
+            .segment(&self.id)
+            .path("kill")
+            .param_opt("signal", signal);
         self.docker
+            .clone()
+            .with_timeout(None)
             .post_string(&ep, Payload::empty(), Headers::none())
             .await
             .map(|_| ())
@@ -159,11 +221,10 @@ impl Container {
     pub async fn rename(&self, name: &str) -> Result<()> {
         self.docker
             .post_string(
-                &format!(
-                    "/containers/{}/rename?{}",
-                    self.id,
-                    encoded_pair("name", name)
-                ),
+                &Endpoint::new("/containers")
+                    .segment(&self.id)
+                    .path("rename")
+                    .param("name", name),
                 Payload::empty(),
                 Headers::none(),
             )
@@ -177,7 +238,7 @@ impl Container {
     pub async fn pause(&self) -> Result<()> {
         self.docker
             .post_string(
-                &format!("/containers/{}/pause", self.id),
+                &Endpoint::new("/containers").segment(&self.id).path("pause"),
                 Payload::empty(),
                 Headers::none(),
             )
@@ -191,7 +252,7 @@ impl Container {
     pub async fn unpause(&self) -> Result<()> {
         self.docker
             .post_string(
-                &format!("/containers/{}/unpause", self.id),
+                &Endpoint::new("/containers").segment(&self.id).path("unpause"),
                 Payload::empty(),
                 Headers::none(),
             )
@@ -202,10 +263,14 @@ impl Container {
     api_doc! { Container => Wait
     |
     /// Wait until the container stops.
+    ///
+    /// The timeout of the client doesn't apply to this call.
     pub async fn wait(&self) -> Result<models::ContainerWaitResponse> {
         self.docker
+            .clone()
+            .with_timeout(None)
             .post_json(
-                format!("/containers/{}/wait", self.id),
+                Endpoint::new("/containers").segment(&self.id).path("wait"),
                 Payload::empty(),
                 Headers::none(),
             )
@@ -235,11 +300,7 @@ impl Container {
     /// copied.  A symlink is always resolved to its target.
     pub fn copy_from(&self, path: impl AsRef<Path>) -> impl Stream<Item = Result<Vec<u8>>> + '_ {
         self.docker
-            .get_stream(format!(
-                "/containers/{}/archive?{}",
-                self.id,
-                encoded_pair("path", path.as_ref().to_string_lossy())
-            ))
+            .get_stream(self.archive_endpoint(path.as_ref()))
             .map_ok(|c| c.to_vec())
     }}
 
@@ -274,20 +335,44 @@ impl Container {
     /// Copy a tarball (see `body`) to the container.
     ///
     /// The tarball will be copied to the container and extracted at the given location (see `path`).
+    ///
+    /// The timeout of the client doesn't apply to this call so that large archives can be
+    /// uploaded.
     pub async fn copy_to(&self, path: &Path, body: Body) -> Result<()> {
         self.docker
+            .clone()
+            .with_timeout(None)
             .put(
-                &format!(
-                    "/containers/{}/archive?{}",
-                    self.id,
-                    encoded_pair("path", path.to_string_lossy())
-                ),
+                &self.archive_endpoint(path),
                 Payload::XTar(body),
             )
             .await
             .map(|_| ())
     }}
 
+    api_doc! { PutContainer => Archive
+    |
+    /// Same as [`Container::copy_to`](Container::copy_to) but streams the chunks of `tarball`
+    /// to the daemon as they are produced, so large archives are uploaded without being held in
+    /// memory.
+    pub async fn copy_to_stream<S>(&self, path: &Path, tarball: S) -> Result<()>
+    where
+        S: Stream<Item = Result<Bytes>> + Send + 'static,
+    {
+        self.copy_to(path, Body::wrap_stream(tarball)).await
+    }}
+
+    api_doc! { PutContainer => Archive
+    |
+    /// Same as [`Container::copy_to_stream`](Container::copy_to_stream) but reads the tarball
+    /// from `reader`, like an opened file.
+    pub async fn copy_to_reader<R>(&self, path: &Path, reader: R) -> Result<()>
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        self.copy_to_stream(path, stream::read_chunks(reader)).await
+    }}
+
     api_doc! { Container => ArchiveInfo
     |
     /// Get information about files in a container.
@@ -298,18 +383,14 @@ impl Container {
         static PATH_STAT_HEADER: &str = "X-Docker-Container-Path-Stat";
         let resp = self
             .docker
-            .head(&format!(
-                "/containers/{}/archive?{}",
-                self.id,
-                encoded_pair("path", path.as_ref().to_string_lossy())
-            ))
+            .head(&self.archive_endpoint(path.as_ref()))
             .await?;
         if let Some(header) = resp.headers().get(PATH_STAT_HEADER) {
             let header = header.to_str().map_err(|e| {
                 Error::InvalidResponse(format!("response header was invalid - {e}"))
             })?;
 
-            base64::decode(header)
+            general_purpose::STANDARD.decode(header)
                 .map_err(|e| {
                     Error::InvalidResponse(format!("expected header to be valid base64 - {e}"))
                 })
@@ -330,11 +411,14 @@ impl Container {
     api_doc! { Image => Commit
     |
     /// Create a new image from this container
-    pub async fn commit(&self, opts: &ContainerCommitOpts, config: Option<&models::ContainerConfig>) -> Result<String> {
-        #[derive(Deserialize)]
+    ///
+    /// The timeout of the client doesn't apply to this call as committing large containers
+    /// takes a while.
+    pub async fn commit(&self, opts: &ContainerCommitOpts, config: Option<&models::ContainerConfig>) -> Result<crate::ImageId> {
+        #[derive(Deserialize, Serialize)]
         struct IdStruct {
             #[serde(rename = "Id")]
-            id: String,
+            id: crate::ImageId,
         }
 
         let payload = if let Some(config) = config {
@@ -344,13 +428,10 @@ impl Container {
         };
 
         self.docker
+            .clone()
+            .with_timeout(None)
             .post_json(
-                format!(
-                    "/commit?{}",
-                    opts.with_container(self.id().as_ref())
-                        .serialize()
-                        .unwrap_or_default()
-                ),
+                Endpoint::new("/commit").query(opts.with_container(self.id().as_ref()).serialize()),
                 payload,
                 Headers::none(),
             )
@@ -369,11 +450,7 @@ impl Containers {
     |
     /// Create a container
     pub async fn create(&self, opts: &ContainerCreateOpts) -> Result<Container> {
-        let ep = if let Some(name) = opts.name() {
-            construct_ep("/containers/create", Some(encoded_pair("name", name)))
-        } else {
-            "/containers/create".to_owned()
-        };
+        let ep = Endpoint::new("/containers/create").param_opt("name", opts.name());
         self.docker
             .post_json(&ep, Payload::Json(opts.serialize_vec()?), Headers::none())
             .await
@@ -4,9 +4,10 @@ use hyper::Body;
 
 use crate::{
     conn::{tty, Headers, Payload},
+    endpoint::Endpoint,
     models,
     opts::{ExecCreateOpts, ExecResizeOpts, ExecStartOpts},
-    stream, Docker, Result,
+    stream, ContainerId, Docker, ExecId, Result,
 };
 
 api_doc! { Exec
@@ -14,11 +15,11 @@ api_doc! { Exec
 |
 pub struct Exec {
     docker: Docker,
-    id: crate::Id,
+    id: ExecId,
 }}
 
 impl Exec {
-    fn new(docker: Docker, id: impl Into<crate::Id>) -> Self {
+    fn new(docker: Docker, id: impl Into<ExecId>) -> Self {
         Exec {
             docker,
             id: id.into(),
@@ -30,10 +31,15 @@ impl Exec {
     /// It's in callers responsibility to ensure that exec instance with specified id actually
     /// exists. Use [Exec::create](Exec::create) to ensure that the exec instance is created
     /// beforehand.
-    pub fn get(docker: Docker, id: impl Into<crate::Id>) -> Exec {
+    pub fn get(docker: Docker, id: impl Into<ExecId>) -> Exec {
         Exec::new(docker, id)
     }
 
+    /// Returns the ID of this exec instance.
+    pub fn id(&self) -> &ExecId {
+        &self.id
+    }
+
     api_doc! { Exec => Inspect
     |
     /// Inspect this Exec instance
@@ -42,23 +48,28 @@ impl Exec {
     }}
 
     async fn inspect_impl(docker: &Docker, id: &str) -> Result<models::ExecInspect200Response> {
-        docker.get_json(&format!("/exec/{id}/json")).await
+        docker
+            .get_json(&Endpoint::new("/exec").segment(id).path("json"))
+            .await
     }
 
     async fn create_impl(
         docker: Docker,
         container_id: &str,
         opts: &ExecCreateOpts,
-    ) -> Result<crate::Id> {
-        #[derive(serde::Deserialize)]
+    ) -> Result<ExecId> {
+        #[derive(serde::Deserialize, serde::Serialize)]
         #[serde(rename_all = "PascalCase")]
         struct Response {
             id: String,
         }
 
+        docker.require_params(opts.params.keys(), ExecCreateOpts::PARAM_VERSIONS)?;
         docker
             .post_json(
-                &format!("/containers/{}/exec", container_id),
+                &Endpoint::new("/containers")
+                    .segment(container_id)
+                    .path("exec"),
                 Payload::Json(opts.serialize_vec()?),
                 Headers::none(),
             )
@@ -71,11 +82,11 @@ impl Exec {
     /// Creates a new exec instance that will be executed in a container with id == container_id.
     pub async fn create(
         docker: Docker,
-        container_id: impl AsRef<str>,
+        container_id: impl Into<ContainerId>,
         opts: &ExecCreateOpts,
     ) -> Result<Exec>
     {
-        Self::create_impl(docker.clone(), container_id.as_ref(), opts)
+        Self::create_impl(docker.clone(), container_id.into().as_str(), opts)
         .await
             .map(|id| Exec::new(docker, id))
     }}
@@ -85,7 +96,8 @@ impl Exec {
         id: &str,
         opts: &ExecStartOpts,
     ) -> Result<tty::Multiplexer> {
-        let endpoint = format!("/exec/{}/start", id);
+        docker.require_params(opts.params.keys(), ExecStartOpts::PARAM_VERSIONS)?;
+        let endpoint = Endpoint::new("/exec").segment(id).path("start");
         let inspect_data = Self::inspect_impl(&docker, id).await?;
         let is_tty = inspect_data
             .process_config
@@ -94,7 +106,7 @@ impl Exec {
 
         stream::attach(
             docker,
-            endpoint,
+            endpoint.into(),
             Payload::Json(opts.serialize_vec()?.into()),
             is_tty,
         )
@@ -129,7 +141,7 @@ impl Exec {
 
         self.docker
             .post_json(
-                &format!("/exec/{}/resize", &self.id),
+                &Endpoint::new("/exec").segment(&self.id).path("resize"),
                 Payload::Json(body),
                 Headers::none(),
             )
@@ -3,28 +3,46 @@
 use crate::{
     models,
     opts::{
-        ClearCacheOpts, ImageBuildOpts, ImageListOpts, ImagePruneOpts, ImagePushOpts,
-        ImageRemoveOpts, PullOpts, TagOpts,
+        api_v1, ClearCacheOpts, ImageBuildOpts, ImageFilter, ImageListOpts, ImagePruneOpts,
+        ImagePushOpts, ImageRemoveOpts, PullOpts, Reference, TagOpts,
     },
 };
 
-use std::io::Read;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
 
-use futures_util::{stream::Stream, TryFutureExt, TryStreamExt};
+use bytes::Bytes;
+use futures_util::{
+    future,
+    stream::{self, Stream, StreamExt},
+    AsyncRead, TryFutureExt, TryStreamExt,
+};
+use hyper::Body;
 
 use containers_api::{
     conn::{Headers, Payload, AUTH_HEADER},
     tarball,
-    url::{construct_ep, encoded_pair, encoded_pairs},
 };
 
-use crate::Result;
+use crate::{endpoint::Endpoint, Error, Result};
+
+impl_api_ty!(Image => name: crate::ImageId);
 
-impl_api_ty!(Image => name);
+/// Chunk of the stream returned by [`Images::pull_many`](Images::pull_many).
+#[derive(Debug)]
+pub enum PullManyChunk {
+    /// Progress reported by the daemon while pulling `image`.
+    Progress {
+        image: String,
+        chunk: models::ImageBuildChunk,
+    },
+    /// Result of every pull keyed by the reference as it was given, sent once all pulls ended.
+    Done(BTreeMap<String, Result<()>>),
+}
 
 impl Image {
     impl_api_ep! {img: Image, resp
-        Inspect -> &format!("/images/{}/json", img.name), models::ImageInspect
+        Inspect -> Endpoint::new("/images").segment(&img.name).path("json"), models::ImageInspect
     }
 
     api_doc! { Image => Delete
@@ -33,8 +51,7 @@ impl Image {
     ///
     /// Use [`delete`](Image::delete) to delete without options.
     pub async fn remove(&self, opts: &ImageRemoveOpts) -> Result<Vec<models::ImageDeleteResponseItem>> {
-        let ep =
-            containers_api::url::construct_ep(format!("/images/{}", self.name), opts.serialize());
+        let ep = Endpoint::new("/images").segment(&self.name).query(opts.serialize());
         self.docker.delete_json(ep.as_ref()).await
     }}
 
@@ -45,7 +62,7 @@ impl Image {
     /// Use [`remove`](Image::remove) to delete with options.
     pub async fn delete(&self) -> Result<Vec<models::ImageDeleteResponseItem>> {
         self.docker
-            .delete_json(&format!("/images/{}", self.name))
+            .delete_json(&Endpoint::new("/images").segment(&self.name))
             .await
     }}
 
@@ -54,7 +71,7 @@ impl Image {
     /// Lists the history of the images set of changes.
     pub async fn history(&self) -> Result<models::ImageHistory200Response> {
         self.docker
-            .get_json(&format!("/images/{}/history", self.name))
+            .get_json(&Endpoint::new("/images").segment(&self.name).path("history"))
             .await
     }}
 
@@ -64,7 +81,7 @@ impl Image {
     pub fn export(&self) -> impl Stream<Item = Result<Vec<u8>>> + Unpin + '_ {
         Box::pin(
             self.docker
-                .get_stream(format!("/images/{}/get", self.name))
+                .get_stream(Endpoint::new("/images").segment(&self.name).path("get"))
                 .map_ok(|c| c.to_vec()),
         )
     }}
@@ -73,7 +90,10 @@ impl Image {
     |
     /// Adds a tag to an image.
     pub async fn tag(&self, opts: &TagOpts) -> Result<()> {
-        let ep = construct_ep(format!("/images/{}/tag", self.name), opts.serialize());
+        let ep = Endpoint::new("/images")
+            .segment(&self.name)
+            .path("tag")
+            .query(opts.serialize());
         self.docker
             .post_string(&ep, Payload::empty(), Headers::none())
             .await
@@ -83,15 +103,23 @@ impl Image {
     api_doc! { Image => Push
     |
     /// Push an image to registry.
+    ///
+    /// The timeout of the client doesn't apply to this call as the daemon answers once the
+    /// image was pushed.
     pub async fn push(&self, opts: &ImagePushOpts) -> Result<()> {
-        let ep = construct_ep(format!("/images/{}/push", self.name), opts.serialize());
+        let ep = Endpoint::new("/images")
+            .segment(&self.name)
+            .path("push")
+            .query(opts.serialize());
 
         let headers = opts
-            .auth_header()
+            .auth_header()?
             .map(|auth| Headers::single(AUTH_HEADER, auth))
             .unwrap_or_default();
 
         self.docker
+            .clone()
+            .with_timeout(None)
             .post_string(&ep, Payload::empty(), Some(headers))
             .await
             .map(|_| ())
@@ -99,11 +127,14 @@ impl Image {
 
     api_doc! { Distribution => Inspect
     |
-    /// Return image digest and platform information by contacting the registry.
+    /// Return image digest and platform information by contacting the registry. Requires API
+    /// version 1.30.
     pub async fn distribution_inspect(&self) -> Result<models::DistributionInspect> {
+        self.docker
+            .require_version("distribution inspect", api_v1(30))?;
         self.docker
             .post_json(
-                &format!("/distribution/{}/json", self.name),
+                &Endpoint::new("/distribution").segment(&self.name).path("json"),
                 Payload::empty(),
                 Headers::none(),
             )
@@ -117,6 +148,46 @@ impl Images {
         Prune ->  "/images/prune", models::ImagePrune200Response
     }
 
+    /// Remove all dangling images, that is untagged images not referenced by any other image.
+    ///
+    /// Unlike [`prune`](Images::prune), this only considers images reported as dangling at the
+    /// time of the call and removes them concurrently without force. Images that are still used
+    /// by a container are left in place. The response lists the removed images together with
+    /// the total size reclaimed.
+    pub async fn remove_dangling(&self) -> Result<models::ImagePrune200Response> {
+        const CONCURRENT_REMOVALS: usize = 8;
+
+        let opts = ImageListOpts::builder()
+            .filter([ImageFilter::Dangling])
+            .build();
+        let dangling = self.list(&opts).await?;
+
+        let removed = stream::iter(dangling)
+            .map(|summary| async move {
+                let opts = ImageRemoveOpts::builder().build();
+                match Image::new(self.docker.clone(), summary.id)
+                    .remove(&opts)
+                    .await
+                {
+                    Ok(items) => Ok(Some((items, summary.size))),
+                    Err(Error::Conflict { .. }) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+            .buffer_unordered(CONCURRENT_REMOVALS)
+            .try_filter_map(|removed| future::ready(Ok(removed)))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let space_reclaimed = removed.iter().map(|(_, size)| size).sum();
+        let images_deleted = removed.into_iter().flat_map(|(items, _)| items).collect();
+
+        Ok(models::ImagePrune200Response {
+            images_deleted: Some(images_deleted),
+            space_reclaimed: Some(space_reclaimed),
+        })
+    }
+
     api_doc! { Image => Build
     |
     /// Builds a new image by reading a Dockerfile in a target directory. If speed is
@@ -126,13 +197,17 @@ impl Images {
         &'docker self,
         opts: &ImageBuildOpts,
     ) -> impl Stream<Item = Result<models::ImageBuildChunk>> + Unpin + 'docker {
-        let ep = construct_ep("/build", opts.serialize());
+        let ep = Endpoint::new("/build").query(opts.serialize());
+        let version_result = self
+            .docker
+            .require_params(opts.params.keys(), ImageBuildOpts::PARAM_VERSIONS);
         let mut bytes = vec![];
         let tar_result = tarball::dir(&mut bytes, &opts.path);
 
         let docker = &self.docker;
         Box::pin(
             async move {
+                version_result?;
                 tar_result?;
 
                 let value_stream =
@@ -144,6 +219,54 @@ impl Images {
         )
     }}
 
+    api_doc! { Image => Build
+    |
+    /// Same as [`Images::build`](Images::build) but additionally passes every raw chunk of the
+    /// daemon output to `output` before it gets decoded. This makes it possible to archive the
+    /// exact build output, for example in CI systems, while still consuming typed chunks.
+    pub fn build_with_output<'docker, F>(
+        &'docker self,
+        opts: &ImageBuildOpts,
+        mut output: F,
+    ) -> impl Stream<Item = Result<models::ImageBuildChunk>> + Unpin + 'docker
+    where
+        F: FnMut(&[u8]) + Send + 'docker,
+    {
+        let ep = Endpoint::new("/build").query(opts.serialize());
+        let version_result = self
+            .docker
+            .require_params(opts.params.keys(), ImageBuildOpts::PARAM_VERSIONS);
+        let mut bytes = vec![];
+        let tar_result = tarball::dir(&mut bytes, &opts.path);
+
+        let docker = &self.docker;
+        Box::pin(
+            async move {
+                version_result?;
+                tar_result?;
+
+                let reader = Box::pin(
+                    docker
+                        .post_stream(ep, Payload::Tar(bytes), Headers::none())
+                        .inspect_ok(move |chunk| output(chunk))
+                        .map_err(io::Error::other),
+                )
+                .into_async_read();
+
+                let value_stream =
+                    asynchronous_codec::FramedRead::new(reader, asynchronous_codec::LinesCodec)
+                        .map_err(Error::IO)
+                        .try_filter(|line| future::ready(!line.trim().is_empty()))
+                        .and_then(|line: String| async move {
+                            serde_json::from_str(&line).map_err(Error::SerdeJsonError)
+                        });
+
+                Ok(value_stream)
+            }
+            .try_flatten_stream(),
+        )
+    }}
+
     api_doc! { Image => Build
     |
     #[cfg(feature = "par-compress")]
@@ -154,13 +277,17 @@ impl Images {
         &'docker self,
         opts: &ImageBuildOpts,
     ) -> impl Stream<Item = Result<models::ImageBuildChunk>> + Unpin + 'docker {
-        let ep = construct_ep("/build", opts.serialize());
+        let ep = Endpoint::new("/build").query(opts.serialize());
+        let version_result = self
+            .docker
+            .require_params(opts.params.keys(), ImageBuildOpts::PARAM_VERSIONS);
 
         let tar_result = tarball::dir_par(&opts.path);
 
         let docker = &self.docker;
         Box::pin(
             async move {
+                version_result?;
                 let bytes = tar_result?;
 
                 let value_stream =
@@ -172,6 +299,64 @@ impl Images {
         )
     }}
 
+    api_doc! { Image => Build
+    |
+    /// Builds a new image from a build context streamed as a tarball, which may be compressed
+    /// with gzip, bzip2 or xz. The chunks are sent to the daemon as they are produced so large
+    /// contexts are uploaded without being held in memory. An uncompressed context can be
+    /// compressed on the fly with
+    /// [`ImageBuildOptsBuilder::compress`](crate::opts::ImageBuildOptsBuilder::compress). The
+    /// `path` of `opts` is not used.
+    pub fn build_from_stream<'docker, S>(
+        &'docker self,
+        opts: &ImageBuildOpts,
+        context: S,
+    ) -> impl Stream<Item = Result<models::ImageBuildChunk>> + Unpin + 'docker
+    where
+        S: Stream<Item = Result<Bytes>> + Send + 'static,
+    {
+        let ep = Endpoint::new("/build").query(opts.serialize());
+        let version_result = self
+            .docker
+            .require_params(opts.params.keys(), ImageBuildOpts::PARAM_VERSIONS);
+
+        let (context, headers) = if opts.compress {
+            (
+                Body::wrap_stream(crate::stream::gzip(context)),
+                Some(Headers::single("Content-Encoding", "gzip")),
+            )
+        } else {
+            (Body::wrap_stream(context), Headers::none())
+        };
+
+        let docker = &self.docker;
+        Box::pin(
+            async move {
+                version_result?;
+
+                let value_stream = docker.post_into_stream(ep, Payload::Tar(context), headers);
+
+                Ok(value_stream)
+            }
+            .try_flatten_stream(),
+        )
+    }}
+
+    api_doc! { Image => Build
+    |
+    /// Same as [`Images::build_from_stream`](Images::build_from_stream) but reads the build
+    /// context from `reader`, like an opened tarball.
+    pub fn build_from_reader<'docker, R>(
+        &'docker self,
+        opts: &ImageBuildOpts,
+        reader: R,
+    ) -> impl Stream<Item = Result<models::ImageBuildChunk>> + Unpin + 'docker
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        self.build_from_stream(opts, crate::stream::read_chunks(reader))
+    }}
+
     api_doc! { Image => Search
     |
     /// Search for docker images by term.
@@ -180,10 +365,7 @@ impl Images {
         T: AsRef<str>,
     {
         self.docker
-            .get_json(&construct_ep(
-                "/images/search",
-                Some(encoded_pair("term", term.as_ref())),
-            ))
+            .get_json(&Endpoint::new("/images/search").param("term", term.as_ref()))
             .await
     }}
 
@@ -194,15 +376,87 @@ impl Images {
         &'docker self,
         opts: &PullOpts,
     ) -> impl Stream<Item = Result<models::ImageBuildChunk>> + Unpin + 'docker {
-        let headers = opts.auth_header().map(|a| Headers::single(AUTH_HEADER, a));
+        let ep = Endpoint::new("/images/create").query(opts.serialize());
+        let auth_result = opts.auth_header();
 
-        Box::pin(self.docker.post_into_stream(
-            construct_ep("/images/create", opts.serialize()),
-            Payload::empty(),
-            headers,
-        ))
+        let docker = &self.docker;
+        Box::pin(
+            async move {
+                let headers = auth_result?.map(|a| Headers::single(AUTH_HEADER, a));
+
+                Ok(docker.post_into_stream(ep, Payload::empty(), headers))
+            }
+            .try_flatten_stream(),
+        )
     }}
 
+    /// Pull several images concurrently, at most `concurrency` at a time, for example to
+    /// prepare the images of a multi-service environment.
+    ///
+    /// Every image is pulled with `opts`, like the registry auth or the platform, with the image
+    /// and tag replaced by the reference of the image.
+    ///
+    /// The progress of all pulls is merged into one stream, each chunk tagged with the reference
+    /// it belongs to as given in `refs`. The last chunk is [`PullManyChunk::Done`] with the
+    /// result of every pull. A pull fails with the first error of its stream or the first error
+    /// reported by the daemon, the other pulls continue.
+    pub fn pull_many<'docker, I>(
+        &'docker self,
+        refs: I,
+        opts: &PullOpts,
+        concurrency: usize,
+    ) -> impl Stream<Item = PullManyChunk> + Unpin + 'docker
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let refs = refs.into_iter().map(Into::into).collect::<Vec<String>>();
+        let results = refs
+            .iter()
+            .map(|image| (image.clone(), Ok(())))
+            .collect::<BTreeMap<_, Result<()>>>();
+
+        let opts = opts.clone();
+        let pulls = stream::iter(refs)
+            .map(move |image| {
+                let pull = match Reference::parse(&image) {
+                    Ok(reference) => {
+                        let opts = opts.with_reference(&reference);
+                        self.pull(&opts).left_stream()
+                    }
+                    Err(e) => stream::once(future::ready(Err(e))).right_stream(),
+                };
+                pull.map(move |chunk| (image.clone(), chunk))
+            })
+            .flatten_unordered(concurrency.max(1));
+
+        let fail = |results: &mut BTreeMap<String, Result<()>>, image: &str, e: Error| {
+            if let Some(result @ Ok(())) = results.get_mut(image) {
+                *result = Err(e);
+            }
+        };
+
+        Box::pin(stream::unfold(
+            Some((Box::pin(pulls), results)),
+            move |state| async move {
+                let (mut pulls, mut results) = state?;
+                loop {
+                    match pulls.next().await {
+                        Some((image, Ok(chunk))) => {
+                            if let models::ImageBuildChunk::Error { error, .. } = &chunk {
+                                fail(&mut results, &image, Error::StringError(error.clone()));
+                            }
+                            let chunk = PullManyChunk::Progress { image, chunk };
+                            return Some((chunk, Some((pulls, results))));
+                        }
+                        Some((image, Err(e))) => fail(&mut results, &image, e),
+                        None => return Some((PullManyChunk::Done(results), None)),
+                    }
+                }
+            },
+        ))
+    }
+
     api_doc! { Image => GetAll
     |
     /// Exports a collection of named images,
@@ -212,10 +466,11 @@ impl Images {
         names: Vec<&str>,
     ) -> impl Stream<Item = Result<Vec<u8>>> + 'docker {
         self.docker
-            .get_stream(format!(
-                "/images/get?{}",
-                encoded_pairs(names.iter().map(|n| ("names", *n)))
-            ))
+            .get_stream(
+                names
+                    .iter()
+                    .fold(Endpoint::new("/images/get"), |ep, name| ep.param("names", name)),
+            )
             .map_ok(|c| c.to_vec())
     }}
 
@@ -250,7 +505,7 @@ impl Images {
     api_doc! { Image => Push
     |
     /// Push an image to registry.
-    pub async fn push(&self, name: impl Into<crate::Id>, opts: &ImagePushOpts) -> Result<()> {
+    pub async fn push(&self, name: impl Into<crate::ImageId>, opts: &ImagePushOpts) -> Result<()> {
         let image = Image::new(self.docker.clone(), name);
         image.push(opts).await
     }}
@@ -264,10 +519,79 @@ impl Images {
     ) -> Result<models::BuildPrune200Response> {
         self.docker
             .post_json(
-                construct_ep("/build/prune", opts.serialize()),
+                Endpoint::new("/build/prune").query(opts.serialize()),
                 Payload::empty(),
                 Headers::none(),
             )
             .await
     }}
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::PullManyChunk;
+    use crate::conn::{Method, MockResponse, MockTransport};
+    use crate::{Docker, Error};
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn pull_many() {
+        let mock = MockTransport::new();
+        mock.on(
+            Method::POST,
+            "/images/create",
+            MockResponse::json_lines([serde_json::json!({ "status": "Pulling" })]),
+        );
+        let docker = Docker::from_mock(mock.clone());
+        let chunks = docker
+            .images()
+            .pull_many(
+                ["alpine", "Not A Reference", "busybox:1.36"],
+                &Default::default(),
+                2,
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut progress = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                PullManyChunk::Progress { image, .. } => Some(image.as_str()),
+                PullManyChunk::Done(_) => None,
+            })
+            .collect::<Vec<_>>();
+        progress.sort();
+        assert_eq!(progress, ["alpine", "busybox:1.36"]);
+        match chunks.last() {
+            Some(PullManyChunk::Done(results)) => {
+                assert_eq!(results.len(), 3);
+                assert!(results["alpine"].is_ok());
+                assert!(results["busybox:1.36"].is_ok());
+                assert!(matches!(
+                    results["Not A Reference"],
+                    Err(Error::InvalidReference(_))
+                ));
+            }
+            other => panic!("expected PullManyChunk::Done, got {other:?}"),
+        }
+
+        let mut pulled = mock
+            .requests()
+            .iter()
+            .map(|request| {
+                (
+                    request.query_param("fromImage").unwrap(),
+                    request.query_param("tag").unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        pulled.sort();
+        assert_eq!(
+            pulled,
+            [
+                ("docker.io/library/alpine".to_string(), "latest".to_string()),
+                ("docker.io/library/busybox".to_string(), "1.36".to_string())
+            ]
+        );
+    }
+}
@@ -15,12 +15,18 @@ pub mod node;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 pub mod plugin;
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+pub mod pod;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 pub mod secret;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 pub mod service;
+#[cfg(feature = "stack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stack")))]
+pub mod stack;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 pub mod swarm;
@@ -33,3 +39,11 @@ pub use {container::*, exec::*, image::*, network::*, volume::*};
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 pub use {config::*, node::*, plugin::*, secret::*, service::*, swarm::*, task::*};
+
+#[cfg(feature = "stack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stack")))]
+pub use stack::*;
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+pub use pod::*;
@@ -4,27 +4,40 @@ use crate::{
     conn::{Headers, Payload},
     models,
     opts::{
-        ContainerConnectionOpts, ContainerDisconnectionOpts, NetworkCreateOpts, NetworkListOpts,
-        NetworkPruneOpts,
+        ContainerConnectionOpts, ContainerDisconnectionOpts, NetworkCreateOpts, NetworkFilter,
+        NetworkInspectOpts, NetworkListOpts, NetworkPruneOpts,
     },
-    Result,
+    Error, Result,
 };
 
-impl_api_ty!(Network => id);
+use crate::endpoint::Endpoint;
+use serde_json::json;
+
+impl_api_ty!(Network => id: crate::NetworkId);
 
 impl Network {
     impl_api_ep! { net: Network, resp
-        Inspect -> &format!("/networks/{}", net.id), models::Network
-        Delete -> &format!("/networks/{}", net.id), ()
+        Inspect -> Endpoint::new("/networks").segment(&net.id), models::Network
+        Delete -> Endpoint::new("/networks").segment(&net.id), ()
     }
 
+    api_doc! { Network => Inspect
+    |
+    /// Inspect this network with options. The returned details include the peers of the network
+    /// and, with [`verbose`](crate::opts::NetworkInspectOptsBuilder::verbose) enabled, the swarm
+    /// services attached to it.
+    pub async fn inspect_details(&self, opts: &NetworkInspectOpts) -> Result<models::NetworkDetails> {
+        let ep = Endpoint::new("/networks").segment(&self.id).query(opts.serialize());
+        self.docker.get_json(&ep).await
+    }}
+
     api_doc! { Network => Connect
     |
     /// Connect a container to a network.
     pub async fn connect(&self, opts: &ContainerConnectionOpts) -> Result<()> {
         self.docker
             .post_string(
-                &format!("/networks/{}/connect", self.id),
+                &Endpoint::new("/networks").segment(&self.id).path("connect"),
                 Payload::Json(opts.serialize_vec()?),
                 Headers::none(),
             )
@@ -38,13 +51,48 @@ impl Network {
     pub async fn disconnect(&self, opts: &ContainerDisconnectionOpts) -> Result<()> {
         self.docker
             .post_string(
-                &format!("/networks/{}/disconnect", &self.id),
+                &Endpoint::new("/networks").segment(&self.id).path("disconnect"),
                 Payload::Json(opts.serialize_vec()?),
                 Headers::none(),
             )
             .await
             .map(|_| ())
     }}
+
+    api_doc! { Network => Connect
+    |
+    /// Connect a container to a network with the provided endpoint settings, for example to
+    /// assign aliases or static IP addresses at attach time.
+    ///
+    /// Use [`connect`](Network::connect) to configure the endpoint with a builder instead.
+    pub async fn connect_container(
+        &self,
+        container: impl AsRef<str>,
+        settings: &models::EndpointSettings,
+    ) -> Result<()> {
+        let body = serde_json::to_vec(&json!({
+            "Container": container.as_ref(),
+            "EndpointConfig": settings,
+        }))?;
+        self.docker
+            .post_string(
+                &Endpoint::new("/networks").segment(&self.id).path("connect"),
+                Payload::Json(body),
+                Headers::none(),
+            )
+            .await
+            .map(|_| ())
+    }}
+
+    api_doc! { Network => Disconnect
+    |
+    /// Disconnect a container from a network, optionally forcing the disconnection.
+    pub async fn disconnect_container(&self, container: impl AsRef<str>, force: bool) -> Result<()> {
+        let opts = ContainerDisconnectionOpts::builder(container)
+            .force(force)
+            .build();
+        self.disconnect(&opts).await
+    }}
 }
 
 impl Networks {
@@ -53,6 +101,25 @@ impl Networks {
         Prune -> "/networks/prune", models::NetworkPrune200Response
     }
 
+    /// Find a network by its exact name.
+    ///
+    /// The name filter of the Docker API matches substrings, so listing networks by name may
+    /// return other networks as well. This only returns a network whose name is equal to `name`
+    /// and fails with [`Error::NotFound`](crate::Error::NotFound) otherwise.
+    pub async fn get_by_name(&self, name: impl AsRef<str>) -> Result<Network> {
+        let name = name.as_ref();
+        let opts = NetworkListOpts::builder()
+            .filter([NetworkFilter::Name(name.to_string())])
+            .build();
+        self.list(&opts)
+            .await?
+            .into_iter()
+            .find(|network| network.name.as_deref() == Some(name))
+            .and_then(|network| network.id)
+            .map(|id| Network::new(self.docker.clone(), id))
+            .ok_or_else(|| Error::not_found(format!("network `{name}`")))
+    }
+
     api_doc! { Network => Create
     |
     /// Create a new network.
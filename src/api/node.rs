@@ -3,41 +3,103 @@
 //! Nodes are instances of the Engine participating in a swarm.
 //! Swarm mode must be enabled for these endpoints to work.
 
+use crate::endpoint::Endpoint;
 use crate::{
     conn::{Headers, Payload},
-    models,
+    models::{self, NodeSpecAvailabilityInlineItem},
     opts::{NodeListOpts, NodeUpdateOpts},
-    Result,
+    Error, Result,
 };
-use containers_api::url::encoded_pair;
 
 impl_api_ty!(Node => name);
 
-type Void = ();
-
 impl Node {
     impl_api_ep! {node: Node, resp
-        Inspect -> &format!("/nodes/{}", node.name), models::Node
-        ForceDelete -> &format!("/nodes/{}", node.name), Void
+        Inspect -> Endpoint::new("/nodes").segment(&node.name), models::Node
+    }
+
+    async fn _delete(&self, force: bool) -> Result<()> {
+        let ep = Endpoint::new("/nodes")
+            .segment(&self.name)
+            .param_opt("force", force.then_some(true));
+        self.docker.delete(&ep).await.map(|_| ())
     }
 
+    api_doc! { Node => Delete
+    |
+    /// Delete this Node.
+    pub async fn delete(&self) -> Result<()> {
+        self._delete(false).await
+    }}
+
+    api_doc! { Node => Delete
+    |
+    /// Delete this Node even if it is still part of the swarm and reachable.
+    pub async fn force_delete(&self) -> Result<()> {
+        self._delete(true).await
+    }}
+
     api_doc! { Node => Update
     |
     /// Update a node.
     pub async fn update(&self, opts: &NodeUpdateOpts) -> Result<()> {
         self.docker
             .post(
-                &format!(
-                    "/nodes/{}/update?{}",
-                    self.name,
-                    encoded_pair("version", opts.version().to_string())
-                ),
+                &Endpoint::new("/nodes")
+                    .segment(&self.name)
+                    .path("update")
+                    .param("version", opts.version()),
                 Payload::Json(opts.serialize_vec()?),
                 Headers::none()
             )
             .await
             .map(|_| ())
     }}
+
+    /// Drain this node so that no new tasks are scheduled on it and its running tasks are moved
+    /// to other nodes.
+    pub async fn drain(&self) -> Result<()> {
+        self.set_availability(NodeSpecAvailabilityInlineItem::Drain)
+            .await
+    }
+
+    /// Make this node available for scheduling tasks again.
+    pub async fn activate(&self) -> Result<()> {
+        self.set_availability(NodeSpecAvailabilityInlineItem::Active)
+            .await
+    }
+
+    /// Updates the availability of this node keeping the rest of its current spec. The update is
+    /// retried once with a freshly fetched version if the node was modified concurrently.
+    async fn set_availability(&self, availability: NodeSpecAvailabilityInlineItem) -> Result<()> {
+        let mut retried = false;
+        loop {
+            let node = self.inspect().await?;
+            let version = node
+                .version
+                .and_then(|v| v.index)
+                .ok_or_else(|| Error::InvalidResponse("expected node version".into()))?;
+            let mut opts = NodeUpdateOpts::builder(version);
+            if let Some(spec) = &node.spec {
+                opts = opts.spec(spec);
+            }
+            let opts = opts.availability(availability.clone()).build();
+
+            match self.update(&opts).await {
+                Err(e) if !retried && is_version_conflict(&e) => retried = true,
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Returns `true` if the error was caused by updating an object with an outdated version.
+fn is_version_conflict(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::ServerError { message, .. } | Error::Conflict { message, .. } | Error::Fault { message, .. }
+            if message.contains("update out of sequence")
+    )
 }
 
 impl Nodes {
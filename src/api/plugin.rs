@@ -1,12 +1,12 @@
 //! Install, create and manage plugins
 
+use crate::endpoint::Endpoint;
 use crate::{
-    conn::{Headers, Payload},
+    conn::{Headers, Payload, AUTH_HEADER},
     models,
-    opts::PluginListOpts,
-    Result,
+    opts::{PluginInstallOpts, PluginListOpts, RegistryAuth},
+    Error, Result,
 };
-use containers_api::url::{construct_ep, encoded_pair};
 
 use std::path::Path;
 
@@ -14,18 +14,21 @@ impl_api_ty!(Plugin => name);
 
 impl Plugin {
     impl_api_ep! {plug: Plugin, resp
-        Inspect -> &format!("/plugins/{}/json", plug.name), models::Plugin
-        ForceDelete -> &format!("/plugins/{}", plug.name), models::Plugin
+        Inspect -> Endpoint::new("/plugins").segment(&plug.name).path("json"), models::Plugin
+        ForceDelete -> Endpoint::new("/plugins").segment(&plug.name), models::Plugin
     }
 
     api_doc! { Plugin => Enable
     |
     /// Enable a plugin.
     pub async fn enable(&self, timeout: Option<u64>) -> Result<()> {
-        let query = timeout.map(|timeout| encoded_pair("timeout", timeout));
+        let ep = Endpoint::new("/plugins")
+            .segment(&self.name)
+            .path("enable")
+            .param_opt("timeout", timeout);
         self.docker
             .post(
-                &construct_ep(format!("/plugins/{}/enable", self.name), query),
+                &ep,
                 Payload::empty(),
                 Headers::none()
             )
@@ -38,7 +41,7 @@ impl Plugin {
     /// Disable a plugin.
     pub async fn disable(&self) -> Result<()> {
         self.docker
-            .post(&format!("/plugins/{}/disable", self.name), Payload::empty(), Headers::none())
+            .post(&Endpoint::new("/plugins").segment(&self.name).path("disable"), Payload::empty(), Headers::none())
             .await
             .map(|_| ())
     }}
@@ -48,7 +51,7 @@ impl Plugin {
     /// Push a plugin to the registry.
     pub async fn push(&self) -> Result<()> {
         self.docker
-            .post(&format!("/plugins/{}/push", self.name), Payload::empty(), Headers::none())
+            .post(&Endpoint::new("/plugins").segment(&self.name).path("push"), Payload::empty(), Headers::none())
             .await
             .map(|_| ())
     }}
@@ -63,7 +66,7 @@ impl Plugin {
     {
         self.docker
             .post(
-                &format!("/plugins/{}/create", self.name),
+                &Endpoint::new("/plugins").segment(&self.name).path("create"),
                 Payload::Text(path.as_ref().to_string_lossy().to_string()),
                 Headers::none()
             )
@@ -76,4 +79,61 @@ impl Plugins {
     impl_api_ep! {plug: Plugin, resp
         List -> "/plugins", models::Plugin
     }
+
+    api_doc! { Get => PluginPrivileges
+    |
+    /// Get the privileges that the plugin `remote` requires to be granted before installation.
+    pub async fn privileges(
+        &self,
+        remote: impl AsRef<str>,
+        auth: Option<RegistryAuth>,
+    ) -> Result<Vec<models::PluginPrivilege>> {
+        let headers = auth
+            .map(|a| a.serialize())
+            .transpose()?
+            .map(|a| Headers::single(AUTH_HEADER, a));
+        self.docker
+            .get_json_with_headers(
+                &Endpoint::new("/plugins/privileges").param("remote", remote.as_ref()),
+                headers,
+            )
+            .await
+    }}
+
+    api_doc! { Plugin => Pull
+    |
+    /// Pull and install a plugin, granting it `privileges`. The plugin is disabled after
+    /// installation. Use [`install_with_grant`](Plugins::install_with_grant) to review the required
+    /// privileges first.
+    pub async fn install(
+        &self,
+        opts: &PluginInstallOpts,
+        privileges: &[models::PluginPrivilege],
+    ) -> Result<Plugin> {
+        let headers = opts
+            .auth_header()?
+            .map(|a| Headers::single(AUTH_HEADER, a));
+        self.docker
+            .post_string(
+                &Endpoint::new("/plugins/pull").query(opts.serialize()),
+                Payload::Json(serde_json::to_vec(privileges)?),
+                headers,
+            )
+            .await?;
+        Ok(Plugin::new(self.docker.clone(), opts.name()))
+    }}
+
+    /// Fetches the privileges required by the plugin and passes them to `grant`. The plugin is
+    /// installed with these privileges only if `grant` returns `true`, otherwise
+    /// [`Error::PrivilegesNotGranted`](Error::PrivilegesNotGranted) is returned.
+    pub async fn install_with_grant<F>(&self, opts: &PluginInstallOpts, grant: F) -> Result<Plugin>
+    where
+        F: FnOnce(&[models::PluginPrivilege]) -> bool,
+    {
+        let privileges = self.privileges(opts.remote(), opts.auth()).await?;
+        if !grant(&privileges) {
+            return Err(Error::PrivilegesNotGranted(opts.remote().to_string()));
+        }
+        self.install(opts, &privileges).await
+    }
 }
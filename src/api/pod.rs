@@ -0,0 +1,161 @@
+//! Pods are groups of containers sharing namespaces, managed through the libpod API of podman.
+//! These endpoints don't exist in Docker, the client has to be connected to a podman socket.
+
+use crate::{
+    conn::{Headers, Payload},
+    endpoint::Endpoint,
+    models,
+    opts::{PodCreateOpts, PodListOpts, PodRemoveOpts, Signal},
+    Docker, Id, Result,
+};
+
+/// Interface for accessing and manipulating a podman pod.
+///
+/// [Api Reference](https://docs.podman.io/en/latest/_static/api.html#tag/pods)
+#[derive(Debug, Clone)]
+pub struct Pod {
+    docker: Docker,
+    id: Id,
+}
+
+impl Pod {
+    /// Exports an interface exposing operations against a pod instance.
+    pub fn new(docker: Docker, id: impl Into<Id>) -> Self {
+        Pod {
+            docker,
+            id: id.into(),
+        }
+    }
+
+    /// A getter for Pod id
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    /// Inspects the pod.
+    pub async fn inspect(&self) -> Result<models::PodInspect> {
+        self.docker
+            .get_json(&Endpoint::new("/libpod/pods").segment(&self.id).path("json"))
+            .await
+    }
+
+    /// Returns whether the pod exists.
+    pub async fn exists(&self) -> Result<bool> {
+        match self
+            .docker
+            .get(
+                &Endpoint::new("/libpod/pods")
+                    .segment(&self.id)
+                    .path("exists"),
+            )
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Starts all containers of the pod.
+    pub async fn start(&self) -> Result<models::PodActionReport> {
+        self.action("start").await
+    }
+
+    /// Stops all containers of the pod.
+    pub async fn stop(&self) -> Result<models::PodActionReport> {
+        self.action("stop").await
+    }
+
+    /// Restarts all containers of the pod.
+    pub async fn restart(&self) -> Result<models::PodActionReport> {
+        self.action("restart").await
+    }
+
+    /// Pauses all containers of the pod.
+    pub async fn pause(&self) -> Result<models::PodActionReport> {
+        self.action("pause").await
+    }
+
+    /// Unpauses all containers of the pod.
+    pub async fn unpause(&self) -> Result<models::PodActionReport> {
+        self.action("unpause").await
+    }
+
+    /// Sends `signal`, `SIGKILL` by default, to all containers of the pod.
+    pub async fn kill(&self, signal: Option<Signal>) -> Result<models::PodActionReport> {
+        let ep = Endpoint::new("/libpod/pods")
+            .segment(&self.id)
+            .path("kill")
+            .param_opt("signal", signal);
+        self.docker
+            .post_json(&ep, Payload::empty(), Headers::none())
+            .await
+    }
+
+    async fn action(&self, action: &str) -> Result<models::PodActionReport> {
+        self.docker
+            .post_json(
+                &Endpoint::new("/libpod/pods").segment(&self.id).path(action),
+                Payload::empty(),
+                Headers::none(),
+            )
+            .await
+    }
+
+    /// Removes the pod, `force` in the options also stops and removes its running containers.
+    pub async fn remove(&self, opts: &PodRemoveOpts) -> Result<models::PodRemoveReport> {
+        self.docker
+            .delete_json(
+                &Endpoint::new("/libpod/pods")
+                    .segment(&self.id)
+                    .query(opts.serialize()),
+            )
+            .await
+    }
+}
+
+/// Interface for podman pods.
+///
+/// [Api Reference](https://docs.podman.io/en/latest/_static/api.html#tag/pods)
+#[derive(Debug, Clone)]
+pub struct Pods {
+    docker: Docker,
+}
+
+impl Pods {
+    /// Exports an interface for interacting with podman pods.
+    pub fn new(docker: Docker) -> Self {
+        Pods { docker }
+    }
+
+    /// Returns a reference to a set of operations available to a specific pod.
+    pub fn get(&self, id: impl Into<Id>) -> Pod {
+        Pod::new(self.docker.clone(), id)
+    }
+
+    /// Lists pods.
+    pub async fn list(&self, opts: &PodListOpts) -> Result<Vec<models::PodListEntry>> {
+        self.docker
+            .get_json(&Endpoint::new("/libpod/pods/json").query(opts.serialize()))
+            .await
+    }
+
+    /// Creates a new pod.
+    pub async fn create(&self, opts: &PodCreateOpts) -> Result<Pod> {
+        self.docker
+            .post_json(
+                "/libpod/pods/create",
+                Payload::Json(opts.serialize_vec()?),
+                Headers::none(),
+            )
+            .await
+            .map(|resp: models::IdResponse| Pod::new(self.docker.clone(), resp.id))
+    }
+
+    /// Removes all stopped pods.
+    pub async fn prune(&self) -> Result<Vec<models::PodRemoveReport>> {
+        self.docker
+            .post_json("/libpod/pods/prune", Payload::empty(), Headers::none())
+            .await
+    }
+}
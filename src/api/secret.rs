@@ -2,6 +2,7 @@
 
 use crate::{
     conn::{Headers, Payload},
+    endpoint::Endpoint,
     models,
     opts::{SecretCreateOpts, SecretListOpts},
     Result,
@@ -11,8 +12,8 @@ impl_api_ty!(Secret => name);
 
 impl Secret {
     impl_api_ep! { secret: Secret, resp
-        Inspect -> &format!("/secrets/{}", secret.name), models::Secret
-        Delete -> &format!("/secrets/{}", secret.name), ()
+        Inspect -> Endpoint::new("/secrets").segment(&secret.name), models::Secret
+        Delete -> Endpoint::new("/secrets").segment(&secret.name), ()
     }
     // TODO: add Secret::update
 }
@@ -26,8 +27,8 @@ impl Secrets {
     |
     /// Create a new secret.
     pub async fn create(&self, opts: &SecretCreateOpts) -> Result<Secret> {
-        use serde::Deserialize;
-        #[derive(Deserialize)]
+        use serde::{Deserialize, Serialize};
+        #[derive(Deserialize, Serialize)]
         struct SecretCreateResponse {
             #[serde(rename = "Id")]
             pub id: String,
@@ -2,33 +2,235 @@
 use crate::{
     conn::{Headers, Payload, AUTH_HEADER},
     models,
-    opts::{ServiceListOpts, ServiceOpts},
-    Result,
+    opts::{
+        NodeListOpts, ServiceInspectOpts, ServiceListOpts, ServiceOpts, ServiceUpdateOpts,
+        TaskFilter, TaskListOpts,
+    },
+    Error, Result,
 };
 
+use crate::endpoint::Endpoint;
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+/// Interval between checks of [`Service::wait_converged`](Service::wait_converged).
+const CONVERGENCE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 impl_api_ty!(Service => name);
 
 impl Service {
     api_doc! { Service => Create
     |
     /// Creates a new service from ServiceOpts.
+    #[deprecated(note = "use `Services::create`")]
     pub async fn create(&self, opts: &ServiceOpts) -> Result<models::ServiceCreate201Response> {
-        let headers = opts
-            .auth_header()
-            .map(|a| Headers::single(AUTH_HEADER, a));
+        Services::new(self.docker.clone()).create(opts).await
+    }}
+
+    api_doc! { Service => Inspect
+    |
+    /// Inspect this service with options. Next to the current state of the service the returned
+    /// details include the spec it had before the last update.
+    pub async fn inspect_details(&self, opts: &ServiceInspectOpts) -> Result<models::ServiceDetails> {
+        let ep = Endpoint::new("/services")
+            .segment(&self.name)
+            .query(opts.serialize());
+        self.docker.get_json(&ep).await
+    }}
+
+    async fn _update(
+        &self,
+        body: Vec<u8>,
+        auth: Option<String>,
+        version: u64,
+        opts: &ServiceUpdateOpts,
+    ) -> Result<models::ServiceUpdateResponse> {
+        let ep = Endpoint::new("/services")
+            .segment(&self.name)
+            .path("update")
+            .param("version", version)
+            .query(opts.serialize());
+        let headers = auth.map(|a| Headers::single(AUTH_HEADER, a));
         self.docker
-            .post_json(
-                "/services/create",
-                Payload::Json(opts.serialize_vec()?),
-                headers,
-            )
+            .post_json(ep, Payload::Json(body), headers)
+            .await
+    }
+
+    api_doc! { Service => Update
+    |
+    /// Update this service with a new `spec`. `version` is the current version of the service
+    /// object as returned by [`Service::inspect`](Service::inspect), used to avoid conflicting
+    /// writes. Returns any warnings reported by the daemon.
+    pub async fn update(
+        &self,
+        spec: &ServiceOpts,
+        version: u64,
+        opts: &ServiceUpdateOpts,
+    ) -> Result<models::ServiceUpdateResponse> {
+        self._update(spec.serialize_vec()?, spec.auth_header()?, version, opts)
             .await
     }}
 
+    api_doc! { Service => Update
+    |
+    /// Roll this service back to the spec it had before the last update.
+    pub async fn rollback(&self) -> Result<models::ServiceUpdateResponse> {
+        let service = self.inspect().await?;
+        let version = service
+            .version
+            .and_then(|v| v.index)
+            .ok_or_else(|| Error::InvalidResponse("expected service version".into()))?;
+        let spec = service
+            .spec
+            .ok_or_else(|| Error::InvalidResponse("expected service spec".into()))?;
+        let opts = ServiceUpdateOpts::builder().rollback(true).build();
+        self._update(serde_json::to_vec(&spec)?, None, version, &opts)
+            .await
+    }}
+
+    /// Lists the tasks of this service together with the hostname of the node they are assigned
+    /// to, equivalent to `docker service ps`. Tasks are sorted by name and the most recent task of
+    /// a slot comes first.
+    pub async fn ps(&self) -> Result<Vec<models::ServiceTaskInfo>> {
+        let service = self.inspect().await?;
+        let service_id = service.id.unwrap_or_else(|| self.name.to_string());
+        let service_name = service
+            .spec
+            .and_then(|spec| spec.name)
+            .unwrap_or_else(|| service_id.clone());
+
+        let opts = TaskListOpts::builder()
+            .filter([TaskFilter::Service(service_id)])
+            .build();
+        let tasks = crate::Tasks::new(self.docker.clone()).list(&opts).await?;
+        let hostnames: HashMap<_, _> = crate::Nodes::new(self.docker.clone())
+            .list(&NodeListOpts::default())
+            .await?
+            .into_iter()
+            .filter_map(|node| Some((node.id?, node.description?.hostname?)))
+            .collect();
+
+        let mut tasks: Vec<_> = tasks
+            .into_iter()
+            .map(|task| {
+                let suffix = match (task.slot, &task.node_id) {
+                    (Some(slot), _) if slot > 0 => slot.to_string(),
+                    (_, Some(node_id)) => node_id.clone(),
+                    _ => task.id.clone().unwrap_or_default(),
+                };
+                let info = models::ServiceTaskInfo {
+                    task_id: task.id.clone().unwrap_or_default(),
+                    name: format!("{service_name}.{suffix}"),
+                    image: task
+                        .spec
+                        .as_ref()
+                        .and_then(|spec| spec.container_spec.as_ref())
+                        .and_then(|container| container.image.clone()),
+                    node: task
+                        .node_id
+                        .as_ref()
+                        .and_then(|id| hostnames.get(id).cloned()),
+                    node_id: task.node_id.clone(),
+                    status: models::TaskStatus::from(&task),
+                };
+                (info, task.created_at)
+            })
+            .collect();
+        tasks.sort_by(|(a, a_created), (b, b_created)| {
+            a.name.cmp(&b.name).then(b_created.cmp(a_created))
+        });
+
+        Ok(tasks.into_iter().map(|(info, _)| info).collect())
+    }
+
+    /// Waits until the desired number of tasks of this service is running the current spec of the
+    /// service and no update or rollback is in progress, checking the state of the tasks every
+    /// second.
+    ///
+    /// Returns [`Error::NotConverged`](Error::NotConverged) with the number of running tasks and
+    /// the errors reported by failed tasks if the service didn't converge within `timeout` or if
+    /// its update was paused.
+    pub async fn wait_converged(&self, timeout: Duration) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            let service = self.inspect().await?;
+            let update_state = service
+                .update_status
+                .as_ref()
+                .and_then(|status| status.state.clone());
+            if let Some(state @ ("paused" | "rollback_paused")) = update_state.as_deref() {
+                let message = service
+                    .update_status
+                    .and_then(|status| status.message)
+                    .unwrap_or_default();
+                return Err(Error::NotConverged(format!("update {state}: {message}")));
+            }
+            let updating = matches!(
+                update_state.as_deref(),
+                Some("updating" | "rollback_started")
+            );
+
+            let opts = TaskListOpts::builder()
+                .filter([TaskFilter::Service(
+                    service.id.clone().unwrap_or_else(|| self.name.to_string()),
+                )])
+                .build();
+            let tasks = crate::Tasks::new(self.docker.clone()).list(&opts).await?;
+            let statuses: Vec<_> = tasks.iter().map(models::TaskStatus::from).collect();
+            let template = service
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.task_template.as_ref());
+
+            let scheduled = statuses
+                .iter()
+                .filter(|status| status.desired_state == Some(models::TaskState::Running))
+                .count();
+            // tasks still running a previous spec are being replaced
+            let running = tasks
+                .iter()
+                .zip(&statuses)
+                .filter(|(task, status)| status.is_running() && task.spec.as_ref() == template)
+                .count();
+            let desired = service
+                .spec
+                .and_then(|spec| spec.mode)
+                .and_then(|mode| mode.replicated)
+                .and_then(|replicated| replicated.replicas)
+                .map(|replicas| replicas as usize)
+                .unwrap_or(scheduled);
+
+            if !updating && running == desired && scheduled == desired {
+                return Ok(());
+            }
+
+            if start.elapsed() >= timeout {
+                let errors: BTreeSet<_> = statuses
+                    .iter()
+                    .filter(|status| {
+                        matches!(
+                            status.state,
+                            Some(models::TaskState::Failed | models::TaskState::Rejected)
+                        )
+                    })
+                    .filter_map(|status| status.error.as_deref())
+                    .collect();
+                let mut message = format!("{running}/{desired} tasks running");
+                if !errors.is_empty() {
+                    message.push_str(" - ");
+                    message.push_str(&errors.into_iter().collect::<Vec<_>>().join(", "));
+                }
+                return Err(Error::NotConverged(message));
+            }
+
+            self.docker.runtime().sleep(CONVERGENCE_POLL_INTERVAL).await;
+        }
+    }
+
     impl_api_ep! { svc: Service, resp
-        Inspect -> &format!("/services/{}", svc.name), models::Service
-        Delete -> &format!("/services/{}", svc.name), models::ServiceUpdateResponse
-        Logs -> &format!("/services/{}/logs", svc.name), ()
+        Inspect -> Endpoint::new("/services").segment(&svc.name), models::Service
+        Delete -> Endpoint::new("/services").segment(&svc.name), models::ServiceUpdateResponse
+        Logs -> Endpoint::new("/services").segment(&svc.name).path("logs"), ()
     }
 }
 
@@ -36,4 +238,21 @@ impl Services {
     impl_api_ep! { svc: Service, resp
         List -> "/services", models::Service
     }
+
+    api_doc! { Service => Create
+    |
+    /// Creates a new service from ServiceOpts. Returns the ID of the created service and any
+    /// warnings reported by the daemon.
+    pub async fn create(&self, opts: &ServiceOpts) -> Result<models::ServiceCreate201Response> {
+        let headers = opts
+            .auth_header()?
+            .map(|a| Headers::single(AUTH_HEADER, a));
+        self.docker
+            .post_json(
+                "/services/create",
+                Payload::Json(opts.serialize_vec()?),
+                headers,
+            )
+            .await
+    }}
 }
@@ -0,0 +1,286 @@
+//! Deploy and manage stacks of services described by a docker-compose file, similar to
+//! `docker stack deploy`. Swarm mode must be enabled for these endpoints to work.
+use crate::{
+    models,
+    opts::{
+        ComposeFile, ComposeFileObject, ConfigCreateOpts, ConfigFilter, ConfigListOpts,
+        NetworkCreateOpts, NetworkFilter, NetworkListOpts, ObjectIds, SecretCreateOpts,
+        SecretFilter, SecretListOpts, ServiceFilter, ServiceListOpts, ServiceUpdateOpts,
+        STACK_NAMESPACE_LABEL,
+    },
+    Docker, Error, Network, Result, Service,
+};
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Interface for deploying and managing a stack of services.
+///
+/// All objects belonging to the stack are prefixed with its namespace and labeled with
+/// `com.docker.stack.namespace`.
+#[derive(Debug, Clone)]
+pub struct Stack {
+    docker: Docker,
+    namespace: String,
+}
+
+impl Stack {
+    /// Exports an interface exposing operations against the stack `namespace`.
+    pub fn new(docker: Docker, namespace: impl Into<String>) -> Self {
+        Stack {
+            docker,
+            namespace: namespace.into(),
+        }
+    }
+
+    /// The namespace of this stack.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn scoped(&self, name: &str) -> String {
+        format!("{}_{name}", self.namespace)
+    }
+
+    /// Creates or updates the networks, secrets, configs and services described by `compose`.
+    ///
+    /// Objects that already exist are reused, secrets and configs are immutable so their data is
+    /// not updated. Services that exist are updated with the new spec. Returns the services of
+    /// the stack in the order they appear in the compose file.
+    pub async fn deploy(&self, compose: &ComposeFile) -> Result<Vec<Service>> {
+        let networks = self.deploy_networks(compose).await?;
+        let secrets = self.deploy_secrets(&compose.secrets).await?;
+        let configs = self.deploy_configs(&compose.configs).await?;
+
+        let existing: HashMap<_, _> = self
+            .services()
+            .await?
+            .into_iter()
+            .filter_map(|service| {
+                let name = service.spec?.name?;
+                Some((name, (service.id?, service.version.and_then(|v| v.index))))
+            })
+            .collect();
+
+        let services = self.docker.services();
+        let mut deployed = Vec::with_capacity(compose.services.len());
+        for (name, service) in &compose.services {
+            let opts =
+                service.to_service_opts(&self.namespace, name, &networks, &secrets, &configs)?;
+            let scoped = self.scoped(name);
+            let id = match existing.get(&scoped) {
+                Some((id, version)) => {
+                    let version = version
+                        .ok_or_else(|| Error::InvalidResponse("expected service version".into()))?;
+                    services
+                        .get(id.as_str())
+                        .update(&opts, version, &ServiceUpdateOpts::default())
+                        .await?;
+                    id.clone()
+                }
+                None => services
+                    .create(&opts)
+                    .await?
+                    .id
+                    .ok_or_else(|| Error::InvalidResponse("expected service id".into()))?,
+            };
+            deployed.push(services.get(id));
+        }
+        Ok(deployed)
+    }
+
+    /// Creates missing networks and returns the names of all networks keyed by their name in
+    /// the compose file.
+    async fn deploy_networks(&self, compose: &ComposeFile) -> Result<BTreeMap<String, String>> {
+        let mut networks = compose.networks.clone();
+        networks.entry("default".into()).or_default();
+
+        let mut names = BTreeMap::new();
+        for (key, network) in networks {
+            let network = network.unwrap_or_default();
+            if network.external {
+                let name = network.name.clone().unwrap_or_else(|| key.clone());
+                self.docker.networks().get_by_name(&name).await?;
+                names.insert(key, name);
+                continue;
+            }
+            let name = network.name.clone().unwrap_or_else(|| self.scoped(&key));
+            match self.docker.networks().get_by_name(&name).await {
+                Ok(_) => {}
+                Err(e) if e.is_not_found() => {
+                    let mut labels = network.labels.to_map();
+                    labels.insert(STACK_NAMESPACE_LABEL.into(), self.namespace.clone());
+                    let mut opts = NetworkCreateOpts::builder(name.as_str())
+                        .driver(network.driver.as_deref().unwrap_or("overlay"))
+                        .labels(labels)
+                        .options(network.driver_opts.clone());
+                    if let Some(attachable) = network.attachable {
+                        opts = opts.attachable(attachable);
+                    }
+                    self.docker.networks().create(&opts.build_owned()).await?;
+                }
+                Err(e) => return Err(e),
+            }
+            names.insert(key, name);
+        }
+        Ok(names)
+    }
+
+    async fn deploy_secrets(
+        &self,
+        secrets: &BTreeMap<String, ComposeFileObject>,
+    ) -> Result<ObjectIds> {
+        let mut ids = ObjectIds::new();
+        for (key, secret) in secrets {
+            let name = self.object_name(key, secret);
+            let opts = SecretListOpts::builder()
+                .filter([SecretFilter::Name(name.clone())])
+                .build();
+            let found = self
+                .docker
+                .secrets()
+                .list(&opts)
+                .await?
+                .into_iter()
+                .find(|s| s.spec.as_ref().and_then(|spec| spec.name.as_deref()) == Some(&name))
+                .and_then(|s| s.id);
+            let id = match found {
+                Some(id) => id,
+                None if secret.external => {
+                    return Err(Error::not_found(format!("secret `{name}`")))
+                }
+                None => {
+                    let file = object_file(key, secret)?;
+                    let mut opts = SecretCreateOpts::from_file(name.as_str(), file)?
+                        .add_label(STACK_NAMESPACE_LABEL, self.namespace.as_str());
+                    for (k, v) in secret.labels.to_map() {
+                        opts = opts.add_label(k, v);
+                    }
+                    self.docker
+                        .secrets()
+                        .create(&opts)
+                        .await?
+                        .name()
+                        .to_string()
+                }
+            };
+            ids.insert(key.clone(), (id, name));
+        }
+        Ok(ids)
+    }
+
+    async fn deploy_configs(
+        &self,
+        configs: &BTreeMap<String, ComposeFileObject>,
+    ) -> Result<ObjectIds> {
+        let mut ids = ObjectIds::new();
+        for (key, config) in configs {
+            let name = self.object_name(key, config);
+            let opts = ConfigListOpts::builder()
+                .filter([ConfigFilter::Name(name.clone())])
+                .build();
+            let found = self
+                .docker
+                .configs()
+                .list(&opts)
+                .await?
+                .into_iter()
+                .find(|c| c.spec.as_ref().and_then(|spec| spec.name.as_deref()) == Some(&name))
+                .and_then(|c| c.id);
+            let id = match found {
+                Some(id) => id,
+                None if config.external => {
+                    return Err(Error::not_found(format!("config `{name}`")))
+                }
+                None => {
+                    let file = object_file(key, config)?;
+                    let mut opts = ConfigCreateOpts::from_file(name.as_str(), file)?
+                        .add_label(STACK_NAMESPACE_LABEL, self.namespace.as_str());
+                    for (k, v) in config.labels.to_map() {
+                        opts = opts.add_label(k, v);
+                    }
+                    self.docker
+                        .configs()
+                        .create(&opts)
+                        .await?
+                        .name()
+                        .to_string()
+                }
+            };
+            ids.insert(key.clone(), (id, name));
+        }
+        Ok(ids)
+    }
+
+    fn object_name(&self, key: &str, object: &ComposeFileObject) -> String {
+        match (&object.name, object.external) {
+            (Some(name), _) => name.clone(),
+            (None, true) => key.to_string(),
+            (None, false) => self.scoped(key),
+        }
+    }
+
+    /// Lists the services of this stack.
+    pub async fn services(&self) -> Result<Vec<models::Service>> {
+        let opts = ServiceListOpts::builder()
+            .filter([ServiceFilter::LabelKeyVal(
+                STACK_NAMESPACE_LABEL.into(),
+                self.namespace.clone(),
+            )])
+            .build();
+        self.docker.services().list(&opts).await
+    }
+
+    /// Removes the services, networks, secrets and configs of this stack, like
+    /// `docker stack rm`.
+    pub async fn remove(&self) -> Result<()> {
+        for service in self.services().await? {
+            if let Some(id) = service.id {
+                self.docker.services().get(id).delete().await?;
+            }
+        }
+
+        let opts = SecretListOpts::builder()
+            .filter([SecretFilter::Label(
+                STACK_NAMESPACE_LABEL.into(),
+                self.namespace.clone(),
+            )])
+            .build();
+        for secret in self.docker.secrets().list(&opts).await? {
+            if let Some(id) = secret.id {
+                self.docker.secrets().get(id).delete().await?;
+            }
+        }
+
+        let opts = ConfigListOpts::builder()
+            .filter([ConfigFilter::Label(
+                STACK_NAMESPACE_LABEL.into(),
+                self.namespace.clone(),
+            )])
+            .build();
+        for config in self.docker.configs().list(&opts).await? {
+            if let Some(id) = config.id {
+                self.docker.configs().get(id).delete().await?;
+            }
+        }
+
+        let opts = NetworkListOpts::builder()
+            .filter([NetworkFilter::LabelKeyVal(
+                STACK_NAMESPACE_LABEL.into(),
+                self.namespace.clone(),
+            )])
+            .build();
+        for network in self.docker.networks().list(&opts).await? {
+            if let Some(id) = network.id {
+                Network::new(self.docker.clone(), id).delete().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn object_file<'a>(key: &str, object: &'a ComposeFileObject) -> Result<&'a std::path::Path> {
+    object
+        .file
+        .as_deref()
+        .ok_or_else(|| Error::InvalidCompose(format!("`{key}` has no file")))
+}
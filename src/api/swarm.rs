@@ -1,10 +1,11 @@
 //! Control and manage clusters of engines also known as Swarm
 
+use crate::endpoint::Endpoint;
 use crate::{
     conn::{Headers, Payload},
     models,
-    opts::{SwarmInitOpts, SwarmJoinOpts},
-    Docker, Result,
+    opts::{SwarmInitOpts, SwarmJoinOpts, SwarmUpdateOpts},
+    Docker, Error, Result,
 };
 
 api_doc! { Swarm
@@ -23,31 +24,119 @@ impl Swarm {
         Inspect -> "/swarm", models::Swarm
     }
 
+    api_doc! { Swarm => Update
+    |
+    /// Update the swarm with a new `spec`. `version` is the current version of the swarm object
+    /// as returned by [`Swarm::inspect`](Swarm::inspect), used to avoid conflicting writes.
+    pub async fn update(
+        &self,
+        spec: &models::SwarmSpec,
+        version: u64,
+        opts: &SwarmUpdateOpts,
+    ) -> Result<()> {
+        let ep = Endpoint::new("/swarm/update")
+            .param("version", version)
+            .query(opts.serialize());
+        self.docker
+            .post(&ep, Payload::Json(serde_json::to_string(spec)?), Headers::none())
+            .await
+            .map(|_| ())
+    }}
+
+    api_doc! { Swarm => Inspect
+    |
+    /// Get the tokens used by workers and managers to join the swarm.
+    pub async fn join_tokens(&self) -> Result<models::JoinTokens> {
+        self.inspect()
+            .await?
+            .join_tokens
+            .ok_or_else(|| Error::InvalidResponse("expected swarm join tokens".into()))
+    }}
+
+    /// Rotate the token used by workers to join the swarm. Returns the new join tokens.
+    pub async fn rotate_worker_token(&self) -> Result<models::JoinTokens> {
+        self.rotate(SwarmUpdateOpts::builder().rotate_worker_token(true).build())
+            .await
+    }
+
+    /// Rotate the token used by managers to join the swarm. Returns the new join tokens.
+    pub async fn rotate_manager_token(&self) -> Result<models::JoinTokens> {
+        self.rotate(
+            SwarmUpdateOpts::builder()
+                .rotate_manager_token(true)
+                .build(),
+        )
+        .await
+    }
+
+    /// Re-submits the current spec of the swarm with the given rotation flags.
+    async fn rotate(&self, opts: SwarmUpdateOpts) -> Result<models::JoinTokens> {
+        let swarm = self.inspect().await?;
+        let version = swarm
+            .version
+            .and_then(|v| v.index)
+            .ok_or_else(|| Error::InvalidResponse("expected swarm version".into()))?;
+        let spec = swarm
+            .spec
+            .ok_or_else(|| Error::InvalidResponse("expected swarm spec".into()))?;
+        self.update(&spec, version, &opts).await?;
+        self.join_tokens().await
+    }
+
+    api_doc! { Swarm => Unlockkey
+    |
+    /// Get the unlock key. Returns an empty string if autolock is not enabled.
+    pub async fn unlock_key(&self) -> Result<String> {
+        self.docker
+            .get_json("/swarm/unlockkey")
+            .await
+            .map(|resp: models::SwarmUnlockkey200Response| resp.unlock_key.unwrap_or_default())
+    }}
+
     api_doc! { Swarm => Unlockkey
     |
     /// Get the unlock key.
+    #[deprecated(note = "use `Swarm::unlock_key`")]
     pub async fn get_unlock_key(&self) -> Result<models::SwarmUnlockkey200Response> {
         self.docker.get_json("/swarm/unlockkey").await
     }}
 
+    api_doc! { Swarm => Unlock
+    |
+    /// Unlock a locked manager using the `key` returned by [`Swarm::unlock_key`](Swarm::unlock_key).
+    pub async fn unlock(&self, key: impl Into<String>) -> Result<()> {
+        let body = models::SwarmUnlockBodyParam {
+            unlock_key: Some(key.into()),
+        };
+        self.docker
+            .post("/swarm/unlock", Payload::Json(serde_json::to_string(&body)?), Headers::none())
+            .await
+            .map(|_| ())
+    }}
+
     api_doc! { Swarm => Unlock
     |
     /// Unlock a locked manager.
+    #[deprecated(note = "use `Swarm::unlock`")]
     pub async fn unlock_manager(&self, key: &models::SwarmUnlockBodyParam) -> Result<()> {
+        self.unlock(key.unlock_key.clone().unwrap_or_default()).await
+    }}
+
+    api_doc! { Swarm => Init
+    |
+    /// Initialize a new swarm. Returns the ID of the node.
+    pub async fn init(&self, opts: &SwarmInitOpts) -> Result<String> {
         self.docker
-            .post("/swarm/unlock", Payload::Json(serde_json::to_string(key)?), Headers::none())
+            .post_json("/swarm/init", Payload::Json(opts.serialize_vec()?), Headers::none())
             .await
-            .map(|_| ())
     }}
 
     api_doc! { Swarm => Init
     |
     /// Initialize a new swarm.
+    #[deprecated(note = "use `Swarm::init` which returns the node ID")]
     pub async fn initialize(&self, opts: &SwarmInitOpts) -> Result<()> {
-        self.docker
-            .post("/swarm/init", Payload::Json(opts.serialize_vec()?), Headers::none())
-            .await
-            .map(|_| ())
+        self.init(opts).await.map(|_| ())
     }}
 
     api_doc! { Swarm => Join
@@ -62,10 +151,15 @@ impl Swarm {
 
     api_doc! { Swarm => Leave
     |
-    /// Leave the current swarm.
-    pub async fn leave(&self) -> Result<()> {
+    /// Leave the current swarm. If `force` is `true` the node leaves even if this is the last manager
+    /// or it will break the cluster.
+    pub async fn leave(&self, force: bool) -> Result<()> {
         self.docker
-            .post("/swarm/leave?force=false", Payload::empty(), Headers::none())
+            .post(
+                &Endpoint::new("/swarm/leave").param("force", force),
+                Payload::empty(),
+                Headers::none(),
+            )
             .await
             .map(|_| ())
     }}
@@ -74,9 +168,6 @@ impl Swarm {
     |
     /// Leave the current swarm forcefully, even if this is the last manager or that it will break the cluster.
     pub async fn force_leave(&self) -> Result<()> {
-        self.docker
-            .post("/swarm/leave?force=true", Payload::empty(), Headers::none())
-            .await
-            .map(|_| ())
+        self.leave(true).await
     }}
 }
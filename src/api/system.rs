@@ -1,12 +1,16 @@
+use crate::endpoint::Endpoint;
 use crate::{
+    conn::{Body, Headers, Method, Payload},
     models,
-    opts::{EventsOpts, SystemDataUsageOpts},
+    opts::{
+        ClearCacheOpts, EventsOpts, EventsRetryPolicy, RegistryAuth, SessionOpts,
+        SystemDataUsageOpts,
+    },
     Docker, Error, Result,
 };
-use containers_api::url::construct_ep;
-use futures_util::{Stream, TryStreamExt};
+use futures_util::{stream, AsyncRead, AsyncWrite, Stream, StreamExt};
 
-use std::{convert::TryFrom, io};
+use std::{convert::TryFrom, pin::Pin};
 
 impl Docker {
     api_doc! { System => Version
@@ -23,6 +27,19 @@ impl Docker {
         self.get_json("/info").await
     }}
 
+    api_doc! { System => Auth
+    |
+    /// Validate credentials for a registry and, if available, get an identity token for
+    /// accessing the registry without password.
+    pub async fn auth(&self, auth: &RegistryAuth) -> Result<models::SystemAuth200Response> {
+        self.post_json(
+            "/auth",
+            Payload::Json(serde_json::to_vec(auth)?),
+            Headers::none(),
+        )
+        .await
+    }}
+
     api_doc! { System => Ping
     |
     /// This is a dummy endpoint you can use to test if the server is accessible
@@ -32,6 +49,16 @@ impl Docker {
             .and_then(|resp| models::PingInfo::try_from(resp.headers()))
     }}
 
+    api_doc! { System => PingHead
+    |
+    /// Same as [`ping`](Docker::ping) but uses a `HEAD` request so that no response body is
+    /// transferred. Useful for frequent health checks.
+    pub async fn ping_head(&self) -> Result<models::PingInfo> {
+        self.head("/_ping")
+            .await
+            .and_then(|resp| models::PingInfo::try_from(resp.headers()))
+    }}
+
     api_doc! { System => Events
     |
     /// Returns a stream of Docker events
@@ -39,27 +66,153 @@ impl Docker {
         &'docker self,
         opts: &EventsOpts,
     ) -> impl Stream<Item = Result<models::EventMessage>> + Unpin + 'docker {
-        let ep = construct_ep("/events", opts.serialize());
-        let reader = Box::pin(
-            self.get_stream(ep)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
-        )
-        .into_async_read();
+        let ep = Endpoint::new("/events").query(opts.serialize());
+
+        #[cfg(feature = "fast-json")]
+        let events = crate::json::decode(self.get_stream(ep));
+        #[cfg(not(feature = "fast-json"))]
+        let events = {
+            use futures_util::TryStreamExt;
+
+            let reader = Box::pin(
+                self.get_stream(ep)
+                    .map_err(std::io::Error::other),
+            )
+            .into_async_read();
 
-        Box::pin(
             asynchronous_codec::FramedRead::new(reader, asynchronous_codec::LinesCodec)
-                .map_err(Error::IO)
+                .map_err(|e| Error::IO(e).unwrap_stream_error())
                 .and_then(|s: String| async move {
                     serde_json::from_str(&s).map_err(Error::SerdeJsonError)
-                }),
+                })
+        };
+
+        Box::pin(events)
+    }}
+
+    api_doc! { System => Events
+    |
+    /// Same as [`events`](Docker::events) but automatically re-subscribes when the connection to
+    /// the daemon is lost. The new subscription starts right after the last received event so no
+    /// events are missed or duplicated.
+    ///
+    /// Reconnection attempts, also made when the stream stalls, are delayed according to
+    /// `policy` on the runtime of the client. The stream ends once the `until` time of the
+    /// options passes or when the policy gives up, in which case the last error is returned.
+    pub fn events_resumable<'docker>(
+        &'docker self,
+        opts: &EventsOpts,
+        policy: EventsRetryPolicy,
+    ) -> impl Stream<Item = Result<models::EventMessage>> + Unpin + 'docker {
+        type EventStream<'a> = Pin<Box<dyn Stream<Item = Result<models::EventMessage>> + Send + 'a>>;
+
+        struct State<'a> {
+            docker: &'a Docker,
+            opts: EventsOpts,
+            policy: EventsRetryPolicy,
+            events: Option<EventStream<'a>>,
+            last_time_nano: Option<i64>,
+            attempt: usize,
+            done: bool,
+        }
+
+        let state = State {
+            docker: self,
+            opts: opts.clone(),
+            policy,
+            events: None,
+            last_time_nano: None,
+            attempt: 0,
+            done: false,
+        };
+
+        Box::pin(stream::unfold(state, |mut state| async move {
+            loop {
+                if state.done {
+                    return None;
+                }
+
+                let events = state.events.get_or_insert_with(|| {
+                    let opts = match state.last_time_nano {
+                        Some(time_nano) => state.opts.resume_after(time_nano),
+                        None => state.opts.clone(),
+                    };
+                    Box::pin(state.docker.events(&opts))
+                });
+
+                let error = match events.next().await {
+                    Some(Ok(event)) => {
+                        if let Some(time_nano) = event
+                            .time_nano
+                            .or_else(|| event.time.map(|time| time * 1_000_000_000))
+                        {
+                            state.last_time_nano = Some(time_nano);
+                        }
+                        state.attempt = 0;
+                        return Some((Ok(event), state));
+                    }
+                    Some(Err(
+                        e @ (Error::IO(_)
+                        | Error::Hyper(_)
+                        | Error::Transport(_)
+                        | Error::Error(_)
+                        | Error::StreamStalled(_)),
+                    )) => Some(e),
+                    Some(Err(e)) => {
+                        state.done = true;
+                        return Some((Err(e), state));
+                    }
+                    None if state.opts.has_until() => return None,
+                    None => None,
+                };
+
+                state.events = None;
+                match state.policy.backoff(state.attempt) {
+                    Some(delay) => {
+                        state.attempt += 1;
+                        state.docker.runtime().sleep(delay).await;
+                    }
+                    None => {
+                        state.done = true;
+                        return error.map(|e| (Err(e), state));
+                    }
+                }
+            }
+        }))
+    }}
+
+    api_doc! { Session
+    |
+    /// Start an interactive session with the daemon, as used by BuildKit to expose files,
+    /// secrets and credentials of the client to builds. The returned connection is upgraded to
+    /// HTTP/2 and the client is expected to serve the gRPC methods listed in `opts` over it.
+    pub async fn session(
+        &self,
+        opts: &SessionOpts,
+    ) -> Result<impl AsyncRead + AsyncWrite + Send + Unpin> {
+        self.upgrade(
+            Method::POST,
+            "/session",
+            "h2c",
+            Some(opts.headers()),
+            Body::empty(),
         )
+        .await
+    }}
+
+    api_doc! { Build => Prune
+    |
+    /// Delete the builder cache. Same as [`Images::clear_cache`](crate::Images::clear_cache).
+    pub async fn build_cache_prune(&self, opts: &ClearCacheOpts) -> Result<models::BuildPrune200Response> {
+        self.images().clear_cache(opts).await
     }}
 
     api_doc! { System => DataUsage
     |
     /// Returns data usage of this Docker instance
     pub async fn data_usage(&self, opts: &SystemDataUsageOpts) -> Result<models::SystemDataUsage200Response> {
-        let ep = construct_ep("/system/df", opts.serialize());
+        self.require_params(opts.vec_params.keys(), SystemDataUsageOpts::PARAM_VERSIONS)?;
+        let ep = Endpoint::new("/system/df").query(opts.serialize());
         self.get_json(&ep).await
     }}
 }
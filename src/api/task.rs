@@ -1,14 +1,21 @@
 //! A task is a container running on a swarm. It is the atomic scheduling unit of swarm.
 //! Swarm mode must be enabled for these endpoints to work.
 
-use crate::{models, opts::TaskListOpts, Result};
+use crate::{endpoint::Endpoint, models, opts::TaskListOpts, Result};
 
 impl_api_ty!(Task => id);
 
 impl Task {
     impl_api_ep! { task: Task, resp
-        Inspect -> &format!("/tasks/{}", task.id), models::Task
-        Logs -> &format!("/tasks/{}/logs", task.id), ()
+        Inspect -> Endpoint::new("/tasks").segment(&task.id), models::Task
+        Logs -> Endpoint::new("/tasks").segment(&task.id).path("logs"), ()
+    }
+
+    /// Inspects this task and returns its typed status.
+    pub async fn status(&self) -> Result<models::TaskStatus> {
+        self.inspect()
+            .await
+            .map(|task| models::TaskStatus::from(&task))
     }
 }
 
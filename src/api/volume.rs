@@ -1,29 +1,97 @@
 //! Create and manage persistent storage that can be attached to containers.
 
 use crate::{
+    api::Containers,
     conn::{Headers, Payload},
+    endpoint::Endpoint,
     models,
-    opts::{ClusterVolumeUpdateOpts, VolumeCreateOpts, VolumeListOpts, VolumePruneOpts},
+    opts::{
+        api_v1, ClusterVolumeUpdateOpts, ContainerCreateOpts, ContainerListOpts,
+        ContainerRemoveOpts, DataUsageType, SystemDataUsageOpts, VolumeCreateOpts, VolumeListOpts,
+        VolumePruneOpts,
+    },
     Result,
 };
-use containers_api::url;
+use containers_api::tarball;
+use hyper::Body;
 
-impl_api_ty!(Volume => name);
+use std::{collections::HashSet, path::Path};
+
+/// Path at which the helper container used by [`Volume::populate_from_tar`] mounts the volume.
+const POPULATE_MOUNT_PATH: &str = "/volume";
+
+impl_api_ty!(Volume => name: crate::VolumeId);
 
 impl Volume {
     impl_api_ep! {vol: Volume, resp
-        Inspect -> &format!("/volumes/{}", vol.name), models::Volume
-        Delete -> &format!("/volumes/{}", vol.name), ()
+        Inspect -> Endpoint::new("/volumes").segment(&vol.name), models::Volume
+        Delete -> Endpoint::new("/volumes").segment(&vol.name), ()
     }
 
     api_doc! { Volume => Update
     |
-    /// Update a volume. Valid only for Swarm cluster volumes
+    /// Update a volume. Valid only for Swarm cluster volumes. Requires API version 1.42.
     pub async fn update(&self, opts: &ClusterVolumeUpdateOpts) -> Result<()> {
-        let mut ep = format!("/volumes/{}", self.name());
-        url::append_query(&mut ep, url::encoded_pair("version", opts.version()));
+        self.docker.require_version("cluster volumes", api_v1(42))?;
+        let ep = Endpoint::new("/volumes")
+            .segment(self.name())
+            .param("version", opts.version());
         self.docker.put(&ep, Payload::Json(opts.serialize_vec()?)).await.map(|_| ())
     }}
+
+    /// Fill this volume with the contents of a tar archive, which may be compressed with gzip,
+    /// bzip2 or xz.
+    ///
+    /// A throwaway container of `image` that mounts the volume is created (but never started) and
+    /// the archive is extracted into it through the archive endpoint. The image must be available
+    /// locally. The helper container is removed afterwards.
+    pub async fn populate_from_tar(&self, image: impl Into<String>, tar: Body) -> Result<()> {
+        let opts = ContainerCreateOpts::builder()
+            .image(image.into())
+            .command(["true"])
+            .volumes([format!("{}:{POPULATE_MOUNT_PATH}", self.name)])
+            .build_owned();
+        let container = Containers::new(self.docker.clone()).create(&opts).await?;
+
+        let copied = container.copy_to(Path::new(POPULATE_MOUNT_PATH), tar).await;
+        let removed = container
+            .remove(&ContainerRemoveOpts::builder().force(true).build())
+            .await;
+
+        copied.and(removed.map(|_| ()))
+    }
+
+    /// Fill this volume with the contents of the directory at `path`.
+    ///
+    /// See [`populate_from_tar`](Volume::populate_from_tar) for details.
+    pub async fn populate_from_dir(
+        &self,
+        image: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let mut bytes = vec![];
+        tarball::dir(&mut bytes, path.as_ref())?;
+        self.populate_from_tar(image, bytes.into()).await
+    }
+
+    api_doc! { System => DataUsage
+    |
+    /// Returns the reference count and size of this volume. The daemon only calculates usage
+    /// data as part of the data usage endpoint so this is not included when inspecting the volume.
+    /// Returns `None` if the daemon doesn't report usage data for this volume.
+    pub async fn usage_data(&self) -> Result<Option<models::UsageData>> {
+        let opts = SystemDataUsageOpts::builder()
+            .types([DataUsageType::Volume])
+            .build();
+        let ep = Endpoint::new("/system/df").query(opts.serialize());
+        let usage: models::SystemDataUsage200Response = self.docker.get_json(&ep).await?;
+        Ok(usage
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .find(|volume| volume.name == self.name.as_ref())
+            .and_then(|volume| volume.usage_data))
+    }}
 }
 
 impl Volumes {
@@ -35,10 +103,38 @@ impl Volumes {
     |
     /// List available volumes
     pub async fn list(&self, opts: &VolumeListOpts) -> Result<models::VolumeListResponse> {
-        let ep = url::construct_ep("/volumes", opts.serialize());
+        let ep = Endpoint::new("/volumes").query(opts.serialize());
         self.docker.get_json(&ep).await
     }}
 
+    /// List volumes that are not referenced by any container, running or stopped.
+    ///
+    /// The volumes are cross-referenced with the mounts of all containers and include usage data
+    /// from the data usage endpoint, so the reclaimable size can be reviewed before pruning.
+    pub async fn dangling(&self) -> Result<Vec<models::Volume>> {
+        let opts = SystemDataUsageOpts::builder()
+            .types([DataUsageType::Volume])
+            .build();
+        let ep = Endpoint::new("/system/df").query(opts.serialize());
+        let usage: models::SystemDataUsage200Response = self.docker.get_json(&ep).await?;
+
+        let containers = Containers::new(self.docker.clone())
+            .list(&ContainerListOpts::builder().all(true).build())
+            .await?;
+        let referenced = containers
+            .into_iter()
+            .flat_map(|container| container.mounts.unwrap_or_default())
+            .filter_map(|mount| mount.name)
+            .collect::<HashSet<_>>();
+
+        Ok(usage
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|volume| !referenced.contains(&volume.name))
+            .collect())
+    }
+
     api_doc! { Volume => Create
     |
     /// Create a new volume.
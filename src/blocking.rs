@@ -0,0 +1,451 @@
+//! Blocking versions of the clients, enabled with the `blocking` feature.
+//!
+//! Every client owns a single-threaded tokio runtime that drives its requests, so CLI tools and
+//! build scripts can talk to the daemon without setting up an async runtime. Streams, like logs
+//! or pull progress, are returned as iterators. The clients must not be used from within an
+//! async runtime, calls panic if they are.
+//!
+//! ```no_run
+//! use stackify_docker_api::{blocking::Docker, opts::ContainerListOpts};
+//!
+//! let docker = Docker::new("unix:///var/run/docker.sock").unwrap();
+//! for container in docker.containers().list(&ContainerListOpts::builder().all(true).build()).unwrap() {
+//!     println!("{:?}", container.names);
+//! }
+//! ```
+//!
+//! Endpoints not covered here can be reached through the async client returned by
+//! [`Docker::as_async`](Docker::as_async) and [`Docker::block_on`](Docker::block_on).
+use futures_util::{Stream, StreamExt};
+use tokio::runtime::Runtime;
+
+use std::{future::Future, path::Path, pin::Pin, sync::Arc};
+
+use crate::{
+    conn::TtyFrame,
+    models,
+    opts::{
+        ContainerCommitOpts, ContainerConnectionOpts, ContainerCreateOpts,
+        ContainerDisconnectionOpts, ContainerListOpts, ContainerPruneOpts, ContainerRemoveOpts,
+        ContainerRestartOpts, ContainerStopOpts, EventsOpts, ImageBuildOpts, ImageListOpts,
+        ImagePruneOpts, ImagePushOpts, ImageRemoveOpts, LogsOpts, NetworkCreateOpts,
+        NetworkListOpts, NetworkPruneOpts, PullOpts, Signal, SystemDataUsageOpts, TagOpts,
+        VolumeCreateOpts, VolumeListOpts, VolumePruneOpts,
+    },
+    Error, Result,
+};
+
+/// Generates methods blocking on the method of the same name of the wrapped async client.
+macro_rules! impl_blocking {
+    ($async_ty:ident { $(fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)* }) => {
+        $(
+            calculated_doc! {
+            #[doc = concat!("Blocking version of [`", stringify!($async_ty), "::", stringify!($name), "`](crate::", stringify!($async_ty), "::", stringify!($name), ").")]
+            pub fn $name(&self, $($arg: $ty),*) -> Result<$ret> {
+                self.rt.block_on(self.inner.$name($($arg),*))
+            }
+            }
+        )*
+    };
+}
+
+/// Generates methods returning an [`Iter`](Iter) over the stream returned by the method of the
+/// same name of the wrapped async client.
+macro_rules! impl_blocking_iter {
+    ($async_ty:ident { $(fn $name:ident($($arg:ident: $ty:ty),*) -> $item:ty;)* }) => {
+        $(
+            calculated_doc! {
+            #[doc = concat!("Blocking version of [`", stringify!($async_ty), "::", stringify!($name), "`](crate::", stringify!($async_ty), "::", stringify!($name), ").")]
+            pub fn $name(&self, $($arg: $ty),*) -> Iter<'_, $item> {
+                Iter::new(&self.rt, self.inner.$name($($arg),*))
+            }
+            }
+        )*
+    };
+}
+
+/// Iterator over the items of a stream, blocking until the next one is received.
+pub struct Iter<'a, T> {
+    rt: &'a Runtime,
+    stream: Pin<Box<dyn Stream<Item = Result<T>> + Send + 'a>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn new(rt: &'a Runtime, stream: impl Stream<Item = Result<T>> + Send + 'a) -> Self {
+        Self {
+            rt,
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rt.block_on(self.stream.next())
+    }
+}
+
+impl<T> std::fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Iter").finish_non_exhaustive()
+    }
+}
+
+/// Blocking version of [`Docker`](crate::Docker).
+#[derive(Clone, Debug)]
+pub struct Docker {
+    inner: crate::Docker,
+    rt: Arc<Runtime>,
+}
+
+impl Docker {
+    /// Same as [`Docker::new`](crate::Docker::new).
+    pub fn new(uri: impl AsRef<str>) -> Result<Self> {
+        Self::from_async(crate::Docker::new(uri)?)
+    }
+
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    /// Same as [`Docker::unix`](crate::Docker::unix).
+    pub fn unix(socket_path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_async(crate::Docker::unix(socket_path))
+    }
+
+    /// Same as [`Docker::tcp`](crate::Docker::tcp).
+    pub fn tcp(host: impl AsRef<str>) -> Result<Self> {
+        Self::from_async(crate::Docker::tcp(host)?)
+    }
+
+    /// Wraps an async client, for example one configured with
+    /// [`Docker::builder`](crate::Docker::builder). The client must use the default
+    /// [`TokioRuntime`](crate::conn::TokioRuntime). Returns an error if the runtime driving the
+    /// requests can't be created.
+    pub fn from_async(docker: crate::Docker) -> Result<Self> {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::IO)?;
+        Ok(Self {
+            inner: docker,
+            rt: Arc::new(rt),
+        })
+    }
+
+    /// The wrapped async client.
+    pub fn as_async(&self) -> &crate::Docker {
+        &self.inner
+    }
+
+    /// Runs `future` to completion on the runtime of this client, for calling endpoints of the
+    /// async client that have no blocking version.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.rt.block_on(future)
+    }
+
+    /// Exports an interface for interacting with Docker images.
+    pub fn images(&self) -> Images {
+        Images {
+            inner: self.inner.images(),
+            rt: self.rt.clone(),
+        }
+    }
+
+    /// Exports an interface for interacting with Docker containers.
+    pub fn containers(&self) -> Containers {
+        Containers {
+            inner: self.inner.containers(),
+            rt: self.rt.clone(),
+        }
+    }
+
+    /// Exports an interface for interacting with Docker networks.
+    pub fn networks(&self) -> Networks {
+        Networks {
+            inner: self.inner.networks(),
+            rt: self.rt.clone(),
+        }
+    }
+
+    /// Exports an interface for interacting with Docker volumes.
+    pub fn volumes(&self) -> Volumes {
+        Volumes {
+            inner: self.inner.volumes(),
+            rt: self.rt.clone(),
+        }
+    }
+
+    impl_blocking!(Docker {
+        fn version() -> models::SystemVersion;
+        fn info() -> models::SystemInfo;
+        fn ping() -> models::PingInfo;
+        fn data_usage(opts: &SystemDataUsageOpts) -> models::SystemDataUsage200Response;
+    });
+
+    impl_blocking_iter!(Docker {
+        fn events(opts: &EventsOpts) -> models::EventMessage;
+    });
+}
+
+/// Blocking version of [`Containers`](crate::Containers).
+#[derive(Debug)]
+pub struct Containers {
+    inner: crate::Containers,
+    rt: Arc<Runtime>,
+}
+
+impl Containers {
+    /// Returns a reference to a set of operations available to a specific container.
+    pub fn get(&self, id: impl Into<crate::ContainerId>) -> Container {
+        Container {
+            inner: self.inner.get(id),
+            rt: self.rt.clone(),
+        }
+    }
+
+    /// Blocking version of [`Containers::create`](crate::Containers::create).
+    pub fn create(&self, opts: &ContainerCreateOpts) -> Result<Container> {
+        let inner = self.rt.block_on(self.inner.create(opts))?;
+        Ok(Container {
+            inner,
+            rt: self.rt.clone(),
+        })
+    }
+
+    impl_blocking!(Containers {
+        fn list(opts: &ContainerListOpts) -> Vec<models::ContainerSummary>;
+        fn prune(opts: &ContainerPruneOpts) -> models::ContainerPrune200Response;
+    });
+}
+
+/// Blocking version of [`Container`](crate::Container).
+#[derive(Debug)]
+pub struct Container {
+    inner: crate::Container,
+    rt: Arc<Runtime>,
+}
+
+impl Container {
+    /// The ID or name of this container.
+    pub fn id(&self) -> &crate::ContainerId {
+        self.inner.id()
+    }
+
+    impl_blocking!(Container {
+        fn inspect() -> models::ContainerInspect200Response;
+        fn top(psargs: Option<&str>) -> models::ContainerTop200Response;
+        fn changes() -> Option<models::ContainerChanges200Response>;
+        fn start() -> ();
+        fn stop(opts: &ContainerStopOpts) -> ();
+        fn restart(opts: &ContainerRestartOpts) -> ();
+        fn kill(signal: Option<Signal>) -> ();
+        fn rename(name: &str) -> ();
+        fn pause() -> ();
+        fn unpause() -> ();
+        fn wait() -> models::ContainerWaitResponse;
+        fn remove(opts: &ContainerRemoveOpts) -> String;
+        fn delete() -> String;
+        fn copy_file_into(path: impl AsRef<Path>, bytes: &[u8]) -> ();
+        fn stat_file(path: impl AsRef<Path>) -> String;
+        fn commit(
+            opts: &ContainerCommitOpts,
+            config: Option<&models::ContainerConfig>
+        ) -> crate::ImageId;
+    });
+
+    impl_blocking_iter!(Container {
+        fn logs(opts: &LogsOpts) -> TtyFrame;
+        fn stats() -> serde_json::Value;
+        fn export() -> Vec<u8>;
+        fn copy_from(path: impl AsRef<Path>) -> Vec<u8>;
+    });
+}
+
+/// Blocking version of [`Images`](crate::Images).
+#[derive(Debug)]
+pub struct Images {
+    inner: crate::Images,
+    rt: Arc<Runtime>,
+}
+
+impl Images {
+    /// Returns a reference to a set of operations available to a specific image.
+    pub fn get(&self, name: impl Into<crate::ImageId>) -> Image {
+        Image {
+            inner: self.inner.get(name),
+            rt: self.rt.clone(),
+        }
+    }
+
+    impl_blocking!(Images {
+        fn list(opts: &ImageListOpts) -> Vec<models::ImageSummary>;
+        fn prune(opts: &ImagePruneOpts) -> models::ImagePrune200Response;
+        fn remove_dangling() -> models::ImagePrune200Response;
+        fn search(term: impl AsRef<str>) -> models::ImageSearch200Response;
+        fn push(name: impl Into<crate::ImageId>, opts: &ImagePushOpts) -> ();
+    });
+
+    impl_blocking_iter!(Images {
+        fn pull(opts: &PullOpts) -> models::ImageBuildChunk;
+        fn build(opts: &ImageBuildOpts) -> models::ImageBuildChunk;
+    });
+}
+
+/// Blocking version of [`Image`](crate::Image).
+#[derive(Debug)]
+pub struct Image {
+    inner: crate::Image,
+    rt: Arc<Runtime>,
+}
+
+impl Image {
+    /// The ID or reference of this image.
+    pub fn name(&self) -> &crate::ImageId {
+        self.inner.name()
+    }
+
+    impl_blocking!(Image {
+        fn inspect() -> models::ImageInspect;
+        fn history() -> models::ImageHistory200Response;
+        fn tag(opts: &TagOpts) -> ();
+        fn push(opts: &ImagePushOpts) -> ();
+        fn remove(opts: &ImageRemoveOpts) -> Vec<models::ImageDeleteResponseItem>;
+        fn delete() -> Vec<models::ImageDeleteResponseItem>;
+        fn distribution_inspect() -> models::DistributionInspect;
+    });
+
+    impl_blocking_iter!(Image {
+        fn export() -> Vec<u8>;
+    });
+}
+
+/// Blocking version of [`Networks`](crate::Networks).
+#[derive(Debug)]
+pub struct Networks {
+    inner: crate::Networks,
+    rt: Arc<Runtime>,
+}
+
+impl Networks {
+    /// Returns a reference to a set of operations available to a specific network.
+    pub fn get(&self, id: impl Into<crate::NetworkId>) -> Network {
+        Network {
+            inner: self.inner.get(id),
+            rt: self.rt.clone(),
+        }
+    }
+
+    /// Blocking version of [`Networks::create`](crate::Networks::create).
+    pub fn create(&self, opts: &NetworkCreateOpts) -> Result<Network> {
+        let inner = self.rt.block_on(self.inner.create(opts))?;
+        Ok(Network {
+            inner,
+            rt: self.rt.clone(),
+        })
+    }
+
+    /// Blocking version of [`Networks::get_by_name`](crate::Networks::get_by_name).
+    pub fn get_by_name(&self, name: impl AsRef<str>) -> Result<Network> {
+        let inner = self.rt.block_on(self.inner.get_by_name(name))?;
+        Ok(Network {
+            inner,
+            rt: self.rt.clone(),
+        })
+    }
+
+    impl_blocking!(Networks {
+        fn list(opts: &NetworkListOpts) -> Vec<models::Network>;
+        fn prune(opts: &NetworkPruneOpts) -> models::NetworkPrune200Response;
+    });
+}
+
+/// Blocking version of [`Network`](crate::Network).
+#[derive(Debug)]
+pub struct Network {
+    inner: crate::Network,
+    rt: Arc<Runtime>,
+}
+
+impl Network {
+    /// The ID of this network.
+    pub fn id(&self) -> &crate::NetworkId {
+        self.inner.id()
+    }
+
+    impl_blocking!(Network {
+        fn inspect() -> models::Network;
+        fn delete() -> ();
+        fn connect(opts: &ContainerConnectionOpts) -> ();
+        fn disconnect(opts: &ContainerDisconnectionOpts) -> ();
+    });
+}
+
+/// Blocking version of [`Volumes`](crate::Volumes).
+#[derive(Debug)]
+pub struct Volumes {
+    inner: crate::Volumes,
+    rt: Arc<Runtime>,
+}
+
+impl Volumes {
+    /// Returns a reference to a set of operations available to a specific volume.
+    pub fn get(&self, name: impl Into<crate::VolumeId>) -> Volume {
+        Volume {
+            inner: self.inner.get(name),
+            rt: self.rt.clone(),
+        }
+    }
+
+    impl_blocking!(Volumes {
+        fn list(opts: &VolumeListOpts) -> models::VolumeListResponse;
+        fn create(opts: &VolumeCreateOpts) -> models::Volume;
+        fn prune(opts: &VolumePruneOpts) -> models::VolumePrune200Response;
+        fn dangling() -> Vec<models::Volume>;
+    });
+}
+
+/// Blocking version of [`Volume`](crate::Volume).
+#[derive(Debug)]
+pub struct Volume {
+    inner: crate::Volume,
+    rt: Arc<Runtime>,
+}
+
+impl Volume {
+    /// The name of this volume.
+    pub fn name(&self) -> &crate::VolumeId {
+        self.inner.name()
+    }
+
+    impl_blocking!(Volume {
+        fn inspect() -> models::Volume;
+        fn delete() -> ();
+        fn usage_data() -> Option<models::UsageData>;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conn::{Method, MockResponse, MockTransport};
+
+    #[test]
+    fn events() {
+        let mock = MockTransport::new();
+        mock.on(
+            Method::GET,
+            "/events",
+            MockResponse::json_lines([
+                serde_json::json!({ "Type": "container", "Action": "start" }),
+                serde_json::json!({ "Type": "container", "Action": "die" }),
+            ]),
+        );
+        let docker = super::Docker::from_async(crate::Docker::from_mock(mock)).unwrap();
+
+        let actions = docker
+            .events(&Default::default())
+            .map(|event| event.unwrap().action.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(actions, ["start", "die"]);
+    }
+}
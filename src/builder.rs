@@ -13,8 +13,84 @@ macro_rules! calculated_doc {
     };
 }
 
+/// Wraps the opts builder macro of `containers_api` to add an `extra_param` method to every
+/// builder of URL query options.
+macro_rules! impl_opts_builder {
+    (url => $(#[doc = $docs:expr])* $name:ident) => {
+        containers_api::impl_opts_builder!(url => $(#[doc = $docs])* $name);
+
+        paste::item! {
+            impl [< $name OptsBuilder >] {
+                /// Set a query parameter not covered by this builder yet, for example one added
+                /// by a newer or experimental version of the API. Overrides a parameter with the
+                /// same key set by another method.
+                pub fn extra_param<V>(mut self, key: &'static str, value: V) -> Self
+                where
+                    V: ToString,
+                {
+                    self.params.insert(key, value.to_string());
+                    self
+                }
+            }
+        }
+    };
+    ($($tt:tt)*) => {
+        containers_api::impl_opts_builder!($($tt)*);
+    };
+}
+
+/// Like `impl_map_field!(url ..)` of `containers_api` but takes string entries, which always
+/// encode as a JSON object, and encodes them in the order of their keys so that the query string
+/// is the same on every run.
+macro_rules! impl_url_map_field {
+    ($(#[doc = $docs:expr])* $name:ident => $param_name:literal) => {
+        $(
+            #[doc = $docs]
+        )*
+        pub fn $name<K, V>(mut self, $name: impl IntoIterator<Item = (K, V)>) -> Self
+        where
+            K: Into<String>,
+            V: Into<String>,
+        {
+            let entries: std::collections::BTreeMap<String, serde_json::Value> = $name
+                .into_iter()
+                .map(|(k, v)| (k.into(), serde_json::Value::String(v.into())))
+                .collect();
+            self.params.insert(
+                $param_name,
+                serde_json::Value::Object(entries.into_iter().collect()).to_string(),
+            );
+            self
+        }
+    };
+}
+
+/// Implements the `filter` method taking typed filters and the `filters` method taking
+/// [`Filters`](crate::opts::Filters), replacing `impl_filter_func!` of `containers_api` so that
+/// all endpoints share the encoding of `Filters`.
+macro_rules! impl_filter_func {
+    ($(#[doc = $doc:expr])* $filter_ty:ident) => {
+        $(
+            #[doc = $doc]
+        )*
+        pub fn filter(self, filters: impl IntoIterator<Item = $filter_ty>) -> Self {
+            self.filters(filters.into_iter().collect())
+        }
+
+        /// Set the filters of the request, replacing filters set before. Can be used to pass
+        /// filter keys not covered by the typed filters yet.
+        pub fn filters(mut self, filters: $crate::opts::Filters) -> Self {
+            self.params.insert("filters", filters.serialize());
+            self
+        }
+    };
+}
+
 macro_rules! impl_api_ty {
     ($(#[doc = $docs:expr])* $name:ident => $name_field:ident) => {
+        impl_api_ty!($(#[doc = $docs])* $name => $name_field: crate::Id);
+    };
+    ($(#[doc = $docs:expr])* $name:ident => $name_field:ident: $id_ty:ty) => {
         paste::item! {
 
             calculated_doc!{
@@ -22,14 +98,14 @@ macro_rules! impl_api_ty {
             #[derive(Debug)]
             pub struct [< $name >] {
                 docker: crate::Docker,
-                $name_field: crate::Id,
+                $name_field: $id_ty,
             }
             }
             impl [< $name >] {
                 // TODO: this is possible on nightly, figure out what to do
                 calculated_doc!{
                 #[doc = concat!("Exports an interface exposing operations against a ", stringify!($name), " instance.")]
-                pub fn new(docker: crate::Docker, $name_field: impl Into<crate::Id>) -> Self
+                pub fn new(docker: crate::Docker, $name_field: impl Into<$id_ty>) -> Self
                 {
                     [< $name >] {
                         docker,
@@ -40,7 +116,7 @@ macro_rules! impl_api_ty {
 
                 calculated_doc!{
                 #[doc = concat!("A getter for ", stringify!($name), " ", stringify!($name_field))]
-                pub fn $name_field(&self) -> &crate::Id {
+                pub fn $name_field(&self) -> &$id_ty {
                     &self.$name_field
                 }
                 }
@@ -67,7 +143,7 @@ macro_rules! impl_api_ty {
 
                 calculated_doc!{
                 #[doc = concat!("Returns a reference to a set of operations available to a specific ", stringify!($name), ".")]
-                pub fn get(&self, $name_field: impl Into<crate::Id>) -> [< $name >]
+                pub fn get(&self, $name_field: impl Into<$id_ty>) -> [< $name >]
                 {
                     [< $name >]::new(self.docker.clone(), $name_field)
                 }
@@ -149,7 +225,17 @@ macro_rules! impl_api_ep {
         #[doc = concat!("Inspect this ", stringify!($base), ".")]
         pub async fn inspect(&self) -> Result<$ret> {
             let $it = self;
-            self.docker.get_json($ep).await
+            self.docker.get_json(&$ep).await
+        }}
+        }
+        paste::item! {
+        api_doc! { $base => Inspect
+        |
+        #[doc = concat!("Inspect this ", stringify!($base), " keeping the response as raw JSON, so single")]
+        #[doc = "fields can be read without deserializing the whole model, see [`RawInspect`](crate::RawInspect)."]
+        pub async fn inspect_raw(&self) -> Result<crate::RawInspect<$ret>> {
+            let $it = self;
+            self.docker.get_raw_json(&$ep).await.map(crate::RawInspect::new)
         }}
         }
     };
@@ -159,14 +245,8 @@ macro_rules! impl_api_ep {
 
         paste::item! {
         async fn _delete(&self, force: bool) -> Result<$ret> {
-            let query = if force {
-                Some(containers_api::url::encoded_pair("force", force))
-            } else {
-                None
-            };
-
             let $it = self;
-            let ep = containers_api::url::construct_ep($ep, query);
+            let ep = $ep.param_opt("force", force.then_some(true));
 
             self.docker
                 .delete_json(ep.as_ref())
@@ -199,7 +279,7 @@ macro_rules! impl_api_ep {
         #[doc = concat!("Delete this ", stringify!($base), ".")]
         pub async fn delete(&self) -> Result<()> {
             let $it = self;
-            self.docker.delete($ep).await.map(|_| ())
+            self.docker.delete(&$ep).await.map(|_| ())
         }}
         }
     };
@@ -218,7 +298,7 @@ macro_rules! impl_api_ep {
         #[doc = concat!("Use [`delete`](", stringify!($base), "::delete) to delete without options.")]
         pub async fn remove(&self, opts: &[< $base RemoveOpts >]) -> Result<$ret> {
             let $it = self;
-            let ep = containers_api::url::construct_ep($ep, opts.serialize());
+            let ep = $ep.query(opts.serialize());
             self.docker.$fn(ep.as_ref()).await
         }}
         }
@@ -229,7 +309,7 @@ macro_rules! impl_api_ep {
         #[doc = concat!("Use [`remove`](", stringify!($base), "::remove) to customize options.")]
         pub async fn delete(&self) -> Result<[< $ret >]> {
             let $it = self;
-            self.docker.$fn($ep).await
+            self.docker.$fn(&$ep).await
         }}
         }
     };
@@ -241,7 +321,7 @@ macro_rules! impl_api_ep {
         |
         #[doc = concat!("List available ", stringify!($base), "s.")]
         pub async fn list(&self, opts: &[< $base ListOpts >]) -> Result<Vec<$ret>> {
-            let ep = containers_api::url::construct_ep($ep, opts.serialize());
+            let ep = $crate::endpoint::Endpoint::new($ep).query(opts.serialize());
             self.docker.get_json(&ep).await
         }}
         }
@@ -269,7 +349,7 @@ macro_rules! impl_api_ep {
         pub async fn prune(&self, opts: &[< $base PruneOpts >]) -> Result<$ret> {
             self.docker
                 .post_json(
-                    &containers_api::url::construct_ep($ep, opts.serialize()),
+                    &$crate::endpoint::Endpoint::new($ep).query(opts.serialize()),
                     crate::conn::Payload::empty(),
                     crate::conn::Headers::none(),
                 ).await
@@ -283,19 +363,47 @@ macro_rules! impl_api_ep {
         api_doc! { $base => Logs
         |
         #[doc = concat!("Returns a stream of logs from a ", stringify!($base), ".")]
+        ///
+        /// Frames are decoded as the stream is polled, so a consumer that falls behind slows down
+        /// reading from the daemon instead of buffering logs in memory.
         pub fn logs<'docker>(
             &'docker self,
             opts: &crate::opts::LogsOpts
-        ) -> impl futures_util::Stream<Item = crate::Result<containers_api::conn::TtyChunk>> + Unpin + 'docker {
-            use containers_api::conn::tty;
-            use futures_util::TryStreamExt;
+        ) -> impl futures_util::Stream<Item = crate::Result<crate::conn::TtyFrame>> + Unpin + 'docker {
             let $it = self;
-            let ep = containers_api::url::construct_ep($ep, opts.serialize());
+            let ep = $ep.query(opts.serialize());
 
-            let stream = Box::pin(self.docker.get_stream(ep).map_err(|e| containers_api::conn::Error::Any(Box::new(e))));
+            let stream = Box::pin(self.docker.get_stream(ep));
 
-            Box::pin(tty::decode(stream).map_err(crate::Error::Error))
+            Box::pin($crate::tty::decode(stream))
         }
         }}
+
+        api_doc! { $base => Logs
+        |
+        #[doc = concat!("Returns a stream of logs from a ", stringify!($base), " read in the background into a buffer of `capacity` frames.")]
+        ///
+        /// Once the buffer is full the oldest frames are dropped and reported with
+        /// [`Buffered::Lagged`](crate::conn::Buffered::Lagged), so following the logs doesn't
+        /// grow memory without bound when the consumer falls behind.
+        pub fn logs_buffered(
+            &self,
+            opts: &crate::opts::LogsOpts,
+            capacity: usize,
+        ) -> crate::conn::BoundedStream<crate::conn::TtyFrame> {
+            let $it = self;
+            let ep = $ep.query(opts.serialize());
+            let docker = self.docker.clone();
+
+            crate::conn::BoundedStream::spawn(self.docker.runtime(), capacity, |sender| async move {
+                use futures_util::StreamExt;
+
+                let frames = $crate::tty::decode(Box::pin(docker.get_stream(ep)));
+                futures_util::pin_mut!(frames);
+                while let Some(frame) = frames.next().await {
+                    sender.push(frame);
+                }
+            })
+        }}
     };
 }
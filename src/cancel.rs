@@ -0,0 +1,178 @@
+//! Cooperative cancellation of requests and streams.
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    waiters: Mutex<Waiters>,
+}
+
+/// Wakers of the tasks waiting for the cancellation, keyed by their [`Waiter`](Waiter).
+#[derive(Debug, Default)]
+struct Waiters {
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+/// Cancels the requests, streams and attached connections of a client set with
+/// [`Docker::with_cancellation`](crate::Docker::with_cancellation), from any task.
+///
+/// Cancelled requests fail with [`Error::Cancelled`](crate::Error::Cancelled). Cancelling wakes
+/// the tasks polling the requests, streams and attached connections of the client, which close
+/// their connection the next time they are polled so the daemon notices the cancellation. A
+/// stream or attached connection that is held by the caller without being polled keeps its
+/// connection open until it is polled or dropped. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<Inner>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every operation observing this token. Operations started afterwards fail right
+    /// away.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut self.waiters().wakers);
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    /// Returns `true` if [`cancel`](CancellationToken::cancel) was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once this token is cancelled.
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + '_ {
+        let mut waiter = self.waiter();
+        futures_util::future::poll_fn(move |cx| {
+            if waiter.poll_cancelled(cx) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Returns a waiter for this token, registered once it is polled.
+    pub(crate) fn waiter(&self) -> Waiter {
+        Waiter {
+            token: self.clone(),
+            key: None,
+        }
+    }
+
+    fn waiters(&self) -> MutexGuard<'_, Waiters> {
+        self.0.waiters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Waits for the cancellation of a token from one task. The waker of the task is kept by the
+/// token until the waiter is dropped or the token is cancelled.
+#[derive(Debug)]
+pub(crate) struct Waiter {
+    token: CancellationToken,
+    key: Option<u64>,
+}
+
+impl Waiter {
+    /// Returns `true` if the token is cancelled, otherwise wakes the task of `cx` once it is.
+    pub(crate) fn poll_cancelled(&mut self, cx: &mut Context<'_>) -> bool {
+        if self.token.is_cancelled() {
+            return true;
+        }
+        let mut waiters = self.token.waiters();
+        let key = *self.key.get_or_insert_with(|| {
+            waiters.next_key += 1;
+            waiters.next_key
+        });
+        match waiters.wakers.get_mut(&key) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => {
+                waiters.wakers.insert(key, cx.waker().clone());
+            }
+        }
+        // checked again in case `cancel` took the wakers before this one was added
+        self.token.is_cancelled()
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.token.waiters().wakers.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+    use crate::conn::{Method, MockResponse, MockTransport};
+    use crate::{Docker, Error};
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn cancellation() {
+        let mock = MockTransport::new();
+        mock.on(
+            Method::POST,
+            "/containers/web/wait",
+            MockResponse::pending(),
+        )
+        .on(Method::GET, "/events", MockResponse::pending());
+        let token = CancellationToken::new();
+        let docker = Docker::from_mock(mock).with_cancellation(token.clone());
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        match docker.containers().get("web").wait().await {
+            Err(Error::Cancelled) => {}
+            other => panic!("expected Error::Cancelled, got {other:?}"),
+        }
+        assert!(token.is_cancelled());
+
+        let mut events = docker.events(&Default::default());
+        assert!(matches!(events.next().await, Some(Err(Error::Cancelled))));
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn waiters_removed() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping", MockResponse::pending()).on(
+            Method::GET,
+            "/events",
+            MockResponse::json_lines([serde_json::json!({ "Action": "start" })]),
+        );
+        let token = CancellationToken::new();
+        let docker = Docker::from_mock(mock).with_cancellation(token.clone());
+
+        let tasks = (0..8)
+            .map(|_| {
+                let docker = docker.clone();
+                tokio::spawn(async move {
+                    // waits for the cancellation until the request is dropped
+                    let ping = docker.ping();
+                    let timeout = std::time::Duration::from_millis(10);
+                    assert!(tokio::time::timeout(timeout, ping).await.is_err());
+                    let events = docker.events(&Default::default()).collect::<Vec<_>>();
+                    assert_eq!(events.await.len(), 1);
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert!(token.waiters().wakers.is_empty());
+        assert_eq!(token.waiters().next_key, 16);
+    }
+}
@@ -0,0 +1,143 @@
+//! Lookup of the contexts managed by `docker context`, stored in the docker configuration
+//! directory.
+
+use crate::{Error, Result};
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Name of the context configured by the `DOCKER_*` environment variables.
+pub(crate) const DEFAULT_CONTEXT: &str = "default";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Meta {
+    name: String,
+    #[serde(default)]
+    endpoints: Endpoints,
+}
+
+#[derive(Default, Deserialize)]
+struct Endpoints {
+    docker: Option<Endpoint>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Endpoint {
+    host: String,
+    #[serde(rename = "SkipTLSVerify", default)]
+    skip_tls_verify: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Config {
+    current_context: Option<String>,
+}
+
+/// The docker endpoint of a context.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ContextEndpoint {
+    pub host: String,
+    /// Directory with the `ca.pem`, `cert.pem` and `key.pem` of the context if it has any.
+    pub tls_path: Option<PathBuf>,
+    pub verify: bool,
+}
+
+/// Returns the docker configuration directory, `DOCKER_CONFIG` or `~/.docker`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("DOCKER_CONFIG")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| crate::docker::home_dir().map(|home| home.join(".docker")))
+}
+
+/// Returns the name of the context selected with `docker context use` in `config_dir`.
+pub(crate) fn current(config_dir: &Path) -> Result<Option<String>> {
+    match std::fs::read(config_dir.join("config.json")) {
+        Ok(data) => Ok(serde_json::from_slice::<Config>(&data)?
+            .current_context
+            .filter(|name| !name.is_empty())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Finds the docker endpoint of the context `name` in `config_dir`.
+///
+/// The directories of a context are named after a hash of its name, instead of computing it the
+/// metadata of all contexts is searched for the name.
+pub(crate) fn find(config_dir: &Path, name: &str) -> Result<ContextEndpoint> {
+    let contexts = config_dir.join("contexts");
+    let not_found = || Error::not_found(format!("docker context `{name}`"));
+
+    let entries = match std::fs::read_dir(contexts.join("meta")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let meta = match std::fs::read(entry.path().join("meta.json")) {
+            Ok(data) => serde_json::from_slice::<Meta>(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if meta.name != name {
+            continue;
+        }
+
+        let endpoint = meta.endpoints.docker.ok_or_else(|| {
+            Error::InvalidHost(format!("docker context `{name}` has no docker endpoint"))
+        })?;
+        let tls_path = contexts.join("tls").join(entry.file_name()).join("docker");
+        return Ok(ContextEndpoint {
+            host: endpoint.host,
+            tls_path: tls_path.is_dir().then_some(tls_path),
+            verify: !endpoint.skip_tls_verify,
+        });
+    }
+    Err(not_found())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("contexts/meta/abcd");
+        std::fs::create_dir_all(&meta).unwrap();
+        std::fs::write(
+            meta.join("meta.json"),
+            r#"{"Name":"remote","Metadata":{},"Endpoints":{"docker":{"Host":"tcp://10.0.0.2:2376","SkipTLSVerify":true}}}"#,
+        )
+        .unwrap();
+        let tls = dir.path().join("contexts/tls/abcd/docker");
+        std::fs::create_dir_all(&tls).unwrap();
+        std::fs::write(
+            dir.path().join("config.json"),
+            r#"{"currentContext":"remote"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(current(dir.path()).unwrap().as_deref(), Some("remote"));
+        assert_eq!(
+            find(dir.path(), "remote").unwrap(),
+            ContextEndpoint {
+                host: "tcp://10.0.0.2:2376".into(),
+                tls_path: Some(tls),
+                verify: false,
+            }
+        );
+        assert!(matches!(
+            find(dir.path(), "missing"),
+            Err(Error::NoSuchObject(_))
+        ));
+
+        let empty = tempfile::tempdir().unwrap();
+        assert_eq!(current(empty.path()).unwrap(), None);
+    }
+}
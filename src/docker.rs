@@ -2,35 +2,764 @@
 //!
 //! API Reference: <https://docs.docker.com/engine/api/v1.42/>
 use crate::{
-    conn::{get_http_connector, Headers, Payload, Transport},
-    errors::{Error, Result},
+    conn::{self, CancellationToken, Headers, Payload, Transport},
+    context,
+    endpoint::Endpoint,
+    errors::{Error, Result, TransportError, TransportErrorKind},
+    models::ResponseMeta,
+    runtime::{Executor, Runtime, TokioRuntime},
+    stream::{Liveness, Watched},
+    transport::{
+        get_http_connector, stream_body, BoxedConnector, FailoverConnector, Proxy, ProxyConnector,
+        TransportClient,
+    },
     ApiVersion, Containers, Images, Networks, Volumes,
 };
-use containers_api::conn::RequestClient;
 
 #[cfg(feature = "swarm")]
 use crate::{Configs, Nodes, Plugins, Secrets, Services, Swarm, Tasks};
 
-#[cfg(feature = "tls")]
-use crate::conn::get_https_connector;
+#[cfg(feature = "podman")]
+use crate::Pods;
+#[cfg(feature = "stack")]
+use crate::Stack;
+
+#[cfg(any(feature = "tls", feature = "rustls"))]
+use crate::transport::get_https_connector;
+#[cfg(windows)]
+use crate::transport::NamedPipeConnector;
+#[cfg(feature = "ssh")]
+use crate::transport::SshConnector;
 #[cfg(unix)]
-use crate::conn::get_unix_connector;
+use crate::transport::{fd_from_uri, get_unix_connector, FdConnector};
 
 use futures_util::{
+    future::TryFutureExt,
     io::{AsyncRead, AsyncWrite},
-    stream::Stream,
+    stream::{Stream, TryStreamExt},
 };
-use hyper::{body::Bytes, Body, Client, Response};
-use serde::de::DeserializeOwned;
-use std::future::Future;
+use hyper::{body::Bytes, header, Body, Client, Method, Request, Response};
+use serde::{de::DeserializeOwned, Serialize};
 use std::path::{Path, PathBuf};
-use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Host used by [`Docker::from_env`](Docker::from_env) when `DOCKER_HOST` is not set, the default
+/// socket of the daemon on this platform.
+#[cfg(unix)]
+pub const DEFAULT_HOST: &str = "unix:///var/run/docker.sock";
+
+/// Host used by [`Docker::from_env`](Docker::from_env) when `DOCKER_HOST` is not set, the default
+/// socket of the daemon on this platform.
+#[cfg(windows)]
+pub const DEFAULT_HOST: &str = "npipe:////./pipe/docker_engine";
+
+/// Host used by [`Docker::from_env`](Docker::from_env) when `DOCKER_HOST` is not set, the default
+/// socket of the daemon on this platform.
+#[cfg(not(any(unix, windows)))]
+pub const DEFAULT_HOST: &str = "tcp://127.0.0.1:2375";
 
 /// Entrypoint interface for communicating with docker daemon
 #[derive(Debug, Clone)]
 pub struct Docker {
     version: Option<ApiVersion>,
-    client: RequestClient<Error>,
+    transport: Transport,
+    response_hook: Option<ResponseHook>,
+    timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    headers: Arc<header::HeaderMap>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    runtime: Arc<dyn Runtime>,
+    stream_idle_timeout: Option<Duration>,
+    deserialize_mode: DeserializeMode,
+    cancellation: Option<CancellationToken>,
+}
+
+/// Builder for a [`Docker`](Docker) client with a customized connection to the daemon, created with
+/// [`Docker::builder`](Docker::builder).
+#[derive(Debug, Clone)]
+pub struct DockerBuilder {
+    uri: String,
+    #[cfg(any(feature = "tls", feature = "rustls"))]
+    tls: Option<(PathBuf, bool)>,
+    headers: Vec<(String, String)>,
+    connector: Option<BoxedConnector>,
+    fallback_hosts: Vec<String>,
+    opts: ClientOptions,
+}
+
+impl DockerBuilder {
+    /// Creates a builder for a client connecting to `uri`.
+    pub fn new(uri: impl Into<String>) -> Self {
+        DockerBuilder {
+            uri: uri.into(),
+            #[cfg(any(feature = "tls", feature = "rustls"))]
+            tls: None,
+            headers: Vec::new(),
+            connector: None,
+            fallback_hosts: Vec::new(),
+            opts: ClientOptions::default(),
+        }
+    }
+
+    /// Pins the API version used by the client, by default the latest version served by the
+    /// daemon is used.
+    pub fn version(mut self, version: impl Into<ApiVersion>) -> Self {
+        self.opts.version = Some(version.into());
+        self
+    }
+
+    #[cfg(any(feature = "tls", feature = "rustls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls"))))]
+    /// Connects to a `tcp://` host with TLS using the certificates in `cert_path`, see
+    /// [`Docker::tls`](Docker::tls).
+    pub fn tls(mut self, cert_path: impl Into<PathBuf>, verify: bool) -> Self {
+        self.tls = Some((cert_path.into(), verify));
+        self
+    }
+
+    /// Maximum number of idle connections kept open to the daemon, defaults to no limit. Upgrade
+    /// requests, like attaching to a container, always get a dedicated connection that is closed
+    /// once the upgraded connection is dropped.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.opts.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle connection is kept open before it is closed, defaults to 90 seconds.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables TCP keep-alive probes sent after `interval` of inactivity on connections to TCP
+    /// hosts, disabled by default.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.opts.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Fails requests that don't complete within `timeout` with
+    /// [`Error::Timeout`](Error::Timeout), disabled by default. The timeout covers sending the
+    /// request and reading the whole response.
+    ///
+    /// Streaming endpoints like logs, events or attach aren't subject to it so that they can stay
+    /// open, neither are long-running operations like
+    /// [`Container::wait`](crate::Container::wait), stopping, restarting or killing containers,
+    /// committing them, copying archives into them and pushing images. Use
+    /// [`Docker::with_timeout`](Docker::with_timeout) to change the timeout of a single call.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.opts.timeout = Some(timeout);
+        self
+    }
+
+    /// Checks the liveness of long-lived streams, like followed logs, events or attached
+    /// containers, once they received no data for `idle`. The daemon is then pinged and if it
+    /// doesn't answer within another `idle` period the stream ends with
+    /// [`Error::StreamStalled`](Error::StreamStalled), or an IO error of kind `TimedOut`
+    /// wrapping it for attached containers. Disabled by default.
+    pub fn stream_idle_timeout(mut self, idle: Duration) -> Self {
+        self.opts.stream_idle_timeout = Some(idle);
+        self
+    }
+
+    /// Sets how fields of responses that the models don't know are handled, by default they are
+    /// ignored.
+    pub fn deserialize_mode(mut self, mode: DeserializeMode) -> Self {
+        self.opts.deserialize_mode = mode;
+        self
+    }
+
+    /// Fails connection attempts to TCP hosts that don't complete within `timeout`, including
+    /// those of streaming endpoints. Disabled by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Retries idempotent requests without a body, like inspecting or listing objects, that
+    /// failed because the daemon couldn't be reached or was unavailable, for example while it
+    /// restarts. Disabled by default.
+    ///
+    /// The [`timeout`](DockerBuilder::timeout) of a request includes its retries.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.opts.retry = Some(policy);
+        self
+    }
+
+    /// Limits the rate of requests sent to the daemon, requests over the limit wait until they
+    /// are allowed. The limit is shared by all clones of the client. Disabled by default.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.opts.rate_limit = Some(limit);
+        self
+    }
+
+    /// Connects to TCP hosts through `proxy`. Pass `None` to connect directly, the default.
+    pub fn proxy(mut self, proxy: impl Into<Option<Proxy>>) -> Self {
+        self.opts.proxy = match proxy.into() {
+            Some(proxy) => ProxyOption::Explicit(proxy),
+            None => ProxyOption::Disabled,
+        };
+        self
+    }
+
+    /// Connects to TCP hosts through the proxy set in the `HTTP_PROXY` or, for TLS hosts,
+    /// `HTTPS_PROXY` environment variable unless the host is excluded by `NO_PROXY`. The
+    /// variables are read when the client is built.
+    pub fn proxy_from_env(mut self) -> Self {
+        self.opts.proxy = ProxyOption::Env;
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    pub fn user_agent(self, user_agent: impl Into<String>) -> Self {
+        self.default_header(header::USER_AGENT.as_str(), user_agent)
+    }
+
+    /// Adds a header sent with every request, for example to authenticate with a reverse proxy
+    /// in front of the daemon. Headers passed to a single request take precedence.
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Runs the tasks and timers of the client on `runtime` instead of tokio.
+    ///
+    /// The built-in connectors need tokio, so a [`connector`](DockerBuilder::connector) for the
+    /// runtime has to be set as well. Its connections still have to implement the IO traits of
+    /// tokio required by hyper, for example through a compatibility wrapper. The connection pool
+    /// can't expire idle connections without tokio, so
+    /// [`pool_idle_timeout`](DockerBuilder::pool_idle_timeout) must not be set.
+    pub fn runtime(mut self, runtime: impl Runtime) -> Self {
+        self.opts.runtime = Some(Arc::new(runtime));
+        self
+    }
+
+    /// Establishes connections to the daemon with `connector`, like
+    /// [`Docker::from_connector`](Docker::from_connector), instead of the connector for the
+    /// scheme of the URI.
+    pub fn connector<C>(mut self, connector: C) -> Self
+    where
+        C: hyper::service::Service<hyper::Uri> + Clone + Send + Sync + 'static,
+        C::Response: tokio::io::AsyncRead
+            + tokio::io::AsyncWrite
+            + hyper::client::connect::Connection
+            + Send
+            + Unpin
+            + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self.connector = Some(BoxedConnector::new(connector));
+        self
+    }
+
+    /// Adds a daemon connected to when the ones before it, starting with the URI of the builder,
+    /// are unreachable, for example another manager node of a swarm cluster. See
+    /// [`Docker::new`](Docker::new) for supported schemes, except `fd://`.
+    ///
+    /// Connections are made to the last reachable daemon first, shared by all clones of the
+    /// client. Only connecting fails over, a request that fails after it was sent is not
+    /// repeated on another daemon.
+    pub fn fallback_host(mut self, uri: impl Into<String>) -> Self {
+        self.fallback_hosts.push(uri.into());
+        self
+    }
+
+    /// Creates the client, returns an error if the URI is not supported or a default header is
+    /// invalid.
+    pub fn build(mut self) -> Result<Docker> {
+        let mut headers = header::HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(hyper::http::Error::from)?;
+            let value = header::HeaderValue::from_str(value).map_err(hyper::http::Error::from)?;
+            if name == header::USER_AGENT {
+                headers.insert(name, value);
+            } else {
+                headers.append(name, value);
+            }
+        }
+        self.opts.headers = Arc::new(headers);
+
+        if !self.fallback_hosts.is_empty() {
+            if self.connector.is_some() {
+                return Err(Error::InvalidHost(
+                    "fallback hosts can't be used with a custom connector".into(),
+                ));
+            }
+            let hosts = std::iter::once(&self.uri)
+                .chain(&self.fallback_hosts)
+                .map(|uri| {
+                    let (target, connector) = self.host_connector(uri)?;
+                    Ok((uri.clone(), target, connector))
+                })
+                .collect::<Result<_>>()?;
+            let connector = FailoverConnector::new(hosts);
+            let transport = Transport::Failover {
+                client: self.opts.client(connector.clone()),
+                connector,
+            };
+            return Ok(Docker::new_with_transport(transport, &self.opts));
+        }
+
+        if let Some(connector) = self.connector {
+            return Docker::new_connector_impl(&self.uri, connector, &self.opts);
+        }
+
+        #[cfg(any(feature = "tls", feature = "rustls"))]
+        if let Some((cert_path, verify)) = &self.tls {
+            let host = self
+                .uri
+                .strip_prefix("tcp://")
+                .or_else(|| self.uri.strip_prefix("https://"))
+                .ok_or_else(|| {
+                    Error::InvalidHost(format!(
+                        "TLS is only supported for TCP hosts, got `{}`",
+                        self.uri
+                    ))
+                })?;
+            return Docker::new_tls_impl(host, &self.opts, cert_path, *verify);
+        }
+        Docker::new_impl(&self.uri, &self.opts)
+    }
+}
+
+impl DockerBuilder {
+    /// Returns the connector for the daemon at `uri` and the URI it has to be called with.
+    fn host_connector(&self, uri: &str) -> Result<(hyper::Uri, BoxedConnector)> {
+        let parse = |uri: String| {
+            uri.parse::<hyper::Uri>()
+                .map_err(|e| Error::from(conn::Error::InvalidUri(e)))
+        };
+        match Host::parse(uri)? {
+            #[cfg(unix)]
+            Host::Unix(path) => Ok((
+                hyperlocal::Uri::new(path, "/").into(),
+                BoxedConnector::new(get_unix_connector()),
+            )),
+            #[cfg(unix)]
+            Host::Fd(_) => Err(Error::UnsupportedScheme("fd".to_string())),
+            #[cfg(windows)]
+            Host::NamedPipe(path) => Ok((
+                hyper::Uri::from_static("http://docker.example.com"),
+                BoxedConnector::new(NamedPipeConnector::new(
+                    crate::transport::named_pipe_path(path),
+                    self.opts.runtime(),
+                )),
+            )),
+            #[cfg(feature = "ssh")]
+            Host::Ssh => {
+                let host = url::Url::parse(uri).map_err(Error::InvalidUrl)?;
+                Ok((
+                    hyper::Uri::from_static("http://docker.example.com"),
+                    BoxedConnector::new(SshConnector::new(&host)?),
+                ))
+            }
+            Host::Tcp(host) => {
+                #[cfg(any(feature = "tls", feature = "rustls"))]
+                if let Some((cert_path, verify)) = &self.tls {
+                    let http = self.opts.http_connector();
+                    let connector = match self.opts.proxy(true) {
+                        Some(proxy) => BoxedConnector::new(get_https_connector(
+                            cert_path,
+                            *verify,
+                            ProxyConnector::new(http, proxy),
+                        )?),
+                        None => BoxedConnector::new(get_https_connector(cert_path, *verify, http)?),
+                    };
+                    return Ok((parse(format!("https://{host}"))?, connector));
+                }
+                let http = self.opts.http_connector();
+                let connector = match self.opts.proxy(false) {
+                    Some(proxy) => BoxedConnector::new(ProxyConnector::new(http, proxy)),
+                    None => BoxedConnector::new(http),
+                };
+                Ok((parse(format!("http://{host}"))?, connector))
+            }
+        }
+    }
+}
+
+/// Address of a daemon parsed from a host URI like `unix:///var/run/docker.sock`, shared by
+/// [`Docker::new`](Docker::new) and the hosts of [`DockerBuilder`](DockerBuilder).
+enum Host<'a> {
+    #[cfg(unix)]
+    Unix(&'a str),
+    /// A file descriptor number, or the first socket passed by systemd if empty.
+    #[cfg(unix)]
+    Fd(&'a str),
+    #[cfg(windows)]
+    NamedPipe(&'a str),
+    Tcp(&'a str),
+    #[cfg(feature = "ssh")]
+    Ssh,
+}
+
+impl<'a> Host<'a> {
+    /// Parses `uri`, failing for schemes that are not supported on this platform or with the
+    /// enabled features and for URIs without an address.
+    fn parse(uri: &'a str) -> Result<Self> {
+        let (scheme, address) = uri
+            .split_once("://")
+            .ok_or_else(|| Error::UnsupportedScheme(uri.to_string()))?;
+        let host = match scheme {
+            #[cfg(unix)]
+            "unix" => Host::Unix(address),
+            #[cfg(unix)]
+            "fd" => return Ok(Host::Fd(address)),
+            #[cfg(windows)]
+            "npipe" => Host::NamedPipe(address),
+            "tcp" | "http" => Host::Tcp(address),
+            #[cfg(feature = "ssh")]
+            "ssh" => Host::Ssh,
+            scheme => return Err(Error::UnsupportedScheme(scheme.to_string())),
+        };
+        if address.is_empty() {
+            return Err(Error::MissingAuthority);
+        }
+        Ok(host)
+    }
+}
+
+/// Policy for retrying failed requests set with [`DockerBuilder::retry`](DockerBuilder::retry).
+///
+/// Requests are retried when connecting to the daemon fails or when it answers with
+/// `429 Too Many Requests`, `502 Bad Gateway` or `503 Service Unavailable`. The delay before a
+/// retry grows exponentially with a random jitter so that many clients don't retry at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Retries a request up to `max_retries` times, waiting 100 milliseconds before the first
+    /// retry and at most 10 seconds between retries.
+    pub fn new(max_retries: u32) -> Self {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// Delay before the first retry, doubled for every following retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Maximum delay between retries.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Maximum number of times a request is retried.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay before retry number `retry`, counted from 0. It is a random duration
+    /// between half and the whole of the exponential backoff.
+    fn backoff(&self, retry: u32) -> Duration {
+        use std::hash::{BuildHasher, Hasher};
+
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u32(retry);
+        let jitter = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+        backoff / 2 + backoff.mul_f64(jitter / 2.0)
+    }
+
+    fn is_retryable(error: &Error) -> bool {
+        match error {
+            Error::Transport(e) => matches!(
+                e.kind(),
+                TransportErrorKind::ConnectionRefused
+                    | TransportErrorKind::SocketNotFound
+                    | TransportErrorKind::Connect
+            ),
+            Error::Fault { code, .. } | Error::ServerError { code, .. } => {
+                matches!(code.as_u16(), 429 | 502 | 503)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Limit of the rate of requests set with
+/// [`DockerBuilder::rate_limit`](DockerBuilder::rate_limit), enforced with a token bucket.
+///
+/// A client can send up to `burst` requests at once, after that requests are spread so that at
+/// most `per_second` requests are sent every second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Allows `per_second` requests every second, with a burst of as many requests.
+    ///
+    /// Panics if `per_second` is not a positive number.
+    pub fn new(per_second: f64) -> Self {
+        assert!(
+            per_second.is_finite() && per_second > 0.0,
+            "the rate of requests must be positive"
+        );
+        RateLimit {
+            per_second,
+            burst: (per_second.ceil() as u32).max(1),
+        }
+    }
+
+    /// Number of requests that can be sent at once after the client was idle, at least 1.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// Token bucket shared by the clones of a client.
+#[derive(Debug)]
+struct RateLimiter {
+    limit: RateLimit,
+    /// Available tokens, negative when requests are waiting for tokens, and when they were
+    /// last refilled.
+    tokens: std::sync::Mutex<(f64, std::time::Instant)>,
+}
+
+impl RateLimiter {
+    fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            tokens: std::sync::Mutex::new((limit.burst as f64, std::time::Instant::now())),
+        }
+    }
+
+    /// Takes a token, waiting on `runtime` until it is refilled if there is none left.
+    async fn acquire(&self, runtime: &dyn Runtime) {
+        if let Some(wait) = self.take(std::time::Instant::now()) {
+            runtime.sleep(wait).await;
+        }
+    }
+
+    /// Takes a token at `now`, returning how long to wait until it is refilled if there is none
+    /// left.
+    fn take(&self, now: std::time::Instant) -> Option<Duration> {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        let (available, refilled) = &mut *tokens;
+        *available = (*available
+            + now.saturating_duration_since(*refilled).as_secs_f64() * self.limit.per_second)
+            .min(self.limit.burst as f64)
+            - 1.0;
+        *refilled = now.max(*refilled);
+        (*available < 0.0).then(|| Duration::from_secs_f64(-*available / self.limit.per_second))
+    }
+}
+
+/// Options of the connection to the daemon set with [`DockerBuilder`](DockerBuilder).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClientOptions {
+    version: Option<ApiVersion>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    proxy: ProxyOption,
+    headers: Arc<header::HeaderMap>,
+    rate_limit: Option<RateLimit>,
+    runtime: Option<Arc<dyn Runtime>>,
+    stream_idle_timeout: Option<Duration>,
+    deserialize_mode: DeserializeMode,
+}
+
+/// Proxy used for TCP hosts.
+#[derive(Debug, Clone, Default)]
+enum ProxyOption {
+    /// Read from `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`.
+    Env,
+    Explicit(Proxy),
+    #[default]
+    Disabled,
+}
+
+impl ClientOptions {
+    fn versioned(version: ApiVersion) -> Self {
+        ClientOptions {
+            version: Some(version),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the runtime of the client, tokio unless another one was set.
+    fn runtime(&self) -> Arc<dyn Runtime> {
+        self.runtime
+            .clone()
+            .unwrap_or_else(|| Arc::new(TokioRuntime))
+    }
+
+    fn client<C>(&self, connector: C) -> TransportClient<C>
+    where
+        C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+    {
+        TransportClient::new(&self.client_builder(), connector)
+    }
+
+    fn client_builder(&self) -> hyper::client::Builder {
+        let mut builder = Client::builder();
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder.pool_idle_timeout(timeout);
+        }
+        if let Some(runtime) = &self.runtime {
+            builder.executor(Executor(runtime.clone()));
+            // idle connections are expired with a tokio timer
+            if self.pool_idle_timeout.is_none() {
+                builder.pool_idle_timeout(None);
+            }
+        }
+        builder
+    }
+
+    /// Returns the proxy used to connect to a TCP host.
+    fn proxy(&self, encrypted: bool) -> Option<Proxy> {
+        match &self.proxy {
+            ProxyOption::Env => Proxy::from_env(encrypted, |name| std::env::var(name).ok()),
+            ProxyOption::Explicit(proxy) => Some(proxy.clone()),
+            ProxyOption::Disabled => None,
+        }
+    }
+
+    fn http_connector(&self) -> hyper::client::HttpConnector {
+        let mut http = get_http_connector();
+        http.set_keepalive(self.tcp_keepalive);
+        http.set_connect_timeout(self.connect_timeout);
+        http
+    }
+}
+
+/// Handling of response fields unknown to the models, which a daemon newer than the models
+/// may return. Set with [`DockerBuilder::deserialize_mode`](DockerBuilder::deserialize_mode) or
+/// [`Docker::with_deserialize_mode`](Docker::with_deserialize_mode).
+///
+/// Only whole responses, like those of inspect and list endpoints, are checked. Fields are
+/// detected by serializing the model again, so other modes than `Lenient` cost an extra
+/// serialization of every response.
+#[derive(Clone, Default)]
+pub enum DeserializeMode {
+    /// Unknown fields are ignored.
+    #[default]
+    Lenient,
+    /// Unknown fields are passed to the callback with the type name of the model, keyed by their
+    /// dot separated path in the response like `State.NewField`.
+    Capture(Arc<UnknownFieldsCallback>),
+    /// Responses with unknown fields fail with
+    /// [`Error::UnknownFields`](Error::UnknownFields).
+    Strict,
+}
+
+/// Callback of [`DeserializeMode::Capture`](DeserializeMode::Capture).
+pub type UnknownFieldsCallback =
+    dyn Fn(&str, &serde_json::Map<String, serde_json::Value>) + Send + Sync;
+
+impl DeserializeMode {
+    /// Passes unknown fields to `callback`, see [`Capture`](DeserializeMode::Capture).
+    pub fn capture<F>(callback: F) -> Self
+    where
+        F: Fn(&str, &serde_json::Map<String, serde_json::Value>) + Send + Sync + 'static,
+    {
+        DeserializeMode::Capture(Arc::new(callback))
+    }
+}
+
+impl std::fmt::Debug for DeserializeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DeserializeMode::Lenient => "Lenient",
+            DeserializeMode::Capture(_) => "Capture",
+            DeserializeMode::Strict => "Strict",
+        })
+    }
+}
+
+/// Converts the body of `payload` into a [`Body`](Body) so that its size can be inspected.
+fn into_body_payload<B: Into<Body>>(payload: Payload<B>) -> Payload<Body> {
+    match payload {
+        Payload::None => Payload::None,
+        Payload::Text(body) => Payload::Text(body.into()),
+        Payload::Json(body) => Payload::Json(body.into()),
+        Payload::XTar(body) => Payload::XTar(body.into()),
+        Payload::Tar(body) => Payload::Tar(body.into()),
+    }
+}
+
+/// Returns whether the body of `payload` is streamed, i.e. its size isn't known upfront.
+fn is_streamed(payload: &Payload<Body>) -> bool {
+    match payload {
+        Payload::None => false,
+        Payload::Text(body) | Payload::Json(body) | Payload::XTar(body) | Payload::Tar(body) => {
+            hyper::body::HttpBody::size_hint(body).exact().is_none()
+        }
+    }
+}
+
+/// Collects the fields of `value` that are missing from `known`, the same value deserialized
+/// into a model and serialized again. Null fields are skipped as the models omit them.
+fn unknown_fields(
+    path: &str,
+    value: &serde_json::Value,
+    known: &serde_json::Value,
+    fields: &mut serde_json::Map<String, serde_json::Value>,
+) {
+    use serde_json::Value;
+
+    let join = |key: &dyn std::fmt::Display| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+    match (value, known) {
+        (Value::Object(value), Value::Object(known)) => {
+            for (key, value) in value {
+                match known.get(key) {
+                    Some(known) => unknown_fields(&join(key), value, known, fields),
+                    None if !value.is_null() => {
+                        fields.insert(join(key), value.clone());
+                    }
+                    None => {}
+                }
+            }
+        }
+        (Value::Array(values), Value::Array(known)) => {
+            for (i, (value, known)) in values.iter().zip(known).enumerate() {
+                unknown_fields(&join(&i), value, known, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Callback invoked with the metadata of every non-streaming response.
+#[derive(Clone)]
+struct ResponseHook(Arc<dyn Fn(&ResponseMeta) + Send + Sync>);
+
+impl std::fmt::Debug for ResponseHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResponseHook")
+    }
 }
 
 impl Docker {
@@ -39,8 +768,13 @@ impl Docker {
     ///
     /// Supported schemes are:
     ///  - `unix://` only works when build target is `unix`, otherwise returns an Error
+    ///  - `npipe://` only works when build target is `windows`, otherwise returns an Error
     ///  - `tcp://`
     ///  - `http://`
+    ///  - `ssh://` only works with the `ssh` feature enabled, otherwise returns an Error
+    ///  - `fd://` only works when build target is `unix`, otherwise returns an Error. `fd://3`
+    ///    connects over the socket with file descriptor 3, `fd://` over the first socket passed
+    ///    by systemd socket activation. See [`Docker::from_fd`](Docker::from_fd)
     ///
     ///  To create a Docker instance utilizing TLS use explicit [Docker::tls](Docker::tls)
     ///  constructor (this requires `tls` feature enabled).
@@ -48,38 +782,51 @@ impl Docker {
     ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
     ///  [`Docker::unix_versioned`](Docker::unix_versioned).
     pub fn new(uri: impl AsRef<str>) -> Result<Self> {
-        Self::new_impl(uri.as_ref(), None)
+        Self::new_impl(uri.as_ref(), &ClientOptions::default())
     }
 
     /// Same as [`Docker::new`](Docker::new) but the API version can be explicitly specified.
     pub fn new_versioned(uri: impl AsRef<str>, version: impl Into<ApiVersion>) -> Result<Self> {
-        Self::new_impl(uri.as_ref(), Some(version.into()))
+        Self::new_impl(uri.as_ref(), &ClientOptions::versioned(version.into()))
     }
 
-    fn new_impl(uri: &str, version: Option<ApiVersion>) -> Result<Self> {
-        let mut it = uri.split("://");
+    /// Same as [`Docker::new_versioned`](Docker::new_versioned) with the API version given as a
+    /// string like `v1.41` or `1.41`, every request path is prefixed with it.
+    ///
+    /// Returns an error if the version is malformed or the URI is not supported.
+    pub fn versioned(uri: impl AsRef<str>, version: impl AsRef<str>) -> Result<Self> {
+        let version = parse_env_version(Some(version.as_ref().to_string()))?;
+        Self::new_impl(
+            uri.as_ref(),
+            &ClientOptions {
+                version,
+                ..Default::default()
+            },
+        )
+    }
 
-        match it.next() {
+    fn new_impl(uri: &str, opts: &ClientOptions) -> Result<Self> {
+        match Host::parse(uri)? {
             #[cfg(unix)]
-            Some("unix") => {
-                if let Some(path) = it.next() {
-                    Ok(Self::new_unix_impl(path, version))
-                } else {
-                    Err(Error::MissingAuthority)
-                }
-            }
-            #[cfg(not(unix))]
-            Some("unix") => Err(Error::UnsupportedScheme("unix".to_string())),
-            Some("tcp") | Some("http") => {
-                if let Some(host) = it.next() {
-                    Self::new_tcp_impl(host, version)
-                } else {
-                    Err(Error::MissingAuthority)
-                }
+            Host::Unix(path) => Ok(Self::new_unix_impl(path, opts)),
+            #[cfg(unix)]
+            Host::Fd(fd) => {
+                let fd = fd_from_uri(fd, |name| std::env::var(name).ok())?;
+                // SAFETY: the descriptor is only borrowed to duplicate it, which fails if it is
+                // not open, so that it stays owned by whoever passed it
+                let fd = unsafe { std::os::unix::io::BorrowedFd::borrow_raw(fd) }
+                    .try_clone_to_owned()
+                    .map_err(|e| Error::InvalidHost(format!("file descriptor {fd}: {e}")))?;
+                Self::new_fd_impl(fd, uri.to_string(), opts)
             }
-            Some(scheme) => Err(Error::UnsupportedScheme(scheme.to_string())),
-            None => unreachable!(), // This is never possible because calling split on an empty string
-                                    // always returns at least one element
+            #[cfg(windows)]
+            Host::NamedPipe(path) => Ok(Self::new_named_pipe_impl(
+                crate::transport::named_pipe_path(path),
+                opts,
+            )),
+            Host::Tcp(host) => Self::new_tcp_impl(host, opts),
+            #[cfg(feature = "ssh")]
+            Host::Ssh => Self::new_ssh_impl(uri, opts),
         }
     }
 
@@ -93,41 +840,130 @@ impl Docker {
     ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
     ///  [`Docker::unix_versioned`](Docker::unix_versioned).
     pub fn unix(socket_path: impl AsRef<Path>) -> Self {
-        Self::new_unix_impl(socket_path.as_ref(), None)
+        Self::new_unix_impl(socket_path.as_ref(), &ClientOptions::default())
     }
 
     #[cfg(unix)]
     #[cfg_attr(docsrs, doc(cfg(unix)))]
     /// Same as [`Docker::unix`](Docker::unix) but the API version can be explicitly specified.
     pub fn unix_versioned(socket_path: impl AsRef<Path>, version: impl Into<ApiVersion>) -> Self {
-        Self::new_unix_impl(socket_path.as_ref(), Some(version.into()))
+        Self::new_unix_impl(
+            socket_path.as_ref(),
+            &ClientOptions::versioned(version.into()),
+        )
     }
 
     #[cfg(unix)]
-    fn new_unix_impl(socket_path: impl Into<PathBuf>, version: Option<ApiVersion>) -> Self {
-        Docker {
-            version,
-            client: RequestClient::new(
-                Transport::Unix {
-                    client: Client::builder()
-                        .pool_max_idle_per_host(0)
-                        .build(get_unix_connector()),
-                    path: socket_path.into(),
-                },
-                Box::new(validate_response),
-            ),
+    fn new_unix_impl(socket_path: impl Into<PathBuf>, opts: &ClientOptions) -> Self {
+        Self::new_with_transport(
+            Transport::Unix {
+                client: opts.client(get_unix_connector()),
+                path: socket_path.into(),
+            },
+            opts,
+        )
+    }
+
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    /// Creates a new docker instance talking to the daemon over an already connected socket, for
+    /// example one passed by systemd socket activation or created by a test harness.
+    ///
+    /// The socket is used by the first connection, which is kept open while idle. Once it is
+    /// closed, or if more connections are needed, like for concurrent requests or attaching to a
+    /// container, they are made to the Unix socket the passed socket was connected to. If it has
+    /// no address, like one end of a socket pair, requests that need a new connection fail.
+    ///
+    /// Returns an error if the socket can't be switched to non-blocking mode.
+    ///
+    ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
+    ///  [`Docker::from_fd_versioned`](Docker::from_fd_versioned).
+    pub fn from_fd(fd: impl Into<std::os::unix::io::OwnedFd>) -> Result<Self> {
+        let fd = fd.into();
+        let uri = format!("fd://{}", std::os::unix::io::AsRawFd::as_raw_fd(&fd));
+        Self::new_fd_impl(fd, uri, &ClientOptions::default())
+    }
+
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    /// Same as [`Docker::from_fd`](Docker::from_fd) but the API version can be explicitly
+    /// specified.
+    pub fn from_fd_versioned(
+        fd: impl Into<std::os::unix::io::OwnedFd>,
+        version: impl Into<ApiVersion>,
+    ) -> Result<Self> {
+        let fd = fd.into();
+        let uri = format!("fd://{}", std::os::unix::io::AsRawFd::as_raw_fd(&fd));
+        Self::new_fd_impl(fd, uri, &ClientOptions::versioned(version.into()))
+    }
+
+    #[cfg(unix)]
+    fn new_fd_impl(
+        fd: std::os::unix::io::OwnedFd,
+        uri: String,
+        opts: &ClientOptions,
+    ) -> Result<Self> {
+        let mut builder = opts.client_builder();
+        // the passed socket may be the only connection to the daemon
+        if opts.pool_idle_timeout.is_none() {
+            builder.pool_idle_timeout(None);
         }
+        Ok(Self::new_with_transport(
+            Transport::Fd {
+                client: TransportClient::new(&builder, FdConnector::new(fd)?),
+                uri,
+            },
+            opts,
+        ))
+    }
+
+    #[cfg(windows)]
+    #[cfg_attr(docsrs, doc(cfg(windows)))]
+    /// Creates a new docker instance for a docker host listening on a given Windows named pipe,
+    /// for example `\\.\pipe\docker_engine` used by Docker Desktop.
+    ///
+    ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
+    ///  [`Docker::named_pipe_versioned`](Docker::named_pipe_versioned).
+    pub fn named_pipe(pipe_path: impl AsRef<Path>) -> Self {
+        Self::new_named_pipe_impl(pipe_path.as_ref(), &ClientOptions::default())
+    }
+
+    #[cfg(windows)]
+    #[cfg_attr(docsrs, doc(cfg(windows)))]
+    /// Same as [`Docker::named_pipe`](Docker::named_pipe) but the API version can be explicitly
+    /// specified.
+    pub fn named_pipe_versioned(
+        pipe_path: impl AsRef<Path>,
+        version: impl Into<ApiVersion>,
+    ) -> Self {
+        Self::new_named_pipe_impl(
+            pipe_path.as_ref(),
+            &ClientOptions::versioned(version.into()),
+        )
+    }
+
+    #[cfg(windows)]
+    fn new_named_pipe_impl(pipe_path: impl Into<PathBuf>, opts: &ClientOptions) -> Self {
+        let path = pipe_path.into();
+        Self::new_with_transport(
+            Transport::NamedPipe {
+                client: opts.client(NamedPipeConnector::new(path.clone(), opts.runtime())),
+                path,
+            },
+            opts,
+        )
     }
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[cfg(any(feature = "tls", feature = "rustls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls"))))]
     /// Creates a new docker instance for a docker host listening on a given TCP socket `host`.
     /// `host` is the part of URI that comes after `tcp://` or `http://` or `https://` schemes,
     /// also known as authority part.
     ///
     /// `cert_path` specifies the base path in the filesystem containing a certificate (`cert.pem`)
-    /// and a key (`key.pem`) that will be used by the client. If verify is `true` a CA file will be
-    /// added (`ca.pem`) to the connector.
+    /// and a key (`key.pem`) that will be used by the client. If verify is `true` the certificate
+    /// and hostname of the daemon are verified against the CA in `ca.pem`, like `docker --tlsverify`.
+    /// Otherwise the connection is encrypted but the daemon is not verified, like `docker --tls`.
     ///
     /// Returns an error if the provided host will fail to parse as URL or reading the certificate
     /// files will fail.
@@ -135,11 +971,16 @@ impl Docker {
     ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
     ///  [`Docker::unix_versioned`](Docker::unix_versioned).
     pub fn tls(host: impl AsRef<str>, cert_path: impl AsRef<Path>, verify: bool) -> Result<Self> {
-        Self::new_tls_impl(host.as_ref(), None, cert_path.as_ref(), verify)
+        Self::new_tls_impl(
+            host.as_ref(),
+            &ClientOptions::default(),
+            cert_path.as_ref(),
+            verify,
+        )
     }
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[cfg(any(feature = "tls", feature = "rustls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tls", feature = "rustls"))))]
     /// Same as [`Docker::tls`](Docker::tls) but the API version can be explicitly specified.
     pub fn tls_versioned(
         host: impl AsRef<str>,
@@ -149,29 +990,39 @@ impl Docker {
     ) -> Result<Self> {
         Self::new_tls_impl(
             host.as_ref(),
-            Some(version.into()),
+            &ClientOptions::versioned(version.into()),
             cert_path.as_ref(),
             verify,
         )
     }
 
-    #[cfg(feature = "tls")]
+    #[cfg(any(feature = "tls", feature = "rustls"))]
     fn new_tls_impl(
         host: &str,
-        version: Option<ApiVersion>,
+        opts: &ClientOptions,
         cert_path: &Path,
         verify: bool,
     ) -> Result<Self> {
-        Ok(Self {
-            version,
-            client: RequestClient::new(
-                Transport::EncryptedTcp {
-                    client: Client::builder().build(get_https_connector(cert_path, verify)?),
-                    host: url::Url::parse(&format!("https://{host}")).map_err(Error::InvalidUrl)?,
-                },
-                Box::new(validate_response),
-            ),
-        })
+        let host = url::Url::parse(&format!("https://{host}")).map_err(Error::InvalidUrl)?;
+        let transport = match opts.proxy(true) {
+            Some(proxy) => Transport::Custom {
+                client: opts.client(BoxedConnector::new(get_https_connector(
+                    cert_path,
+                    verify,
+                    ProxyConnector::new(opts.http_connector(), proxy),
+                )?)),
+                host,
+            },
+            None => Transport::EncryptedTcp {
+                client: opts.client(get_https_connector(
+                    cert_path,
+                    verify,
+                    opts.http_connector(),
+                )?),
+                host,
+            },
+        };
+        Ok(Self::new_with_transport(transport, opts))
     }
 
     /// Creates a new docker instance for a docker host listening on a given TCP socket `host`.
@@ -180,54 +1031,362 @@ impl Docker {
     ///
     /// TLS is supported with feature `tls` enabled through [Docker::tls](Docker::tls) constructor.
     ///
+    /// The host is reached directly, use [`DockerBuilder::proxy`](DockerBuilder::proxy) or
+    /// [`DockerBuilder::proxy_from_env`](DockerBuilder::proxy_from_env) to connect through a
+    /// proxy.
+    ///
     /// Returns an error if the provided host will fail to parse as URL.
     ///  
     ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
     ///  [`Docker::unix_versioned`](Docker::unix_versioned).
     pub fn tcp(host: impl AsRef<str>) -> Result<Self> {
-        Self::new_tcp_impl(host.as_ref(), None)
+        Self::new_tcp_impl(host.as_ref(), &ClientOptions::default())
     }
 
     /// Same as [`Docker::tcp`](Docker::tcp) but the API version can be explicitly specified.
     pub fn tcp_versioned(host: impl AsRef<str>, version: impl Into<ApiVersion>) -> Result<Self> {
-        Self::new_tcp_impl(host.as_ref(), Some(version.into()))
-    }
-
-    fn new_tcp_impl(host: &str, version: Option<ApiVersion>) -> Result<Self> {
-        Ok(Self {
-            version,
-            client: RequestClient::new(
-                Transport::Tcp {
-                    client: Client::builder().build(get_http_connector()),
-                    host: url::Url::parse(&format!("tcp://{host}")).map_err(Error::InvalidUrl)?,
-                },
-                Box::new(validate_response),
-            ),
-        })
+        Self::new_tcp_impl(host.as_ref(), &ClientOptions::versioned(version.into()))
     }
 
-    /// Exports an interface for interacting with Docker images
-    pub fn images(&'_ self) -> Images {
-        Images::new(self.clone())
+    fn new_tcp_impl(host: &str, opts: &ClientOptions) -> Result<Self> {
+        let host = url::Url::parse(&format!("tcp://{host}")).map_err(Error::InvalidUrl)?;
+        let transport = match opts.proxy(false) {
+            Some(proxy) => Transport::Custom {
+                client: opts.client(BoxedConnector::new(ProxyConnector::new(
+                    opts.http_connector(),
+                    proxy,
+                ))),
+                host,
+            },
+            None => Transport::Tcp {
+                client: opts.client(opts.http_connector()),
+                host,
+            },
+        };
+        Ok(Self::new_with_transport(transport, opts))
     }
 
-    /// Exports an interface for interacting with Docker containers
-    pub fn containers(&'_ self) -> Containers {
-        Containers::new(self.clone())
+    #[cfg(feature = "ssh")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ssh")))]
+    /// Creates a new docker instance for a remote docker host reached over SSH. `destination` is
+    /// the part of URI that comes after `ssh://`, for example `user@host` or `user@host:2222`.
+    ///
+    /// The API is tunneled through `docker system dial-stdio` ran on the remote host by the `ssh`
+    /// binary, so the docker CLI has to be installed there. Authentication is handled by `ssh`
+    /// and its configuration, like keys loaded in the SSH agent or `~/.ssh/config`.
+    ///
+    /// Returns an error if the provided destination will fail to parse as URL.
+    pub fn ssh(destination: impl AsRef<str>) -> Result<Self> {
+        Self::new_ssh_impl(
+            &format!("ssh://{}", destination.as_ref()),
+            &ClientOptions::default(),
+        )
     }
 
-    /// Exports an interface for interacting with Docker networks
-    pub fn networks(&'_ self) -> Networks {
-        Networks::new(self.clone())
+    #[cfg(feature = "ssh")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ssh")))]
+    /// Same as [`Docker::ssh`](Docker::ssh) but the API version can be explicitly specified.
+    pub fn ssh_versioned(
+        destination: impl AsRef<str>,
+        version: impl Into<ApiVersion>,
+    ) -> Result<Self> {
+        Self::new_ssh_impl(
+            &format!("ssh://{}", destination.as_ref()),
+            &ClientOptions::versioned(version.into()),
+        )
     }
 
-    /// Exports an interface for interacting with Docker volumes
-    pub fn volumes(&'_ self) -> Volumes {
-        Volumes::new(self.clone())
+    #[cfg(feature = "ssh")]
+    fn new_ssh_impl(uri: &str, opts: &ClientOptions) -> Result<Self> {
+        let host = url::Url::parse(uri).map_err(Error::InvalidUrl)?;
+        Ok(Self::new_with_transport(
+            Transport::Ssh {
+                client: opts.client(SshConnector::new(&host)?),
+                host,
+            },
+            opts,
+        ))
     }
 
-    /// Verifies the API version returned by the server and adjusts the version used by this client
-    /// in future requests.
+    /// Creates a new docker instance configured by the environment variables used by the docker
+    /// CLI:
+    ///  - `DOCKER_HOST` is the URI of the daemon passed to [`Docker::new`](Docker::new), it
+    ///    defaults to [`DEFAULT_HOST`](DEFAULT_HOST)
+    ///  - `DOCKER_CERT_PATH` is the directory with `ca.pem`, `cert.pem` and `key.pem`, it
+    ///    defaults to `~/.docker`. TCP connections are made with TLS if this variable or
+    ///    `DOCKER_TLS_VERIFY` is set, this requires the `tls` or `rustls` feature
+    ///  - `DOCKER_TLS_VERIFY` if non empty requires the daemon certificate to be verified, see
+    ///    [`Docker::tls`](Docker::tls)
+    ///  - `DOCKER_API_VERSION` pins the API version used by the client, for example `1.41`
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_impl(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+    }
+
+    fn from_env_impl(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let opts = ClientOptions {
+            version: parse_env_version(var("DOCKER_API_VERSION"))?,
+            ..Default::default()
+        };
+        let host = var("DOCKER_HOST").unwrap_or_else(|| DEFAULT_HOST.to_string());
+        let cert_path = var("DOCKER_CERT_PATH");
+        let verify = var("DOCKER_TLS_VERIFY").is_some();
+
+        let tcp_host = host
+            .strip_prefix("tcp://")
+            .or_else(|| host.strip_prefix("https://"));
+        match tcp_host {
+            Some(tcp_host) if verify || cert_path.is_some() || host.starts_with("https://") => {
+                #[cfg(any(feature = "tls", feature = "rustls"))]
+                {
+                    let cert_path = cert_path
+                        .map(PathBuf::from)
+                        .or_else(|| home_dir().map(|home| home.join(".docker")))
+                        .ok_or_else(|| Error::InvalidHost("DOCKER_CERT_PATH is not set".into()))?;
+                    Self::new_tls_impl(tcp_host, &opts, &cert_path, verify)
+                }
+                #[cfg(not(any(feature = "tls", feature = "rustls")))]
+                {
+                    let _ = tcp_host;
+                    Err(Error::InvalidHost(format!(
+                        "TLS is required to connect to `{host}` but neither the `tls` nor the `rustls` feature is enabled"
+                    )))
+                }
+            }
+            _ => Self::new_impl(&host, &opts),
+        }
+    }
+
+    /// Creates a new docker instance for the endpoint of the docker context `name`, created with
+    /// `docker context create`. The `default` context is configured by the environment like
+    /// [`Docker::from_env`](Docker::from_env).
+    ///
+    /// Contexts are read from the `contexts` directory in `DOCKER_CONFIG` or `~/.docker`. TLS
+    /// material stored with the context requires the `tls` or `rustls` feature.
+    pub fn from_context(name: impl AsRef<str>) -> Result<Self> {
+        let name = name.as_ref();
+        if name == context::DEFAULT_CONTEXT {
+            return Self::from_env();
+        }
+        let config_dir = context::config_dir()
+            .ok_or_else(|| Error::not_found(format!("docker context `{name}`")))?;
+        let endpoint = context::find(&config_dir, name)?;
+        let opts = ClientOptions {
+            version: parse_env_version(
+                std::env::var("DOCKER_API_VERSION")
+                    .ok()
+                    .filter(|v| !v.is_empty()),
+            )?,
+            ..Default::default()
+        };
+
+        match (endpoint.tls_path, endpoint.host.strip_prefix("tcp://")) {
+            #[cfg(any(feature = "tls", feature = "rustls"))]
+            (Some(tls_path), Some(host)) => {
+                Self::new_tls_impl(host, &opts, &tls_path, endpoint.verify)
+            }
+            #[cfg(not(any(feature = "tls", feature = "rustls")))]
+            (Some(_), Some(_)) => Err(Error::InvalidHost(format!(
+                "docker context `{name}` requires TLS but neither the `tls` nor the `rustls` feature is enabled"
+            ))),
+            _ => Self::new_impl(&endpoint.host, &opts),
+        }
+    }
+
+    /// Creates a new docker instance for the current docker context, like the docker CLI does.
+    ///
+    /// The context is `DOCKER_CONTEXT` if set, otherwise the `default` context if `DOCKER_HOST`
+    /// is set, otherwise the context selected with `docker context use`.
+    pub fn from_current_context() -> Result<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let name = match (var("DOCKER_CONTEXT"), var("DOCKER_HOST")) {
+            (Some(name), _) => Some(name),
+            (None, Some(_)) => None,
+            (None, None) => match context::config_dir() {
+                Some(dir) => context::current(&dir)?,
+                None => None,
+            },
+        };
+        Self::from_context(name.as_deref().unwrap_or(context::DEFAULT_CONTEXT))
+    }
+
+    /// Creates a new docker instance sending requests over connections established by
+    /// `connector`, for example to go through a custom proxy, use an in-memory transport in
+    /// tests or a socket type not supported by this crate.
+    ///
+    /// `connector` is a hyper connector, a service returning a connection to the daemon when
+    /// called with the URI `host`, like `http://docker`. Requests are sent to paths of `host`.
+    ///
+    /// Returns an error if the provided host will fail to parse as URL.
+    ///
+    ///  This creates an unversioned connector that'll use the latest server version, to use a specific version see
+    ///  [`Docker::from_connector_versioned`](Docker::from_connector_versioned).
+    pub fn from_connector<C>(host: impl AsRef<str>, connector: C) -> Result<Self>
+    where
+        C: hyper::service::Service<hyper::Uri> + Clone + Send + Sync + 'static,
+        C::Response: tokio::io::AsyncRead
+            + tokio::io::AsyncWrite
+            + hyper::client::connect::Connection
+            + Send
+            + Unpin
+            + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::new_connector_impl(
+            host.as_ref(),
+            BoxedConnector::new(connector),
+            &ClientOptions::default(),
+        )
+    }
+
+    /// Same as [`Docker::from_connector`](Docker::from_connector) but the API version can be
+    /// explicitly specified.
+    pub fn from_connector_versioned<C>(
+        host: impl AsRef<str>,
+        connector: C,
+        version: impl Into<ApiVersion>,
+    ) -> Result<Self>
+    where
+        C: hyper::service::Service<hyper::Uri> + Clone + Send + Sync + 'static,
+        C::Response: tokio::io::AsyncRead
+            + tokio::io::AsyncWrite
+            + hyper::client::connect::Connection
+            + Send
+            + Unpin
+            + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::new_connector_impl(
+            host.as_ref(),
+            BoxedConnector::new(connector),
+            &ClientOptions::versioned(version.into()),
+        )
+    }
+
+    /// Creates a new Docker instance answering requests with the canned responses of `mock`
+    /// instead of connecting to a daemon, so code using this crate can be unit tested. See
+    /// [`MockTransport`](crate::conn::MockTransport).
+    #[cfg(any(test, feature = "mock"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+    pub fn from_mock(mock: crate::conn::MockTransport) -> Self {
+        Self::new_with_transport(Transport::Mock { mock }, &ClientOptions::default())
+    }
+
+    fn new_connector_impl(
+        host: &str,
+        connector: BoxedConnector,
+        opts: &ClientOptions,
+    ) -> Result<Self> {
+        Ok(Self::new_with_transport(
+            Transport::Custom {
+                client: opts.client(connector),
+                host: url::Url::parse(host).map_err(Error::InvalidUrl)?,
+            },
+            opts,
+        ))
+    }
+
+    /// Returns a builder for a client connecting to `uri`, see [`Docker::new`](Docker::new) for
+    /// supported schemes, with a customized connection.
+    pub fn builder(uri: impl Into<String>) -> DockerBuilder {
+        DockerBuilder::new(uri)
+    }
+
+    fn new_with_transport(transport: Transport, opts: &ClientOptions) -> Self {
+        Docker {
+            version: opts.version,
+            transport,
+            response_hook: None,
+            timeout: opts.timeout,
+            retry: opts.retry,
+            headers: opts.headers.clone(),
+            rate_limiter: opts
+                .rate_limit
+                .map(|limit| Arc::new(RateLimiter::new(limit))),
+            runtime: opts.runtime(),
+            stream_idle_timeout: opts.stream_idle_timeout,
+            deserialize_mode: opts.deserialize_mode.clone(),
+            cancellation: None,
+        }
+    }
+
+    /// Sets the timeout of requests made through this client, overriding the timeout set with
+    /// [`DockerBuilder::timeout`](DockerBuilder::timeout). Pass `None` to disable it.
+    ///
+    /// Clients are cheap to clone so a different timeout can be used for a single call, for
+    /// example `docker.clone().with_timeout(Duration::from_secs(5)).containers().list(&opts)`.
+    pub fn with_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    /// Sets the idle period after which the liveness of streams is checked, overriding the one
+    /// set with [`DockerBuilder::stream_idle_timeout`](DockerBuilder::stream_idle_timeout). Pass
+    /// `None` to disable the check.
+    pub fn with_stream_idle_timeout(mut self, idle: impl Into<Option<Duration>>) -> Self {
+        self.stream_idle_timeout = idle.into();
+        self
+    }
+
+    /// Sets how fields of responses that the models don't know are handled, overriding the mode
+    /// set with [`DockerBuilder::deserialize_mode`](DockerBuilder::deserialize_mode).
+    pub fn with_deserialize_mode(mut self, mode: DeserializeMode) -> Self {
+        self.deserialize_mode = mode;
+        self
+    }
+
+    /// Makes requests, streams and attached connections of this client fail with
+    /// [`Error::Cancelled`](Error::Cancelled) and close their connection the next time they are
+    /// polled once `token` is cancelled. Pass `None` to stop observing a token.
+    ///
+    /// Like the timeout, this is meant to be set on a clone of the client used for long-running
+    /// operations like builds, pulls, attach, events and waits, for example
+    /// `docker.clone().with_cancellation(token.clone()).containers().get(id).wait()`.
+    pub fn with_cancellation(mut self, token: impl Into<Option<CancellationToken>>) -> Self {
+        self.cancellation = token.into();
+        self
+    }
+
+    /// Returns the timeout of requests made through this client.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Registers a callback invoked with the [`ResponseMeta`] of every response received by this
+    /// client, for example to log a warning whenever the daemon flags a deprecated endpoint.
+    ///
+    /// Streaming and upgraded responses (logs, events, attach, etc.) are not passed to the hook.
+    pub fn with_response_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ResponseMeta) + Send + Sync + 'static,
+    {
+        self.response_hook = Some(ResponseHook(Arc::new(hook)));
+        self
+    }
+
+    /// Exports an interface for interacting with Docker images
+    pub fn images(&'_ self) -> Images {
+        Images::new(self.clone())
+    }
+
+    /// Exports an interface for interacting with Docker containers
+    pub fn containers(&'_ self) -> Containers {
+        Containers::new(self.clone())
+    }
+
+    /// Exports an interface for interacting with Docker networks
+    pub fn networks(&'_ self) -> Networks {
+        Networks::new(self.clone())
+    }
+
+    /// Exports an interface for interacting with Docker volumes
+    pub fn volumes(&'_ self) -> Volumes {
+        Volumes::new(self.clone())
+    }
+
+    /// Verifies the API version returned by the server and adjusts the version used by this client
+    /// in future requests.
     pub async fn adjust_api_version(&mut self) -> Result<()> {
         let server_version: ApiVersion = self.version().await.and_then(|v| {
             v.api_version
@@ -241,6 +1400,76 @@ impl Docker {
         Ok(())
     }
 
+    /// Pings the server and pins the API version used by this client in future requests to the
+    /// lower of [`LATEST_API_VERSION`](crate::LATEST_API_VERSION) and the version served by the
+    /// daemon. Returns the negotiated version.
+    ///
+    /// The ping is unversioned so that a client pinned to a version newer than the daemon's can
+    /// negotiate down.
+    pub async fn negotiate_version(&mut self) -> Result<ApiVersion> {
+        let mut unversioned = self.clone();
+        unversioned.version = None;
+        let server_version: ApiVersion = unversioned
+            .ping()
+            .await?
+            .api_version
+            .parse()
+            .map_err(Error::MalformedVersion)?;
+
+        let version = std::cmp::min(crate::LATEST_API_VERSION, server_version);
+        self.version = Some(version);
+
+        Ok(version)
+    }
+
+    /// Returns the API version used by this client or `None` if requests are unversioned and use
+    /// the latest version served by the daemon.
+    pub fn api_version(&self) -> Option<ApiVersion> {
+        self.version
+    }
+
+    /// Pins the API version used by this client in future requests, or makes requests
+    /// unversioned if `version` is `None`.
+    ///
+    /// Interfaces like [`Containers`](Containers) hold their own copy of the client, so they
+    /// have to be created again to use the new version.
+    pub fn set_api_version(&mut self, version: impl Into<Option<ApiVersion>>) {
+        self.version = version.into();
+    }
+
+    /// Fails with [`UnsupportedApiVersion`](Error::UnsupportedApiVersion) if this client is
+    /// pinned to an API version older than `required`. Unversioned requests always pass as the
+    /// daemon serves them with its latest version.
+    pub(crate) fn require_version(
+        &self,
+        feature: &'static str,
+        required: ApiVersion,
+    ) -> Result<()> {
+        match self.version {
+            Some(version) if version < required => Err(Error::UnsupportedApiVersion {
+                feature,
+                required,
+                version,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the parameters of options that are set against the minimum API versions of
+    /// `versions`.
+    pub(crate) fn require_params<'a>(
+        &self,
+        params: impl IntoIterator<Item = &'a &'static str>,
+        versions: crate::opts::ParamVersions,
+    ) -> Result<()> {
+        params.into_iter().try_for_each(|param| {
+            versions
+                .iter()
+                .filter(|(name, _)| name == param)
+                .try_for_each(|(name, required)| self.require_version(name, *required))
+        })
+    }
+
     //####################################################################################################
     //
     // Utility functions to make requests
@@ -248,6 +1477,11 @@ impl Docker {
     //####################################################################################################
 
     fn make_endpoint(&self, endpoint: impl AsRef<str>) -> String {
+        // libpod endpoints of podman are versioned independently of the Docker API, without a
+        // version the latest one is used
+        if endpoint.as_ref().starts_with("/libpod/") {
+            return endpoint.as_ref().to_owned();
+        }
         if let Some(version) = self.version {
             version.make_endpoint(endpoint)
         } else {
@@ -255,15 +1489,270 @@ impl Docker {
         }
     }
 
+    /// Passes the metadata of `response` to the registered hook, if any.
+    fn inspect_response(&self, response: Response<Body>) -> Response<Body> {
+        if let Some(hook) = &self.response_hook {
+            (hook.0)(&ResponseMeta::from(response.headers()));
+        }
+        response
+    }
+
+    /// Builds a request to the versioned `endpoint` with the content type of `body`.
+    fn build_request<B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Request<Body>>
+    where
+        B: Into<Body>,
+    {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(self.transport.make_uri(&self.make_endpoint(endpoint))?)
+            .header(header::HOST, "");
+        for (key, val) in headers.into_iter().flatten() {
+            request = request.header(key, val);
+        }
+        if let Some(mime) = body.mime_type() {
+            request = request.header(header::CONTENT_TYPE, mime.to_string());
+        }
+        let body = body
+            .into_inner()
+            .map(Into::into)
+            .unwrap_or_else(Body::empty);
+        let mut request = request.body(body)?;
+        self.add_default_headers(&mut request);
+        Ok(request)
+    }
+
+    /// Adds the default headers of the client that are not set by `request`.
+    fn add_default_headers(&self, request: &mut Request<Body>) {
+        let headers = request.headers_mut();
+        for name in self.headers.keys() {
+            if !headers.contains_key(name) {
+                for value in self.headers.get_all(name) {
+                    headers.append(name, value.clone());
+                }
+            }
+        }
+    }
+
+    /// Sends a request and retries it according to the retry policy of the client if it is
+    /// idempotent and has no body.
+    async fn send_retrying<B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Response<Body>>
+    where
+        B: Into<Body>,
+    {
+        let retry = self.retry.filter(|_| {
+            body.is_none() && matches!(method, Method::GET | Method::HEAD | Method::DELETE)
+        });
+        let Some(retry) = retry else {
+            let request = self.build_request(method, endpoint, body, headers)?;
+            return self.send_request(request).await;
+        };
+
+        let mut retries = 0;
+        loop {
+            let request =
+                self.build_request(method.clone(), endpoint, Payload::empty(), headers.clone())?;
+            match self.send_request(request).await {
+                Err(e) if retries < retry.max_retries && RetryPolicy::is_retryable(&e) => {
+                    let backoff = retry.backoff(retries);
+                    log::debug!("retrying {method} {endpoint} in {backoff:?} after: {e}");
+                    self.runtime.sleep(backoff).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub(crate) fn runtime(&self) -> &dyn Runtime {
+        &*self.runtime
+    }
+
+    /// Returns the liveness check of streams if it is enabled.
+    fn liveness(&self) -> Option<Liveness> {
+        self.stream_idle_timeout
+            .map(|idle| Liveness::new(self.clone(), idle))
+    }
+
+    /// Fails with [`Error::Timeout`](Error::Timeout) if `future` doesn't complete within
+    /// `timeout` on the runtime of the client.
+    async fn within<F: std::future::Future>(
+        &self,
+        timeout: Duration,
+        future: F,
+    ) -> Result<F::Output> {
+        futures_util::pin_mut!(future);
+        match futures_util::future::select(future, self.runtime.sleep(timeout)).await {
+            futures_util::future::Either::Left((output, _)) => Ok(output),
+            futures_util::future::Either::Right(_) => Err(Error::Timeout(timeout)),
+        }
+    }
+
+    /// Fails with [`Error::Cancelled`](Error::Cancelled) if the cancellation token of the client
+    /// is cancelled before `future` completes, dropping the future.
+    async fn cancellable<T>(
+        &self,
+        future: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some(token) = &self.cancellation else {
+            return future.await;
+        };
+        futures_util::pin_mut!(future);
+        match futures_util::future::select(future, token.cancelled()).await {
+            futures_util::future::Either::Left((output, _)) => output,
+            futures_util::future::Either::Right(_) => Err(Error::Cancelled),
+        }
+    }
+
+    /// Sends `request` over the transport of this client.
+    #[cfg(not(feature = "tracing"))]
+    async fn transport_request(&self, request: Request<Body>) -> Result<Response<Body>> {
+        self.transport.request(request).await
+    }
+
+    /// Sends `request` over the transport of this client within a span of the request.
+    #[cfg(feature = "tracing")]
+    async fn transport_request(&self, request: Request<Body>) -> Result<Response<Body>> {
+        crate::trace::send(&self.transport, self.version, request).await
+    }
+
+    /// Sends `request` and converts an error status of the response into an [`Error`](Error).
+    async fn send_request(&self, request: Request<Body>) -> Result<Response<Body>> {
+        self.cancellable(async {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(&*self.runtime).await;
+            }
+            let response = self.transport_request(request).await?;
+            validate_response(response).await
+        })
+        .await
+    }
+
+    /// Sends a request and, if the client has a timeout, reads the whole response within it.
+    ///
+    /// Requests with a streamed body, like a tarball read from a reader, aren't subject to the
+    /// timeout as uploading them takes as long as the stream produces data.
+    async fn send<B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Response<Body>>
+    where
+        B: Into<Body>,
+    {
+        let body = into_body_payload(body);
+        let streamed = is_streamed(&body);
+        let response = match self.timeout.filter(|_| !streamed) {
+            Some(timeout) => {
+                let buffered = async {
+                    let (parts, body) = self
+                        .send_retrying(method, endpoint, body, headers)
+                        .await?
+                        .into_parts();
+                    let body = hyper::body::to_bytes(body).await?;
+                    Ok::<_, Error>(Response::from_parts(parts, Body::from(body)))
+                };
+                self.within(timeout, buffered).await??
+            }
+            None => self.send_retrying(method, endpoint, body, headers).await?,
+        };
+        Ok(self.inspect_response(response))
+    }
+
+    async fn response_string(&self, response: Response<Body>) -> Result<String> {
+        #[cfg(feature = "tracing")]
+        let span = crate::trace::span_of(&response);
+        let bytes = self
+            .cancellable(async {
+                hyper::body::to_bytes(response.into_body())
+                    .await
+                    .map_err(|e| conn::Error::from(e).into())
+            })
+            .await?;
+        #[cfg(feature = "tracing")]
+        crate::trace::response_body(span, &bytes);
+        String::from_utf8(bytes.to_vec())
+            .map_err(conn::Error::from)
+            .map_err(Error::from)
+    }
+
+    async fn response_json<T>(&self, response: Response<Body>) -> Result<T>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let raw_string = self.response_string(response).await?;
+        log::trace!("{raw_string}");
+        if let DeserializeMode::Lenient = self.deserialize_mode {
+            return serde_json::from_str::<T>(&raw_string).map_err(Error::from);
+        }
+
+        let value = serde_json::from_str::<serde_json::Value>(&raw_string)?;
+        let model = T::deserialize(&value)?;
+        let mut fields = serde_json::Map::new();
+        unknown_fields("", &value, &serde_json::to_value(&model)?, &mut fields);
+        if !fields.is_empty() {
+            let name = std::any::type_name::<T>();
+            match &self.deserialize_mode {
+                DeserializeMode::Capture(callback) => callback(name, &fields),
+                DeserializeMode::Strict => {
+                    return Err(Error::UnknownFields {
+                        model: name,
+                        fields: fields.keys().cloned().collect(),
+                    })
+                }
+                DeserializeMode::Lenient => {}
+            }
+        }
+        Ok(model)
+    }
+
     pub(crate) async fn get(&self, endpoint: &str) -> Result<Response<Body>> {
-        self.client.get(self.make_endpoint(endpoint)).await
+        self.send(Method::GET, endpoint, Payload::empty(), Headers::none())
+            .await
+    }
+
+    pub(crate) async fn get_json<T: DeserializeOwned + Serialize>(
+        &self,
+        endpoint: &str,
+    ) -> Result<T> {
+        self.response_json(self.get(endpoint).await?).await
+    }
+
+    /// Reads the JSON response of `endpoint` without deserializing it, only checking that it is
+    /// valid.
+    pub(crate) async fn get_raw_json(
+        &self,
+        endpoint: &str,
+    ) -> Result<Box<serde_json::value::RawValue>> {
+        let raw_string = self.response_string(self.get(endpoint).await?).await?;
+        serde_json::value::RawValue::from_string(raw_string).map_err(Error::from)
     }
 
-    pub(crate) async fn get_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
-        self.client.get_json(self.make_endpoint(endpoint)).await
+    #[cfg(feature = "swarm")]
+    pub(crate) async fn get_json_with_headers<T: DeserializeOwned + Serialize>(
+        &self,
+        endpoint: &str,
+        headers: Option<Headers>,
+    ) -> Result<T> {
+        let response = self
+            .send(Method::GET, endpoint, Payload::empty(), headers)
+            .await?;
+        self.response_json(response).await
     }
 
-    #[allow(dead_code)]
     pub(crate) async fn post<B>(
         &self,
         endpoint: &str,
@@ -273,9 +1762,7 @@ impl Docker {
     where
         B: Into<Body>,
     {
-        self.client
-            .post(self.make_endpoint(endpoint), body, headers)
-            .await
+        self.send(Method::POST, endpoint, body, headers).await
     }
 
     pub(crate) async fn post_string<B>(
@@ -287,8 +1774,7 @@ impl Docker {
     where
         B: Into<Body>,
     {
-        self.client
-            .post_string(self.make_endpoint(endpoint), body, headers)
+        self.response_string(self.post(endpoint, body, headers).await?)
             .await
     }
 
@@ -299,11 +1785,10 @@ impl Docker {
         headers: Option<Headers>,
     ) -> Result<T>
     where
-        T: DeserializeOwned,
+        T: DeserializeOwned + Serialize,
         B: Into<Body>,
     {
-        self.client
-            .post_json(self.make_endpoint(endpoint), body, headers)
+        self.response_json(self.post(endpoint.as_ref(), body, headers).await?)
             .await
     }
 
@@ -311,26 +1796,51 @@ impl Docker {
     where
         B: Into<Body>,
     {
-        self.client
-            .put_string(self.make_endpoint(endpoint), body)
-            .await
+        let response = self
+            .send(Method::PUT, endpoint, body, Headers::none())
+            .await?;
+        self.response_string(response).await
     }
 
     pub(crate) async fn delete(&self, endpoint: &str) -> Result<String> {
-        self.client
-            .delete_string(self.make_endpoint(endpoint))
-            .await
+        let response = self
+            .send(Method::DELETE, endpoint, Payload::empty(), Headers::none())
+            .await?;
+        self.response_string(response).await
     }
 
-    pub(crate) async fn delete_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
-        self.client.delete_json(self.make_endpoint(endpoint)).await
+    pub(crate) async fn delete_json<T: DeserializeOwned + Serialize>(
+        &self,
+        endpoint: &str,
+    ) -> Result<T> {
+        let response = self
+            .send(Method::DELETE, endpoint, Payload::empty(), Headers::none())
+            .await?;
+        self.response_json(response).await
     }
 
     pub(crate) async fn head(&self, endpoint: &str) -> Result<Response<Body>> {
-        self.client.head(self.make_endpoint(endpoint)).await
+        self.send(Method::HEAD, endpoint, Payload::empty(), Headers::none())
+            .await
+    }
+
+    /// Sends a request whose response body is streamed, these responses are not passed to the
+    /// response hook.
+    async fn send_streaming<B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> Result<Body>
+    where
+        B: Into<Body>,
+    {
+        self.send_retrying(method, endpoint, body, headers)
+            .await
+            .map(Response::into_body)
     }
 
-    #[allow(dead_code)]
     /// Send a streaming post request.
     ///
     /// Use stream_post_into_values if the endpoint returns JSON values
@@ -343,13 +1853,19 @@ impl Docker {
     where
         B: Into<Body> + 'a,
     {
-        self.client
-            .post_stream(self.make_endpoint(endpoint), body, headers)
+        let stream = async move {
+            self.send_streaming(Method::POST, endpoint.as_ref(), body, headers)
+                .await
+                .map(stream_body)
+        }
+        .try_flatten_stream();
+        Watched::new(stream, self.liveness(), self.cancellation.clone())
     }
 
     /// Send a streaming post request that returns a stream of JSON values
     ///
     /// When a received chunk does not contain a full JSON reads more chunks from the stream
+    #[cfg(feature = "fast-json")]
     pub(crate) fn post_into_stream<'a, B, T>(
         &'a self,
         endpoint: impl AsRef<str> + 'a,
@@ -360,15 +1876,62 @@ impl Docker {
         B: Into<Body> + 'a,
         T: DeserializeOwned + 'a,
     {
-        self.client
-            .post_into_stream(self.make_endpoint(endpoint), body, headers)
+        let stream = async move {
+            self.send_streaming(Method::POST, endpoint.as_ref(), body, headers)
+                .await
+                .map(stream_body)
+        }
+        .try_flatten_stream();
+        crate::json::decode(Watched::new(stream, None, self.cancellation.clone()))
+    }
+
+    /// Send a streaming post request that returns a stream of JSON values
+    ///
+    /// When a received chunk does not contain a full JSON reads more chunks from the stream
+    #[cfg(not(feature = "fast-json"))]
+    pub(crate) fn post_into_stream<'a, B, T>(
+        &'a self,
+        endpoint: impl AsRef<str> + 'a,
+        body: Payload<B>,
+        headers: Option<Headers>,
+    ) -> impl Stream<Item = Result<T>> + 'a
+    where
+        B: Into<Body> + 'a,
+        T: DeserializeOwned + 'a,
+    {
+        let stream = async move {
+            self.send_streaming(Method::POST, endpoint.as_ref(), body, headers)
+                .await
+                .map(crate::transport::stream_json_body)
+        }
+        .try_flatten_stream();
+        Watched::new(stream, None, self.cancellation.clone())
+            .and_then(|chunk| async move {
+                log::trace!("got chunk {:?}", chunk);
+                let values = serde_json::Deserializer::from_slice(&chunk)
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                Ok(futures_util::stream::iter(values).map_err(Error::from))
+            })
+            .try_flatten()
     }
 
     pub(crate) fn get_stream<'a>(
         &'a self,
         endpoint: impl AsRef<str> + Unpin + 'a,
     ) -> impl Stream<Item = Result<Bytes>> + 'a {
-        self.client.get_stream(self.make_endpoint(endpoint))
+        let stream = async move {
+            self.send_streaming(
+                Method::GET,
+                endpoint.as_ref(),
+                Payload::empty(),
+                Headers::none(),
+            )
+            .await
+            .map(stream_body)
+        }
+        .try_flatten_stream();
+        Watched::new(stream, self.liveness(), self.cancellation.clone())
     }
 
     pub(crate) async fn post_upgrade_stream<B>(
@@ -379,58 +1942,216 @@ impl Docker {
     where
         B: Into<Body>,
     {
-        let ep = self.make_endpoint(endpoint);
-        self.client.post_upgrade_stream(ep, body).await
+        self.upgrade_impl(
+            Method::POST,
+            endpoint.as_ref(),
+            "tcp",
+            Headers::none(),
+            body,
+        )
+        .await
+    }
+
+    async fn upgrade_impl<B>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        protocol: &str,
+        headers: Option<Headers>,
+        body: Payload<B>,
+    ) -> Result<crate::stream::Upgraded>
+    where
+        B: Into<Body>,
+    {
+        let mut upgrade = Headers::single("Connection", "Upgrade");
+        upgrade.add("Upgrade", protocol);
+        for (key, val) in headers.into_iter().flatten() {
+            upgrade.add(key, val);
+        }
+        let request = self.build_request(method, endpoint, body, Some(upgrade))?;
+        let response = self.send_request(request).await?;
+        if response.status() != hyper::StatusCode::SWITCHING_PROTOCOLS {
+            return Err(Error::ConnectionNotUpgraded);
+        }
+        hyper::upgrade::on(response)
+            .await
+            .map(|io| crate::stream::Upgraded::new(io, self.liveness(), self.cancellation.clone()))
+            .map_err(|e| TransportError::upgrade(self.transport.remote_addr(), e).into())
+    }
+
+    /// Sends a raw request to `endpoint` with the URL encoded pairs of `query` and returns the
+    /// response as is. This is an escape hatch for new or experimental daemon endpoints that this
+    /// crate doesn't cover yet.
+    ///
+    /// The `endpoint` is prefixed with the API version of this client. Unlike the typed
+    /// endpoints an error status is not converted into an [`Error`](Error), check
+    /// [`RawResponse::status`](RawResponse::status) instead. The timeout of the client only
+    /// applies until the headers of the response are received.
+    pub async fn request<K, V>(
+        &self,
+        method: conn::Method,
+        endpoint: impl AsRef<str>,
+        query: impl IntoIterator<Item = (K, V)>,
+        body: impl Into<Body>,
+    ) -> Result<RawResponse>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let endpoint = self.make_endpoint(
+            query
+                .into_iter()
+                .fold(Endpoint::new(endpoint.as_ref()), |ep, (k, v)| {
+                    ep.param(k.as_ref(), v.as_ref())
+                }),
+        );
+        let mut request = hyper::Request::builder()
+            .method(method)
+            .uri(self.transport.make_uri(&endpoint)?)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(body.into())?;
+        self.add_default_headers(&mut request);
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire(&*self.runtime).await;
+        }
+        let response = match self.timeout {
+            Some(timeout) => {
+                self.within(timeout, self.transport_request(request))
+                    .await??
+            }
+            None => self.transport_request(request).await?,
+        };
+        let response = self.inspect_response(response);
+        let (parts, body) = response.into_parts();
+        Ok(RawResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body,
+        })
+    }
+
+    /// Sends a request to `endpoint` asking the daemon to upgrade the connection to `protocol`
+    /// and returns the raw upgraded connection. This is an escape hatch for endpoints that hijack
+    /// the connection and aren't modeled by this crate yet. Hijacking endpoints like attach or
+    /// exec start use the `tcp` protocol while the session endpoint uses `h2c`.
+    ///
+    /// The `endpoint` is prefixed with the API version of this client and may include a query.
+    /// Fails with [`Error::ConnectionNotUpgraded`](Error::ConnectionNotUpgraded) if the daemon
+    /// answers without switching protocols.
+    pub async fn upgrade(
+        &self,
+        method: conn::Method,
+        endpoint: impl AsRef<str>,
+        protocol: &str,
+        headers: Option<Headers>,
+        body: impl Into<Body>,
+    ) -> Result<impl AsyncRead + AsyncWrite + Send + Unpin> {
+        self.upgrade_impl(
+            method,
+            endpoint.as_ref(),
+            protocol,
+            headers,
+            Payload::Text(body.into()),
+        )
+        .await
+    }
+}
+
+/// Parses the value of `DOCKER_API_VERSION`, like `1.41` or `v1.41`.
+fn parse_env_version(version: Option<String>) -> Result<Option<ApiVersion>> {
+    version
+        .map(|v| v.trim_start_matches('v').parse::<ApiVersion>())
+        .transpose()
+        .map_err(Error::MalformedVersion)
+}
+
+/// Home directory of the current user.
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let home = std::env::var_os("USERPROFILE");
+    #[cfg(not(windows))]
+    let home = std::env::var_os("HOME");
+    home.filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+/// Response of a raw request made with [`Docker::request`](Docker::request).
+#[derive(Debug)]
+pub struct RawResponse {
+    status: hyper::StatusCode,
+    headers: hyper::HeaderMap,
+    body: Body,
+}
+
+impl RawResponse {
+    /// HTTP status of the response.
+    pub fn status(&self) -> hyper::StatusCode {
+        self.status
+    }
+
+    /// Headers of the response.
+    pub fn headers(&self) -> &hyper::HeaderMap {
+        &self.headers
+    }
+
+    /// Reads the whole body of the response.
+    pub async fn bytes(self) -> Result<Bytes> {
+        hyper::body::to_bytes(self.body).await.map_err(Error::from)
+    }
+
+    /// Reads the whole body of the response and deserializes it from JSON.
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
+        serde_json::from_slice(&self.bytes().await?).map_err(Error::from)
+    }
+
+    /// Returns the body of the response as a stream of chunks, for endpoints that stream their
+    /// output.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes>> + Send + Unpin {
+        self.body.map_err(Error::from)
     }
 }
 
-fn validate_response(
-    response: Response<Body>,
-) -> Pin<Box<dyn Future<Output = Result<Response<Body>>> + Send + Sync>> {
+async fn validate_response(response: Response<Body>) -> Result<Response<Body>> {
     use serde::{Deserialize, Serialize};
     #[derive(Serialize, Deserialize)]
     struct ErrorResponse {
         message: String,
     }
 
-    Box::pin(async move {
-        log::trace!(
-            "got response {} {:?}",
-            response.status(),
-            response.headers()
-        );
-        let status = response.status();
-
-        use crate::conn::{self, hyper::StatusCode};
-        match status {
-            // Success case: pass on the response
-            StatusCode::OK
-            | StatusCode::CREATED
-            | StatusCode::SWITCHING_PROTOCOLS
-            | StatusCode::NO_CONTENT => Ok(response),
-            // Error case: try to deserialize error message
-            _ => {
-                let body = response.into_body();
-                let bytes = hyper::body::to_bytes(body)
-                    .await
-                    .map_err(conn::Error::from)?;
-                let message_body = String::from_utf8(bytes.to_vec()).map_err(conn::Error::from)?;
-                log::trace!("{message_body:#?}");
-                let message = serde_json::from_str::<ErrorResponse>(&message_body)
-                    .map(|e| e.message)
-                    .unwrap_or_else(|_| {
-                        status
-                            .canonical_reason()
-                            .unwrap_or("unknown error code")
-                            .to_owned()
-                    });
-                Err(Error::Fault {
-                    code: status,
-                    message,
-                })
-            }
+    log::trace!(
+        "got response {} {:?}",
+        response.status(),
+        response.headers()
+    );
+    let status = response.status();
+
+    use crate::conn::hyper::StatusCode;
+    match status {
+        // Success case: pass on the response
+        StatusCode::OK
+        | StatusCode::CREATED
+        | StatusCode::SWITCHING_PROTOCOLS
+        | StatusCode::NO_CONTENT => Ok(response),
+        // Error case: try to deserialize error message
+        _ => {
+            let body = response.into_body();
+            let bytes = hyper::body::to_bytes(body)
+                .await
+                .map_err(conn::Error::from)?;
+            let message_body = String::from_utf8(bytes.to_vec()).map_err(conn::Error::from)?;
+            log::trace!("{message_body:#?}");
+            let message = serde_json::from_str::<ErrorResponse>(&message_body)
+                .map(|e| e.message)
+                .unwrap_or_else(|_| match message_body.trim() {
+                    // a proxy in front of the daemon may answer with plain text
+                    "" => status
+                        .canonical_reason()
+                        .unwrap_or("unknown error code")
+                        .to_owned(),
+                    body => body.to_owned(),
+                });
+            Err(Error::from_status(status, message))
         }
-    })
+    }
 }
 
 #[cfg(feature = "swarm")]
@@ -471,9 +2192,772 @@ impl Docker {
     }
 }
 
+#[cfg(feature = "stack")]
+impl Docker {
+    /// Exports an interface for deploying and managing the stack `namespace`.
+    pub fn stack(&'_ self, namespace: impl Into<String>) -> Stack {
+        Stack::new(self.clone(), namespace)
+    }
+}
+
+#[cfg(feature = "podman")]
+impl Docker {
+    /// Exports an interface for interacting with podman pods, only available when connected to
+    /// podman.
+    pub fn pods(&'_ self) -> Pods {
+        Pods::new(self.clone())
+    }
+
+    /// Returns whether the daemon is podman, which serves the Docker API next to its own libpod
+    /// API.
+    pub async fn is_podman(&self) -> Result<bool> {
+        Ok(self
+            .version()
+            .await?
+            .components
+            .unwrap_or_default()
+            .iter()
+            .any(|component| component.name == "Podman Engine"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Docker, Error};
+    use super::{DeserializeMode, Docker, Error};
+    use crate::errors::TransportErrorKind;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[cfg(any(feature = "tls", feature = "rustls"))]
+    #[test]
+    fn tls_missing_files() {
+        let dir = std::env::temp_dir().join("docker-api-missing-certs");
+        match Docker::tls("127.0.0.1:2376", &dir, true) {
+            Err(Error::IO(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+                assert!(e.to_string().contains("cert.pem"));
+            }
+            other => panic!("expected missing file error, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn rustls_invalid_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["ca.pem", "cert.pem", "key.pem"] {
+            std::fs::write(dir.path().join(name), "not a certificate").unwrap();
+        }
+        match Docker::tls("127.0.0.1:2376", dir.path(), true) {
+            Err(Error::InvalidCertificate(msg)) => assert!(msg.contains("cert.pem")),
+            other => panic!("expected invalid certificate error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+
+        let docker = Docker::from_env_impl(env(&[
+            ("DOCKER_HOST", "tcp://127.0.0.1:2375"),
+            ("DOCKER_API_VERSION", "1.41"),
+        ]))
+        .unwrap();
+        assert_eq!(docker.transport.remote_addr(), "tcp://127.0.0.1:2375");
+        assert_eq!(
+            docker.api_version(),
+            Some(crate::ApiVersion::new(1, Some(41), None))
+        );
+
+        let docker = Docker::from_env_impl(env(&[])).unwrap();
+        assert_eq!(docker.api_version(), None);
+        #[cfg(unix)]
+        assert_eq!(docker.transport.remote_addr(), "/var/run/docker.sock");
+
+        assert!(matches!(
+            Docker::from_env_impl(env(&[("DOCKER_API_VERSION", "latest")])),
+            Err(Error::MalformedVersion(_))
+        ));
+
+        let tls = Docker::from_env_impl(env(&[
+            ("DOCKER_HOST", "tcp://127.0.0.1:2376"),
+            ("DOCKER_TLS_VERIFY", "1"),
+            ("DOCKER_CERT_PATH", "/nonexistent/docker-api-certs"),
+        ]));
+        #[cfg(any(feature = "tls", feature = "rustls"))]
+        assert!(matches!(tls, Err(Error::IO(_))));
+        #[cfg(not(any(feature = "tls", feature = "rustls")))]
+        assert!(matches!(tls, Err(Error::InvalidHost(_))));
+    }
+
+    /// Serves every connection in memory, answering requests with `OK` on keep-alive
+    /// connections and switching protocols on upgrade requests. The first `failures` connection
+    /// attempts are refused, the attempts and the closed connections are counted.
+    #[derive(Clone, Default)]
+    struct TestConnector {
+        failures: usize,
+        attempts: Arc<AtomicUsize>,
+        closed: Arc<AtomicUsize>,
+    }
+
+    impl hyper::service::Service<hyper::Uri> for TestConnector {
+        type Response = InMemoryIo;
+        type Error = std::io::Error;
+        type Future = std::future::Ready<std::io::Result<InMemoryIo>>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: hyper::Uri) -> Self::Future {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return std::future::ready(Err(std::io::ErrorKind::ConnectionRefused.into()));
+            }
+            let closed = self.closed.clone();
+            let (client, mut server) = tokio::io::duplex(4096);
+            tokio::spawn(async move {
+                let mut buf = [0; 4096];
+                while let Ok(n @ 1..) = server.read(&mut buf).await {
+                    let upgrade = buf[..n]
+                        .windows(8)
+                        .any(|w| w.eq_ignore_ascii_case(b"upgrade:"));
+                    let response: &[u8] = if upgrade {
+                        b"HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: tcp\r\n\r\n"
+                    } else {
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK"
+                    };
+                    if server.write_all(response).await.is_err() {
+                        break;
+                    }
+                }
+                closed.fetch_add(1, Ordering::SeqCst);
+            });
+            std::future::ready(Ok(InMemoryIo(client)))
+        }
+    }
+
+    struct InMemoryIo(tokio::io::DuplexStream);
+
+    impl tokio::io::AsyncRead for InMemoryIo {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl tokio::io::AsyncWrite for InMemoryIo {
+        fn poll_write(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            std::pin::Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_flush(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.0).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.0).poll_shutdown(cx)
+        }
+    }
+
+    impl hyper::client::connect::Connection for InMemoryIo {
+        fn connected(&self) -> hyper::client::connect::Connected {
+            hyper::client::connect::Connected::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_connector() {
+        let docker = Docker::from_connector_versioned(
+            "http://docker",
+            TestConnector::default(),
+            crate::ApiVersion::new(1, Some(41), None),
+        )
+        .unwrap();
+        let response = docker
+            .request(
+                crate::conn::Method::GET,
+                "/_ping",
+                Vec::<(&str, &str)>::new(),
+                hyper::Body::empty(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+        assert_eq!(response.bytes().await.unwrap(), "OK");
+    }
+
+    #[tokio::test]
+    async fn pooled_connections() {
+        let connector = TestConnector::default();
+        let docker = Docker::from_connector("http://docker", connector.clone()).unwrap();
+        let ping = || async {
+            let response = docker.get("/_ping").await.unwrap();
+            assert_eq!(hyper::body::to_bytes(response).await.unwrap(), "OK");
+            // give the connection time to return to the pool
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+
+        ping().await;
+        ping().await;
+        assert_eq!(connector.attempts.load(Ordering::SeqCst), 1);
+
+        let upgraded = docker
+            .clone()
+            .post_upgrade_stream("/containers/app/attach", crate::conn::Payload::empty())
+            .await
+            .unwrap();
+        assert_eq!(connector.attempts.load(Ordering::SeqCst), 2);
+        drop(upgraded);
+        for _ in 0..100 {
+            if connector.closed.load(Ordering::SeqCst) == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(connector.closed.load(Ordering::SeqCst), 1);
+
+        ping().await;
+        assert_eq!(connector.attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn negotiate_version() {
+        use crate::conn::{Method, MockResponse, MockTransport};
+        use hyper::header::HeaderName;
+
+        let ping = [
+            ("api-version", "1.41"),
+            ("docker-experimental", "false"),
+            ("cache-control", "no-cache"),
+            ("pragma", "no-cache"),
+            ("ostype", "linux"),
+            ("date", "Mon, 02 Jan 2023 03:04:05 GMT"),
+            ("server", "Docker/20.10.24 (linux)"),
+        ]
+        .into_iter()
+        .fold(
+            MockResponse::new(200).body("OK"),
+            |response, (name, value)| response.header(HeaderName::from_static(name), value),
+        );
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping", ping);
+        // pinned to a version newer than the one of the daemon, which rejects it
+        let mut docker = Docker::from_mock(mock.clone());
+        docker.set_api_version(crate::ApiVersion::new(1, Some(43), None));
+
+        let version = docker.negotiate_version().await.unwrap();
+        assert_eq!(version, crate::ApiVersion::new(1, Some(41), None));
+        assert_eq!(docker.api_version(), Some(version));
+        assert_eq!(mock.requests()[0].api_version(), None);
+    }
+
+    #[tokio::test]
+    async fn unsupported_api_version() {
+        use crate::conn::{Method, MockResponse, MockTransport};
+        use crate::opts::{ContainerStopOpts, Signal};
+
+        let mock = MockTransport::new();
+        mock.on(Method::POST, "/containers/web/stop", MockResponse::new(204));
+        let mut docker = Docker::from_mock(mock);
+        docker.set_api_version(crate::ApiVersion::new(1, Some(41), None));
+        let container = docker.containers().get("web");
+        let opts = ContainerStopOpts::builder().signal(Signal::Int).build();
+        match container.stop(&opts).await {
+            Err(Error::UnsupportedApiVersion {
+                feature,
+                required,
+                version,
+            }) => {
+                assert_eq!(feature, "signal");
+                assert_eq!(required, crate::ApiVersion::new(1, Some(42), None));
+                assert_eq!(version, crate::ApiVersion::new(1, Some(41), None));
+            }
+            other => panic!("expected Error::UnsupportedApiVersion, got {other:?}"),
+        }
+        container.stop(&Default::default()).await.unwrap();
+
+        docker.set_api_version(None);
+        docker.containers().get("web").stop(&opts).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn from_fd() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (client, server) = std::os::unix::net::UnixStream::pair().unwrap();
+        server.set_nonblocking(true).unwrap();
+        let mut server = tokio::net::UnixStream::from_std(server).unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0; 4096];
+            for _ in 0..2 {
+                let _ = server.read(&mut buf).await;
+                server
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK")
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let docker = Docker::from_fd(client).unwrap();
+        assert!(docker.transport.remote_addr().starts_with("fd://"));
+        // both requests go over the passed socket
+        for _ in 0..2 {
+            let response = docker.get("/_ping").await.unwrap();
+            assert_eq!(hyper::body::to_bytes(response).await.unwrap(), "OK");
+        }
+    }
+
+    #[tokio::test]
+    async fn daemon_errors() {
+        use crate::conn::{Method, MockResponse, MockTransport};
+        use hyper::StatusCode;
+
+        let get = |response: MockResponse| async move {
+            let mock = MockTransport::new();
+            mock.on(Method::GET, "/containers/missing/json", response);
+            Docker::from_mock(mock)
+                .get("/containers/missing/json")
+                .await
+                .unwrap_err()
+        };
+
+        match get(MockResponse::error(404, "No such container: missing")).await {
+            Error::NotFound { code, message } => {
+                assert_eq!(code, StatusCode::NOT_FOUND);
+                assert_eq!(message, "No such container: missing");
+            }
+            e => panic!("expected Error::NotFound, got {e:?}"),
+        }
+        assert!(matches!(
+            get(MockResponse::new(304)).await,
+            Error::NotModified { message, .. } if message == "Not Modified"
+        ));
+        assert!(matches!(
+            get(MockResponse::error(400, "a")).await,
+            Error::BadParameter { .. }
+        ));
+        assert!(matches!(
+            get(MockResponse::error(409, "a")).await,
+            Error::Conflict { .. }
+        ));
+        assert!(matches!(
+            get(MockResponse::new(502).body("proxy error")).await,
+            Error::ServerError { code: StatusCode::BAD_GATEWAY, message } if message == "proxy error"
+        ));
+        assert!(matches!(
+            get(MockResponse::new(401)).await,
+            Error::Fault {
+                code: StatusCode::UNAUTHORIZED,
+                ..
+            }
+        ));
+    }
+
+    /// Runs futures on tokio, counting the spawned tasks and timers.
+    #[derive(Clone, Debug, Default)]
+    struct CountingRuntime {
+        spawned: Arc<AtomicUsize>,
+        slept: Arc<AtomicUsize>,
+    }
+
+    impl crate::conn::Runtime for CountingRuntime {
+        fn spawn(&self, future: crate::conn::BoxFuture) {
+            self.spawned.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(future);
+        }
+
+        fn sleep(&self, duration: std::time::Duration) -> crate::conn::BoxFuture {
+            self.slept.fetch_add(1, Ordering::SeqCst);
+            Box::pin(tokio::time::sleep(duration))
+        }
+    }
+
+    #[tokio::test]
+    async fn deserialize_mode() {
+        use crate::conn::{Method, MockResponse, MockTransport};
+        use serde::{Deserialize, Serialize};
+        use std::sync::Mutex;
+
+        #[derive(Deserialize, Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Model {
+            id: String,
+            state: State,
+        }
+        #[derive(Deserialize, Serialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct State {
+            running: bool,
+        }
+
+        let mock = MockTransport::new();
+        mock.on(
+            Method::GET,
+            "/containers/a/json",
+            MockResponse::json(&serde_json::json!({
+                "Id": "a",
+                "State": {"Running": true, "Pid": 1},
+                "Extra": null,
+                "Labels": []
+            })),
+        );
+        let docker = Docker::from_mock(mock);
+        let model: Model = docker.get_json("/containers/a/json").await.unwrap();
+        assert_eq!(model.id, "a");
+
+        match docker
+            .clone()
+            .with_deserialize_mode(DeserializeMode::Strict)
+            .get_json::<Model>("/containers/a/json")
+            .await
+        {
+            Err(Error::UnknownFields { model, fields }) => {
+                assert!(model.ends_with("Model"));
+                assert_eq!(fields, ["Labels", "State.Pid"]);
+            }
+            _ => panic!("expected Error::UnknownFields"),
+        }
+
+        let captured = Arc::new(Mutex::new(None));
+        let docker = docker.with_deserialize_mode(DeserializeMode::capture({
+            let captured = Arc::clone(&captured);
+            move |_, fields| *captured.lock().unwrap() = Some(fields.clone())
+        }));
+        let model: Model = docker.get_json("/containers/a/json").await.unwrap();
+        assert!(model.state.running);
+        let captured = captured.lock().unwrap().take().unwrap();
+        assert_eq!(captured["State.Pid"], 1);
+        assert_eq!(captured["Labels"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn custom_runtime() {
+        let runtime = CountingRuntime::default();
+        let docker = Docker::builder("http://docker")
+            .runtime(runtime.clone())
+            .connector(TestConnector::default())
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let response = docker
+            .request(
+                crate::conn::Method::GET,
+                "/_ping",
+                Vec::<(&str, &str)>::new(),
+                hyper::Body::empty(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.bytes().await.unwrap(), "OK");
+        // the connection task and the timer of the timeout
+        assert_eq!(runtime.spawned.load(Ordering::SeqCst), 1);
+        assert_eq!(runtime.slept.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        use crate::conn::{Headers, Payload};
+
+        let connector = TestConnector {
+            failures: 2,
+            ..Default::default()
+        };
+        let attempts = connector.attempts.clone();
+        let docker = Docker::builder("http://docker")
+            .retry(super::RetryPolicy::new(2).initial_backoff(std::time::Duration::from_millis(1)))
+            .connector(connector)
+            // connect for every request so that the failures apply to it
+            .pool_max_idle_per_host(0)
+            .build()
+            .unwrap();
+        let response = docker.get("/_ping").await.unwrap();
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // requests that aren't idempotent are not retried
+        attempts.store(0, Ordering::SeqCst);
+        assert!(matches!(
+            docker
+                .post("/_ping", Payload::empty(), Headers::none())
+                .await,
+            Err(Error::Transport(e)) if e.kind() == TransportErrorKind::ConnectionRefused
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn transport_errors() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let docker = Docker::builder(format!("tcp://{addr}"))
+            .proxy(None)
+            .build()
+            .unwrap();
+        match docker.ping().await {
+            Err(Error::Transport(e)) => {
+                assert_eq!(e.kind(), TransportErrorKind::ConnectionRefused);
+                assert_eq!(e.endpoint(), format!("tcp://{addr}"));
+                assert!(e.to_string().contains("is the docker daemon running"));
+            }
+            other => panic!("expected transport error, got {other:?}"),
+        }
+
+        #[cfg(unix)]
+        {
+            let dir = tempfile::tempdir().unwrap();
+            let socket = dir.path().join("docker.sock");
+            match Docker::unix(&socket).ping().await {
+                Err(Error::Transport(e)) => {
+                    assert_eq!(e.kind(), TransportErrorKind::SocketNotFound);
+                    assert_eq!(e.endpoint(), socket.to_str().unwrap());
+                    assert!(e.hint().is_some());
+                }
+                other => panic!("expected transport error, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rate_limit() {
+        use std::time::Duration;
+
+        let limiter = super::RateLimiter::new(super::RateLimit::new(20.0).burst(2));
+        let start = limiter.tokens.lock().unwrap().1;
+        // milliseconds to wait for a token taken `millis` after the limiter was created
+        let wait = |millis| {
+            limiter
+                .take(start + Duration::from_millis(millis))
+                .map(|wait| (wait.as_secs_f64() * 1000.0).round() as u64)
+        };
+        assert_eq!(wait(0), None);
+        assert_eq!(wait(0), None);
+        // the next tokens are refilled every 50 milliseconds
+        assert_eq!(wait(0), Some(50));
+        assert_eq!(wait(10), Some(90));
+        assert_eq!(wait(150), None);
+        assert_eq!(wait(150), Some(50));
+    }
+
+    #[test]
+    fn retry_backoff() {
+        let policy = super::RetryPolicy::new(5).max_backoff(std::time::Duration::from_millis(300));
+        for (retry, max) in [(0, 100), (1, 200), (2, 300), (4, 300)] {
+            let backoff = policy.backoff(retry).as_millis();
+            assert!(backoff >= max / 2 && backoff <= max, "{retry}: {backoff}");
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        use crate::conn::{Method, MockResponse, MockTransport};
+
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping", MockResponse::pending());
+        let timeout = std::time::Duration::from_millis(50);
+        let docker = Docker::from_mock(mock).with_timeout(timeout);
+        assert_eq!(docker.timeout(), Some(timeout));
+        match docker.ping().await {
+            Err(Error::Timeout(t)) => assert_eq!(t, timeout),
+            other => panic!("expected Error::Timeout, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn streamed_payload() {
+        use super::{into_body_payload, is_streamed, Body, Bytes, Payload};
+
+        assert!(!is_streamed(&Payload::empty()));
+        assert!(!is_streamed(&into_body_payload(Payload::Json("{}"))));
+        let chunks = futures_util::stream::iter([Ok::<_, std::io::Error>(Bytes::from("tar"))]);
+        assert!(is_streamed(&Payload::XTar(Body::wrap_stream(chunks))));
+    }
+
+    #[test]
+    fn builder() {
+        let docker = Docker::builder("tcp://127.0.0.1:2375")
+            .version(crate::ApiVersion::new(1, Some(41), None))
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(std::time::Duration::from_secs(30))
+            .tcp_keepalive(std::time::Duration::from_secs(60))
+            .timeout(std::time::Duration::from_secs(10))
+            .connect_timeout(std::time::Duration::from_secs(1))
+            .build()
+            .unwrap();
+        assert_eq!(
+            docker.api_version(),
+            Some(crate::ApiVersion::new(1, Some(41), None))
+        );
+        assert_eq!(docker.timeout(), Some(std::time::Duration::from_secs(10)));
+        assert!(matches!(
+            Docker::builder("rand://127.0.0.1").build(),
+            Err(Error::UnsupportedScheme(_))
+        ));
+
+        #[cfg(any(feature = "tls", feature = "rustls"))]
+        assert!(matches!(
+            Docker::builder("unix:///var/run/docker.sock")
+                .tls("/certs", true)
+                .build(),
+            Err(Error::InvalidHost(_))
+        ));
+    }
+
+    #[test]
+    fn versioned() {
+        let mut docker = Docker::versioned("tcp://127.0.0.1:2375", "v1.41").unwrap();
+        assert_eq!(
+            docker.api_version(),
+            Some(crate::ApiVersion::new(1, Some(41), None))
+        );
+        assert_eq!(docker.make_endpoint("/_ping"), "/v1.41/_ping");
+
+        docker.set_api_version(crate::ApiVersion::from((1, 43)));
+        assert_eq!(docker.make_endpoint("/_ping"), "/v1.43/_ping");
+        docker.set_api_version(None);
+        assert_eq!(docker.make_endpoint("/_ping"), "/_ping");
+
+        // libpod endpoints have their own versions
+        let docker = Docker::versioned("tcp://127.0.0.1:2375", "v1.41").unwrap();
+        assert_eq!(
+            docker.make_endpoint("/libpod/pods/json"),
+            "/libpod/pods/json"
+        );
+
+        assert!(matches!(
+            Docker::versioned("tcp://127.0.0.1:2375", "latest"),
+            Err(Error::MalformedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn default_headers() {
+        use crate::conn::{Headers, Method, Payload};
+
+        let docker = Docker::builder("tcp://127.0.0.1:2375")
+            .user_agent("my-controller/1.0")
+            .default_header("X-Auth", "default")
+            .default_header("X-Tenant", "a")
+            .build()
+            .unwrap();
+        let request = docker
+            .build_request(
+                Method::GET,
+                "/_ping",
+                Payload::empty(),
+                Some(Headers::single("X-Auth", "override")),
+            )
+            .unwrap();
+        let headers = request.headers();
+        assert_eq!(headers["user-agent"], "my-controller/1.0");
+        assert_eq!(headers["x-tenant"], "a");
+        assert_eq!(
+            headers.get_all("x-auth").iter().collect::<Vec<_>>(),
+            ["override"]
+        );
+
+        assert!(matches!(
+            Docker::builder("tcp://127.0.0.1:2375")
+                .default_header("bad header", "a")
+                .build(),
+            Err(Error::Http(_))
+        ));
+    }
+
+    #[test]
+    fn builder_proxy() {
+        use crate::conn::{Proxy, Transport};
+
+        let proxy = Proxy::new("http://proxy.corp:3128").unwrap();
+        let docker = Docker::builder("tcp://docker.corp:2375")
+            .proxy(proxy)
+            .build()
+            .unwrap();
+        assert!(matches!(docker.transport, Transport::Custom { .. }));
+        assert_eq!(docker.transport.remote_addr(), "tcp://docker.corp:2375");
+
+        let docker = Docker::builder("tcp://docker.corp:2375")
+            .proxy(None)
+            .build()
+            .unwrap();
+        assert!(matches!(docker.transport, Transport::Tcp { .. }));
+    }
+
+    #[tokio::test]
+    async fn fallback_hosts() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // a port that was just released so that connecting to it is refused
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0; 4096];
+                    while stream.read(&mut buf).await.unwrap_or(0) > 0 {
+                        stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK")
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let docker = Docker::builder(format!("tcp://{closed}"))
+            .fallback_host(format!("tcp://{addr}"))
+            .proxy(None)
+            .pool_max_idle_per_host(0)
+            .build()
+            .unwrap();
+        assert_eq!(docker.transport.remote_addr(), format!("tcp://{closed}"));
+        for _ in 0..2 {
+            let response = docker.get("/_ping").await.unwrap();
+            assert_eq!(hyper::body::to_bytes(response).await.unwrap(), "OK");
+            assert_eq!(docker.transport.remote_addr(), format!("tcp://{addr}"));
+        }
+
+        assert!(matches!(
+            Docker::builder("tcp://127.0.0.1:2375")
+                .fallback_host("udp://127.0.0.1:2375")
+                .build(),
+            Err(Error::UnsupportedScheme(_))
+        ));
+    }
+
     #[test]
     fn creates_correct_docker() {
         let d = Docker::new("tcp://127.0.0.1:80");
@@ -496,6 +2980,26 @@ mod tests {
             }
         }
 
+        #[cfg(feature = "ssh")]
+        {
+            Docker::new("ssh://me@127.0.0.1:2222").unwrap();
+            match Docker::new("ssh://") {
+                Err(Error::MissingAuthority) => {}
+                other => panic!("expected Error::MissingAuthority, got {other:?}"),
+            }
+        }
+
+        #[cfg(not(windows))]
+        match Docker::new("npipe:////./pipe/docker_engine") {
+            Err(Error::UnsupportedScheme(scheme)) if scheme == "npipe" => {}
+            other => panic!(r#"Expected Error::UnsupportedScheme("npipe"), got {other:?}"#),
+        }
+
+        match Docker::new("tcp://") {
+            Err(Error::MissingAuthority) => {}
+            other => panic!("expected Error::MissingAuthority, got {other:?}"),
+        }
+
         let d = Docker::new("rand://127.0.0.1:80");
         match d.unwrap_err() {
             Error::UnsupportedScheme(scheme) if &scheme == "rand" => {}
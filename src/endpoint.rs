@@ -0,0 +1,144 @@
+//! Building of request endpoints with percent-encoded path segments and query parameters.
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use url::form_urlencoded;
+
+use std::ops::Deref;
+
+/// Characters encoded in path segments. `/` is kept because image, plugin and volume names may
+/// contain it and the daemon matches them with wildcard routes.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Endpoint of a request, built from a static path followed by encoded path segments and query
+/// parameters. Dereferences to the endpoint string so it can be passed to the request methods
+/// of [`Docker`](crate::Docker).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Endpoint {
+    ep: String,
+    has_query: bool,
+}
+
+impl Endpoint {
+    /// Starts an endpoint with `path`, which is used as is.
+    pub(crate) fn new(path: impl Into<String>) -> Self {
+        Self {
+            ep: path.into(),
+            has_query: false,
+        }
+    }
+
+    /// Appends `/` and the percent-encoded `segment` to the path, like the ID or name of an
+    /// object. Must be called before adding query parameters.
+    pub(crate) fn segment(mut self, segment: impl AsRef<str>) -> Self {
+        debug_assert!(!self.has_query, "path segment added after the query");
+        self.ep.push('/');
+        self.ep
+            .extend(utf8_percent_encode(segment.as_ref(), SEGMENT));
+        self
+    }
+
+    /// Appends `/` and `path` to the path as is.
+    pub(crate) fn path(mut self, path: &str) -> Self {
+        debug_assert!(!self.has_query, "path added after the query");
+        self.ep.push('/');
+        self.ep.push_str(path);
+        self
+    }
+
+    /// Adds the query parameter `key` with the encoded `value`.
+    pub(crate) fn param(mut self, key: &str, value: impl ToString) -> Self {
+        self.start_param();
+        let start = self.ep.len();
+        form_urlencoded::Serializer::for_suffix(&mut self.ep, start)
+            .append_pair(key, &value.to_string());
+        self
+    }
+
+    /// Adds the query parameter `key` if `value` is `Some`.
+    pub(crate) fn param_opt(self, key: &str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.param(key, value),
+            None => self,
+        }
+    }
+
+    /// Adds an already encoded query, like the one returned by `serialize` of URL options.
+    pub(crate) fn query(mut self, query: Option<impl AsRef<str>>) -> Self {
+        if let Some(query) = query.as_ref().map(AsRef::as_ref) {
+            if !query.is_empty() {
+                self.start_param();
+                self.ep.push_str(query);
+            }
+        }
+        self
+    }
+
+    fn start_param(&mut self) {
+        self.ep.push(if self.has_query { '&' } else { '?' });
+        self.has_query = true;
+    }
+}
+
+impl Deref for Endpoint {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.ep
+    }
+}
+
+impl AsRef<str> for Endpoint {
+    fn as_ref(&self) -> &str {
+        &self.ep
+    }
+}
+
+impl From<Endpoint> for String {
+    fn from(endpoint: Endpoint) -> Self {
+        endpoint.ep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Endpoint;
+
+    #[test]
+    fn special_characters() {
+        let ep = Endpoint::new("/containers")
+            .segment("my app#1?")
+            .path("archive")
+            .param("path", "/tmp/a b&c=d%");
+        assert_eq!(
+            "/containers/my%20app%231%3F/archive?path=%2Ftmp%2Fa+b%26c%3Dd%25",
+            &*ep
+        );
+
+        let ep = Endpoint::new("/images")
+            .segment("ghcr.io/org/app:1.0")
+            .path("json");
+        assert_eq!("/images/ghcr.io/org/app:1.0/json", &*ep);
+
+        let ep = Endpoint::new("/containers/create")
+            .param_opt("name", Some("web/1"))
+            .query(Some(r#"filters=%7B%22a%22%3A%5B%22b%22%5D%7D"#))
+            .param_opt("platform", None::<&str>)
+            .param("force", true);
+        assert_eq!(
+            "/containers/create?name=web%2F1&filters=%7B%22a%22%3A%5B%22b%22%5D%7D&force=true",
+            &*ep
+        );
+
+        assert_eq!("/build", &*Endpoint::new("/build").query(None::<String>));
+        assert_eq!("/build", &*Endpoint::new("/build").query(Some("")));
+    }
+}
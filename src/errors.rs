@@ -1,5 +1,6 @@
 //! Representations of various client errors
 
+use containers_api::version::ApiVersion;
 use hyper::{self, StatusCode};
 use serde_json::Error as SerdeError;
 use thiserror::Error as ThisError;
@@ -16,6 +17,8 @@ pub enum Error {
     #[error(transparent)]
     Hyper(#[from] hyper::Error),
     #[error(transparent)]
+    Transport(#[from] TransportError),
+    #[error(transparent)]
     Http(#[from] hyper::http::Error),
     #[error(transparent)]
     #[allow(clippy::upper_case_acronyms)]
@@ -24,12 +27,28 @@ pub enum Error {
     InvalidResponse(String),
     #[error("error {code} - {message}")]
     Fault { code: StatusCode, message: String },
+    #[error("Not modified - {message}")]
+    NotModified { code: StatusCode, message: String },
+    #[error("Bad parameter - {message}")]
+    BadParameter { code: StatusCode, message: String },
+    #[error("Conflict - {message}")]
+    Conflict { code: StatusCode, message: String },
+    #[error("Server error {code} - {message}")]
+    ServerError { code: StatusCode, message: String },
+    #[error("Request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("Stream stalled, no data was received for {0:?} and the daemon didn't answer a ping")]
+    StreamStalled(std::time::Duration),
+    #[error("The operation was cancelled")]
+    Cancelled,
     #[error("The HTTP connection was not upgraded by the docker host")]
     ConnectionNotUpgraded,
     #[error("Provided scheme `{0}` is not supported")]
     UnsupportedScheme(String),
     #[error("Provided URI is missing authority part after scheme")]
     MissingAuthority,
+    #[error("Invalid docker host - {0}")]
+    InvalidHost(String),
     #[error("Failed to parse url - {0}")]
     InvalidUrl(url::ParseError),
     #[error("Failed to parse uri - {0}")]
@@ -38,6 +57,38 @@ pub enum Error {
     InvalidPort(String),
     #[error("Invalid protocol - {0}")]
     InvalidProtocol(String),
+    #[error("Invalid image reference - {0}")]
+    InvalidReference(String),
+    #[error("Invalid TLS certificate - {0}")]
+    InvalidCertificate(String),
+    #[error("Invalid placement constraint - {0}")]
+    InvalidConstraint(String),
+    #[error("Privileges required by plugin `{0}` were not granted")]
+    PrivilegesNotGranted(String),
+    #[error("Not found - {message}")]
+    NotFound { code: StatusCode, message: String },
+    /// An object looked up by the client, like a network by its name, doesn't exist.
+    #[error("No such object - {0}")]
+    NoSuchObject(String),
+    #[error("Service did not converge - {0}")]
+    NotConverged(String),
+    #[error("Data of {size} bytes exceeds the maximum size of {max} bytes")]
+    DataTooLarge { size: usize, max: usize },
+    #[error("Response of `{model}` has fields unknown to the model: {}", .fields.join(", "))]
+    UnknownFields {
+        model: &'static str,
+        fields: Vec<String>,
+    },
+    #[error("{feature} requires API version {required} but the client uses {version}")]
+    UnsupportedApiVersion {
+        feature: &'static str,
+        required: ApiVersion,
+        version: ApiVersion,
+    },
+    #[error("Invalid byte size - {0}")]
+    InvalidByteSize(String),
+    #[error("Invalid compose file - {0}")]
+    InvalidCompose(String),
     #[error(transparent)]
     MalformedVersion(#[from] containers_api::version::Error),
     #[error(transparent)]
@@ -48,6 +99,244 @@ pub enum Error {
     StringError(String),
 }
 
+/// Failure to reach the daemon or to keep a connection to it, with the address of the daemon and
+/// a hint about the likely cause.
+#[derive(Debug)]
+pub struct TransportError {
+    kind: TransportErrorKind,
+    endpoint: String,
+    source: hyper::Error,
+}
+
+/// Cause of a [`TransportError`](TransportError).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransportErrorKind {
+    /// Nothing is listening on the address of the daemon.
+    ConnectionRefused,
+    /// The current user is not allowed to connect to the socket of the daemon.
+    PermissionDenied,
+    /// The socket of the daemon doesn't exist.
+    SocketNotFound,
+    /// The TLS handshake with the daemon failed.
+    TlsHandshake,
+    /// The connection couldn't be upgraded after the daemon switched protocols.
+    UpgradeFailed,
+    /// Connecting to the daemon failed for another reason.
+    Connect,
+}
+
+impl TransportError {
+    /// Classifies the connection error `source` that occurred while reaching `endpoint`.
+    pub(crate) fn connect(endpoint: impl Into<String>, source: hyper::Error) -> Self {
+        TransportError {
+            kind: TransportErrorKind::classify(&source),
+            endpoint: endpoint.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn upgrade(endpoint: impl Into<String>, source: hyper::Error) -> Self {
+        TransportError {
+            kind: TransportErrorKind::UpgradeFailed,
+            endpoint: endpoint.into(),
+            source,
+        }
+    }
+
+    /// The cause of this error.
+    pub fn kind(&self) -> TransportErrorKind {
+        self.kind
+    }
+
+    /// The address of the daemon, like a URL or the path of its socket.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// A suggestion to fix the error, if its cause is common.
+    pub fn hint(&self) -> Option<&'static str> {
+        use TransportErrorKind::*;
+        match self.kind {
+            ConnectionRefused => {
+                Some("is the docker daemon running and listening on this address?")
+            }
+            PermissionDenied => Some("is your user in the docker group?"),
+            SocketNotFound => {
+                Some("is the docker daemon running? Set DOCKER_HOST if it uses another socket")
+            }
+            TlsHandshake => {
+                Some("check that the daemon serves TLS with the configured certificates")
+            }
+            UpgradeFailed => {
+                Some("a proxy in front of the daemon may not support upgraded connections")
+            }
+            Connect => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} for `{}`: {}", self.kind, self.endpoint, self.source)?;
+        if let Some(hint) = self.hint() {
+            write!(f, " ({hint})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl TransportErrorKind {
+    fn classify(error: &hyper::Error) -> Self {
+        use std::io::ErrorKind;
+
+        let is_tls = |_e: &(dyn std::error::Error + 'static)| {
+            #[cfg(feature = "tls")]
+            if _e.is::<openssl::ssl::Error>() || _e.is::<openssl::error::ErrorStack>() {
+                return true;
+            }
+            #[cfg(feature = "rustls")]
+            if _e.is::<rustls::Error>() {
+                return true;
+            }
+            false
+        };
+
+        let mut source = std::error::Error::source(error);
+        while let Some(e) = source {
+            if is_tls(e) {
+                return TransportErrorKind::TlsHandshake;
+            }
+            if let Some(io) = e.downcast_ref::<std::io::Error>() {
+                // errors wrapped in an io error are not returned by its `source`
+                if io.get_ref().is_some_and(|inner| is_tls(inner)) {
+                    return TransportErrorKind::TlsHandshake;
+                }
+                match io.kind() {
+                    ErrorKind::ConnectionRefused => return TransportErrorKind::ConnectionRefused,
+                    ErrorKind::PermissionDenied => return TransportErrorKind::PermissionDenied,
+                    ErrorKind::NotFound => return TransportErrorKind::SocketNotFound,
+                    _ => {}
+                }
+            }
+            source = e.source();
+        }
+        TransportErrorKind::Connect
+    }
+}
+
+impl std::fmt::Display for TransportErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use TransportErrorKind::*;
+        f.write_str(match self {
+            ConnectionRefused => "connection refused",
+            PermissionDenied => "permission denied",
+            SocketNotFound => "socket not found",
+            TlsHandshake => "TLS handshake failed",
+            UpgradeFailed => "connection upgrade failed",
+            Connect => "failed to connect",
+        })
+    }
+}
+
+impl Error {
+    /// Maps an error status `code` answered by the daemon with `message` to the variant of its
+    /// class, other statuses are returned as [`Fault`](Error::Fault).
+    pub(crate) fn from_status(code: StatusCode, message: String) -> Self {
+        match code {
+            StatusCode::NOT_MODIFIED => Error::NotModified { code, message },
+            StatusCode::BAD_REQUEST => Error::BadParameter { code, message },
+            StatusCode::NOT_FOUND => Error::NotFound { code, message },
+            StatusCode::CONFLICT => Error::Conflict { code, message },
+            code if code.is_server_error() => Error::ServerError { code, message },
+            code => Error::Fault { code, message },
+        }
+    }
+
+    /// Returns `true` if the object of the request doesn't exist, for example to ignore removing
+    /// a container that was already removed, or if an object looked up by the client doesn't
+    /// exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound { .. } | Error::NoSuchObject(_))
+    }
+
+    /// Returns `true` if the request conflicts with the state of the daemon, like creating a
+    /// container with a name that is already taken or removing an object that is in use.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Error::Conflict { .. })
+    }
+
+    /// Returns `true` if an object can't be removed because it is in use, like an image used by
+    /// a container, a volume mounted by a container or a network with connected containers.
+    /// Other conflicts, like a container name that is already taken, are not reported as in use.
+    pub fn is_in_use(&self) -> bool {
+        match self {
+            Error::Conflict { message, .. } => {
+                let message = message.to_lowercase();
+                // volumes, plugins, secrets and configs report "is in use", unlike names that
+                // are "already in use"
+                [
+                    "is in use",
+                    "image is being used by",
+                    "is using its referenced image",
+                ]
+                .iter()
+                .any(|pattern| message.contains(pattern))
+            }
+            Error::Fault {
+                code: StatusCode::FORBIDDEN,
+                message,
+            } => message.to_lowercase().contains("has active endpoints"),
+            _ => false,
+        }
+    }
+
+    /// Returns the HTTP status of an error answered by the daemon.
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            Error::Fault { code, .. }
+            | Error::NotModified { code, .. }
+            | Error::BadParameter { code, .. }
+            | Error::NotFound { code, .. }
+            | Error::Conflict { code, .. }
+            | Error::ServerError { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// An object looked up by the client, like a network by its name, doesn't exist.
+    pub(crate) fn not_found(message: impl Into<String>) -> Self {
+        Error::NoSuchObject(message.into())
+    }
+
+    /// Recovers a [`StreamStalled`](Error::StreamStalled) or [`Cancelled`](Error::Cancelled)
+    /// error that was wrapped to pass it through a decoder of the stream, other errors are
+    /// returned as is.
+    #[cfg(not(feature = "fast-json"))]
+    pub(crate) fn unwrap_stream_error(self) -> Self {
+        let recover = |e: &(dyn std::error::Error + 'static)| match e.downcast_ref::<Error>() {
+            Some(Error::StreamStalled(idle)) => Some(Error::StreamStalled(*idle)),
+            Some(Error::Cancelled) => Some(Error::Cancelled),
+            _ => None,
+        };
+        let recovered = match &self {
+            Error::IO(e) => e.get_ref().and_then(|e| recover(e)),
+            Error::Error(containers_api::conn::Error::IO(e)) => {
+                e.get_ref().and_then(|e| recover(e))
+            }
+            Error::Error(containers_api::conn::Error::Any(e)) => recover(&**e),
+            _ => None,
+        };
+        recovered.unwrap_or(self)
+    }
+}
+
 impl Clone for Error {
     fn clone(&self) -> Self {
         match self {
@@ -58,3 +347,55 @@ impl Clone for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classification() {
+        let not_found = Error::from_status(StatusCode::NOT_FOUND, "No such volume: data".into());
+        assert!(not_found.is_not_found());
+        assert!(!not_found.is_conflict());
+        assert_eq!(not_found.status_code(), Some(StatusCode::NOT_FOUND));
+
+        let in_use = Error::from_status(
+            StatusCode::CONFLICT,
+            "remove data: volume is in use - [4f2d]".into(),
+        );
+        assert!(in_use.is_conflict());
+        assert!(in_use.is_in_use());
+
+        let name_taken = Error::from_status(
+            StatusCode::CONFLICT,
+            "Conflict. The container name \"/web\" is already in use by container \"4f2d\"".into(),
+        );
+        assert!(name_taken.is_conflict());
+        assert!(!name_taken.is_in_use());
+
+        let plugin_used = Error::from_status(
+            StatusCode::CONFLICT,
+            "plugin \"vieux/sshfs:latest\" is in use".into(),
+        );
+        assert!(plugin_used.is_in_use());
+
+        let image_used = Error::from_status(
+            StatusCode::CONFLICT,
+            "conflict: unable to delete 4f2d (must be forced) - image is being used by stopped container 9a1b".into(),
+        );
+        assert!(image_used.is_in_use());
+
+        let network_used = Error::from_status(
+            StatusCode::FORBIDDEN,
+            "error while removing network: network web id 4f2d has active endpoints".into(),
+        );
+        assert!(network_used.is_in_use());
+        assert!(!network_used.is_conflict());
+
+        assert!(!Error::from_status(StatusCode::CONFLICT, "bad state".into()).is_in_use());
+        assert_eq!(Error::ConnectionNotUpgraded.status_code(), None);
+        let missing = Error::not_found("network `web`");
+        assert!(missing.is_not_found());
+        assert_eq!(missing.status_code(), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Typed identifiers of docker objects.
+use serde::{Deserialize, Serialize};
+
+use crate::Id;
+
+macro_rules! impl_id_ty {
+    ($(#[doc = $docs:expr])* $name:ident) => {
+        $(
+            #[doc = $docs]
+        )*
+        ///
+        /// Any string converts into this type, but identifiers of other object types don't, so
+        /// they can't be passed by accident.
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            /// Returns the identifier as a string slice.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Consumes the identifier returning the inner string.
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(id: &String) -> Self {
+                Self(id.clone())
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<&$name> for $name {
+            fn from(id: &$name) -> Self {
+                id.clone()
+            }
+        }
+
+        impl From<$name> for Id {
+            fn from(id: $name) -> Self {
+                Id::from(id.0)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+impl_id_ty!(
+    /// ID or name of a container.
+    ContainerId
+);
+impl_id_ty!(
+    /// ID or reference of an image, like `alpine:latest`.
+    ImageId
+);
+impl_id_ty!(
+    /// ID or name of a network.
+    NetworkId
+);
+impl_id_ty!(
+    /// Name of a volume.
+    VolumeId
+);
+impl_id_ty!(
+    /// ID of an exec instance.
+    ExecId
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids() {
+        let id = ContainerId::from("abc");
+        assert_eq!(id, "abc");
+        assert_eq!(id.to_string(), "abc");
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""abc""#);
+        assert_eq!(
+            serde_json::from_str::<ImageId>(r#""sha256:123""#).unwrap(),
+            ImageId::from("sha256:123")
+        );
+        assert_eq!(Id::from(id).as_ref(), "abc");
+    }
+}
@@ -0,0 +1,176 @@
+//! Incremental decoding of the newline delimited JSON streams of the daemon, like events, stats
+//! and progress messages, enabled with the `fast-json` feature.
+//!
+//! JSON strings can't contain raw newlines, so every line holds whole values. Lines are sliced
+//! out of the received chunks and deserialized straight from bytes, which skips the copies and
+//! UTF-8 validation of decoding lines into strings first. Only a line split across chunks is
+//! copied, once.
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::{stream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use std::collections::VecDeque;
+
+use crate::{Error, Result};
+
+/// Splits a stream received in chunks of arbitrary size into lines.
+#[derive(Debug, Default)]
+struct LineDecoder {
+    chunk: Bytes,
+    partial: BytesMut,
+}
+
+impl LineDecoder {
+    fn push(&mut self, chunk: Bytes) {
+        if !self.chunk.is_empty() {
+            self.partial.extend_from_slice(&self.chunk);
+        }
+        self.chunk = chunk;
+    }
+
+    /// Returns the next line including its newline, or `None` if more data has to be pushed
+    /// first.
+    fn next_line(&mut self) -> Option<Bytes> {
+        match self.chunk.iter().position(|&b| b == b'\n') {
+            Some(pos) if self.partial.is_empty() => Some(self.chunk.split_to(pos + 1)),
+            Some(pos) => {
+                self.partial.extend_from_slice(&self.chunk[..=pos]);
+                self.chunk.advance(pos + 1);
+                Some(self.partial.split().freeze())
+            }
+            None => {
+                self.partial.extend_from_slice(&self.chunk);
+                self.chunk.clear();
+                None
+            }
+        }
+    }
+
+    /// Returns the data left after the last newline once the stream ended.
+    fn finish(&mut self) -> Bytes {
+        self.partial.extend_from_slice(&self.chunk);
+        self.chunk.clear();
+        self.partial.split().freeze()
+    }
+}
+
+/// Decodes a stream of newline delimited JSON values. A line may hold several values. Errors of
+/// the stream and of values that fail to deserialize are returned in place of the values and
+/// decoding continues after them.
+pub(crate) fn decode<T, S>(chunks: S) -> impl Stream<Item = Result<T>>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<Bytes>>,
+{
+    struct State<S, T> {
+        chunks: std::pin::Pin<Box<S>>,
+        lines: LineDecoder,
+        values: VecDeque<Result<T>>,
+        done: bool,
+    }
+
+    fn parse<T: DeserializeOwned>(line: &[u8], values: &mut VecDeque<Result<T>>) {
+        values.extend(
+            serde_json::Deserializer::from_slice(line)
+                .into_iter()
+                .map(|value| value.map_err(Error::from)),
+        );
+    }
+
+    let state = State {
+        chunks: Box::pin(chunks),
+        lines: LineDecoder::default(),
+        values: VecDeque::new(),
+        done: false,
+    };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(value) = state.values.pop_front() {
+                return Some((value, state));
+            }
+            if state.done {
+                return None;
+            }
+            if let Some(line) = state.lines.next_line() {
+                parse(&line, &mut state.values);
+                continue;
+            }
+            match state.chunks.next().await {
+                Some(Ok(chunk)) => state.lines.push(chunk),
+                Some(Err(e)) => return Some((Err(e), state)),
+                None => {
+                    state.done = true;
+                    let rest = state.lines.finish();
+                    parse(&rest, &mut state.values);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Message {
+        status: String,
+    }
+
+    fn decode_chunks(data: &[u8], size: usize) -> Vec<Result<Message>> {
+        let chunks = data
+            .chunks(size)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        futures::executor::block_on(decode(stream::iter(chunks)).collect())
+    }
+
+    #[test]
+    fn decode_lines() {
+        let data = b"{\"status\":\"a\\nb\"}\r\n\r\n{\"status\":\"c\"}{\"status\":\"d\"}\n{\"status\":\"e\"}";
+        for size in [1, 2, 7, 16, data.len()] {
+            let messages = decode_chunks(data, size)
+                .into_iter()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            let statuses = messages
+                .iter()
+                .map(|m| m.status.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(statuses, ["a\nb", "c", "d", "e"], "chunk size {size}");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let data = b"{\"status\":\"a\"}\n{\"other\":1}\n{\"status\":\"b\"}\n";
+        let messages = decode_chunks(data, 5);
+        assert!(matches!(
+            &messages[..],
+            [Ok(_), Err(Error::SerdeJsonError(_)), Ok(_)]
+        ));
+
+        let chunks = vec![
+            Ok(Bytes::from_static(b"{\"status\":")),
+            Err(Error::StreamStalled(std::time::Duration::from_secs(1))),
+        ];
+        let messages = futures::executor::block_on(
+            decode::<Message, _>(stream::iter(chunks)).collect::<Vec<_>>(),
+        );
+        assert!(matches!(
+            &messages[..],
+            [Err(Error::StreamStalled(_)), Err(Error::SerdeJsonError(_))]
+        ));
+    }
+
+    #[test]
+    fn zero_copy() {
+        let chunk = Bytes::from_static(b"{\"status\":\"a\"}\n{\"status\":\"b\"}\n");
+        let mut lines = LineDecoder::default();
+        lines.push(chunk.clone());
+        assert_eq!(lines.next_line().unwrap().as_ptr(), chunk.as_ptr());
+        assert_eq!(lines.next_line().unwrap(), &chunk[15..]);
+        assert!(lines.next_line().is_none());
+        assert!(lines.finish().is_empty());
+    }
+}
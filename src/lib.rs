@@ -4,7 +4,7 @@
 //!
 //! ```no_run
 //! # async {
-//! let docker = docker_api::Docker::new("tcp://127.0.0.1:80").unwrap();
+//! let docker = stackify_docker_api::Docker::new("tcp://127.0.0.1:80").unwrap();
 //!
 //! match docker.images().list(&Default::default()).await {
 //!     Ok(images) => {
@@ -18,6 +18,9 @@
 //! ```
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(all(feature = "tls", feature = "rustls"))]
+compile_error!("features `tls` and `rustls` are mutually exclusive, enable only one TLS backend");
+
 /// Latest Docker API version supported by this crate.
 pub const LATEST_API_VERSION: ApiVersion = ApiVersion::new(1, Some(42), None);
 
@@ -32,12 +35,48 @@ macro_rules! version {
 mod builder;
 
 pub mod api;
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
+mod cancel;
+mod context;
+mod endpoint;
+mod id;
+#[cfg(feature = "fast-json")]
+mod json;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
 pub mod models;
+mod raw;
+mod runtime;
 mod stream;
+#[cfg(feature = "tracing")]
+mod trace;
+mod transport;
+mod tty;
 pub mod conn {
     //! Connection related items
+    pub use crate::cancel::CancellationToken;
+    #[cfg(any(test, feature = "mock"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+    pub use crate::mock::{MockRequest, MockResponse, MockTransport};
+    pub use crate::runtime::{BoxFuture, Runtime, TokioRuntime};
+    pub use crate::stream::{BoundedStream, Buffered};
+    #[cfg(any(feature = "tls", feature = "rustls"))]
+    pub use crate::transport::HttpsConnector;
+    pub use crate::transport::{
+        BoxedConnector, BoxedIo, FailoverConnector, Proxy, Transport, TransportClient,
+    };
+    #[cfg(unix)]
+    pub use crate::transport::{FdConnector, FdStream};
+    #[cfg(windows)]
+    pub use crate::transport::{NamedPipeConnector, NamedPipeStream};
+    #[cfg(feature = "ssh")]
+    pub use crate::transport::{SshConnector, SshStream};
+    pub use crate::tty::{TtyFrame, TtyFrameDecoder};
+    pub use containers_api::conn::hyper::{Body, Method};
     pub(crate) use containers_api::conn::*;
-    pub use containers_api::conn::{Error, Transport, TtyChunk};
+    pub use containers_api::conn::{Error, Headers, TtyChunk};
 }
 pub mod docker;
 pub mod errors;
@@ -45,6 +84,8 @@ pub mod opts;
 
 pub use containers_api::id::Id;
 pub use containers_api::version::ApiVersion;
+pub use id::{ContainerId, ExecId, ImageId, NetworkId, VolumeId};
+pub use raw::RawInspect;
 
 pub use crate::{
     api::{
@@ -69,3 +110,11 @@ pub use crate::api::{
     swarm::{self, Swarm},
     task::{self, Task, Tasks},
 };
+
+#[cfg(feature = "stack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stack")))]
+pub use crate::api::stack::{self, Stack};
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+pub use crate::api::pod::{self, Pod, Pods};
@@ -0,0 +1,392 @@
+//! A transport answering requests with canned responses, enabled with the `mock` feature, so code
+//! using this crate can be unit tested without a daemon.
+use bytes::Bytes;
+use hyper::{
+    header::{HeaderName, HeaderValue, CONTENT_TYPE},
+    Body, HeaderMap, Method, Request, Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{Error, Result};
+
+/// Answers the requests of a client created with [`Docker::from_mock`](crate::Docker::from_mock)
+/// with canned responses and records them.
+///
+/// Responses are registered for a method and the path of an endpoint, like `/containers/json`.
+/// Paths are matched without the API version prefix and the query, so the same mock works for
+/// versioned and unversioned clients. A request is answered by the first registered response
+/// that matches it. Requests matching no response fail with a `501 Not Implemented` status.
+///
+/// Clones share the same responses and recorded requests, so a clone can be passed to the client
+/// and the original inspected once the code under test ran:
+///
+/// ```no_run
+/// use stackify_docker_api::conn::{Method, MockResponse, MockTransport};
+/// use stackify_docker_api::{opts::ContainerListOpts, Docker};
+///
+/// # async fn test() -> stackify_docker_api::Result<()> {
+/// let mock = MockTransport::new();
+/// let containers = serde_json::json!([{ "Id": "abc" }]);
+/// mock.on(Method::GET, "/containers/json", MockResponse::json(&containers));
+/// let docker = Docker::from_mock(mock.clone());
+///
+/// let containers = docker.containers().list(&ContainerListOpts::builder().all(true).build()).await?;
+/// assert_eq!(containers[0].id.as_deref(), Some("abc"));
+/// assert_eq!(mock.requests()[0].query_param("all").as_deref(), Some("true"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Endpoints upgrading the connection, like attaching to a container, can't be mocked.
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport(Arc<Mutex<State>>);
+
+#[derive(Debug, Default)]
+struct State {
+    rules: Vec<Rule>,
+    requests: Vec<MockRequest>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    method: Method,
+    path: String,
+    response: MockResponse,
+    once: bool,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add(&self, method: Method, path: impl Into<String>, response: MockResponse, once: bool) {
+        self.state().rules.push(Rule {
+            method,
+            path: path.into(),
+            response,
+            once,
+        });
+    }
+
+    /// Answers every request with `method` to `path` with `response`.
+    pub fn on(&self, method: Method, path: impl Into<String>, response: MockResponse) -> &Self {
+        self.add(method, path, response, false);
+        self
+    }
+
+    /// Answers the next request with `method` to `path` with `response`. Registering several
+    /// responses this way answers consecutive requests with them in order.
+    pub fn once(&self, method: Method, path: impl Into<String>, response: MockResponse) -> &Self {
+        self.add(method, path, response, true);
+        self
+    }
+
+    /// Returns the requests received so far in the order they were sent.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state().requests.clone()
+    }
+
+    /// Removes the registered responses and recorded requests.
+    pub fn reset(&self) {
+        let mut state = self.state();
+        state.rules.clear();
+        state.requests.clear();
+    }
+
+    pub(crate) async fn request(&self, req: Request<Body>) -> Result<Response<Body>> {
+        let (parts, body) = req.into_parts();
+        let path = unversioned(parts.uri.path());
+        let prefix = &parts.uri.path()[..parts.uri.path().len() - path.len()];
+        let request = MockRequest {
+            method: parts.method,
+            api_version: prefix.strip_prefix("/v").map(str::to_owned),
+            path: path.to_owned(),
+            query: parts.uri.query().map(str::to_owned),
+            headers: parts.headers,
+            body: hyper::body::to_bytes(body).await?,
+        };
+
+        let response = {
+            let mut state = self.state();
+            let matched = state
+                .rules
+                .iter()
+                .position(|rule| rule.method == request.method && rule.path == request.path);
+            let response = match matched {
+                Some(i) if state.rules[i].once => state.rules.remove(i).response,
+                Some(i) => state.rules[i].response.clone(),
+                None => MockResponse::error(
+                    StatusCode::NOT_IMPLEMENTED.as_u16(),
+                    format!("no mock response for {} {}", request.method, request.path),
+                ),
+            };
+            state.requests.push(request);
+            response
+        };
+        if response.pending {
+            std::future::pending::<()>().await;
+        }
+        response.into_response()
+    }
+}
+
+/// Strips the API version prefix, like `/v1.42`, from `path`.
+fn unversioned(path: &str) -> &str {
+    if let Some(rest) = path.strip_prefix("/v") {
+        let end = rest.find('/').unwrap_or(rest.len());
+        let version = &rest[..end];
+        if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return &rest[end..];
+        }
+    }
+    path
+}
+
+/// A canned response of a [`MockTransport`](MockTransport).
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    pending: bool,
+}
+
+impl MockResponse {
+    /// An empty response with `status`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a valid status code.
+    pub fn new(status: u16) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            pending: false,
+        }
+        .status(status)
+    }
+
+    /// A response that never arrives, like from a daemon that hangs, to test timeouts and
+    /// cancellation.
+    pub fn pending() -> Self {
+        Self {
+            pending: true,
+            ..Self::new(200)
+        }
+    }
+
+    /// A `200 OK` response with `value` serialized as JSON, for example a model of this crate.
+    pub fn json<T: Serialize>(value: &T) -> Self {
+        Self::new(200)
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(value).expect("failed to serialize mock response"))
+    }
+
+    /// A `200 OK` response streaming `values` serialized as newline delimited JSON, like the
+    /// events or the progress of an image pull.
+    pub fn json_lines<T: Serialize>(values: impl IntoIterator<Item = T>) -> Self {
+        let mut body = Vec::new();
+        for value in values {
+            serde_json::to_writer(&mut body, &value).expect("failed to serialize mock response");
+            body.push(b'\n');
+        }
+        Self::new(200)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+    }
+
+    /// An error response with `status` and `message` like the daemon answers failed requests
+    /// with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a valid status code.
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(&serde_json::json!({ "message": message.into() })).status(status)
+    }
+
+    /// Sets the status of the response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` is not a valid status code.
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = StatusCode::from_u16(status).expect("invalid status code");
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Adds a header to the response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    pub fn header(mut self, name: HeaderName, value: impl AsRef<str>) -> Self {
+        let value = HeaderValue::from_str(value.as_ref()).expect("invalid header value");
+        self.headers.append(name, value);
+        self
+    }
+
+    fn into_response(self) -> Result<Response<Body>> {
+        let mut response = Response::builder().status(self.status);
+        if let Some(headers) = response.headers_mut() {
+            headers.extend(self.headers);
+        }
+        response.body(Body::from(self.body)).map_err(Error::from)
+    }
+}
+
+/// A request received by a [`MockTransport`](MockTransport).
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    method: Method,
+    api_version: Option<String>,
+    path: String,
+    query: Option<String>,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl MockRequest {
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The API version the request was sent with, like `1.42`, or `None` if it is unversioned.
+    pub fn api_version(&self) -> Option<&str> {
+        self.api_version.as_deref()
+    }
+
+    /// The path of the request without the API version prefix.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The query of the request, like `all=true`.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// Returns the decoded value of the query parameter `key`, the first one if it is repeated.
+    pub fn query_param(&self, key: &str) -> Option<String> {
+        url::form_urlencoded::parse(self.query.as_deref()?.as_bytes())
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.into_owned())
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Deserializes the JSON body of the request, like the options of a created container.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_paths() {
+        assert_eq!(unversioned("/v1.42/containers/json"), "/containers/json");
+        assert_eq!(unversioned("/v1.42"), "");
+        assert_eq!(unversioned("/containers/json"), "/containers/json");
+        assert_eq!(unversioned("/volumes/v1/json"), "/volumes/v1/json");
+        assert_eq!(unversioned("/vault/json"), "/vault/json");
+    }
+
+    #[tokio::test]
+    async fn client() {
+        use crate::Docker;
+        use futures_util::StreamExt;
+
+        let mock = MockTransport::new();
+        mock.once(
+            Method::POST,
+            "/containers/create",
+            MockResponse::json(&serde_json::json!({ "Id": "abc", "Warnings": [] })).status(201),
+        )
+        .on(
+            Method::POST,
+            "/containers/create",
+            MockResponse::error(409, "name already in use"),
+        )
+        .on(
+            Method::GET,
+            "/events",
+            MockResponse::json_lines([
+                serde_json::json!({ "Action": "start" }),
+                serde_json::json!({ "Action": "die" }),
+            ]),
+        );
+        let mut docker = Docker::from_mock(mock.clone());
+        docker.set_api_version(crate::LATEST_API_VERSION);
+
+        let opts = crate::opts::ContainerCreateOpts::builder()
+            .name("web")
+            .image("nginx")
+            .env(["TOKEN=1"])
+            .build();
+        let container = docker.containers().create(&opts).await.unwrap();
+        assert_eq!(container.id().as_ref(), "abc");
+        match docker.containers().create(&opts).await {
+            Err(Error::Conflict { message, .. }) => assert_eq!(message, "name already in use"),
+            other => panic!("expected Error::Conflict, got {other:?}"),
+        }
+        let actions = docker
+            .events(&Default::default())
+            .map(|event| event.unwrap().action.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(actions, ["start", "die"]);
+        assert!(matches!(
+            docker.version().await,
+            Err(Error::ServerError { .. })
+        ));
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].path(), "/containers/create");
+        let version = crate::LATEST_API_VERSION.to_string();
+        assert_eq!(requests[0].api_version(), Some(version.as_str()));
+        assert_eq!(requests[0].query_param("name").as_deref(), Some("web"));
+        let body: serde_json::Value = requests[0].json().unwrap();
+        assert_eq!(body["Image"], "nginx");
+        assert_eq!(body["Env"], serde_json::json!(["TOKEN=1"]));
+        assert_eq!(requests[3].method(), Method::GET);
+        assert_eq!(requests[3].path(), "/version");
+
+        mock.reset();
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/_ping", MockResponse::pending());
+        let docker = crate::Docker::from_mock(mock.clone());
+        let ping = docker.ping();
+        futures_util::pin_mut!(ping);
+        assert!(futures_util::poll!(&mut ping).is_pending());
+        assert_eq!(mock.requests()[0].path(), "/_ping");
+    }
+}
@@ -1,8 +1,10 @@
 //! Generated Docker models
 
 pub use stackify_docker_api_stubs::models::*;
+pub use stackify_docker_api_stubs::API_VERSION;
 
 use crate::errors::{Error, Result};
+use crate::ContainerId;
 
 use hyper::header::HeaderMap;
 use serde::{Deserialize, Serialize};
@@ -60,6 +62,62 @@ impl TryFrom<&HeaderMap> for PingInfo {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+/// Metadata returned by the Docker daemon in the headers of every API response.
+///
+/// Use [`Docker::with_response_hook`](crate::Docker::with_response_hook) to inspect it, for
+/// example to detect reliance on deprecated endpoints.
+pub struct ResponseMeta {
+    /// Version of the API that served the request (`Api-Version` header).
+    pub api_version: Option<String>,
+    /// Whether the daemon runs with experimental features (`Docker-Experimental` header).
+    pub docker_experimental: bool,
+    pub os_type: Option<String>,
+    pub server: Option<String>,
+    /// Value of the `Deprecation` header if the endpoint is marked as deprecated.
+    pub deprecation: Option<String>,
+    /// Values of all `Warning` headers.
+    pub warnings: Vec<String>,
+}
+
+impl ResponseMeta {
+    /// Returns `true` if the daemon flagged the endpoint as deprecated with a `Deprecation`
+    /// header or a `Warning` about a deprecation, like the one sent for deprecated API versions.
+    /// Other warnings are ignored.
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecation.is_some()
+            || self
+                .warnings
+                .iter()
+                .any(|warning| warning.to_ascii_lowercase().contains("deprecated"))
+    }
+}
+
+impl From<&HeaderMap> for ResponseMeta {
+    fn from(value: &HeaderMap) -> Self {
+        let extract_str = |id: &str| {
+            value
+                .get(id)
+                .and_then(|v| v.to_str().map(ToString::to_string).ok())
+        };
+
+        ResponseMeta {
+            api_version: extract_str("api-version"),
+            docker_experimental: extract_str("docker-experimental")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            os_type: extract_str("ostype"),
+            server: extract_str("server"),
+            deprecation: extract_str("deprecation"),
+            warnings: value
+                .get_all("warning")
+                .iter()
+                .filter_map(|v| v.to_str().map(ToString::to_string).ok())
+                .collect(),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(untagged)]
 /// Represents a response chunk from Docker api when building, pulling or importing an image.
@@ -102,3 +160,648 @@ pub struct ProgressDetail {
 }
 
 pub type Labels = std::collections::HashMap<String, String>;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+/// Detailed information about a network as returned by
+/// [`Network::inspect_details`](crate::Network::inspect_details). Next to the fields of
+/// [`Network`](Network) it contains the peers of the network and, when inspected in verbose
+/// mode, information about the swarm services attached to it.
+pub struct NetworkDetails {
+    #[serde(flatten)]
+    pub network: Network,
+    #[serde(rename = "ConfigFrom")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The network from which this network takes its configuration.
+    pub config_from: Option<NetworkConfigReference>,
+    #[serde(rename = "ConfigOnly")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Whether this network is a configuration placeholder that can't be used directly.
+    pub config_only: Option<bool>,
+    #[serde(rename = "Peers")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Nodes participating in the network, only set for swarm scoped networks.
+    pub peers: Option<Vec<NetworkPeer>>,
+    #[serde(rename = "Services")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Services attached to the network, keyed by service name. Only set in verbose mode.
+    pub services: Option<std::collections::HashMap<String, NetworkServiceInfo>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct NetworkConfigReference {
+    #[serde(rename = "Network")]
+    pub network: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct NetworkPeer {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "IP")]
+    pub ip: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct NetworkServiceInfo {
+    #[serde(rename = "VIP")]
+    pub vip: String,
+    #[serde(rename = "Ports")]
+    #[serde(default)]
+    pub ports: Vec<String>,
+    #[serde(rename = "LocalLBIndex")]
+    pub local_lb_index: i64,
+    #[serde(rename = "Tasks")]
+    #[serde(default)]
+    pub tasks: Vec<NetworkTaskInfo>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct NetworkTaskInfo {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "EndpointID")]
+    pub endpoint_id: String,
+    #[serde(rename = "EndpointIP")]
+    pub endpoint_ip: String,
+    #[serde(rename = "Info")]
+    #[serde(default)]
+    pub info: std::collections::HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Typed status of a swarm [`Task`](Task), created with `TaskStatus::from(&task)`.
+pub struct TaskStatus {
+    /// Current state of the task, `None` if the daemon returned an unknown state.
+    pub state: Option<TaskState>,
+    /// State the orchestrator wants the task to reach.
+    pub desired_state: Option<TaskState>,
+    pub message: Option<String>,
+    /// Error that caused the task to fail, if any.
+    pub error: Option<String>,
+    pub container_id: Option<ContainerId>,
+    pub exit_code: Option<isize>,
+    pub pid: Option<isize>,
+    /// When the current state was reached.
+    pub timestamp: Option<Timestamp>,
+}
+
+impl TaskStatus {
+    /// Returns `true` if the task is running and is meant to be running.
+    pub fn is_running(&self) -> bool {
+        self.state == Some(TaskState::Running) && self.desired_state == Some(TaskState::Running)
+    }
+
+    /// Returns `true` if the task reached a state it can't leave anymore.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.state,
+            Some(
+                TaskState::Complete
+                    | TaskState::Shutdown
+                    | TaskState::Failed
+                    | TaskState::Rejected
+                    | TaskState::Remove
+                    | TaskState::Orphaned
+            )
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A single task of a service as returned by [`Service::ps`](crate::Service::ps), similar to a row
+/// of `docker service ps`.
+pub struct ServiceTaskInfo {
+    pub task_id: String,
+    /// Name of the task in the form `<service>.<slot>`, or `<service>.<node id>` for global services.
+    pub name: String,
+    pub image: Option<String>,
+    pub node_id: Option<String>,
+    /// Hostname of the node the task is assigned to.
+    pub node: Option<String>,
+    pub status: TaskStatus,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+/// Detailed information about a service as returned by
+/// [`Service::inspect_details`](crate::Service::inspect_details). Next to the fields of
+/// [`Service`](Service) it contains the spec the service had before its last update.
+pub struct ServiceDetails {
+    #[serde(flatten)]
+    pub service: Service,
+    #[serde(rename = "PreviousSpec")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The spec of the service before its last update, used when rolling back.
+    pub previous_spec: Option<ServiceSpec>,
+}
+
+impl ServiceDetails {
+    /// Ports published by the service, including the ones assigned by the daemon.
+    pub fn ports(&self) -> &[EndpointPortConfig] {
+        self.service
+            .endpoint
+            .as_ref()
+            .and_then(|e| e.ports.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// State of the last update of the service, `None` if it was never updated.
+    pub fn update_state(&self) -> Option<ServiceUpdateState> {
+        self.service
+            .update_status
+            .as_ref()
+            .and_then(|status| status.state.as_deref())
+            .and_then(|state| serde_json::from_value(serde_json::Value::String(state.into())).ok())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// State of a service update, including the states of an automatic or manual rollback.
+pub enum ServiceUpdateState {
+    Updating,
+    Paused,
+    Completed,
+    RollbackStarted,
+    RollbackPaused,
+    RollbackCompleted,
+}
+
+fn parse_task_state(state: Option<&str>) -> Option<TaskState> {
+    state.and_then(|s| serde_json::from_value(serde_json::Value::String(s.to_string())).ok())
+}
+
+impl From<&Task> for TaskStatus {
+    fn from(task: &Task) -> Self {
+        let status = task.status.as_ref();
+        let container = status.and_then(|s| s.container_status.as_ref());
+        TaskStatus {
+            state: parse_task_state(status.and_then(|s| s.state.as_deref())),
+            desired_state: parse_task_state(task.desired_state.as_deref()),
+            message: status.and_then(|s| s.message.clone()),
+            error: status.and_then(|s| s.err.clone()),
+            container_id: container.and_then(|c| c.container_id.clone().map(ContainerId::from)),
+            exit_code: container.and_then(|c| c.exit_code),
+            pid: container.and_then(|c| c.pid),
+            timestamp: status.and_then(|s| s.timestamp.as_ref()).cloned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Strongly typed action of an [`EventMessage`](EventMessage), created with
+/// `EventAction::from(&event)`. Actions that this crate doesn't know about are preserved as
+/// `Other` variants so that matching on them stays possible.
+pub enum EventAction {
+    Container(ContainerEvent),
+    Image(ImageEvent),
+    Network(NetworkEvent),
+    Volume(VolumeEvent),
+    Daemon(DaemonEvent),
+    /// An event of an object type without typed actions, e.g. swarm services or plugins.
+    Other {
+        type_: String,
+        action: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContainerEvent {
+    Attach,
+    Commit,
+    Copy,
+    Create,
+    Destroy,
+    Detach,
+    Die { exit_code: Option<i64> },
+    ExecCreate { command: String },
+    ExecDetach,
+    ExecDie { exit_code: Option<i64> },
+    ExecStart { command: String },
+    Export,
+    HealthStatus { status: String },
+    Kill { signal: Option<String> },
+    Oom,
+    Pause,
+    Rename { old_name: Option<String> },
+    Resize,
+    Restart,
+    Start,
+    Stop,
+    Top,
+    Unpause,
+    Update,
+    Prune,
+    Other(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageEvent {
+    Delete,
+    Import,
+    Load,
+    Pull,
+    Push,
+    Save,
+    Tag,
+    Untag,
+    Prune,
+    Other(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkEvent {
+    Create,
+    Connect { container: Option<String> },
+    Disconnect { container: Option<String> },
+    Destroy,
+    Remove,
+    Update,
+    Prune,
+    Other(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VolumeEvent {
+    Create,
+    Mount {
+        container: Option<String>,
+        destination: Option<String>,
+    },
+    Unmount {
+        container: Option<String>,
+    },
+    Destroy,
+    Prune,
+    Other(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DaemonEvent {
+    Reload,
+    Other(String),
+}
+
+impl From<&EventMessage> for EventAction {
+    fn from(event: &EventMessage) -> Self {
+        let action = event.action.as_deref().unwrap_or_default();
+        let attributes = event
+            .actor
+            .as_ref()
+            .and_then(|actor| actor.attributes.as_ref());
+        let attribute = |key: &str| attributes.and_then(|a| a.get(key)).cloned();
+        let exit_code = || attribute("exitCode").and_then(|code| code.parse().ok());
+
+        // some actions carry additional details after a colon, e.g. `health_status: healthy`
+        let (name, detail) = match action.split_once(':') {
+            Some((name, detail)) => (name, detail.trim().to_string()),
+            None => (action, String::new()),
+        };
+
+        match event.type_.as_deref().unwrap_or_default() {
+            "container" => EventAction::Container(match name {
+                "attach" => ContainerEvent::Attach,
+                "commit" => ContainerEvent::Commit,
+                "copy" => ContainerEvent::Copy,
+                "create" => ContainerEvent::Create,
+                "destroy" => ContainerEvent::Destroy,
+                "detach" => ContainerEvent::Detach,
+                "die" => ContainerEvent::Die {
+                    exit_code: exit_code(),
+                },
+                "exec_create" => ContainerEvent::ExecCreate { command: detail },
+                "exec_detach" => ContainerEvent::ExecDetach,
+                "exec_die" => ContainerEvent::ExecDie {
+                    exit_code: exit_code(),
+                },
+                "exec_start" => ContainerEvent::ExecStart { command: detail },
+                "export" => ContainerEvent::Export,
+                "health_status" => ContainerEvent::HealthStatus { status: detail },
+                "kill" => ContainerEvent::Kill {
+                    signal: attribute("signal"),
+                },
+                "oom" => ContainerEvent::Oom,
+                "pause" => ContainerEvent::Pause,
+                "rename" => ContainerEvent::Rename {
+                    old_name: attribute("oldName"),
+                },
+                "resize" => ContainerEvent::Resize,
+                "restart" => ContainerEvent::Restart,
+                "start" => ContainerEvent::Start,
+                "stop" => ContainerEvent::Stop,
+                "top" => ContainerEvent::Top,
+                "unpause" => ContainerEvent::Unpause,
+                "update" => ContainerEvent::Update,
+                "prune" => ContainerEvent::Prune,
+                _ => ContainerEvent::Other(action.to_string()),
+            }),
+            "image" => EventAction::Image(match name {
+                "delete" => ImageEvent::Delete,
+                "import" => ImageEvent::Import,
+                "load" => ImageEvent::Load,
+                "pull" => ImageEvent::Pull,
+                "push" => ImageEvent::Push,
+                "save" => ImageEvent::Save,
+                "tag" => ImageEvent::Tag,
+                "untag" => ImageEvent::Untag,
+                "prune" => ImageEvent::Prune,
+                _ => ImageEvent::Other(action.to_string()),
+            }),
+            "network" => EventAction::Network(match name {
+                "create" => NetworkEvent::Create,
+                "connect" => NetworkEvent::Connect {
+                    container: attribute("container"),
+                },
+                "disconnect" => NetworkEvent::Disconnect {
+                    container: attribute("container"),
+                },
+                "destroy" => NetworkEvent::Destroy,
+                "remove" => NetworkEvent::Remove,
+                "update" => NetworkEvent::Update,
+                "prune" => NetworkEvent::Prune,
+                _ => NetworkEvent::Other(action.to_string()),
+            }),
+            "volume" => EventAction::Volume(match name {
+                "create" => VolumeEvent::Create,
+                "mount" => VolumeEvent::Mount {
+                    container: attribute("container"),
+                    destination: attribute("destination"),
+                },
+                "unmount" => VolumeEvent::Unmount {
+                    container: attribute("container"),
+                },
+                "destroy" => VolumeEvent::Destroy,
+                "prune" => VolumeEvent::Prune,
+                _ => VolumeEvent::Other(action.to_string()),
+            }),
+            "daemon" => EventAction::Daemon(match name {
+                "reload" => DaemonEvent::Reload,
+                _ => DaemonEvent::Other(action.to_string()),
+            }),
+            type_ => EventAction::Other {
+                type_: type_.to_string(),
+                action: action.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+/// A pod as listed by [`Pods::list`](crate::Pods::list).
+pub struct PodListEntry {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub created: String,
+    #[serde(default)]
+    pub infra_id: String,
+    #[serde(default)]
+    pub namespace: String,
+    #[serde(default)]
+    pub cgroup: String,
+    #[serde(default)]
+    pub labels: Option<Labels>,
+    #[serde(default)]
+    pub networks: Option<Vec<String>>,
+    #[serde(default)]
+    pub containers: Option<Vec<PodListContainer>>,
+}
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct PodListContainer {
+    pub id: ContainerId,
+    #[serde(default)]
+    pub names: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+/// Detailed information about a pod as returned by [`Pod::inspect`](crate::Pod::inspect).
+pub struct PodInspect {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub namespace: String,
+    #[serde(default)]
+    pub created: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub hostname: String,
+    #[serde(default)]
+    pub labels: Option<Labels>,
+    #[serde(default)]
+    pub create_infra: bool,
+    #[serde(rename = "InfraContainerID")]
+    #[serde(default)]
+    pub infra_container_id: Option<ContainerId>,
+    #[serde(default)]
+    pub shared_namespaces: Option<Vec<String>>,
+    #[serde(default)]
+    pub num_containers: u64,
+    #[serde(default)]
+    pub containers: Option<Vec<PodInspectContainer>>,
+}
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct PodInspectContainer {
+    pub id: ContainerId,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub state: String,
+}
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+/// Result of starting, stopping or otherwise changing the state of a pod. Errors of individual
+/// containers of the pod are reported in `errs`.
+pub struct PodActionReport {
+    pub id: String,
+    #[serde(default)]
+    pub errs: Option<Vec<serde_json::Value>>,
+}
+
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+/// Result of removing or pruning a pod.
+pub struct PodRemoveReport {
+    pub id: String,
+    #[serde(default)]
+    pub err: Option<serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn models_cover_latest_api_version() {
+        let models: crate::ApiVersion = API_VERSION.parse().unwrap();
+        assert!(models >= crate::LATEST_API_VERSION);
+    }
+
+    fn event(type_: &str, action: &str, attributes: &[(&str, &str)]) -> EventMessage {
+        EventMessage {
+            action: Some(action.to_string()),
+            actor: Some(EventActor {
+                attributes: Some(
+                    attributes
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                ),
+                id: Some("abc".to_string()),
+            }),
+            type_: Some(type_.to_string()),
+            scope: Some("local".to_string()),
+            time: Some(0),
+            time_nano: Some(0),
+        }
+    }
+
+    #[test]
+    fn event_actions() {
+        assert_eq!(
+            EventAction::from(&event("container", "die", &[("exitCode", "137")])),
+            EventAction::Container(ContainerEvent::Die {
+                exit_code: Some(137)
+            })
+        );
+        assert_eq!(
+            EventAction::from(&event("container", "health_status: unhealthy", &[])),
+            EventAction::Container(ContainerEvent::HealthStatus {
+                status: "unhealthy".into()
+            })
+        );
+        assert_eq!(
+            EventAction::from(&event("container", "exec_start: sh -c echo", &[])),
+            EventAction::Container(ContainerEvent::ExecStart {
+                command: "sh -c echo".into()
+            })
+        );
+        assert_eq!(
+            EventAction::from(&event("image", "pull", &[])),
+            EventAction::Image(ImageEvent::Pull)
+        );
+        assert_eq!(
+            EventAction::from(&event("network", "connect", &[("container", "xyz")])),
+            EventAction::Network(NetworkEvent::Connect {
+                container: Some("xyz".into())
+            })
+        );
+        assert_eq!(
+            EventAction::from(&event("volume", "unknown", &[])),
+            EventAction::Volume(VolumeEvent::Other("unknown".into()))
+        );
+        assert_eq!(
+            EventAction::from(&event("service", "create", &[])),
+            EventAction::Other {
+                type_: "service".into(),
+                action: "create".into()
+            }
+        );
+    }
+
+    #[test]
+    fn response_meta_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("api-version", "1.42".parse().unwrap());
+        headers.insert("docker-experimental", "true".parse().unwrap());
+        headers.insert("ostype", "linux".parse().unwrap());
+        headers.append("warning", "299 - \"first\"".parse().unwrap());
+        headers.append("warning", "299 - \"second\"".parse().unwrap());
+
+        let meta = ResponseMeta::from(&headers);
+        assert_eq!(meta.api_version.as_deref(), Some("1.42"));
+        assert!(meta.docker_experimental);
+        assert_eq!(meta.os_type.as_deref(), Some("linux"));
+        assert_eq!(meta.server, None);
+        assert_eq!(meta.warnings.len(), 2);
+        assert!(!meta.is_deprecated());
+
+        headers.append(
+            "warning",
+            "299 - \"Deprecated API version 1.23, use 1.24 or newer\""
+                .parse()
+                .unwrap(),
+        );
+        assert!(ResponseMeta::from(&headers).is_deprecated());
+
+        let meta = ResponseMeta::from(&HeaderMap::new());
+        assert_eq!(meta, ResponseMeta::default());
+        assert!(!meta.is_deprecated());
+    }
+
+    #[test]
+    fn task_status_from_task() {
+        let task: Task = serde_json::from_value(serde_json::json!({
+            "ID": "abc",
+            "DesiredState": "shutdown",
+            "Status": {
+                "State": "failed",
+                "Err": "task: non-zero exit (1)",
+                "Timestamp": "2023-01-02T03:04:05.123456789Z",
+                "ContainerStatus": {"ContainerID": "123", "ExitCode": 1}
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&task).unwrap()["Status"]["Timestamp"],
+            "2023-01-02T03:04:05.123456789Z"
+        );
+        let status = TaskStatus::from(&task);
+        assert_eq!(status.timestamp, task.status.unwrap().timestamp);
+        assert_eq!(status.state, Some(TaskState::Failed));
+        assert_eq!(status.desired_state, Some(TaskState::Shutdown));
+        assert_eq!(status.error.as_deref(), Some("task: non-zero exit (1)"));
+        assert_eq!(status.container_id.as_deref(), Some("123"));
+        assert_eq!(status.exit_code, Some(1));
+        assert!(status.is_terminal());
+        assert!(!status.is_running());
+    }
+
+    #[test]
+    fn service_details() {
+        let details: ServiceDetails = serde_json::from_value(serde_json::json!({
+            "ID": "svc",
+            "Version": {"Index": 12},
+            "Spec": {"Name": "web", "Labels": {"env": "prod"}},
+            "PreviousSpec": {"Name": "web"},
+            "Endpoint": {
+                "Ports": [{"Protocol": "tcp", "TargetPort": 80, "PublishedPort": 30000}]
+            },
+            "UpdateStatus": {"State": "rollback_completed", "Message": "rollback completed"}
+        }))
+        .unwrap();
+        assert_eq!(details.service.id.as_deref(), Some("svc"));
+        assert_eq!(details.service.version.as_ref().unwrap().index, Some(12));
+        assert_eq!(
+            details.previous_spec.as_ref().unwrap().name.as_deref(),
+            Some("web")
+        );
+        assert_eq!(details.ports()[0].published_port, Some(30000));
+        assert_eq!(
+            details.update_state(),
+            Some(ServiceUpdateState::RollbackCompleted)
+        );
+    }
+}
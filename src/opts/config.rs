@@ -1,11 +1,16 @@
 use crate::models::{Driver, Labels};
 use crate::{Error, Result};
+use base64::{engine::general_purpose, Engine};
 use containers_api::opts::{Filter, FilterItem};
-use containers_api::{impl_filter_func, impl_opts_builder};
 use serde::{Deserialize, Serialize};
 
+use std::path::Path;
+
 impl_opts_builder!(url => ConfigList);
 
+/// Maximum size of the data of a config accepted by the daemon.
+pub const MAX_CONFIG_SIZE: usize = 1000 * 1024;
+
 pub enum ConfigFilter {
     /// The ID of the config.
     Id(String),
@@ -59,7 +64,7 @@ impl ConfigCreateOpts {
         Self {
             name: name.into(),
             labels: Labels::new(),
-            data: base64::encode(data.as_ref()),
+            data: general_purpose::STANDARD.encode(data.as_ref()),
             templating: Driver {
                 name: "".into(),
                 options: None,
@@ -67,6 +72,38 @@ impl ConfigCreateOpts {
         }
     }
 
+    /// Create a new config with name and raw data read from `bytes`, encoded as base64.
+    ///
+    /// Returns an error if the data exceeds the maximum size of configs accepted by the daemon.
+    pub fn from_bytes<N, D>(name: N, bytes: D) -> Result<Self>
+    where
+        N: Into<String>,
+        D: AsRef<[u8]>,
+    {
+        let bytes = bytes.as_ref();
+        if bytes.len() > MAX_CONFIG_SIZE {
+            return Err(Error::DataTooLarge {
+                size: bytes.len(),
+                max: MAX_CONFIG_SIZE,
+            });
+        }
+        let mut opts = Self::new(name, "");
+        opts.data = general_purpose::STANDARD.encode(bytes);
+        Ok(opts)
+    }
+
+    /// Create a new config with name and data read from the file at `path`.
+    ///
+    /// Returns an error if the file can't be read or exceeds the maximum size of configs accepted by
+    /// the daemon.
+    pub fn from_file<N, P>(name: N, path: P) -> Result<Self>
+    where
+        N: Into<String>,
+        P: AsRef<Path>,
+    {
+        Self::from_bytes(name, std::fs::read(path)?)
+    }
+
     /// Set the templating driver of this config.
     pub fn set_templating(mut self, driver: Driver) -> Self {
         self.templating = driver;
@@ -91,3 +128,20 @@ impl ConfigCreateOpts {
         serde_json::to_vec(&self).map_err(Error::from)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_create_from_bytes() {
+        let opts = ConfigCreateOpts::from_bytes("test", [0u8, 159, 146, 150]).unwrap();
+        assert_eq!(opts.data, "AJ+Slg==");
+        assert_eq!(MAX_CONFIG_SIZE, 1000 * 1024);
+        assert!(ConfigCreateOpts::from_bytes("test", vec![0u8; MAX_CONFIG_SIZE]).is_ok());
+        assert!(matches!(
+            ConfigCreateOpts::from_bytes("test", vec![0u8; MAX_CONFIG_SIZE + 1]),
+            Err(Error::DataTooLarge { size, max }) if size == MAX_CONFIG_SIZE + 1 && max == MAX_CONFIG_SIZE
+        ));
+    }
+}
@@ -1,14 +1,16 @@
-use crate::models::{DeviceRequest, Labels, NetworkingConfig};
-use crate::opts::ImageName;
+use crate::models::{
+    ContainerConfig, DeviceRequest, HealthConfig, HostConfig, Labels, NetworkingConfig,
+};
+use crate::opts::{api_v1, duration_nanos, ByteSize, ImageName, ParamVersions, Signal};
 use containers_api::opts::{Filter, FilterItem};
 use containers_api::{
-    impl_field, impl_filter_func, impl_map_field, impl_opts_builder, impl_str_enum_field,
-    impl_str_field, impl_url_bool_field, impl_url_str_field, impl_vec_field,
+    impl_field, impl_map_field, impl_str_enum_field, impl_str_field, impl_url_bool_field,
+    impl_url_str_field, impl_url_vec_field, impl_vec_field,
 };
 
 use std::net::SocketAddr;
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     hash::Hash,
     iter::Peekable,
     str::{self, FromStr},
@@ -167,12 +169,58 @@ impl ContainerListOptsBuilder {
 #[derive(Serialize, Debug, Clone)]
 pub struct ContainerCreateOpts {
     name: Option<String>,
-    params: HashMap<&'static str, Value>,
+    body: Option<Box<ContainerCreateBody>>,
+    params: BTreeMap<&'static str, Value>,
+}
+
+/// The full configuration of a container to create, as sent in the body of the request. Useful
+/// for a configuration held as a whole, for example deserialized from a file or taken from the
+/// inspect response of another container, instead of replaying it through builder calls.
+///
+/// Converts into [`ContainerCreateOpts`](ContainerCreateOpts), or into a
+/// [`ContainerCreateOptsBuilder`](ContainerCreateOptsBuilder) to set the name of the container or
+/// override single fields, which take precedence over the fields of the body.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContainerCreateBody {
+    /// Configuration of the container that is portable between hosts, like the image, command
+    /// and environment.
+    #[serde(flatten)]
+    pub config: ContainerConfig,
+    /// Configuration of the container that depends on the host, like mounts, published ports and
+    /// resource limits.
+    #[serde(
+        rename = "HostConfig",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub host_config: Option<HostConfig>,
+    /// Configuration of the networks the container is connected to.
+    #[serde(
+        rename = "NetworkingConfig",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub networking_config: Option<NetworkingConfig>,
+}
+
+impl From<ContainerCreateBody> for ContainerCreateOpts {
+    fn from(body: ContainerCreateBody) -> Self {
+        ContainerCreateOptsBuilder::from(body).build_owned()
+    }
+}
+
+impl From<ContainerCreateBody> for ContainerCreateOptsBuilder {
+    fn from(body: ContainerCreateBody) -> Self {
+        ContainerCreateOptsBuilder {
+            body: Some(Box::new(body)),
+            ..Default::default()
+        }
+    }
 }
 
 /// Function to insert a JSON value into a tree where the desired
 /// location of the value is given as a path of JSON keys.
-fn insert<'a, I, V>(key_path: &mut Peekable<I>, value: &V, parent_node: &mut Value)
+fn insert<'a, I, V>(key_path: &mut Peekable<I>, value: &V, parent_node: &mut Value) -> Result<()>
 where
     V: Serialize,
     I: Iterator<Item = &'a str>,
@@ -184,14 +232,25 @@ where
                     .entry(local_key.to_string())
                     .or_insert(Value::Object(Map::new()));
 
-                insert(key_path, value, node);
+                insert(key_path, value, node)?;
             }
         } else if let Some(node) = parent_node.as_object_mut() {
-            node.insert(
-                local_key.to_string(),
-                serde_json::to_value(value).unwrap_or_default(),
-            );
+            node.insert(local_key.to_string(), serde_json::to_value(value)?);
+        }
+    }
+    Ok(())
+}
+
+/// Removes the members of objects in `value` that are `null`, like the unset fields of models
+/// that are serialized even if they are `None`. The daemon treats them like missing members.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(members) => {
+            members.retain(|_, member| !member.is_null());
+            members.values_mut().for_each(remove_nulls);
         }
+        Value::Array(items) => items.iter_mut().for_each(remove_nulls),
+        _ => {}
     }
 }
 
@@ -203,34 +262,47 @@ impl ContainerCreateOpts {
 
     /// Serialize options as a JSON string.
     pub fn serialize(&self) -> Result<String> {
-        serde_json::to_string(&self.to_json()).map_err(Error::from)
+        serde_json::to_string(&self.to_json()?).map_err(Error::from)
     }
 
     /// Serialize options as a JSON bytes.
     pub fn serialize_vec(&self) -> Result<Vec<u8>> {
-        serde_json::to_vec(&self.to_json()).map_err(Error::from)
+        serde_json::to_vec(&self.to_json()?).map_err(Error::from)
     }
 
-    fn to_json(&self) -> Value {
-        let mut body_members = Map::new();
+    fn to_json(&self) -> Result<Value> {
+        let mut body_members = match &self.body {
+            Some(body) => {
+                let mut body = serde_json::to_value(body)?;
+                remove_nulls(&mut body);
+                match body {
+                    Value::Object(members) => members,
+                    _ => Map::new(),
+                }
+            }
+            None => Map::new(),
+        };
         // The HostConfig element gets initialized to an empty object,
         // for backward compatibility.
-        body_members.insert("HostConfig".to_string(), Value::Object(Map::new()));
+        body_members
+            .entry("HostConfig")
+            .or_insert_with(|| Value::Object(Map::new()));
         let mut body = Value::Object(body_members);
-        self.parse_from(&self.params, &mut body);
-        body
+        self.parse_from(&self.params, &mut body)?;
+        Ok(body)
     }
 
-    fn parse_from<'a, K, V>(&self, params: &'a HashMap<K, V>, body: &mut Value)
+    fn parse_from<'a, K, V>(&self, params: &'a BTreeMap<K, V>, body: &mut Value) -> Result<()>
     where
-        &'a HashMap<K, V>: IntoIterator,
+        &'a BTreeMap<K, V>: IntoIterator,
         K: ToString + Eq + Hash,
         V: Serialize,
     {
         for (k, v) in params.iter() {
             let key_string = k.to_string();
-            insert(&mut key_string.split('.').peekable(), v, body)
+            insert(&mut key_string.split('.').peekable(), v, body)?;
         }
+        Ok(())
     }
 
     pub(crate) fn name(&self) -> Option<&str> {
@@ -238,10 +310,11 @@ impl ContainerCreateOpts {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ContainerCreateOptsBuilder {
     name: Option<String>,
-    params: HashMap<&'static str, Value>,
+    body: Option<Box<ContainerCreateBody>>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -331,9 +404,9 @@ impl FromStr for PublishPort {
     }
 }
 
-impl ToString for PublishPort {
-    fn to_string(&self) -> String {
-        format!("{}/{}", self.port, self.protocol.as_ref())
+impl std::fmt::Display for PublishPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.port, self.protocol.as_ref())
     }
 }
 
@@ -388,14 +461,14 @@ pub enum IpcMode {
     Host,
 }
 
-impl ToString for IpcMode {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for IpcMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
-            IpcMode::None => String::from("none"),
-            IpcMode::Private => String::from("private"),
-            IpcMode::Shareable => String::from("shareable"),
-            IpcMode::Container(id) => format!("container:{}", id),
-            IpcMode::Host => String::from("host"),
+            IpcMode::None => write!(f, "none"),
+            IpcMode::Private => write!(f, "private"),
+            IpcMode::Shareable => write!(f, "shareable"),
+            IpcMode::Container(id) => write!(f, "container:{id}"),
+            IpcMode::Host => write!(f, "host"),
         }
     }
 }
@@ -408,11 +481,11 @@ pub enum PidMode {
     Host,
 }
 
-impl ToString for PidMode {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for PidMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
-            PidMode::Container(id) => format!("container:{}", id),
-            PidMode::Host => String::from("host"),
+            PidMode::Container(id) => write!(f, "container:{id}"),
+            PidMode::Host => write!(f, "host"),
         }
     }
 }
@@ -420,8 +493,8 @@ impl ToString for PidMode {
 impl ContainerCreateOptsBuilder {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
-            params: Default::default(),
             name: Some(name.into()),
+            ..Default::default()
         }
     }
 
@@ -442,7 +515,7 @@ impl ContainerCreateOptsBuilder {
     }
 
     pub fn expose<P: Into<HostPort>>(mut self, srcport: PublishPort, hostport: P) -> Self {
-        let mut exposedport: HashMap<String, String> = HashMap::new();
+        let mut exposedport: BTreeMap<String, String> = BTreeMap::new();
         let hostport = hostport.into();
         exposedport.insert("HostPort".to_string(), hostport.port.to_string());
         if let Some(ip) = hostport.ip {
@@ -451,7 +524,7 @@ impl ContainerCreateOptsBuilder {
 
         // The idea here is to go thought the 'old' port binds and to apply them to the local
         // 'port_bindings' variable, add the bind we want and replace the 'old' value
-        let mut port_bindings: HashMap<String, Value> = HashMap::new();
+        let mut port_bindings: BTreeMap<String, Value> = BTreeMap::new();
         for (key, val) in self
             .params
             .get("HostConfig.PortBindings")
@@ -468,8 +541,8 @@ impl ContainerCreateOptsBuilder {
             .insert("HostConfig.PortBindings", json!(port_bindings));
 
         // Replicate the port bindings over to the exposed ports config
-        let mut exposed_ports: HashMap<String, Value> = HashMap::new();
-        let empty_config: HashMap<String, Value> = HashMap::new();
+        let mut exposed_ports: BTreeMap<String, Value> = BTreeMap::new();
+        let empty_config: BTreeMap<String, Value> = BTreeMap::new();
         for key in port_bindings.keys() {
             exposed_ports.insert(key.to_string(), json!(empty_config));
         }
@@ -484,7 +557,7 @@ impl ContainerCreateOptsBuilder {
         /* The idea here is to go thought the 'old' port binds
          * and to apply them to the local 'exposedport_bindings' variable,
          * add the bind we want and replace the 'old' value */
-        let mut exposed_port_bindings: HashMap<String, Value> = HashMap::new();
+        let mut exposed_port_bindings: BTreeMap<String, Value> = BTreeMap::new();
         for (key, val) in self
             .params
             .get("ExposedPorts")
@@ -498,8 +571,8 @@ impl ContainerCreateOptsBuilder {
         exposed_port_bindings.insert(port.to_string(), json!({}));
 
         // Replicate the port bindings over to the exposed ports config
-        let mut exposed_ports: HashMap<String, Value> = HashMap::new();
-        let empty_config: HashMap<String, Value> = HashMap::new();
+        let mut exposed_ports: BTreeMap<String, Value> = BTreeMap::new();
+        let empty_config: BTreeMap<String, Value> = BTreeMap::new();
         for key in exposed_port_bindings.keys() {
             exposed_ports.insert(key.to_string(), json!(empty_config));
         }
@@ -531,12 +604,44 @@ impl ContainerCreateOptsBuilder {
 
     impl_vec_field!(links => "HostConfig.Links");
 
-    impl_field!(memory: u64 => "HostConfig.Memory");
+    /// Memory limit, either in bytes or as a [`ByteSize`](ByteSize) parsed from a
+    /// size like `512m`.
+    pub fn memory(mut self, memory: impl Into<ByteSize>) -> Self {
+        self.params
+            .insert("HostConfig.Memory", memory.into().bytes().into());
+        self
+    }
+
+    /// Size of `/dev/shm`. If omitted the daemon uses 64MB.
+    pub fn shm_size(mut self, size: impl Into<ByteSize>) -> Self {
+        self.params
+            .insert("HostConfig.ShmSize", size.into().bytes().into());
+        self
+    }
 
-    impl_field!(
-        /// Total memory limit (memory + swap) in bytes. Set to -1 (default) to enable unlimited swap.
-        memory_swap: i64 => "HostConfig.MemorySwap"
-    );
+    /// Total memory limit (memory + swap), either in bytes or as a [`ByteSize`](ByteSize) parsed
+    /// from a size like `1g`. Use [`unlimited_swap`](Self::unlimited_swap) to enable unlimited
+    /// swap.
+    pub fn memory_swap(mut self, memory_swap: impl Into<ByteSize>) -> Self {
+        self.params
+            .insert("HostConfig.MemorySwap", memory_swap.into().bytes().into());
+        self
+    }
+
+    /// Enable unlimited swap, the default of the daemon.
+    pub fn unlimited_swap(mut self) -> Self {
+        self.params.insert("HostConfig.MemorySwap", (-1).into());
+        self
+    }
+
+    /// Size of the writable layer of the container, for storage drivers supporting it.
+    pub fn storage_size(mut self, size: impl Into<ByteSize>) -> Self {
+        self.params.insert(
+            "HostConfig.StorageOpt.size",
+            size.into().bytes().to_string().into(),
+        );
+        self
+    }
 
     impl_field!(
         /// CPU quota in units of 10<sup>-9</sup> CPUs. Set to 0 (default) for there to be no limit.
@@ -615,17 +720,24 @@ impl ContainerCreateOptsBuilder {
 
     impl_field!(auto_remove: bool => "HostConfig.AutoRemove");
 
-    impl_str_field!(
-    /// Signal to stop a container as a string. Default is \"SIGTERM\"
-    stop_signal => "StopSignal");
-
-    impl_field!(
-    /// Signal to stop a container as an integer. Default is 15 (SIGTERM).
-    stop_signal_num: u64 => "StopSignal");
+    /// Signal to stop a container. Default is `SIGTERM`.
+    pub fn stop_signal(mut self, signal: impl Into<Signal>) -> Self {
+        self.params
+            .insert("StopSignal", signal.into().to_string().into());
+        self
+    }
 
-    impl_field!(
     /// Timeout to stop a container. Only seconds are counted. Default is 10s
-    stop_timeout: Duration => "StopTimeout");
+    pub fn stop_timeout(mut self, timeout: Duration) -> Self {
+        self.params.insert("StopTimeout", timeout.as_secs().into());
+        self
+    }
+
+    /// Health check of the container, see [`HealthcheckBuilder`](HealthcheckBuilder).
+    pub fn healthcheck(mut self, healthcheck: HealthConfig) -> Self {
+        self.params.insert("Healthcheck", json!(healthcheck));
+        self
+    }
 
     impl_str_field!(userns_mode => "HostConfig.UsernsMode");
 
@@ -642,10 +754,30 @@ impl ContainerCreateOptsBuilder {
     pub fn build(&self) -> ContainerCreateOpts {
         ContainerCreateOpts {
             name: self.name.clone(),
+            body: self.body.clone(),
             params: self.params.clone(),
         }
     }
 
+    /// Same as [`build`](ContainerCreateOptsBuilder::build) but consumes the builder, moving the
+    /// parameters instead of cloning them, which matters for large sets of environment variables,
+    /// labels or mounts.
+    pub fn build_owned(self) -> ContainerCreateOpts {
+        ContainerCreateOpts {
+            name: self.name,
+            body: self.body,
+            params: self.params,
+        }
+    }
+
+    /// Set a field of the request body that this builder doesn't cover yet. The `path` is a
+    /// dot separated path of JSON keys, for example `HostConfig.CgroupnsMode`, and overrides a
+    /// value set by another method at the same path.
+    pub fn set_raw(mut self, path: &'static str, value: Value) -> Self {
+        self.params.insert(path, value);
+        self
+    }
+
     impl_str_field!(
     /// The hostname to use for the container, as a valid RFC 1123 hostname.
         hostname => "Hostname"
@@ -703,33 +835,8 @@ impl ContainerRemoveOptsBuilder {
 
 impl_opts_builder!(url => ContainerPrune);
 
-pub enum ContainerPruneFilter {
-    /// Prune containers created before this timestamp. The <timestamp> can be Unix timestamps,
-    /// date formatted timestamps, or Go duration strings (e.g. 10m, 1h30m) computed relative to
-    /// the daemon machine’s time.
-    Until(String),
-    #[cfg(feature = "chrono")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
-    /// Prune containers created before this timestamp. Same as `Until` but takes a datetime object.
-    UntilDate(chrono::DateTime<chrono::Utc>),
-    /// Label in the form of `label=key`.
-    LabelKey(String),
-    /// Label in the form of `label=key=val`.
-    Label(String, String),
-}
-
-impl Filter for ContainerPruneFilter {
-    fn query_item(&self) -> FilterItem {
-        use ContainerPruneFilter::*;
-        match &self {
-            Until(until) => FilterItem::new("until", until.to_owned()),
-            #[cfg(feature = "chrono")]
-            UntilDate(until) => FilterItem::new("until", until.timestamp().to_string()),
-            LabelKey(label) => FilterItem::new("label", label.to_owned()),
-            Label(key, val) => FilterItem::new("label", format!("{key}={val}")),
-        }
-    }
-}
+/// Filter used to filter pruned containers, see [`PruneFilter`](crate::opts::PruneFilter).
+pub type ContainerPruneFilter = crate::opts::PruneFilter;
 
 impl ContainerPruneOptsBuilder {
     impl_filter_func!(ContainerPruneFilter);
@@ -739,7 +846,6 @@ impl_opts_builder!(url => ContainerCommit);
 
 impl ContainerCommitOpts {
     pub(crate) fn with_container(&self, id: &str) -> Self {
-        // not exactly a nice solution but temporary
         let mut s = self.clone();
         s.params.insert("container", id.to_owned());
         s
@@ -767,19 +873,94 @@ impl ContainerCommitOptsBuilder {
         /// Whether to pause the container before committing
         pause => "pause"
     );
-    impl_url_str_field!(
-        /// Dockerfile instructions to apply while committing
+    impl_url_vec_field!(
+        /// Dockerfile instructions to apply while committing, like `CMD ["sleep", "inf"]` or
+        /// `ENV FOO=bar`. Each item is a single instruction.
         changes => "changes"
     );
 }
 
+/// Builder for the health check of a container, passed to
+/// [`ContainerCreateOptsBuilder::healthcheck`](ContainerCreateOptsBuilder::healthcheck) or the
+/// `health_check` of a service's container spec.
+#[derive(Debug, Clone)]
+pub struct HealthcheckBuilder {
+    config: HealthConfig,
+}
+
+impl HealthcheckBuilder {
+    /// Runs `command` with the container's default shell, like `HEALTHCHECK CMD` in a Dockerfile.
+    pub fn shell(command: impl Into<String>) -> Self {
+        Self::test(["CMD-SHELL".to_string(), command.into()])
+    }
+
+    /// Runs `command` directly, without a shell.
+    pub fn command<S>(command: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::test(std::iter::once("CMD".to_string()).chain(command.into_iter().map(Into::into)))
+    }
+
+    /// Disables the health check inherited from the image.
+    pub fn disabled() -> Self {
+        Self::test(["NONE".to_string()])
+    }
+
+    fn test(test: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            config: HealthConfig {
+                test: Some(test.into_iter().collect()),
+                interval: None,
+                timeout: None,
+                start_period: None,
+                retries: None,
+            },
+        }
+    }
+
+    /// Time to wait between checks.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.config.interval = Some(duration_nanos(interval));
+        self
+    }
+
+    /// Time to wait before considering a check to have hung.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(duration_nanos(timeout));
+        self
+    }
+
+    /// Time the container has to start before failed checks count.
+    pub fn start_period(mut self, period: Duration) -> Self {
+        self.config.start_period = Some(duration_nanos(period));
+        self
+    }
+
+    /// Number of consecutive failures needed to consider the container unhealthy.
+    pub fn retries(mut self, retries: isize) -> Self {
+        self.config.retries = Some(retries);
+        self
+    }
+
+    pub fn build(self) -> HealthConfig {
+        self.config
+    }
+}
+
 impl_opts_builder!(url => ContainerStop);
 
+impl ContainerStopOpts {
+    pub(crate) const PARAM_VERSIONS: ParamVersions = &[("signal", api_v1(42))];
+}
+
 impl ContainerStopOptsBuilder {
-    impl_url_str_field!(
-        /// Signal to send to the container as an integer or string (e.g. `SIGINT`).
-        signal => "signal"
-    );
+    /// Signal to send to the container, `SIGTERM` or the stop signal of the container by
+    /// default. Requires API version 1.42.
+    pub fn signal(mut self, signal: impl Into<Signal>) -> Self {
+        self.params.insert("signal", signal.into().to_string());
+        self
+    }
 
     /// Duration to wait before stopping the container
     pub fn wait(mut self, duration: Duration) -> Self {
@@ -790,11 +971,17 @@ impl ContainerStopOptsBuilder {
 
 impl_opts_builder!(url => ContainerRestart);
 
+impl ContainerRestartOpts {
+    pub(crate) const PARAM_VERSIONS: ParamVersions = &[("signal", api_v1(42))];
+}
+
 impl ContainerRestartOptsBuilder {
-    impl_url_str_field!(
-        /// Signal to send to the container as an integer or string (e.g. `SIGINT`).
-        signal => "signal"
-    );
+    /// Signal to send to the container, `SIGTERM` or the stop signal of the container by
+    /// default. Requires API version 1.42.
+    pub fn signal(mut self, signal: impl Into<Signal>) -> Self {
+        self.params.insert("signal", signal.into().to_string());
+        self
+    }
 
     /// Duration to wait before restarting the container
     pub fn wait(mut self, duration: Duration) -> Self {
@@ -807,6 +994,30 @@ impl ContainerRestartOptsBuilder {
 mod tests {
     use super::*;
 
+    #[test]
+    fn insert_serialization_error() {
+        struct Unserializable;
+
+        impl Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                _: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("unserializable value"))
+            }
+        }
+
+        let mut body = json!({});
+        match insert(
+            &mut "HostConfig.Foo".split('.').peekable(),
+            &Unserializable,
+            &mut body,
+        ) {
+            Err(Error::SerdeJsonError(e)) => assert_eq!(e.to_string(), "unserializable value"),
+            other => panic!("expected a serialization error, got {other:?}"),
+        }
+    }
+
     macro_rules! test_case {
         ($opts:expr, $want:expr) => {
             let opts = $opts.build();
@@ -815,6 +1026,104 @@ mod tests {
         };
     }
 
+    #[test]
+    fn create_container_opts_durations() {
+        test_case!(
+            ContainerCreateOptsBuilder::default()
+                .image("test_image")
+                .stop_timeout(Duration::from_millis(30_500))
+                .healthcheck(
+                    HealthcheckBuilder::shell("curl -f localhost")
+                        .interval(Duration::from_secs(30))
+                        .timeout(Duration::from_millis(1500))
+                        .retries(3)
+                        .build()
+                ),
+            r#"{"Healthcheck":{"Interval":30000000000,"Retries":3,"Test":["CMD-SHELL","curl -f localhost"],"Timeout":1500000000},"HostConfig":{},"Image":"test_image","StopTimeout":30}"#
+        );
+    }
+
+    #[test]
+    fn create_container_opts_sizes() {
+        test_case!(
+            ContainerCreateOptsBuilder::default()
+                .image("test_image")
+                .memory(ByteSize::parse("512m").unwrap())
+                .memory_swap(ByteSize::gib(1))
+                .storage_size(ByteSize::gib(10)),
+            r#"{"HostConfig":{"Memory":536870912,"MemorySwap":1073741824,"StorageOpt":{"size":"10737418240"}},"Image":"test_image"}"#
+        );
+        test_case!(
+            ContainerCreateOptsBuilder::default()
+                .image("test_image")
+                .unlimited_swap(),
+            r#"{"HostConfig":{"MemorySwap":-1},"Image":"test_image"}"#
+        );
+    }
+
+    #[test]
+    fn create_container_opts_raw() {
+        test_case!(
+            ContainerCreateOptsBuilder::default()
+                .image("test_image")
+                .set_raw("HostConfig.CgroupnsMode", json!("private"))
+                .set_raw("Image", json!("other_image")),
+            r#"{"HostConfig":{"CgroupnsMode":"private"},"Image":"other_image"}"#
+        );
+    }
+
+    #[test]
+    fn create_container_opts_body() {
+        let body: ContainerCreateBody = serde_json::from_str(
+            r#"{
+                "Image": "nginx",
+                "Env": ["A=1"],
+                "HostConfig": {"Privileged": true, "PortBindings": {"80/tcp": [{"HostPort": "8080"}]}},
+                "NetworkingConfig": {"EndpointsConfig": {"web": {"Aliases": ["nginx"]}}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(body.config.image.as_deref(), Some("nginx"));
+        assert_eq!(body.host_config.as_ref().unwrap().privileged, Some(true));
+
+        let opts = ContainerCreateOpts::from(body.clone());
+        assert_eq!(opts.name(), None);
+        let json: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+        assert_eq!(json["Image"], "nginx");
+        assert_eq!(
+            json["HostConfig"]["PortBindings"]["80/tcp"][0]["HostPort"],
+            "8080"
+        );
+        assert_eq!(
+            json["NetworkingConfig"]["EndpointsConfig"]["web"]["Aliases"],
+            json!(["nginx"])
+        );
+
+        let opts = ContainerCreateOptsBuilder::from(body)
+            .name("web")
+            .env(["A=2"])
+            .auto_remove(true)
+            .build();
+        assert_eq!(opts.name(), Some("web"));
+        let json: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+        assert_eq!(json["Env"], json!(["A=2"]));
+        assert_eq!(json["HostConfig"]["Privileged"], true);
+        assert_eq!(json["HostConfig"]["AutoRemove"], true);
+
+        let config = ContainerConfig {
+            image: Some("alpine".into()),
+            ..serde_json::from_str("{}").unwrap()
+        };
+        test_case!(
+            ContainerCreateOptsBuilder::from(ContainerCreateBody {
+                config,
+                host_config: None,
+                networking_config: None,
+            }),
+            r#"{"HostConfig":{},"Image":"alpine"}"#
+        );
+    }
+
     #[test]
     fn create_container_opts() {
         test_case!(
@@ -928,4 +1237,30 @@ mod tests {
             r#"{"HostConfig":{"RestartPolicy":{"Name":"always"}},"Image":"test_image"}"#
         );
     }
+
+    #[test]
+    fn commit_container_opts() {
+        let opts = ContainerCommitOpts::builder()
+            .repo("test-repo")
+            .tag("v1")
+            .comment("a comment")
+            .author("docker api rs")
+            .pause(true)
+            .changes([r#"CMD ["sleep", "inf"]"#, "ENV FOO=bar"])
+            .build();
+
+        pretty_assertions::assert_eq!(
+            Some("author=docker+api+rs&comment=a+comment&pause=true&repo=test-repo&tag=v1&changes=CMD+%5B%22sleep%22%2C+%22inf%22%5D&changes=ENV+FOO%3Dbar".to_string()),
+            opts.serialize()
+        );
+
+        pretty_assertions::assert_eq!(
+            Some("container=abc&repo=test-repo".to_string()),
+            ContainerCommitOpts::builder()
+                .repo("test-repo")
+                .build()
+                .with_container("abc")
+                .serialize()
+        );
+    }
 }
@@ -1,6 +1,8 @@
-use containers_api::{impl_field, impl_opts_builder, impl_str_field, impl_vec_field};
+use containers_api::{impl_field, impl_str_field, impl_vec_field};
 use serde::Serialize;
 
+use crate::opts::{api_v1, ParamVersions};
+
 impl_opts_builder!(json => ExecCreate);
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -19,6 +21,10 @@ impl Serialize for ConsoleSize {
     }
 }
 
+impl ExecCreateOpts {
+    pub(crate) const PARAM_VERSIONS: ParamVersions = &[("ConsoleSize", api_v1(42))];
+}
+
 impl ExecCreateOptsBuilder {
     impl_vec_field!(
         /// Command to run, as an array of strings.
@@ -73,7 +79,7 @@ impl ExecCreateOptsBuilder {
     );
 
     impl_field!(
-        /// Initial console size
+        /// Initial console size. Requires API version 1.42.
         console_size: ConsoleSize => "ConsoleSize"
     );
 }
@@ -87,6 +93,10 @@ impl ExecResizeOptsBuilder {
 
 impl_opts_builder!(json => ExecStart);
 
+impl ExecStartOpts {
+    pub(crate) const PARAM_VERSIONS: ParamVersions = &[("ConsoleSize", api_v1(42))];
+}
+
 impl ExecStartOptsBuilder {
     impl_field!(
         /// Detach from the command.
@@ -99,7 +109,7 @@ impl ExecStartOptsBuilder {
     );
 
     impl_field!(
-        /// Initial console size
+        /// Initial console size. Requires API version 1.42.
         console_size: ConsoleSize => "ConsoleSize"
     );
 }
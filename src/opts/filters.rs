@@ -0,0 +1,105 @@
+use containers_api::opts::Filter;
+
+use std::collections::BTreeMap;
+
+/// Filters of a list, prune or events endpoint. Maps each key to one or more values and is sent
+/// JSON encoded as the `filters` query parameter.
+///
+/// Usually built from the typed filters passed to the `filter` method of a builder, but keys
+/// that the typed filters don't cover yet can be added with [`add`](Filters::add) and passed
+/// to the `filters` method instead.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filters(BTreeMap<String, Vec<String>>);
+
+impl Filters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `value` to the values of `key`. Adding the same value twice has no effect.
+    pub fn add(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.insert(key.into(), value.into());
+        self
+    }
+
+    fn insert(&mut self, key: String, value: String) {
+        let values = self.0.entry(key).or_default();
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+
+    /// Returns the values of `key`.
+    pub fn get(&self, key: &str) -> Option<&[String]> {
+        self.0.get(key).map(Vec::as_slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Encodes the filters as a JSON object mapping keys to lists of values, the format
+    /// expected by the `filters` query parameter.
+    pub fn serialize(&self) -> String {
+        serde_json::to_string(&self.0).unwrap_or_default()
+    }
+}
+
+impl<F: Filter> Extend<F> for Filters {
+    fn extend<I: IntoIterator<Item = F>>(&mut self, filters: I) {
+        for item in filters.into_iter().map(|f| f.query_item()) {
+            self.insert(item.key().to_string(), item.to_string());
+        }
+    }
+}
+
+impl<F: Filter> FromIterator<F> for Filters {
+    fn from_iter<I: IntoIterator<Item = F>>(filters: I) -> Self {
+        let mut this = Self::new();
+        this.extend(filters);
+        this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opts::{ContainerFilter, ContainerListOpts, PruneFilter, VolumePruneOpts};
+
+    #[test]
+    fn filters() {
+        let filters = [
+            ContainerFilter::LabelKey("a".into()),
+            ContainerFilter::Label("b".into(), "c".into()),
+            ContainerFilter::LabelKey("a".into()),
+        ]
+        .into_iter()
+        .collect::<Filters>()
+        .add("is-task", "true");
+        assert_eq!(
+            Some(&["a".to_string(), "b=c".to_string()][..]),
+            filters.get("label")
+        );
+        assert_eq!(
+            r#"{"is-task":["true"],"label":["a","b=c"]}"#,
+            filters.serialize()
+        );
+
+        let opts = ContainerListOpts::builder().filters(filters).build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22is-task%22%3A%5B%22true%22%5D%2C%22label%22%3A%5B%22a%22%2C%22b%3Dc%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+
+        let opts = VolumePruneOpts::builder()
+            .filter([PruneFilter::All(true)])
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22all%22%3A%5B%22true%22%5D%7D".to_string()),
+            opts.serialize()
+        );
+    }
+}
@@ -1,18 +1,21 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     path::{Path, PathBuf},
+    str::FromStr,
     string::ToString,
 };
 
 use base64::{engine::general_purpose, Engine};
 use containers_api::opts::{Filter, FilterItem};
 use containers_api::url::encoded_pairs;
-use containers_api::{
-    impl_filter_func, impl_map_field, impl_opts_builder, impl_str_field, impl_url_bool_field,
-    impl_url_field, impl_url_str_field,
-};
+use containers_api::{impl_url_bool_field, impl_url_field, impl_url_str_field};
 use serde::Serialize;
 
+use crate::{
+    opts::{api_v1, ByteSize, ParamVersions},
+    Error, Result,
+};
+
 #[derive(Clone, Serialize, Debug)]
 #[serde(untagged)]
 pub enum RegistryAuth {
@@ -50,14 +53,12 @@ impl RegistryAuth {
     }
 
     /// serialize authentication as JSON in base64
-    pub fn serialize(&self) -> String {
-        serde_json::to_string(self)
-            .map(|c| general_purpose::URL_SAFE.encode(c))
-            .unwrap_or_default()
+    pub fn serialize(&self) -> Result<String> {
+        Ok(general_purpose::URL_SAFE.encode(serde_json::to_string(self)?))
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct RegistryAuthBuilder {
     username: Option<String>,
     password: Option<String>,
@@ -112,6 +113,17 @@ impl RegistryAuthBuilder {
             server_address: self.server_address.clone(),
         }
     }
+
+    /// Same as [`build`](RegistryAuthBuilder::build) but consumes the builder so the credentials
+    /// are moved rather than cloned.
+    pub fn build_owned(self) -> RegistryAuth {
+        RegistryAuth::Password {
+            username: self.username.unwrap_or_default(),
+            password: self.password.unwrap_or_default(),
+            email: self.email,
+            server_address: self.server_address,
+        }
+    }
 }
 
 impl_opts_builder!(url => Tag);
@@ -120,12 +132,22 @@ impl TagOptsBuilder {
     impl_url_str_field!(repo => "repo");
 
     impl_url_str_field!(tag => "tag");
+
+    /// Set both the repository and the tag from a parsed [`Reference`](Reference). The digest of
+    /// the reference, if any, is ignored as images can't be tagged with a digest.
+    pub fn reference(self, reference: &Reference) -> Self {
+        let builder = self.repo(reference.name());
+        match reference.tag() {
+            Some(tag) => builder.tag(tag),
+            None => builder,
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct PullOpts {
     auth: Option<RegistryAuth>,
-    params: HashMap<&'static str, serde_json::Value>,
+    params: BTreeMap<&'static str, String>,
 }
 
 impl PullOpts {
@@ -139,35 +161,45 @@ impl PullOpts {
         if self.params.is_empty() {
             None
         } else {
-            Some(encoded_pairs(
-                self.params
-                    .iter()
-                    .map(|(k, v)| (k, v.as_str().unwrap_or_default())),
-            ))
+            Some(encoded_pairs(self.params.iter()))
         }
     }
 
-    pub(crate) fn auth_header(&self) -> Option<String> {
-        self.auth.clone().map(|a| a.serialize())
+    pub(crate) fn auth_header(&self) -> Result<Option<String>> {
+        self.auth.as_ref().map(RegistryAuth::serialize).transpose()
+    }
+
+    /// Returns these options pulling `reference` instead of the image set on them.
+    pub(crate) fn with_reference(&self, reference: &Reference) -> Self {
+        let mut params = self.params.clone();
+        params.remove("fromImage");
+        params.insert("tag", "latest".into());
+        PullOptsBuilder {
+            auth: self.auth.clone(),
+            params,
+        }
+        .reference(reference)
+        .build()
     }
 }
 
+#[derive(Clone)]
 pub struct PullOptsBuilder {
     auth: Option<RegistryAuth>,
-    params: HashMap<&'static str, serde_json::Value>,
+    params: BTreeMap<&'static str, String>,
 }
 
 impl Default for PullOptsBuilder {
     fn default() -> Self {
-        let mut params = HashMap::new();
-        params.insert("tag", serde_json::Value::String("latest".into()));
+        let mut params = BTreeMap::new();
+        params.insert("tag", "latest".into());
 
         PullOptsBuilder { auth: None, params }
     }
 }
 
 impl PullOptsBuilder {
-    impl_str_field!(
+    impl_url_str_field!(
     /// Name of the image to pull. The name may include a tag or digest.
     /// This parameter may only be used when pulling an image.
     /// If an untagged value is provided and no `tag` is provided, _all_
@@ -175,9 +207,9 @@ impl PullOptsBuilder {
     /// The pull is cancelled if the HTTP connection is closed.
     image => "fromImage");
 
-    impl_str_field!(src => "fromSrc");
+    impl_url_str_field!(src => "fromSrc");
 
-    impl_str_field!(
+    impl_url_str_field!(
     /// Repository name given to an image when it is imported. The repo may include a tag.
     /// This parameter may only be used when importing an image.
     /// 
@@ -185,11 +217,26 @@ impl PullOptsBuilder {
     /// [PullOptsBuilder::default](PullOptsBuilder::default).
     repo => "repo");
 
-    impl_str_field!(
+    impl_url_str_field!(
     /// Tag or digest. If empty when pulling an image,
     /// this causes all tags for the given image to be pulled.
     tag => "tag");
 
+    impl_url_str_field!(
+    /// Platform in the format os[/arch[/variant]] to pull the image for. Requires API version
+    /// 1.32.
+    platform => "platform");
+
+    /// Set the image to pull from a parsed [`Reference`](Reference). If the reference contains a
+    /// digest it takes precedence over the tag.
+    pub fn reference(self, reference: &Reference) -> Self {
+        let builder = self.image(reference.name());
+        match reference.digest().or_else(|| reference.tag()) {
+            Some(tag) => builder.tag(tag),
+            None => builder,
+        }
+    }
+
     pub fn auth(mut self, auth: RegistryAuth) -> Self {
         self.auth = Some(auth);
         self
@@ -206,10 +253,14 @@ impl PullOptsBuilder {
 #[derive(Default, Debug, Clone)]
 pub struct ImageBuildOpts {
     pub path: PathBuf,
-    params: HashMap<&'static str, String>,
+    pub(crate) params: BTreeMap<&'static str, String>,
+    pub(crate) compress: bool,
 }
 
 impl ImageBuildOpts {
+    pub(crate) const PARAM_VERSIONS: ParamVersions =
+        &[("platform", api_v1(32)), ("outputs", api_v1(40))];
+
     /// return a new instance of a builder for Opts
     /// path is expected to be a file path to a directory containing a Dockerfile
     /// describing how to build a Docker image
@@ -230,10 +281,11 @@ impl ImageBuildOpts {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ImageBuildOptsBuilder {
     path: PathBuf,
-    params: HashMap<&'static str, String>,
+    params: BTreeMap<&'static str, String>,
+    compress: bool,
 }
 
 impl ImageBuildOptsBuilder {
@@ -285,15 +337,17 @@ impl ImageBuildOptsBuilder {
 
     impl_url_bool_field!(forcerm => "forcerm");
 
-    impl_url_field!(
-        /// Set memory limit for build.
-        memory: usize => "memory"
-    );
+    /// Set memory limit for build.
+    pub fn memory(mut self, memory: impl Into<ByteSize>) -> Self {
+        self.params.insert("memory", memory.into().to_string());
+        self
+    }
 
-    impl_url_field!(
-        /// Total memory (memory + swap). Set as -1 to disable swap.
-        memswap: usize => "memswap"
-    );
+    /// Total memory (memory + swap).
+    pub fn memswap(mut self, memswap: impl Into<ByteSize>) -> Self {
+        self.params.insert("memswap", memswap.into().to_string());
+        self
+    }
 
     impl_url_field!(
         /// CPU shares (relative weight).
@@ -315,15 +369,16 @@ impl ImageBuildOptsBuilder {
         cpu_quota: usize => "cpuquota"
     );
 
-    impl_map_field!(url
+    impl_url_map_field!(
         /// Set build-time variables.
         build_args => "buildargs"
     );
 
-    impl_url_field!(
-        /// Size of /dev/shm in bytes. The size must be greater than 0. If omitted the system uses 64MB.
-        shm_size: usize => "shmsize"
-    );
+    /// Size of /dev/shm. The size must be greater than 0. If omitted the system uses 64MB.
+    pub fn shm_size(mut self, size: impl Into<ByteSize>) -> Self {
+        self.params.insert("shmsize", size.into().to_string());
+        self
+    }
 
     impl_url_bool_field!(
         /// Squash the resulting images layers into a single layer. (Experimental release only.)
@@ -337,7 +392,7 @@ impl ImageBuildOptsBuilder {
     );
 
     impl_url_str_field!(
-        /// Platform in the format os[/arch[/variant]].
+        /// Platform in the format os[/arch[/variant]]. Requires API version 1.32.
         platform => "platform"
     );
 
@@ -347,19 +402,40 @@ impl ImageBuildOptsBuilder {
     );
 
     impl_url_str_field!(
-        /// BuildKit output configuration.
+        /// BuildKit output configuration. Requires API version 1.40.
         outputs => "outputs"
     );
 
-    impl_map_field!(url
+    impl_url_map_field!(
         /// Add labels to this image.
         labels => "labels"
     );
 
+    /// Compress the build context with gzip while it is uploaded by
+    /// [`Images::build_from_stream`](crate::Images::build_from_stream) and
+    /// [`Images::build_from_reader`](crate::Images::build_from_reader). This cuts the upload time
+    /// of large contexts sent to remote daemons at the cost of CPU time. The context must not be
+    /// compressed already. Contexts read from `path` are always compressed.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
     pub fn build(&self) -> ImageBuildOpts {
         ImageBuildOpts {
             path: self.path.clone(),
             params: self.params.clone(),
+            compress: self.compress,
+        }
+    }
+
+    /// Same as [`build`](ImageBuildOptsBuilder::build) but consumes the builder so the parameters,
+    /// like build arguments and labels, are moved rather than cloned.
+    pub fn build_owned(self) -> ImageBuildOpts {
+        ImageBuildOpts {
+            path: self.path,
+            params: self.params,
+            compress: self.compress,
         }
     }
 }
@@ -374,15 +450,15 @@ pub enum ImageName {
     Digest { image: String, digest: String },
 }
 
-impl ToString for ImageName {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ImageName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
             ImageName::Tag { image, tag } => match tag {
-                Some(tag) => format!("{image}:{tag}"),
-                None => image.to_owned(),
+                Some(tag) => write!(f, "{image}:{tag}"),
+                None => write!(f, "{image}"),
             },
-            ImageName::Id(id) => id.to_owned(),
-            ImageName::Digest { image, digest } => format!("{image}@{digest}"),
+            ImageName::Id(id) => write!(f, "{id}"),
+            ImageName::Digest { image, digest } => write!(f, "{image}@{digest}"),
         }
     }
 }
@@ -421,6 +497,174 @@ impl ImageName {
     }
 }
 
+const DEFAULT_REGISTRY: &str = "docker.io";
+const LEGACY_DEFAULT_REGISTRY: &str = "index.docker.io";
+const OFFICIAL_REPOSITORY_PREFIX: &str = "library/";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A parsed and normalized image reference in the form of
+/// `[registry/]repository[:tag][@digest]`.
+///
+/// References without a registry default to `docker.io` and single component repositories on
+/// Docker Hub get the `library/` prefix, so `alpine` becomes `docker.io/library/alpine`.
+pub struct Reference {
+    registry: String,
+    repository: String,
+    tag: Option<String>,
+    digest: Option<String>,
+}
+
+impl Reference {
+    /// Parse and normalize an image reference.
+    pub fn parse(reference: impl AsRef<str>) -> Result<Self> {
+        let reference = reference.as_ref();
+        let invalid = |reason: &str| Error::InvalidReference(format!("{reference} - {reason}"));
+
+        if reference.is_empty() {
+            return Err(invalid("reference is empty"));
+        }
+
+        let (name, digest) = match reference.split_once('@') {
+            Some((name, digest)) => {
+                if !is_valid_digest(digest) {
+                    return Err(invalid("invalid digest format"));
+                }
+                (name, Some(digest.to_string()))
+            }
+            None => (reference, None),
+        };
+
+        let last_component_start = name.rfind('/').map(|i| i + 1).unwrap_or(0);
+        let (name, tag) = match name[last_component_start..].rfind(':') {
+            Some(i) => {
+                let tag = &name[last_component_start + i + 1..];
+                if !is_valid_tag(tag) {
+                    return Err(invalid("invalid tag format"));
+                }
+                (&name[..last_component_start + i], Some(tag.to_string()))
+            }
+            None => (name, None),
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((domain, rest))
+                if domain.contains(['.', ':'])
+                    || domain == "localhost"
+                    || domain.chars().any(|c| c.is_ascii_uppercase()) =>
+            {
+                (domain, rest.to_string())
+            }
+            _ => (DEFAULT_REGISTRY, name.to_string()),
+        };
+
+        let registry = if registry == LEGACY_DEFAULT_REGISTRY {
+            DEFAULT_REGISTRY
+        } else {
+            registry
+        };
+
+        let repository = if registry == DEFAULT_REGISTRY && !repository.contains('/') {
+            format!("{OFFICIAL_REPOSITORY_PREFIX}{repository}")
+        } else {
+            repository
+        };
+
+        if !repository.split('/').all(is_valid_path_component) {
+            return Err(invalid(
+                "repository must consist of lowercase alphanumeric components separated by `/`",
+            ));
+        }
+
+        Ok(Self {
+            registry: registry.to_string(),
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    /// The registry hosting the image, for example `docker.io`.
+    pub fn registry(&self) -> &str {
+        &self.registry
+    }
+
+    /// The repository path inside of the registry, for example `library/alpine`.
+    pub fn repository(&self) -> &str {
+        &self.repository
+    }
+
+    /// The tag of this reference if one was specified.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// The digest of this reference if one was specified.
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    /// Fully qualified name of the image without the tag and digest, for example
+    /// `docker.io/library/alpine`.
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.registry, self.repository)
+    }
+}
+
+impl FromStr for Reference {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.registry, self.repository)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    let mut chars = tag.chars();
+    tag.len() <= 128
+        && chars
+            .next()
+            .map(|c| c.is_ascii_alphanumeric() || c == '_')
+            .unwrap_or_default()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+fn is_valid_digest(digest: &str) -> bool {
+    match digest.split_once(':') {
+        Some((algorithm, hex)) => {
+            !algorithm.is_empty()
+                && algorithm
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '_' | '-'))
+                && hex.len() >= 32
+                && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+fn is_valid_path_component(component: &str) -> bool {
+    let is_separator = |c: char| matches!(c, '.' | '_' | '-');
+    !component.is_empty()
+        && !component.starts_with(is_separator)
+        && !component.ends_with(is_separator)
+        && component
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || is_separator(c))
+}
+
 /// Filter type used to filter listed images.
 pub enum ImageFilter {
     Before(ImageName),
@@ -491,37 +735,8 @@ impl ImageRemoveOptsBuilder {
 
 impl_opts_builder!(url => ImagePrune);
 
-pub enum ImagesPruneFilter {
-    /// When set to `true`, prune only unused and untagged images.
-    /// When set to `false`, all unused images are pruned.
-    Dangling(bool),
-    #[cfg(feature = "chrono")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
-    /// Prune images created before this timestamp. Same as `Until` but takes a datetime object.
-    UntilDate(chrono::DateTime<chrono::Utc>),
-    /// Prune images created before this timestamp. The <timestamp> can be Unix timestamps,
-    /// date formatted timestamps, or Go duration strings (e.g. 10m, 1h30m)
-    /// computed relative to the daemon machine’s time.
-    Until(String),
-    /// Label in the form of `label=key`.
-    LabelKey(String),
-    /// Label in the form of `label=key=val`.
-    Label(String, String),
-}
-
-impl Filter for ImagesPruneFilter {
-    fn query_item(&self) -> FilterItem {
-        use ImagesPruneFilter::*;
-        match &self {
-            Dangling(dangling) => FilterItem::new("dangling", dangling.to_string()),
-            Until(until) => FilterItem::new("until", until.to_owned()),
-            #[cfg(feature = "chrono")]
-            UntilDate(until) => FilterItem::new("until", until.timestamp().to_string()),
-            LabelKey(label) => FilterItem::new("label", label.to_owned()),
-            Label(key, val) => FilterItem::new("label", format!("{key}={val}")),
-        }
-    }
-}
+/// Filter used to filter pruned images, see [`PruneFilter`](crate::opts::PruneFilter).
+pub type ImagesPruneFilter = crate::opts::PruneFilter;
 
 impl ImagePruneOptsBuilder {
     impl_filter_func!(ImagesPruneFilter);
@@ -534,8 +749,9 @@ pub enum CacheFilter {
     /// in Go's duration format (e.g., '24h').
     Until(String),
     Id(String),
-    // ID of the parent.
+    /// ID of the parent.
     Parent(String),
+    /// Type of the cache record, for example `regular` or `source.local`.
     Type(String),
     Description(String),
     InUse,
@@ -550,7 +766,7 @@ impl Filter for CacheFilter {
             Until(until) => FilterItem::new("until", until.to_owned()),
             Id(id) => FilterItem::new("id", id.to_owned()),
             Parent(parent) => FilterItem::new("parent", parent.to_owned()),
-            Type(type_) => FilterItem::new("type_", type_.to_owned()),
+            Type(type_) => FilterItem::new("type", type_.to_owned()),
             Description(description) => FilterItem::new("description", description.to_owned()),
             InUse => FilterItem::new("inuse", "".to_owned()),
             Shared => FilterItem::new("shared", "".to_owned()),
@@ -576,7 +792,7 @@ impl ClearCacheOptsBuilder {
 
 pub struct ImagePushOpts {
     auth: Option<RegistryAuth>,
-    params: HashMap<&'static str, String>,
+    params: BTreeMap<&'static str, String>,
 }
 
 impl ImagePushOpts {
@@ -592,14 +808,15 @@ impl ImagePushOpts {
         }
     }
 
-    pub(crate) fn auth_header(&self) -> Option<String> {
-        self.auth.clone().map(|a| a.serialize())
+    pub(crate) fn auth_header(&self) -> Result<Option<String>> {
+        self.auth.as_ref().map(RegistryAuth::serialize).transpose()
     }
 }
 
+#[derive(Clone)]
 pub struct ImagePushOptsBuilder {
     auth: Option<RegistryAuth>,
-    params: HashMap<&'static str, String>,
+    params: BTreeMap<&'static str, String>,
 }
 
 impl Default for ImagePushOptsBuilder {
@@ -617,6 +834,15 @@ impl ImagePushOptsBuilder {
         tag => "tag"
     );
 
+    /// Set the tag to push from a parsed [`Reference`](Reference). The image itself is selected
+    /// by [`Images::push`](crate::Images::push) so pass [`Reference::name`](Reference::name) there.
+    pub fn reference(self, reference: &Reference) -> Self {
+        match reference.tag() {
+            Some(tag) => self.tag(tag),
+            None => self,
+        }
+    }
+
     pub fn auth(mut self, auth: RegistryAuth) -> Self {
         self.auth = Some(auth);
         self
@@ -639,8 +865,8 @@ mod tests {
     fn registry_auth_token() {
         let opts = RegistryAuth::token("abc");
         assert_eq!(
-            base64::encode(r#"{"identitytoken":"abc"}"#),
-            opts.serialize()
+            general_purpose::URL_SAFE.encode(r#"{"identitytoken":"abc"}"#),
+            opts.serialize().unwrap()
         );
     }
 
@@ -652,8 +878,9 @@ mod tests {
             .password("password_abc")
             .build();
         assert_eq!(
-            base64::encode(r#"{"username":"user_abc","password":"password_abc"}"#),
-            opts.serialize()
+            general_purpose::URL_SAFE
+                .encode(r#"{"username":"user_abc","password":"password_abc"}"#),
+            opts.serialize().unwrap()
         );
     }
 
@@ -667,9 +894,138 @@ mod tests {
             .server_address("https://example.org")
             .build();
         assert_eq!(
-            base64::encode(
+            general_purpose::URL_SAFE.encode(
                 r#"{"username":"user_abc","password":"password_abc","email":"email_abc","serveraddress":"https://example.org"}"#
             ),
+            opts.serialize().unwrap()
+        );
+    }
+
+    #[test]
+    fn build_opts_deterministic() {
+        let opts = ImageBuildOpts::builder(".")
+            .tag("app")
+            .labels([("z", "1"), ("a", "2")])
+            .build_args([("VERSION", "1.0"), ("ARCH", "amd64")])
+            .build();
+        assert_eq!(
+            Some(
+                "buildargs=%7B%22ARCH%22%3A%22amd64%22%2C%22VERSION%22%3A%221.0%22%7D\
+                 &labels=%7B%22a%22%3A%222%22%2C%22z%22%3A%221%22%7D&t=app"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn reference_normalization() {
+        let reference = Reference::parse("alpine").unwrap();
+        assert_eq!("docker.io", reference.registry());
+        assert_eq!("library/alpine", reference.repository());
+        assert_eq!(None, reference.tag());
+        assert_eq!(None, reference.digest());
+        assert_eq!("docker.io/library/alpine", reference.to_string());
+
+        let reference = Reference::parse("index.docker.io/user/app:1.0").unwrap();
+        assert_eq!("docker.io/user/app:1.0", reference.to_string());
+
+        let reference: Reference = "localhost:5000/team/app:v2".parse().unwrap();
+        assert_eq!("localhost:5000", reference.registry());
+        assert_eq!("team/app", reference.repository());
+        assert_eq!(Some("v2"), reference.tag());
+
+        let digest = "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let reference = Reference::parse(format!("ghcr.io/org/app:latest@{digest}")).unwrap();
+        assert_eq!("ghcr.io", reference.registry());
+        assert_eq!(Some("latest"), reference.tag());
+        assert_eq!(Some(digest), reference.digest());
+        assert_eq!(
+            format!("ghcr.io/org/app:latest@{digest}"),
+            reference.to_string()
+        );
+    }
+
+    #[test]
+    fn reference_invalid() {
+        for reference in [
+            "",
+            "Alpine",
+            "alpine:",
+            "alpine:-tag",
+            "alpine@sha256:abc",
+            "alpine@nodigest",
+            "example.com/",
+            "example.com/app_/x",
+        ] {
+            assert!(
+                matches!(Reference::parse(reference), Err(Error::InvalidReference(_))),
+                "{reference} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn reference_opts() {
+        let reference = Reference::parse("alpine:3.18").unwrap();
+
+        let opts = PullOpts::builder().reference(&reference).build();
+        let serialized = opts.serialize().unwrap();
+        assert!(serialized.contains("fromImage=docker.io%2Flibrary%2Falpine"));
+        assert!(serialized.contains("tag=3.18"));
+
+        let opts = PullOpts::builder()
+            .image("busybox")
+            .platform("linux/arm64")
+            .build()
+            .with_reference(&Reference::parse("alpine").unwrap());
+        assert_eq!(
+            Some(
+                "fromImage=docker.io%2Flibrary%2Falpine&platform=linux%2Farm64&tag=latest"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+
+        let opts = TagOpts::builder().reference(&reference).build();
+        assert_eq!(
+            Some("repo=docker.io%2Flibrary%2Falpine&tag=3.18".to_string()),
+            opts.serialize()
+        );
+
+        let opts = ImagePushOpts::builder().reference(&reference).build();
+        assert_eq!(Some("tag=3.18".to_string()), opts.serialize());
+    }
+
+    /// Test registry auth body sent to the auth endpoint
+    #[test]
+    fn registry_auth_json() {
+        let auth = RegistryAuth::builder()
+            .username("user_abc")
+            .password("password_abc")
+            .server_address("registry.example.org")
+            .build();
+        assert_eq!(
+            r#"{"username":"user_abc","password":"password_abc","serveraddress":"registry.example.org"}"#,
+            serde_json::to_string(&auth).unwrap()
+        );
+    }
+
+    #[test]
+    fn clear_cache_opts() {
+        let opts = ClearCacheOpts::builder()
+            .keep_storage(1024)
+            .all(true)
+            .filter([
+                CacheFilter::Until("24h".into()),
+                CacheFilter::Type("regular".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "all=true&filters=%7B%22type%22%3A%5B%22regular%22%5D%2C%22until%22%3A%5B%2224h%22%5D%7D&keep-storage=1024"
+                    .to_string()
+            ),
             opts.serialize()
         );
     }
@@ -1,6 +1,11 @@
 //! Options used for configuring the behavior of certain API endpoints
+//!
+//! Durations like timeouts and intervals are taken as [`Duration`](std::time::Duration) and
+//! converted to the unit each endpoint expects. Points in time are taken as chrono or time
+//! datetimes depending on the enabled features, or as UNIX timestamps.
 mod container;
 mod exec;
+mod filters;
 mod image;
 mod network;
 mod system;
@@ -15,12 +20,18 @@ mod node;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 mod plugin;
+#[cfg(feature = "podman")]
+#[cfg_attr(docsrs, doc(cfg(feature = "podman")))]
+mod pod;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 mod secret;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 mod service;
+#[cfg(feature = "stack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stack")))]
+mod stack;
 #[cfg(feature = "swarm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "swarm")))]
 mod swarm;
@@ -30,6 +41,7 @@ mod task;
 
 pub use container::*;
 pub use exec::*;
+pub use filters::*;
 pub use image::*;
 pub use network::*;
 pub use system::*;
@@ -41,16 +53,291 @@ pub use config::*;
 pub use node::*;
 #[cfg(feature = "swarm")]
 pub use plugin::*;
+#[cfg(feature = "podman")]
+pub use pod::*;
 #[cfg(feature = "swarm")]
 pub use secret::*;
 #[cfg(feature = "swarm")]
 pub use service::*;
+#[cfg(feature = "stack")]
+pub use stack::*;
 #[cfg(feature = "swarm")]
 pub use swarm::*;
 #[cfg(feature = "swarm")]
 pub use task::*;
 
-use containers_api::{impl_opts_builder, impl_url_bool_field, impl_url_field};
+use containers_api::opts::{Filter, FilterItem};
+use containers_api::{impl_url_bool_field, impl_url_field};
+
+use crate::{ApiVersion, Error, Result};
+use std::time::Duration;
+
+/// Parameters of options paired with the minimum API version that accepts them. Requests of a
+/// client pinned to an older version fail with
+/// [`UnsupportedApiVersion`](crate::Error::UnsupportedApiVersion) before reaching the daemon.
+pub(crate) type ParamVersions = &'static [(&'static str, ApiVersion)];
+
+/// Returns the API version `1.minor`.
+pub(crate) const fn api_v1(minor: usize) -> ApiVersion {
+    ApiVersion::new(1, Some(minor), None)
+}
+
+/// Converts a duration to the nanoseconds used by duration fields of the models, saturating at
+/// `i64::MAX`.
+pub(crate) fn duration_nanos(duration: Duration) -> i64 {
+    duration.as_nanos().try_into().unwrap_or(i64::MAX)
+}
+
+/// Size in bytes used by the memory and shm size fields of the builders.
+///
+/// Can be created from a number of bytes or parsed from a human readable size like `512m`,
+/// `1.5g` or `2GiB` the same way the docker CLI does, so that invalid sizes are caught before
+/// sending a request. Units are case insensitive and based on 1024.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    pub const KIB: u64 = 1 << 10;
+    pub const MIB: u64 = 1 << 20;
+    pub const GIB: u64 = 1 << 30;
+
+    /// Creates a size of `bytes` bytes.
+    pub const fn b(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    /// Creates a size of `kib` kibibytes, saturating at `u64::MAX` bytes.
+    pub const fn kib(kib: u64) -> Self {
+        Self(kib.saturating_mul(Self::KIB))
+    }
+
+    /// Creates a size of `mib` mebibytes, saturating at `u64::MAX` bytes.
+    pub const fn mib(mib: u64) -> Self {
+        Self(mib.saturating_mul(Self::MIB))
+    }
+
+    /// Creates a size of `gib` gibibytes, saturating at `u64::MAX` bytes.
+    pub const fn gib(gib: u64) -> Self {
+        Self(gib.saturating_mul(Self::GIB))
+    }
+
+    /// Parses a size like `512m`, `1.5G`, `2 GiB` or `1024`. Sizes that don't fit in a `u64` are
+    /// rejected.
+    pub fn parse(size: impl AsRef<str>) -> Result<Self> {
+        let size = size.as_ref();
+        let invalid = || Error::InvalidByteSize(size.to_string());
+        let lower = size.trim().to_ascii_lowercase();
+        let number = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let multiplier: u64 = match &lower[number.len()..] {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => 1 << 10,
+            "m" | "mb" | "mib" => 1 << 20,
+            "g" | "gb" | "gib" => 1 << 30,
+            "t" | "tb" | "tib" => 1 << 40,
+            "p" | "pb" | "pib" => 1 << 50,
+            _ => return Err(invalid()),
+        };
+        let number = number.trim_end();
+        if let Ok(value) = number.parse::<u64>() {
+            return value.checked_mul(multiplier).map(Self).ok_or_else(invalid);
+        }
+        let value: f64 = number.parse().map_err(|_| invalid())?;
+        let bytes = value * multiplier as f64;
+        // u64::MAX rounds up to 2^64 as a float, so sizes equal to it overflow as well
+        if !bytes.is_finite() || bytes < 0. || bytes >= u64::MAX as f64 {
+            return Err(invalid());
+        }
+        Ok(Self(bytes as u64))
+    }
+
+    /// Returns the size in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Signal sent to a process, used to stop, restart or kill containers.
+///
+/// Names and numbers of signals are those of Linux. Signals without a variant, like real-time
+/// signals, can be passed as a [`Number`](Signal::Number) or by name as [`Other`](Signal::Other).
+/// Strings and numbers convert into the matching variant, with or without the `SIG` prefix.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Signal {
+    Hup,
+    Int,
+    Quit,
+    Abrt,
+    Kill,
+    Usr1,
+    Usr2,
+    Pipe,
+    Alrm,
+    Term,
+    Cont,
+    Stop,
+    Tstp,
+    Winch,
+    Number(u32),
+    Other(String),
+}
+
+impl Signal {
+    const SIGNALS: [(Signal, &'static str, u32); 14] = [
+        (Signal::Hup, "SIGHUP", 1),
+        (Signal::Int, "SIGINT", 2),
+        (Signal::Quit, "SIGQUIT", 3),
+        (Signal::Abrt, "SIGABRT", 6),
+        (Signal::Kill, "SIGKILL", 9),
+        (Signal::Usr1, "SIGUSR1", 10),
+        (Signal::Usr2, "SIGUSR2", 12),
+        (Signal::Pipe, "SIGPIPE", 13),
+        (Signal::Alrm, "SIGALRM", 14),
+        (Signal::Term, "SIGTERM", 15),
+        (Signal::Cont, "SIGCONT", 18),
+        (Signal::Stop, "SIGSTOP", 19),
+        (Signal::Tstp, "SIGTSTP", 20),
+        (Signal::Winch, "SIGWINCH", 28),
+    ];
+
+    /// Returns the name of this signal like `SIGTERM`, `None` for signals given as a number
+    /// without a variant.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Signal::Number(_) => None,
+            Signal::Other(name) => Some(name),
+            signal => Self::SIGNALS
+                .iter()
+                .find(|(s, ..)| s == signal)
+                .map(|(_, name, _)| *name),
+        }
+    }
+
+    /// Returns the number of this signal, `None` for signals given by a name without a variant.
+    pub fn number(&self) -> Option<u32> {
+        match self {
+            Signal::Number(number) => Some(*number),
+            Signal::Other(_) => None,
+            signal => Self::SIGNALS
+                .iter()
+                .find(|(s, ..)| s == signal)
+                .map(|(.., number)| *number),
+        }
+    }
+}
+
+impl From<u32> for Signal {
+    fn from(number: u32) -> Self {
+        Self::SIGNALS
+            .iter()
+            .find(|(.., n)| *n == number)
+            .map(|(signal, ..)| signal.clone())
+            .unwrap_or(Signal::Number(number))
+    }
+}
+
+impl From<&str> for Signal {
+    fn from(signal: &str) -> Self {
+        if let Ok(number) = signal.parse::<u32>() {
+            return Self::from(number);
+        }
+        let upper = signal.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        Self::SIGNALS
+            .iter()
+            .find(|(_, n, _)| n[3..] == *name)
+            .map(|(signal, ..)| signal.clone())
+            .unwrap_or_else(|| Signal::Other(signal.to_string()))
+    }
+}
+
+impl From<String> for Signal {
+    fn from(signal: String) -> Self {
+        Self::from(signal.as_str())
+    }
+}
+
+impl std::fmt::Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Signal::Number(number) => write!(f, "{number}"),
+            signal => f.write_str(signal.name().unwrap_or_default()),
+        }
+    }
+}
+
+/// Filter shared by all prune endpoints, that is [`ContainerPruneOpts`](ContainerPruneOpts),
+/// [`ImagePruneOpts`](ImagePruneOpts), [`NetworkPruneOpts`](NetworkPruneOpts) and
+/// [`VolumePruneOpts`](VolumePruneOpts).
+pub enum PruneFilter {
+    /// Prune objects created before this timestamp. The <timestamp> can be Unix timestamps,
+    /// date formatted timestamps, or Go duration strings (e.g. 10m, 1h30m) computed relative to
+    /// the daemon machine’s time. Not supported when pruning volumes.
+    Until(String),
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    /// Prune objects created before this timestamp. Same as `Until` but takes a datetime object.
+    UntilDate(chrono::DateTime<chrono::Utc>),
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    /// Prune objects created before this timestamp. Same as `Until` but takes a datetime object.
+    UntilTime(time::OffsetDateTime),
+    /// Prune objects created more than this long ago, relative to the daemon machine's time.
+    OlderThan(Duration),
+    /// Label in the form of `label=key`.
+    LabelKey(String),
+    /// Label in the form of `label=key=val`.
+    Label(String, String),
+    /// Label in the form of `label!=key`, prunes only objects without the label.
+    NoLabelKey(String),
+    /// Label in the form of `label!=key=val`, prunes only objects without the label value.
+    NoLabel(String, String),
+    /// Only applies to images. When set to `true`, prune only unused and untagged images.
+    /// When set to `false`, all unused images are pruned.
+    Dangling(bool),
+    /// Only applies to volumes (API v1.42+). When set to `true`, named volumes are pruned as well.
+    /// By default only anonymous volumes are pruned.
+    All(bool),
+}
+
+impl Filter for PruneFilter {
+    fn query_item(&self) -> FilterItem {
+        use PruneFilter::*;
+        match &self {
+            Until(until) => FilterItem::new("until", until.to_owned()),
+            #[cfg(feature = "chrono")]
+            UntilDate(until) => FilterItem::new("until", until.timestamp().to_string()),
+            #[cfg(feature = "time")]
+            UntilTime(until) => FilterItem::new("until", until.unix_timestamp().to_string()),
+            OlderThan(age) => FilterItem::new("until", format!("{}s", age.as_secs())),
+            LabelKey(label) => FilterItem::new("label", label.to_owned()),
+            Label(key, val) => FilterItem::new("label", format!("{key}={val}")),
+            NoLabelKey(label) => FilterItem::new("label!", label.to_owned()),
+            NoLabel(key, val) => FilterItem::new("label!", format!("{key}={val}")),
+            Dangling(dangling) => FilterItem::new("dangling", dangling.to_string()),
+            All(all) => FilterItem::new("all", all.to_string()),
+        }
+    }
+}
 
 impl_opts_builder!(url => Logs);
 
@@ -121,16 +408,121 @@ impl LogsOptsBuilder {
         self.params.insert("until", timestamp.to_string());
         self
     }
+
+    /// Only return logs since this UNIX timestamp. Available regardless of the `chrono`
+    /// feature.
+    pub fn since_timestamp(mut self, timestamp: i64) -> Self {
+        self.params.insert("since", timestamp.to_string());
+        self
+    }
+
+    /// Only return logs before this UNIX timestamp. Available regardless of the `chrono`
+    /// feature.
+    pub fn until_timestamp(mut self, timestamp: i64) -> Self {
+        self.params.insert("until", timestamp.to_string());
+        self
+    }
+
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    /// Only return logs since this time.
+    pub fn since_time(mut self, timestamp: &time::OffsetDateTime) -> Self {
+        self.params
+            .insert("since", timestamp.unix_timestamp().to_string());
+        self
+    }
+
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    /// Only return logs before this time.
+    pub fn until_time(mut self, timestamp: &time::OffsetDateTime) -> Self {
+        self.params
+            .insert("until", timestamp.unix_timestamp().to_string());
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn owned_builders() {
+        fn assert_owned<T: Clone + Send + Sync + 'static>(_: T) {}
+
+        // setters consume and return the builder so options are built in one expression and
+        // builders can be held across awaits
+        assert_owned(ContainerListOpts::builder().all(true));
+        assert_owned(
+            ContainerCreateOpts::builder()
+                .image("alpine")
+                .env(["A=1"])
+                .build_owned(),
+        );
+        assert_owned(
+            NetworkCreateOpts::builder("net").ipam(
+                IpamBuilder::new()
+                    .config(IpamConfigBuilder::new().subnet("10.0.0.0/24"))
+                    .build_owned(),
+            ),
+        );
+        assert_owned(ImageBuildOpts::builder(".").tag("app").compress(true));
+        assert_owned(
+            PullOpts::builder().image("alpine").auth(
+                RegistryAuth::builder()
+                    .username("me")
+                    .password("secret")
+                    .build_owned(),
+            ),
+        );
+        assert_owned(VolumeCreateOpts::builder().name("data"));
+        assert_owned(ExecCreateOpts::builder().command(["ls"]));
+    }
+
+    #[test]
+    fn extra_param() {
+        let opts = ContainerListOpts::builder()
+            .all(true)
+            .extra_param("all", false)
+            .extra_param("experimental", "x y")
+            .build();
+        assert_eq!(
+            Some("all=false&experimental=x+y".to_string()),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn prune_filter() {
+        let opts = ContainerPruneOpts::builder()
+            .filter([
+                PruneFilter::Until("10m".into()),
+                PruneFilter::LabelKey("a".into()),
+                PruneFilter::Label("b".into(), "c".into()),
+                PruneFilter::NoLabelKey("d".into()),
+                PruneFilter::NoLabel("e".into(), "f".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22label%22%3A%5B%22a%22%2C%22b%3Dc%22%5D%2C%22label%21%22%3A%5B%22d%22%2C%22e%3Df%22%5D%2C%22until%22%3A%5B%2210m%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+
+        let opts = ImagePruneOpts::builder()
+            .filter([PruneFilter::Dangling(false)])
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22dangling%22%3A%5B%22false%22%5D%7D".to_string()),
+            opts.serialize()
+        );
+    }
     #[cfg(feature = "chrono")]
     #[test]
     fn logs_options() {
-        let timestamp = chrono::NaiveDateTime::from_timestamp_opt(2_147_483_647, 0);
-        let since = chrono::DateTime::<chrono::Utc>::from_utc(timestamp.unwrap(), chrono::Utc);
+        let since = chrono::DateTime::<chrono::Utc>::from_timestamp(2_147_483_647, 0).unwrap();
 
         let options = LogsOptsBuilder::default()
             .follow(true)
@@ -158,6 +550,101 @@ mod tests {
         assert!(serialized.contains("until=2147483647"));
     }
 
+    #[test]
+    fn time_params() {
+        let opts = LogsOpts::builder()
+            .since_timestamp(1_700_000_000)
+            .until_timestamp(1_700_000_600)
+            .build();
+        assert_eq!(
+            Some("since=1700000000&until=1700000600".to_string()),
+            opts.serialize()
+        );
+
+        let opts = ContainerPruneOpts::builder()
+            .filter([PruneFilter::OlderThan(Duration::from_secs(90 * 60))])
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22until%22%3A%5B%225400s%22%5D%7D".to_string()),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn byte_size() {
+        for (size, bytes) in [
+            ("1024", 1024),
+            ("512m", 512 << 20),
+            ("512MB", 512 << 20),
+            ("1.5g", 3 << 29),
+            ("2 GiB", 2 << 30),
+            ("4k", 4096),
+            ("3b", 3),
+            ("18446744073709551615", u64::MAX),
+        ] {
+            assert_eq!(ByteSize::parse(size).unwrap().bytes(), bytes, "{size}");
+        }
+        assert_eq!(ByteSize::mib(512), "512m".parse().unwrap());
+        assert_eq!(ByteSize::gib(u64::MAX).bytes(), u64::MAX);
+        for size in [
+            "",
+            "m",
+            "12x",
+            "-1",
+            "1.5.5g",
+            "nan",
+            "512bbb",
+            "5ib",
+            "5bi",
+            "16777216p",
+            "1e30",
+        ] {
+            assert!(
+                matches!(ByteSize::parse(size), Err(Error::InvalidByteSize(_))),
+                "{size}"
+            );
+        }
+    }
+
+    #[test]
+    fn signal() {
+        assert_eq!(Signal::from("SIGTERM"), Signal::Term);
+        assert_eq!(Signal::from("kill"), Signal::Kill);
+        assert_eq!(Signal::from("9"), Signal::Kill);
+        assert_eq!(Signal::from(15), Signal::Term);
+        assert_eq!(Signal::from(34), Signal::Number(34));
+        assert_eq!(
+            Signal::from("SIGRTMIN+3"),
+            Signal::Other("SIGRTMIN+3".into())
+        );
+        assert_eq!(Signal::Int.to_string(), "SIGINT");
+        assert_eq!(Signal::Int.number(), Some(2));
+        assert_eq!(Signal::Number(34).to_string(), "34");
+        assert_eq!(Signal::Other("SIGRTMIN+3".into()).number(), None);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn logs_options_time() {
+        let since = time::OffsetDateTime::from_unix_timestamp(2_147_483_647).unwrap();
+        let options = LogsOptsBuilder::default()
+            .since_time(&since)
+            .until_time(&since)
+            .build();
+        assert_eq!(
+            Some("since=2147483647&until=2147483647".to_string()),
+            options.serialize()
+        );
+
+        let opts = ContainerPruneOpts::builder()
+            .filter([PruneFilter::UntilTime(since)])
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22until%22%3A%5B%222147483647%22%5D%7D".to_string()),
+            opts.serialize()
+        );
+    }
+
     #[cfg(not(feature = "chrono"))]
     #[test]
     fn logs_options() {
@@ -1,10 +1,16 @@
-use crate::{models::Ipam, Error, Result};
+use crate::{
+    models::{Ipam, IpamConfig},
+    Error, Result,
+};
 use containers_api::opts::{Filter, FilterItem};
 use containers_api::{
-    impl_field, impl_filter_func, impl_map_field, impl_opts_builder, impl_str_field, impl_vec_field,
+    impl_field, impl_map_field, impl_str_field, impl_url_bool_field, impl_vec_field,
 };
 
-use std::{collections::HashMap, convert::AsRef};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::AsRef,
+};
 
 use serde::Serialize;
 use serde_json::{json, Value};
@@ -31,6 +37,7 @@ impl AsRef<str> for Scope {
     }
 }
 
+/// Used for [`NetworkFilter::Type`](NetworkFilter::Type).
 pub enum NetworkType {
     Custom,
     Builtin,
@@ -60,7 +67,9 @@ pub enum NetworkFilter {
     LabelKeyVal(String, String),
     /// Matches all or part of a network name.
     Name(String),
+    /// Matches networks by their scope.
     Scope(Scope),
+    /// Matches either predefined networks (`bridge`, `host`, `none`) or user-defined ones.
     Type(NetworkType),
 }
 
@@ -88,10 +97,29 @@ impl NetworkListOptsBuilder {
     );
 }
 
+impl_opts_builder!(url =>
+    /// Options for inspecting a network
+    NetworkInspect
+);
+
+impl NetworkInspectOptsBuilder {
+    impl_url_bool_field!(
+        /// Detailed inspect output for troubleshooting, includes information about swarm
+        /// services attached to the network.
+        verbose => "verbose"
+    );
+
+    /// Filter the network by scope.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.params.insert("scope", scope.as_ref().to_owned());
+        self
+    }
+}
+
 /// Interface for creating new docker network
 #[derive(Serialize, Debug, Clone)]
 pub struct NetworkCreateOpts {
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 impl NetworkCreateOpts {
@@ -114,14 +142,14 @@ impl NetworkCreateOpts {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct NetworkCreateOptsBuilder {
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 impl NetworkCreateOptsBuilder {
     pub(crate) fn new(name: &str) -> Self {
-        let mut params = HashMap::new();
+        let mut params = BTreeMap::new();
         params.insert("Name", json!(name));
         NetworkCreateOptsBuilder { params }
     }
@@ -166,13 +194,36 @@ impl NetworkCreateOptsBuilder {
         options => "Options"
     );
 
+    /// The level at which the network exists, for example `swarm` for cluster-wide
+    /// or `local` for machine level networks.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.params.insert("Scope", json!(scope.as_ref()));
+        self
+    }
+
+    impl_field!(
+        /// Create a configuration-only network. Such networks are placeholders whose
+        /// configuration can be used by other networks with [`config_from`](NetworkCreateOptsBuilder::config_from).
+        config_only: bool => "ConfigOnly"
+    );
+
+    /// Name of a configuration-only network from which this network takes its configuration.
+    pub fn config_from<N>(mut self, network: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.params
+            .insert("ConfigFrom", json!({ "Network": network.into() }));
+        self
+    }
+
     impl_map_field!(json
         /// User-defined key/value metadata.
         labels => "Labels"
     );
 
     impl_field!(
-        /// IP Address Management configuration
+        /// IP Address Management configuration. Use [`IpamBuilder`](IpamBuilder) to construct it.
         ipam: Ipam => "IPAM"
     );
 
@@ -181,11 +232,170 @@ impl NetworkCreateOptsBuilder {
             params: self.params.clone(),
         }
     }
+
+    /// Same as [`build`](NetworkCreateOptsBuilder::build) but consumes the builder so the
+    /// parameters are moved rather than cloned.
+    pub fn build_owned(self) -> NetworkCreateOpts {
+        NetworkCreateOpts {
+            params: self.params,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+/// Builder for the IP address management configuration of a network.
+/// See [`ipam`](NetworkCreateOptsBuilder::ipam).
+pub struct IpamBuilder {
+    driver: Option<String>,
+    options: Option<HashMap<String, String>>,
+    config: Vec<IpamConfig>,
+}
+
+impl IpamBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of the IPAM driver to use.
+    pub fn driver<D>(mut self, driver: D) -> Self
+    where
+        D: Into<String>,
+    {
+        self.driver = Some(driver.into());
+        self
+    }
+
+    /// Driver-specific options.
+    pub fn options<K, V>(mut self, options: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.options = Some(
+            options
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Add an address pool to the network. Can be called multiple times, for example to
+    /// configure both an IPv4 and an IPv6 subnet.
+    pub fn config(mut self, config: IpamConfigBuilder) -> Self {
+        self.config.push(config.build_owned());
+        self
+    }
+
+    pub fn build(&self) -> Ipam {
+        Ipam {
+            config: if self.config.is_empty() {
+                None
+            } else {
+                Some(self.config.clone())
+            },
+            driver: self.driver.clone(),
+            options: self.options.clone(),
+        }
+    }
+
+    /// Same as [`build`](IpamBuilder::build) but consumes the builder so the address pools and
+    /// options are moved rather than cloned.
+    pub fn build_owned(self) -> Ipam {
+        Ipam {
+            config: if self.config.is_empty() {
+                None
+            } else {
+                Some(self.config)
+            },
+            driver: self.driver,
+            options: self.options,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+/// Builder for a single address pool of a network used with [`IpamBuilder::config`](IpamBuilder::config).
+pub struct IpamConfigBuilder {
+    subnet: Option<String>,
+    ip_range: Option<String>,
+    gateway: Option<String>,
+    auxiliary_addresses: Option<HashMap<String, String>>,
 }
+
+impl IpamConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subnet of the pool in CIDR format, for example `172.20.0.0/16`.
+    pub fn subnet<S>(mut self, subnet: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.subnet = Some(subnet.into());
+        self
+    }
+
+    /// Range of the subnet from which container addresses are allocated, in CIDR format.
+    pub fn ip_range<R>(mut self, ip_range: R) -> Self
+    where
+        R: Into<String>,
+    {
+        self.ip_range = Some(ip_range.into());
+        self
+    }
+
+    /// IPv4 or IPv6 gateway of the subnet.
+    pub fn gateway<G>(mut self, gateway: G) -> Self
+    where
+        G: Into<String>,
+    {
+        self.gateway = Some(gateway.into());
+        self
+    }
+
+    /// Addresses used by the network driver that must not be assigned to containers, as a mapping
+    /// of a device name to an IP address.
+    pub fn auxiliary_addresses<K, V>(mut self, addresses: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.auxiliary_addresses = Some(
+            addresses
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn build(&self) -> IpamConfig {
+        IpamConfig {
+            auxiliary_addresses: self.auxiliary_addresses.clone(),
+            gateway: self.gateway.clone(),
+            ip_range: self.ip_range.clone(),
+            subnet: self.subnet.clone(),
+        }
+    }
+
+    /// Same as [`build`](IpamConfigBuilder::build) but consumes the builder so the addresses are
+    /// moved rather than cloned.
+    pub fn build_owned(self) -> IpamConfig {
+        IpamConfig {
+            auxiliary_addresses: self.auxiliary_addresses,
+            gateway: self.gateway,
+            ip_range: self.ip_range,
+            subnet: self.subnet,
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 /// Interface for disconnecting a container from a network.
 pub struct ContainerDisconnectionOpts {
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 impl ContainerDisconnectionOpts {
@@ -208,9 +418,9 @@ impl ContainerDisconnectionOpts {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ContainerDisconnectionOptsBuilder {
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 impl ContainerDisconnectionOptsBuilder {
@@ -235,7 +445,7 @@ impl ContainerDisconnectionOptsBuilder {
 #[derive(Serialize, Debug)]
 /// Interface for connecting a container to a network.
 pub struct ContainerConnectionOpts {
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 impl ContainerConnectionOpts {
@@ -258,16 +468,16 @@ impl ContainerConnectionOpts {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ContainerConnectionOptsBuilder {
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
     container: String,
 }
 
 impl ContainerConnectionOptsBuilder {
     pub(crate) fn new(container_id: &str) -> Self {
         ContainerConnectionOptsBuilder {
-            params: HashMap::new(),
+            params: BTreeMap::new(),
             container: container_id.to_string(),
         }
     }
@@ -334,7 +544,7 @@ impl ContainerConnectionOptsBuilder {
     );
 
     pub fn build(self) -> ContainerConnectionOpts {
-        let mut params = HashMap::new();
+        let mut params = BTreeMap::new();
         params.insert("EndpointConfig", json!(self.params));
         params.insert("Container", json!(self.container));
         ContainerConnectionOpts { params }
@@ -345,7 +555,7 @@ impl ContainerConnectionOptsBuilder {
 /// Used to configure endpoint IPAM configuration when connection a container to a network.
 /// See [`ipam_config`](ContainerConnectOptsBuilder::ipam_config).
 pub struct EndpointIpamConfig {
-    params: HashMap<&'static str, serde_json::Value>,
+    params: BTreeMap<&'static str, serde_json::Value>,
 }
 
 impl EndpointIpamConfig {
@@ -384,33 +594,8 @@ impl EndpointIpamConfig {
 
 impl_opts_builder!(url => NetworkPrune);
 
-pub enum NetworkPruneFilter {
-    /// Prune networks created before this timestamp. The <timestamp> can be Unix timestamps,
-    /// date formatted timestamps, or Go duration strings (e.g. 10m, 1h30m) computed relative
-    /// to the daemon machine’s time.
-    Until(String),
-    #[cfg(feature = "chrono")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
-    /// Prune networks created before this timestamp. Same as `Until` but takes a datetime object.
-    UntilDate(chrono::DateTime<chrono::Utc>),
-    /// Label in the form of `label=key`.
-    LabelKey(String),
-    /// Label in the form of `label=key=val`.
-    Label(String, String),
-}
-
-impl Filter for NetworkPruneFilter {
-    fn query_item(&self) -> FilterItem {
-        use NetworkPruneFilter::*;
-        match &self {
-            Until(until) => FilterItem::new("until", until.to_owned()),
-            #[cfg(feature = "chrono")]
-            UntilDate(until) => FilterItem::new("until", until.timestamp().to_string()),
-            LabelKey(label) => FilterItem::new("label", label.to_owned()),
-            Label(key, val) => FilterItem::new("label", format!("{key}={val}")),
-        }
-    }
-}
+/// Filter used to filter pruned networks, see [`PruneFilter`](crate::opts::PruneFilter).
+pub type NetworkPruneFilter = crate::opts::PruneFilter;
 
 impl NetworkPruneOptsBuilder {
     impl_filter_func!(
@@ -418,3 +603,155 @@ impl NetworkPruneOptsBuilder {
         NetworkPruneFilter
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_create_flags() {
+        let opts = NetworkCreateOpts::builder("test")
+            .driver("overlay")
+            .check_duplicate(true)
+            .internal(true)
+            .attachable(true)
+            .ingress(false)
+            .enable_ipv6(true)
+            .options([("com.docker.network.driver.mtu", "1400")])
+            .scope(Scope::Swarm)
+            .config_from("test-config")
+            .build();
+        let serialized: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+
+        assert_eq!(
+            json!({
+                "Name": "test",
+                "Driver": "overlay",
+                "CheckDuplicate": true,
+                "Internal": true,
+                "Attachable": true,
+                "Ingress": false,
+                "EnableIPv6": true,
+                "Options": {"com.docker.network.driver.mtu": "1400"},
+                "Scope": "swarm",
+                "ConfigFrom": {"Network": "test-config"}
+            }),
+            serialized
+        );
+
+        let opts = NetworkCreateOpts::builder("test-config")
+            .config_only(true)
+            .build();
+        let serialized: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+        assert_eq!(
+            json!({"Name": "test-config", "ConfigOnly": true}),
+            serialized
+        );
+    }
+
+    #[test]
+    fn network_create_ipam() {
+        let ipam = IpamBuilder::new()
+            .driver("default")
+            .options([("opt", "val")])
+            .config(
+                IpamConfigBuilder::new()
+                    .subnet("172.28.0.0/16")
+                    .ip_range("172.28.5.0/24")
+                    .gateway("172.28.5.254")
+                    .auxiliary_addresses([("host1", "172.28.1.5")]),
+            );
+        assert_eq!(ipam.build(), ipam.clone().build_owned());
+
+        let builder = NetworkCreateOpts::builder("test").ipam(ipam.build_owned());
+        let opts = builder.build();
+        assert_eq!(
+            opts.serialize().unwrap(),
+            builder.build_owned().serialize().unwrap()
+        );
+        let serialized: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+
+        assert_eq!(
+            json!({
+                "Name": "test",
+                "IPAM": {
+                    "Driver": "default",
+                    "Options": {"opt": "val"},
+                    "Config": [{
+                        "Subnet": "172.28.0.0/16",
+                        "IPRange": "172.28.5.0/24",
+                        "Gateway": "172.28.5.254",
+                        "AuxiliaryAddresses": {"host1": "172.28.1.5"}
+                    }]
+                }
+            }),
+            serialized
+        );
+    }
+
+    #[test]
+    fn network_list_filters() {
+        let opts = NetworkListOpts::builder()
+            .filter([
+                NetworkFilter::Dangling(true),
+                NetworkFilter::Driver("bridge".into()),
+                NetworkFilter::Id("abc".into()),
+                NetworkFilter::LabelKey("a".into()),
+                NetworkFilter::LabelKeyVal("b".into(), "c".into()),
+                NetworkFilter::Name("net".into()),
+                NetworkFilter::Scope(Scope::Local),
+                NetworkFilter::Type(NetworkType::Custom),
+            ])
+            .build();
+        let serialized = opts.serialize().unwrap();
+        let filters = serialized.strip_prefix("filters=").unwrap();
+        let filters: Value = serde_json::from_str(
+            &url::form_urlencoded::parse(format!("f={filters}").as_bytes())
+                .next()
+                .unwrap()
+                .1,
+        )
+        .unwrap();
+        assert_eq!(
+            json!({
+                "dangling": ["true"],
+                "driver": ["bridge"],
+                "id": ["abc"],
+                "label": ["a", "b=c"],
+                "name": ["net"],
+                "scope": ["local"],
+                "type": ["custom"],
+            }),
+            filters
+        );
+    }
+
+    #[test]
+    fn network_inspect_opts() {
+        let opts = NetworkInspectOpts::builder()
+            .verbose(true)
+            .scope(Scope::Swarm)
+            .build();
+        assert_eq!(
+            Some("scope=swarm&verbose=true".to_string()),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn network_prune_filters() {
+        let opts = NetworkPruneOpts::builder()
+            .filter([
+                NetworkPruneFilter::Until("24h".into()),
+                NetworkPruneFilter::Label("env".into(), "test".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22label%22%3A%5B%22env%3Dtest%22%5D%2C%22until%22%3A%5B%2224h%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+}
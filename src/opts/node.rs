@@ -1,13 +1,11 @@
-use crate::models::{NodeSpecAvailabilityInlineItem, NodeSpecRoleInlineItem};
+use crate::models::{NodeSpec, NodeSpecAvailabilityInlineItem, NodeSpecRoleInlineItem};
 use crate::{Error, Result};
 use containers_api::opts::{Filter, FilterItem};
-use containers_api::{
-    impl_filter_func, impl_map_field, impl_opts_builder, impl_str_enum_field, impl_str_field,
-};
+use containers_api::{impl_map_field, impl_str_enum_field, impl_str_field};
 
 use serde::Serialize;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Debug)]
 pub enum Membership {
@@ -24,18 +22,65 @@ impl AsRef<str> for Membership {
     }
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct NodeUpdateOpts {
     version: String,
-    params: HashMap<&'static str, serde_json::Value>,
+    params: BTreeMap<&'static str, serde_json::Value>,
 }
 
 impl NodeUpdateOpts {
-    /// return a new instance of a builder for Opts
-    pub fn builder<V: Into<String>>(version: V) -> NodeUpdateOptsBuilder {
+    /// return a new instance of a builder for Opts. `version` is the current version of the node
+    /// object as returned by [`Node::inspect`](crate::Node::inspect).
+    pub fn builder<V: ToString>(version: V) -> NodeUpdateOptsBuilder {
         NodeUpdateOptsBuilder::new(version)
     }
 
+    pub fn serialize(&self) -> Result<String> {
+        serde_json::to_string(&self.params).map_err(Error::from)
+    }
+
+    pub fn serialize_vec(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(&self.params).map_err(Error::from)
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct NodeUpdateOptsBuilder {
+    version: String,
+    params: BTreeMap<&'static str, serde_json::Value>,
+}
+
+impl NodeUpdateOptsBuilder {
+    pub fn new<V: ToString>(version: V) -> Self {
+        Self {
+            version: version.to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Initializes all fields from an existing node `spec`. Useful to change a single field of
+    /// the spec, as the daemon replaces the whole spec on update.
+    pub fn spec(mut self, spec: &NodeSpec) -> Self {
+        if let Some(availability) = &spec.availability {
+            self.params
+                .insert("Availability", serde_json::json!(availability));
+        }
+        if let Some(labels) = &spec.labels {
+            self.params.insert("Labels", serde_json::json!(labels));
+        }
+        if let Some(name) = &spec.name {
+            self.params.insert("Name", serde_json::json!(name));
+        }
+        if let Some(role) = &spec.role {
+            self.params.insert("Role", serde_json::json!(role));
+        }
+        self
+    }
+
     impl_map_field!(json
         /// User-defined key/value metadata
         labels => "Labels"
@@ -56,30 +101,10 @@ impl NodeUpdateOpts {
         availability: NodeSpecAvailabilityInlineItem => "Availability"
     );
 
-    pub fn serialize(&self) -> Result<String> {
-        serde_json::to_string(&self.params).map_err(Error::from)
-    }
-
-    pub fn serialize_vec(&self) -> Result<Vec<u8>> {
-        serde_json::to_vec(&self).map_err(Error::from)
-    }
-
-    pub fn version(&self) -> &str {
-        &self.version
-    }
-}
-
-#[derive(Serialize, Debug)]
-pub struct NodeUpdateOptsBuilder {
-    version: String,
-    params: HashMap<&'static str, serde_json::Value>,
-}
-
-impl NodeUpdateOptsBuilder {
-    pub fn new<V: Into<String>>(version: V) -> Self {
-        Self {
-            version: version.into(),
-            params: HashMap::new(),
+    pub fn build(self) -> NodeUpdateOpts {
+        NodeUpdateOpts {
+            version: self.version,
+            params: self.params,
         }
     }
 }
@@ -115,3 +140,40 @@ impl_opts_builder!(url => NodeList);
 impl NodeListOptsBuilder {
     impl_filter_func!(NodeFilter);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_update_opts() {
+        let opts = NodeUpdateOpts::builder(42u64)
+            .role(NodeSpecRoleInlineItem::Worker)
+            .availability(NodeSpecAvailabilityInlineItem::Drain)
+            .build();
+        assert_eq!(opts.version(), "42");
+        let value: serde_json::Value =
+            serde_json::from_slice(&opts.serialize_vec().unwrap()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"Role": "worker", "Availability": "drain"})
+        );
+
+        let spec = NodeSpec {
+            availability: Some("active".into()),
+            labels: Some([("zone".to_string(), "a".to_string())].into()),
+            name: None,
+            role: Some("manager".into()),
+        };
+        let opts = NodeUpdateOpts::builder(1)
+            .spec(&spec)
+            .availability(NodeSpecAvailabilityInlineItem::Pause)
+            .build();
+        let value: serde_json::Value =
+            serde_json::from_slice(&opts.serialize_vec().unwrap()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"Role": "manager", "Availability": "pause", "Labels": {"zone": "a"}})
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use crate::{opts::RegistryAuth, Result};
+use containers_api::impl_url_str_field;
 use containers_api::opts::{Filter, FilterItem};
-use containers_api::{impl_filter_func, impl_opts_builder};
+use containers_api::url::encoded_pairs;
+
+use std::collections::BTreeMap;
 
 impl_opts_builder!(url => PluginList);
 
@@ -25,3 +29,108 @@ impl PluginListOptsBuilder {
         PluginFilter
     );
 }
+
+/// Options for installing a plugin with [`Plugins::install`](crate::Plugins::install).
+#[derive(Clone, Debug)]
+pub struct PluginInstallOpts {
+    auth: Option<RegistryAuth>,
+    params: BTreeMap<&'static str, String>,
+}
+
+impl PluginInstallOpts {
+    /// Install the plugin `remote`, a reference like `vieux/sshfs:latest`.
+    pub fn builder<R>(remote: R) -> PluginInstallOptsBuilder
+    where
+        R: Into<String>,
+    {
+        PluginInstallOptsBuilder {
+            auth: None,
+            params: BTreeMap::new(),
+        }
+        .remote(remote)
+    }
+
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(encoded_pairs(self.params.iter()))
+        }
+    }
+
+    /// The remote reference of the plugin.
+    pub fn remote(&self) -> &str {
+        self.params
+            .get("remote")
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// The local name of the plugin once installed, defaults to the remote reference.
+    pub fn name(&self) -> &str {
+        self.params
+            .get("name")
+            .map(String::as_str)
+            .unwrap_or_else(|| self.remote())
+    }
+
+    pub(crate) fn auth(&self) -> Option<RegistryAuth> {
+        self.auth.clone()
+    }
+
+    pub(crate) fn auth_header(&self) -> Result<Option<String>> {
+        self.auth.as_ref().map(RegistryAuth::serialize).transpose()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PluginInstallOptsBuilder {
+    auth: Option<RegistryAuth>,
+    params: BTreeMap<&'static str, String>,
+}
+
+impl PluginInstallOptsBuilder {
+    impl_url_str_field!(
+        /// Remote reference of the plugin, like `vieux/sshfs:latest`.
+        remote => "remote"
+    );
+
+    impl_url_str_field!(
+        /// Local name for the installed plugin.
+        name => "name"
+    );
+
+    /// Authentication for pulling the plugin from a private registry.
+    pub fn auth(mut self, auth: RegistryAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn build(self) -> PluginInstallOpts {
+        PluginInstallOpts {
+            auth: self.auth,
+            params: self.params,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_install_opts() {
+        let opts = PluginInstallOpts::builder("vieux/sshfs:latest").build();
+        assert_eq!(opts.name(), "vieux/sshfs:latest");
+        assert_eq!(
+            opts.serialize(),
+            Some("remote=vieux%2Fsshfs%3Alatest".to_string())
+        );
+
+        let opts = PluginInstallOpts::builder("vieux/sshfs:latest")
+            .name("sshfs")
+            .build();
+        assert_eq!(opts.name(), "sshfs");
+        assert!(opts.serialize().unwrap().contains("name=sshfs"));
+    }
+}
@@ -0,0 +1,127 @@
+use containers_api::opts::{Filter, FilterItem};
+use containers_api::{
+    impl_field, impl_map_field, impl_str_field, impl_url_bool_field, impl_vec_field,
+};
+use std::time::Duration;
+
+impl_opts_builder!(json => PodCreate);
+
+impl PodCreateOptsBuilder {
+    impl_str_field!(
+        /// The name of the pod. If not specified, podman generates a name.
+        name => "name"
+    );
+
+    impl_str_field!(
+        /// The hostname of the pod, shared by its containers.
+        hostname => "hostname"
+    );
+
+    impl_map_field!(json
+        /// User-defined key/value metadata.
+        labels => "labels"
+    );
+
+    impl_vec_field!(
+        /// Namespaces shared by the containers of the pod, like `ipc`, `net` or `uts`.
+        share => "shared_namespaces"
+    );
+
+    impl_field!(
+        /// Whether to create the pod without an infra container holding its namespaces.
+        no_infra: bool => "no_infra"
+    );
+
+    impl_str_field!(
+        /// The image used for the infra container.
+        infra_image => "infra_image"
+    );
+}
+
+impl_opts_builder!(url => PodList);
+
+/// Filter type used to filter pods by one of the variants.
+#[derive(Clone, Debug)]
+pub enum PodFilter {
+    /// The ID of the pod.
+    Id(String),
+    /// The name of the pod.
+    Name(String),
+    /// Label in the form of `label=key`.
+    LabelKey(String),
+    /// Label in the form of `label=key=val`.
+    Label(String, String),
+    /// The status of the pod, like `running` or `exited`.
+    Status(String),
+    /// Name or ID of a network the pod is connected to.
+    Network(String),
+    /// Name of a container in the pod.
+    ContainerName(String),
+}
+
+impl Filter for PodFilter {
+    fn query_item(&self) -> FilterItem {
+        use PodFilter::*;
+        match &self {
+            Id(id) => FilterItem::new("id", id.to_owned()),
+            Name(name) => FilterItem::new("name", name.to_owned()),
+            LabelKey(key) => FilterItem::new("label", key.to_owned()),
+            Label(key, val) => FilterItem::new("label", format!("{key}={val}")),
+            Status(status) => FilterItem::new("status", status.to_owned()),
+            Network(network) => FilterItem::new("network", network.to_owned()),
+            ContainerName(name) => FilterItem::new("ctr-names", name.to_owned()),
+        }
+    }
+}
+
+impl PodListOptsBuilder {
+    impl_filter_func!(
+        /// Filter listed pods by variants of the enum.
+        PodFilter
+    );
+}
+
+impl_opts_builder!(url => PodRemove);
+
+impl PodRemoveOptsBuilder {
+    impl_url_bool_field!(
+        /// Stop and remove the running containers of the pod.
+        force => "force"
+    );
+
+    /// Time to wait for the containers to stop before killing them. Only seconds are counted.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.params.insert("t", timeout.as_secs().to_string());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pod_create() {
+        let opts = PodCreateOpts::builder()
+            .name("web")
+            .share(["net", "ipc"])
+            .no_infra(false)
+            .build();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&opts.serialize().unwrap()).unwrap(),
+            serde_json::json!({
+                "name": "web",
+                "shared_namespaces": ["net", "ipc"],
+                "no_infra": false,
+            })
+        );
+
+        let opts = PodListOpts::builder()
+            .filter([PodFilter::Status("running".into())])
+            .build();
+        assert_eq!(
+            Some("filters=%7B%22status%22%3A%5B%22running%22%5D%7D".to_string()),
+            opts.serialize()
+        );
+    }
+}
@@ -1,19 +1,36 @@
-use crate::{models, opts::RegistryAuth, Error, Result};
+use crate::{
+    models,
+    opts::{duration_nanos, ByteSize, RegistryAuth, Signal},
+    Error, Result,
+};
+use containers_api::impl_url_bool_field;
 use containers_api::opts::{Filter, FilterItem};
-use containers_api::{impl_filter_func, impl_opts_builder, impl_url_bool_field};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
+use std::time::Duration;
 
 use serde::Serialize;
 use serde_json::{json, Value};
 
 /// Filter Opts for services listings
+#[derive(Clone, Debug)]
 pub enum ServiceFilter {
+    /// The ID of the service, also matches a prefix of the ID.
     Id(String),
+    /// Label in the form of `label=key` or `label=key=val`.
     Label(String),
+    /// Label in the form of `label=key=val`.
+    LabelKeyVal(String, String),
+    /// Only list replicated services.
     ReplicatedMode,
+    /// Only list global services.
     GlobalMode,
+    /// Only list replicated jobs.
+    ReplicatedJobMode,
+    /// Only list global jobs.
+    GlobalJobMode,
+    /// The name of the service, also matches a prefix of the name.
     Name(String),
 }
 
@@ -22,8 +39,15 @@ impl Filter for ServiceFilter {
         match &self {
             ServiceFilter::Id(i) => FilterItem::new("id", i.to_owned()),
             ServiceFilter::Label(l) => FilterItem::new("label", l.to_owned()),
+            ServiceFilter::LabelKeyVal(key, val) => {
+                FilterItem::new("label", format!("{key}={val}"))
+            }
             ServiceFilter::ReplicatedMode => FilterItem::new("mode", "replicated".to_string()),
             ServiceFilter::GlobalMode => FilterItem::new("mode", "global".to_string()),
+            ServiceFilter::ReplicatedJobMode => {
+                FilterItem::new("mode", "replicated-job".to_string())
+            }
+            ServiceFilter::GlobalJobMode => FilterItem::new("mode", "global-job".to_string()),
             ServiceFilter::Name(n) => FilterItem::new("name", n.to_string()),
         }
     }
@@ -32,7 +56,10 @@ impl Filter for ServiceFilter {
 impl_opts_builder!(url => ServiceList);
 
 impl ServiceListOptsBuilder {
-    impl_filter_func!(ServiceFilter);
+    impl_filter_func!(
+        /// Filter listed services by variants of the enum.
+        ServiceFilter
+    );
 
     impl_url_bool_field!(
         /// Include service status, with count of running and desired tasks.
@@ -40,10 +67,60 @@ impl ServiceListOptsBuilder {
     );
 }
 
+/// Which spec to use for registry authentication when pulling the image of an updated service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryAuthFrom {
+    Spec,
+    PreviousSpec,
+}
+
+impl AsRef<str> for RegistryAuthFrom {
+    fn as_ref(&self) -> &str {
+        match &self {
+            RegistryAuthFrom::Spec => "spec",
+            RegistryAuthFrom::PreviousSpec => "previous-spec",
+        }
+    }
+}
+
+impl_opts_builder!(url =>
+    /// Options for inspecting a service
+    ServiceInspect
+);
+
+impl ServiceInspectOptsBuilder {
+    impl_url_bool_field!(
+        /// Fill empty fields of the spec with their default values.
+        insert_defaults => "insertDefaults"
+    );
+}
+
+impl_opts_builder!(url => ServiceUpdate);
+
+impl ServiceUpdateOptsBuilder {
+    /// If the registry authorization header is not set, use the credentials of the current or the
+    /// previous spec of the service to pull the image.
+    pub fn registry_auth_from(mut self, from: RegistryAuthFrom) -> Self {
+        self.params
+            .insert("registryAuthFrom", from.as_ref().to_string());
+        self
+    }
+
+    /// Roll back the service to its previous spec instead of applying the provided one.
+    pub fn rollback(mut self, rollback: bool) -> Self {
+        if rollback {
+            self.params.insert("rollback", "previous".to_string());
+        } else {
+            self.params.remove("rollback");
+        }
+        self
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct ServiceOpts {
     auth: Option<RegistryAuth>,
-    params: HashMap<&'static str, Value>,
+    params: BTreeMap<&'static str, Value>,
 }
 
 impl ServiceOpts {
@@ -61,15 +138,15 @@ impl ServiceOpts {
         serde_json::to_vec(&self.params).map_err(Error::from)
     }
 
-    pub(crate) fn auth_header(&self) -> Option<String> {
-        self.auth.clone().map(|a| a.serialize())
+    pub(crate) fn auth_header(&self) -> Result<Option<String>> {
+        self.auth.as_ref().map(RegistryAuth::serialize).transpose()
     }
 }
 
 #[derive(Default)]
 pub struct ServiceOptsBuilder {
     auth: Option<RegistryAuth>,
-    params: HashMap<&'static str, Result<Value>>,
+    params: BTreeMap<&'static str, Result<Value>>,
 }
 
 impl ServiceOptsBuilder {
@@ -89,11 +166,12 @@ impl ServiceOptsBuilder {
     {
         self.params.insert(
             "Labels",
-            Ok(json!(labels.into_iter().collect::<HashMap<_, _>>())),
+            to_value_result(labels.into_iter().collect::<HashMap<_, _>>()),
         );
         self
     }
 
+    /// User modifiable task configuration. Use [`TaskSpecBuilder`](TaskSpecBuilder) to construct it.
     pub fn task_template(mut self, spec: &models::TaskSpec) -> Self {
         self.params.insert("TaskTemplate", to_value_result(spec));
         self
@@ -104,11 +182,20 @@ impl ServiceOptsBuilder {
         self
     }
 
+    /// Scheduling mode of the service.
+    pub fn service_mode(self, mode: ServiceMode) -> Self {
+        self.mode(&mode.into())
+    }
+
+    /// Specification for the update strategy of the service. Use
+    /// [`UpdateConfigBuilder`](UpdateConfigBuilder) to construct it.
     pub fn update_config(mut self, conf: &models::ServiceSpecUpdateConfigInlineItem) -> Self {
         self.params.insert("UpdateConfig", to_value_result(conf));
         self
     }
 
+    /// Specification for the rollback strategy of the service. Use
+    /// [`RollbackConfigBuilder`](RollbackConfigBuilder) to construct it.
     pub fn rollback_config(mut self, conf: &models::ServiceSpecRollbackConfigInlineItem) -> Self {
         self.params.insert("RollbackConfig", to_value_result(conf));
         self
@@ -129,18 +216,22 @@ impl ServiceOptsBuilder {
         self
     }
 
+    /// Properties that can be configured to access and load balance a service. Use
+    /// [`EndpointSpecBuilder`](EndpointSpecBuilder) to construct it.
     pub fn endpoint_spec(mut self, spec: &models::EndpointSpec) -> Self {
         self.params.insert("EndpointSpec", to_value_result(spec));
         self
     }
 
+    /// Credentials sent in the `X-Registry-Auth` header, used to pull the image of a private
+    /// registry.
     pub fn auth(mut self, auth: RegistryAuth) -> Self {
         self.auth = Some(auth);
         self
     }
 
     pub fn build(self) -> Result<ServiceOpts> {
-        let mut new_params = HashMap::new();
+        let mut new_params = BTreeMap::new();
         for (k, v) in self.params.into_iter() {
             new_params.insert(k, v?);
         }
@@ -157,3 +248,1063 @@ where
 {
     Ok(serde_json::to_value(value)?)
 }
+
+/// Scheduling mode of a service used with [`ServiceOptsBuilder::service_mode`](ServiceOptsBuilder::service_mode).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServiceMode {
+    /// Run the given number of replicas of the task.
+    Replicated(u64),
+    /// Run one task on every node of the swarm.
+    Global,
+    /// Run tasks until the given number of completions is reached, with at most `max_concurrent`
+    /// tasks running at the same time.
+    ReplicatedJob {
+        max_concurrent: Option<u64>,
+        total_completions: Option<u64>,
+    },
+    /// Run one task to completion on every node of the swarm.
+    GlobalJob,
+}
+
+impl From<ServiceMode> for models::ServiceSpecModeInlineItem {
+    fn from(mode: ServiceMode) -> Self {
+        let mut spec = models::ServiceSpecModeInlineItem {
+            global: None,
+            global_job: None,
+            replicated: None,
+            replicated_job: None,
+        };
+        match mode {
+            ServiceMode::Replicated(replicas) => {
+                spec.replicated = Some(models::ServiceSpecModeInlineItemReplicatedInlineItem {
+                    replicas: Some(replicas as i64),
+                })
+            }
+            ServiceMode::Global => spec.global = Some(json!({})),
+            ServiceMode::ReplicatedJob {
+                max_concurrent,
+                total_completions,
+            } => {
+                spec.replicated_job =
+                    Some(models::ServiceSpecModeInlineItemReplicatedJobInlineItem {
+                        max_concurrent: max_concurrent.map(|n| n as i64),
+                        total_completions: total_completions.map(|n| n as i64),
+                    })
+            }
+            ServiceMode::GlobalJob => spec.global_job = Some(json!({})),
+        }
+        spec
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Builder for the task template of a service. See [`task_template`](ServiceOptsBuilder::task_template).
+pub struct TaskSpecBuilder {
+    spec: models::TaskSpec,
+}
+
+impl Default for TaskSpecBuilder {
+    fn default() -> Self {
+        Self {
+            spec: models::TaskSpec {
+                container_spec: None,
+                force_update: None,
+                log_driver: None,
+                network_attachment_spec: None,
+                networks: None,
+                placement: None,
+                plugin_spec: None,
+                resources: None,
+                restart_policy: None,
+                runtime: None,
+            },
+        }
+    }
+}
+
+impl TaskSpecBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Container to run for each task of the service.
+    pub fn container_spec(mut self, spec: ContainerSpecBuilder) -> Self {
+        self.spec.container_spec = Some(spec.build());
+        self
+    }
+
+    /// Resource limits and reservations of each task.
+    pub fn resources(mut self, resources: ResourcesBuilder) -> Self {
+        self.spec.resources = Some(resources.build());
+        self
+    }
+
+    /// Restart policy applied to containers of the tasks.
+    pub fn restart_policy(mut self, policy: RestartPolicyBuilder) -> Self {
+        self.spec.restart_policy = Some(policy.build());
+        self
+    }
+
+    /// Placement constraints and preferences of the tasks. Use
+    /// [`PlacementBuilder`](PlacementBuilder) to construct and validate them.
+    pub fn placement(mut self, placement: models::TaskSpecPlacementInlineItem) -> Self {
+        self.spec.placement = Some(placement);
+        self
+    }
+
+    /// Attach the tasks to a network identified by name or ID. Can be called multiple times.
+    pub fn network<N>(mut self, target: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.spec
+            .networks
+            .get_or_insert_with(Vec::new)
+            .push(models::NetworkAttachmentConfig {
+                aliases: None,
+                driver_opts: None,
+                target: Some(target.into()),
+            });
+        self
+    }
+
+    /// Attach the tasks to a network with a custom attachment configuration.
+    pub fn network_attachment(mut self, network: models::NetworkAttachmentConfig) -> Self {
+        self.spec
+            .networks
+            .get_or_insert_with(Vec::new)
+            .push(network);
+        self
+    }
+
+    /// Log driver used by containers of the tasks, with driver-specific options.
+    pub fn log_driver<N, K, V>(mut self, name: N, options: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        N: Into<String>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.spec.log_driver = Some(models::TaskSpecLogDriverInlineItem {
+            name: Some(name.into()),
+            options: Some(
+                options
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v.into()))
+                    .collect(),
+            ),
+        });
+        self
+    }
+
+    /// A counter that triggers an update even if no relevant parameters have been changed.
+    pub fn force_update(mut self, counter: isize) -> Self {
+        self.spec.force_update = Some(counter);
+        self
+    }
+
+    /// Runtime is the type of runtime specified for the task executor.
+    pub fn runtime<R>(mut self, runtime: R) -> Self
+    where
+        R: Into<String>,
+    {
+        self.spec.runtime = Some(runtime.into());
+        self
+    }
+
+    pub fn build(self) -> models::TaskSpec {
+        self.spec
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Builder for the container of a service task. See [`container_spec`](TaskSpecBuilder::container_spec).
+pub struct ContainerSpecBuilder {
+    spec: models::TaskSpecContainerSpecInlineItem,
+}
+
+impl ContainerSpecBuilder {
+    /// Creates a new builder for a container running `image`.
+    pub fn new<I>(image: I) -> Self
+    where
+        I: Into<String>,
+    {
+        Self {
+            spec: models::TaskSpecContainerSpecInlineItem {
+                args: None,
+                capability_add: None,
+                capability_drop: None,
+                command: None,
+                configs: None,
+                dns_config: None,
+                dir: None,
+                env: None,
+                groups: None,
+                health_check: None,
+                hostname: None,
+                hosts: None,
+                image: Some(image.into()),
+                init: None,
+                isolation: None,
+                labels: None,
+                mounts: None,
+                open_stdin: None,
+                privileges: None,
+                read_only: None,
+                secrets: None,
+                stop_grace_period: None,
+                stop_signal: None,
+                sysctls: None,
+                tty: None,
+                ulimits: None,
+                user: None,
+            },
+        }
+    }
+
+    /// The command to be run in the image.
+    pub fn command<S>(mut self, command: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.spec.command = Some(command.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Arguments to the command.
+    pub fn args<S>(mut self, args: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.spec.args = Some(args.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// A list of environment variables in the form `VAR=value`.
+    pub fn env<S>(mut self, env: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.spec.env = Some(env.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// User-defined key/value data of the container.
+    pub fn labels<K, V>(mut self, labels: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.spec.labels = Some(
+            labels
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    /// The hostname to use for the container.
+    pub fn hostname<H>(mut self, hostname: H) -> Self
+    where
+        H: Into<String>,
+    {
+        self.spec.hostname = Some(hostname.into());
+        self
+    }
+
+    /// The working directory for commands to run in.
+    pub fn working_dir<D>(mut self, dir: D) -> Self
+    where
+        D: Into<String>,
+    {
+        self.spec.dir = Some(dir.into());
+        self
+    }
+
+    /// The user inside the container.
+    pub fn user<U>(mut self, user: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.spec.user = Some(user.into());
+        self
+    }
+
+    /// Whether a pseudo-TTY should be allocated.
+    pub fn tty(mut self, tty: bool) -> Self {
+        self.spec.tty = Some(tty);
+        self
+    }
+
+    /// Mount the container's root filesystem as read only.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.spec.read_only = Some(read_only);
+        self
+    }
+
+    /// Run an init inside the container that forwards signals and reaps processes.
+    pub fn init(mut self, init: bool) -> Self {
+        self.spec.init = Some(init);
+        self
+    }
+
+    /// Mounts to be added to the container.
+    pub fn mounts(mut self, mounts: impl IntoIterator<Item = models::Mount>) -> Self {
+        self.spec.mounts = Some(mounts.into_iter().collect());
+        self
+    }
+
+    /// Secrets exposed to the container.
+    pub fn secrets(
+        mut self,
+        secrets: impl IntoIterator<Item = models::TaskSpecContainerSpecInlineItemSecretsInlineItem>,
+    ) -> Self {
+        self.spec.secrets = Some(secrets.into_iter().collect());
+        self
+    }
+
+    /// Configs exposed to the container. Use [`ConfigReferenceBuilder`](ConfigReferenceBuilder)
+    /// to construct them.
+    pub fn configs(
+        mut self,
+        configs: impl IntoIterator<Item = models::TaskSpecContainerSpecInlineItemConfigsInlineItem>,
+    ) -> Self {
+        self.spec.configs = Some(configs.into_iter().collect());
+        self
+    }
+
+    /// Expose a single config to the container. Can be called multiple times.
+    pub fn config(mut self, config: ConfigReferenceBuilder) -> Self {
+        self.spec
+            .configs
+            .get_or_insert_with(Vec::new)
+            .push(config.build());
+        self
+    }
+
+    /// A list of hostname/IP mappings to add to the container's hosts file, in the form `IP_address canonical_hostname [aliases...]`.
+    pub fn hosts<S>(mut self, hosts: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.spec.hosts = Some(hosts.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Signal to stop the container.
+    pub fn stop_signal(mut self, signal: impl Into<Signal>) -> Self {
+        self.spec.stop_signal = Some(signal.into().to_string());
+        self
+    }
+
+    /// Amount of time to wait for the container to terminate before forcefully killing it.
+    pub fn stop_grace_period(mut self, period: Duration) -> Self {
+        self.spec.stop_grace_period = Some(duration_nanos(period));
+        self
+    }
+
+    /// Test to perform to check that the container is healthy, see
+    /// [`HealthcheckBuilder`](crate::opts::HealthcheckBuilder).
+    pub fn health_check(mut self, health_check: models::HealthConfig) -> Self {
+        self.spec.health_check = Some(health_check);
+        self
+    }
+
+    pub fn build(self) -> models::TaskSpecContainerSpecInlineItem {
+        self.spec
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Builder for a reference to a swarm config exposed to a service container as a file.
+/// See [`config`](ContainerSpecBuilder::config).
+pub struct ConfigReferenceBuilder {
+    reference: models::TaskSpecContainerSpecInlineItemConfigsInlineItem,
+}
+
+impl ConfigReferenceBuilder {
+    /// Creates a reference to the config with the given ID and name. Unless changed with
+    /// [`file_name`](ConfigReferenceBuilder::file_name) the config is mounted at `/<config_name>`.
+    pub fn new<I, N>(config_id: I, config_name: N) -> Self
+    where
+        I: Into<String>,
+        N: Into<String>,
+    {
+        let config_name = config_name.into();
+        Self {
+            reference: models::TaskSpecContainerSpecInlineItemConfigsInlineItem {
+                config_id: Some(config_id.into()),
+                file: Some(
+                    models::TaskSpecContainerSpecInlineItemConfigsInlineItemFileInlineItem {
+                        gid: None,
+                        mode: None,
+                        name: Some(config_name.clone()),
+                        uid: None,
+                    },
+                ),
+                config_name: Some(config_name),
+                runtime: None,
+            },
+        }
+    }
+
+    fn file(
+        &mut self,
+    ) -> &mut models::TaskSpecContainerSpecInlineItemConfigsInlineItemFileInlineItem {
+        self.reference.file.get_or_insert(
+            models::TaskSpecContainerSpecInlineItemConfigsInlineItemFileInlineItem {
+                gid: None,
+                mode: None,
+                name: None,
+                uid: None,
+            },
+        )
+    }
+
+    /// Path of the file in the container the config is mounted at.
+    pub fn file_name<N>(mut self, name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.file().name = Some(name.into());
+        self
+    }
+
+    /// User ID owning the file.
+    pub fn uid<U>(mut self, uid: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.file().uid = Some(uid.into());
+        self
+    }
+
+    /// Group ID owning the file.
+    pub fn gid<G>(mut self, gid: G) -> Self
+    where
+        G: Into<String>,
+    {
+        self.file().gid = Some(gid.into());
+        self
+    }
+
+    /// Permissions of the file, for example `0o440`.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.file().mode = Some(mode);
+        self
+    }
+
+    pub fn build(self) -> models::TaskSpecContainerSpecInlineItemConfigsInlineItem {
+        self.reference
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+/// Builder for resource limits and reservations of a task. See [`resources`](TaskSpecBuilder::resources).
+pub struct ResourcesBuilder {
+    limit_cpus: Option<i64>,
+    limit_memory: Option<i64>,
+    limit_pids: Option<i64>,
+    reserve_cpus: Option<i64>,
+    reserve_memory: Option<i64>,
+}
+
+impl ResourcesBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of CPUs the task can use, for example `0.5`.
+    pub fn cpu_limit(mut self, cpus: f64) -> Self {
+        self.limit_cpus = Some((cpus * 1e9) as i64);
+        self
+    }
+
+    /// Memory limit, either in bytes or as a [`ByteSize`](ByteSize).
+    pub fn memory_limit(mut self, memory: impl Into<ByteSize>) -> Self {
+        self.limit_memory = Some(memory.into().bytes() as i64);
+        self
+    }
+
+    /// Maximum number of processes in the container. `0` means unlimited.
+    pub fn pids_limit(mut self, pids: u64) -> Self {
+        self.limit_pids = Some(pids as i64);
+        self
+    }
+
+    /// Number of CPUs reserved for the task.
+    pub fn cpu_reservation(mut self, cpus: f64) -> Self {
+        self.reserve_cpus = Some((cpus * 1e9) as i64);
+        self
+    }
+
+    /// Memory reserved for the task, either in bytes or as a [`ByteSize`](ByteSize).
+    pub fn memory_reservation(mut self, memory: impl Into<ByteSize>) -> Self {
+        self.reserve_memory = Some(memory.into().bytes() as i64);
+        self
+    }
+
+    pub fn build(self) -> models::TaskSpecResourcesInlineItem {
+        let limits = if self.limit_cpus.is_some()
+            || self.limit_memory.is_some()
+            || self.limit_pids.is_some()
+        {
+            Some(models::Limit {
+                memory_bytes: self.limit_memory,
+                nano_cp_us: self.limit_cpus,
+                pids: self.limit_pids,
+            })
+        } else {
+            None
+        };
+        let reservations = if self.reserve_cpus.is_some() || self.reserve_memory.is_some() {
+            Some(models::ResourceObject {
+                generic_resources: None,
+                memory_bytes: self.reserve_memory,
+                nano_cp_us: self.reserve_cpus,
+            })
+        } else {
+            None
+        };
+        models::TaskSpecResourcesInlineItem {
+            limits,
+            reservations,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+/// Builder for the placement of the tasks of a service. See [`placement`](TaskSpecBuilder::placement).
+pub struct PlacementBuilder {
+    constraints: Vec<String>,
+    preferences: Vec<String>,
+    max_replicas: Option<u64>,
+    platforms: Vec<models::Platform>,
+}
+
+impl PlacementBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the nodes where tasks can be scheduled with a constraint expression like
+    /// `node.role==worker` or `node.labels.zone!=eu-1`. Constraints are validated on
+    /// [`build`](PlacementBuilder::build).
+    pub fn constraint<C>(mut self, constraint: C) -> Self
+    where
+        C: Into<String>,
+    {
+        self.constraints.push(constraint.into());
+        self
+    }
+
+    /// Spread tasks evenly over the values of a node label like `node.labels.zone`. Can be
+    /// called multiple times, preferences are applied in order.
+    pub fn spread<D>(mut self, descriptor: D) -> Self
+    where
+        D: Into<String>,
+    {
+        self.preferences.push(descriptor.into());
+        self
+    }
+
+    /// Maximum number of replicas scheduled on a single node, unlimited by default.
+    pub fn max_replicas_per_node(mut self, max: u64) -> Self {
+        self.max_replicas = Some(max);
+        self
+    }
+
+    /// Platform the image of the service can run on, used to schedule tasks on matching nodes.
+    pub fn platform<A, O>(mut self, architecture: A, os: O) -> Self
+    where
+        A: Into<String>,
+        O: Into<String>,
+    {
+        self.platforms.push(models::Platform {
+            architecture: Some(architecture.into()),
+            os: Some(os.into()),
+        });
+        self
+    }
+
+    /// Validates the constraints and builds the placement.
+    pub fn build(self) -> Result<models::TaskSpecPlacementInlineItem> {
+        for constraint in &self.constraints {
+            validate_constraint(constraint)?;
+        }
+        if let Some(descriptor) = self.preferences.iter().find(|d| d.trim().is_empty()) {
+            return Err(Error::InvalidConstraint(format!(
+                "`{descriptor}` - empty spread descriptor"
+            )));
+        }
+        Ok(models::TaskSpecPlacementInlineItem {
+            constraints: Some(self.constraints),
+            max_replicas: self.max_replicas.map(|max| max as i64),
+            platforms: (!self.platforms.is_empty()).then_some(self.platforms),
+            preferences: Some(
+                self.preferences
+                    .into_iter()
+                    .map(|descriptor| models::TaskSpecPlacementInlineItemPreferencesInlineItem {
+                        spread: Some(
+                            models::TaskSpecPlacementInlineItemPreferencesInlineItemSpreadInlineItem {
+                                spread_descriptor: Some(descriptor),
+                            },
+                        ),
+                    })
+                    .collect(),
+            ),
+        })
+    }
+}
+
+/// Checks that `constraint` has the form `<attribute>==<value>` or `<attribute>!=<value>` with
+/// an attribute known to the swarm scheduler.
+fn validate_constraint(constraint: &str) -> Result<()> {
+    let invalid = |reason: &str| Error::InvalidConstraint(format!("`{constraint}` - {reason}"));
+    let (attribute, value) = match (constraint.split_once("=="), constraint.split_once("!=")) {
+        (Some(_), Some(_)) => return Err(invalid("multiple operators")),
+        (Some(parts), None) | (None, Some(parts)) => parts,
+        (None, None) => return Err(invalid("expected `==` or `!=`")),
+    };
+    let (attribute, value) = (attribute.trim(), value.trim());
+    match attribute {
+        "node.id" | "node.hostname" | "node.platform.os" | "node.platform.arch" => {}
+        "node.role" => {
+            if !value.eq_ignore_ascii_case("manager") && !value.eq_ignore_ascii_case("worker") {
+                return Err(invalid("node role must be `manager` or `worker`"));
+            }
+        }
+        _ => match attribute
+            .strip_prefix("node.labels.")
+            .or_else(|| attribute.strip_prefix("engine.labels."))
+        {
+            Some(label) if !label.is_empty() => {}
+            _ => return Err(invalid("unknown attribute")),
+        },
+    }
+    if value.is_empty() || value.contains('=') {
+        return Err(invalid("invalid value"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+/// Builder for the restart policy of a task. See [`restart_policy`](TaskSpecBuilder::restart_policy).
+pub struct RestartPolicyBuilder {
+    policy: models::TaskSpecRestartPolicyInlineItem,
+}
+
+impl RestartPolicyBuilder {
+    /// Creates a new policy restarting tasks on the given `condition`.
+    pub fn new(condition: models::TaskSpecRestartPolicyInlineItemConditionInlineItem) -> Self {
+        Self {
+            policy: models::TaskSpecRestartPolicyInlineItem {
+                condition: Some(condition.as_ref().to_string()),
+                delay: None,
+                max_attempts: None,
+                window: None,
+            },
+        }
+    }
+
+    /// Delay between restart attempts.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.policy.delay = Some(duration_nanos(delay));
+        self
+    }
+
+    /// Maximum attempts to restart a given container before giving up. `0` means unlimited.
+    pub fn max_attempts(mut self, attempts: u64) -> Self {
+        self.policy.max_attempts = Some(attempts as i64);
+        self
+    }
+
+    /// Time window used to evaluate the restart policy.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.policy.window = Some(duration_nanos(window));
+        self
+    }
+
+    pub fn build(self) -> models::TaskSpecRestartPolicyInlineItem {
+        self.policy
+    }
+}
+
+macro_rules! impl_update_config_builder {
+    ($(#[doc = $docs:expr])* $builder:ident => $model:ident, $failure_action:ident, $order:ident) => {
+        $(#[doc = $docs])*
+        #[derive(Debug, Clone)]
+        pub struct $builder {
+            config: models::$model,
+        }
+
+        impl Default for $builder {
+            fn default() -> Self {
+                Self {
+                    config: models::$model {
+                        delay: None,
+                        failure_action: None,
+                        max_failure_ratio: None,
+                        monitor: None,
+                        order: None,
+                        parallelism: None,
+                    },
+                }
+            }
+        }
+
+        impl $builder {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Maximum number of tasks to be updated in one iteration. `0` means unlimited.
+            pub fn parallelism(mut self, parallelism: u64) -> Self {
+                self.config.parallelism = Some(parallelism as i64);
+                self
+            }
+
+            /// Amount of time between updates.
+            pub fn delay(mut self, delay: Duration) -> Self {
+                self.config.delay = Some(duration_nanos(delay));
+                self
+            }
+
+            /// Action to take if an updated task fails to run, or stops running during the update.
+            pub fn failure_action(mut self, action: models::$failure_action) -> Self {
+                self.config.failure_action = Some(action.as_ref().to_string());
+                self
+            }
+
+            /// Amount of time to monitor each updated task for failures.
+            pub fn monitor(mut self, monitor: Duration) -> Self {
+                self.config.monitor = Some(duration_nanos(monitor));
+                self
+            }
+
+            /// The fraction of tasks that may fail during an update before the failure action is
+            /// invoked, between `0` and `1`.
+            pub fn max_failure_ratio(mut self, ratio: f64) -> Self {
+                self.config.max_failure_ratio = Some(json!(ratio));
+                self
+            }
+
+            /// The order of operations when rolling out an updated task. Either the old task is
+            /// shut down before the new task is started, or the new task is started before the old
+            /// task is shut down.
+            pub fn order(mut self, order: models::$order) -> Self {
+                self.config.order = Some(order.as_ref().to_string());
+                self
+            }
+
+            pub fn build(self) -> models::$model {
+                self.config
+            }
+        }
+    };
+}
+
+impl_update_config_builder!(
+    /// Builder for the update strategy of a service. See [`update_config`](ServiceOptsBuilder::update_config).
+    UpdateConfigBuilder =>
+    ServiceSpecUpdateConfigInlineItem,
+    ServiceSpecUpdateConfigInlineItemFailureActionInlineItem,
+    ServiceSpecUpdateConfigInlineItemOrderInlineItem
+);
+
+impl_update_config_builder!(
+    /// Builder for the rollback strategy of a service. See [`rollback_config`](ServiceOptsBuilder::rollback_config).
+    RollbackConfigBuilder =>
+    ServiceSpecRollbackConfigInlineItem,
+    ServiceSpecRollbackConfigInlineItemFailureActionInlineItem,
+    ServiceSpecRollbackConfigInlineItemOrderInlineItem
+);
+
+#[derive(Default, Debug, Clone)]
+/// Builder for the endpoint of a service. See [`endpoint_spec`](ServiceOptsBuilder::endpoint_spec).
+pub struct EndpointSpecBuilder {
+    mode: Option<models::EndpointSpecModeInlineItem>,
+    ports: Vec<models::EndpointPortConfig>,
+}
+
+impl EndpointSpecBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The mode of resolution to use for internal load balancing between tasks.
+    pub fn mode(mut self, mode: models::EndpointSpecModeInlineItem) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Publish the `target` port of the tasks as `published` on the routing mesh.
+    pub fn port(
+        self,
+        published: u16,
+        target: u16,
+        protocol: models::EndpointPortConfigProtocolInlineItem,
+    ) -> Self {
+        self.port_config(models::EndpointPortConfig {
+            name: None,
+            protocol: Some(protocol.as_ref().to_string()),
+            publish_mode: None,
+            published_port: Some(published as isize),
+            target_port: Some(target as isize),
+        })
+    }
+
+    /// Publish a port with a custom configuration, for example using the `host` publish mode.
+    pub fn port_config(mut self, port: models::EndpointPortConfig) -> Self {
+        self.ports.push(port);
+        self
+    }
+
+    pub fn build(self) -> models::EndpointSpec {
+        models::EndpointSpec {
+            mode: self.mode.map(|m| m.as_ref().to_string()),
+            ports: if self.ports.is_empty() {
+                None
+            } else {
+                Some(self.ports)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A label that fails to serialize.
+    #[derive(PartialEq, Eq, Hash)]
+    struct Unserializable;
+
+    impl AsRef<str> for Unserializable {
+        fn as_ref(&self) -> &str {
+            "unserializable"
+        }
+    }
+
+    impl Serialize for Unserializable {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> std::result::Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("unserializable label"))
+        }
+    }
+
+    #[test]
+    fn labels_serialization_error() {
+        match ServiceOpts::builder()
+            .labels([(Unserializable, "a")])
+            .build()
+        {
+            Err(Error::SerdeJsonError(e)) => assert_eq!(e.to_string(), "unserializable label"),
+            other => panic!("expected a serialization error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn placement() {
+        let placement = PlacementBuilder::new()
+            .constraint("node.role == worker")
+            .constraint("node.labels.zone!=eu-1")
+            .spread("node.labels.zone")
+            .max_replicas_per_node(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(placement).unwrap(),
+            json!({
+                "Constraints": ["node.role == worker", "node.labels.zone!=eu-1"],
+                "MaxReplicas": 2,
+                "Preferences": [{"Spread": {"SpreadDescriptor": "node.labels.zone"}}]
+            })
+        );
+
+        for constraint in [
+            "node.role",
+            "node.role==admin",
+            "node.labels.==a",
+            "node.name==a",
+            "node.id==",
+            "node.id==a!=b",
+        ] {
+            assert!(
+                matches!(
+                    PlacementBuilder::new().constraint(constraint).build(),
+                    Err(Error::InvalidConstraint(_))
+                ),
+                "{constraint}"
+            );
+        }
+    }
+
+    #[test]
+    fn service_list_filters() {
+        let opts = ServiceListOpts::builder()
+            .filter([
+                ServiceFilter::LabelKeyVal("env".into(), "prod".into()),
+                ServiceFilter::GlobalJobMode,
+                ServiceFilter::Name("web".into()),
+            ])
+            .status(true)
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22label%22%3A%5B%22env%3Dprod%22%5D%2C%22mode%22%3A%5B%22global-job%22%5D%2C%22name%22%3A%5B%22web%22%5D%7D&status=true"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn service_opts_with_builders() {
+        let task = TaskSpecBuilder::new()
+            .container_spec(
+                ContainerSpecBuilder::new("nginx:alpine")
+                    .env(["A=1"])
+                    .stop_grace_period(Duration::from_secs(5)),
+            )
+            .resources(
+                ResourcesBuilder::new()
+                    .cpu_limit(0.5)
+                    .memory_reservation(1024),
+            )
+            .restart_policy(
+                RestartPolicyBuilder::new(
+                    models::TaskSpecRestartPolicyInlineItemConditionInlineItem::OnFailure,
+                )
+                .max_attempts(3),
+            )
+            .network("backend")
+            .build();
+        let endpoint = EndpointSpecBuilder::new()
+            .port(8080, 80, models::EndpointPortConfigProtocolInlineItem::Tcp)
+            .build();
+        let opts = ServiceOpts::builder()
+            .name("web")
+            .service_mode(ServiceMode::Replicated(3))
+            .task_template(&task)
+            .endpoint_spec(&endpoint)
+            .build()
+            .unwrap();
+
+        let value: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+        assert_eq!(value["Name"], "web");
+        assert_eq!(value["Mode"], json!({"Replicated": {"Replicas": 3}}));
+        let template = &value["TaskTemplate"];
+        let container = &template["ContainerSpec"];
+        assert_eq!(container["Image"], "nginx:alpine");
+        assert_eq!(container["Env"], json!(["A=1"]));
+        assert_eq!(container["StopGracePeriod"], 5_000_000_000i64);
+        assert_eq!(
+            template["Resources"]["Limits"],
+            json!({"NanoCPUs": 500_000_000})
+        );
+        assert_eq!(template["Resources"]["Reservations"]["MemoryBytes"], 1024);
+        assert_eq!(
+            template["RestartPolicy"],
+            json!({"Condition": "on-failure", "MaxAttempts": 3})
+        );
+        assert_eq!(template["Networks"], json!([{"Target": "backend"}]));
+        assert_eq!(
+            value["EndpointSpec"],
+            json!({"Ports": [{"Protocol": "tcp", "PublishedPort": 8080, "TargetPort": 80}]})
+        );
+    }
+
+    #[test]
+    fn service_update_opts() {
+        let opts = ServiceUpdateOpts::builder()
+            .registry_auth_from(RegistryAuthFrom::PreviousSpec)
+            .rollback(true)
+            .build();
+        assert_eq!(
+            opts.serialize().unwrap(),
+            "registryAuthFrom=previous-spec&rollback=previous"
+        );
+        let opts = ServiceUpdateOpts::builder()
+            .rollback(true)
+            .rollback(false)
+            .build();
+        assert!(opts.serialize().is_none());
+    }
+
+    #[test]
+    fn config_reference() {
+        let spec = ContainerSpecBuilder::new("nginx")
+            .config(
+                ConfigReferenceBuilder::new("abc", "nginx.conf")
+                    .file_name("/etc/nginx/nginx.conf")
+                    .uid("0")
+                    .gid("0")
+                    .mode(0o440),
+            )
+            .build();
+        assert_eq!(
+            serde_json::to_value(&spec.configs).unwrap(),
+            json!([{
+                "ConfigID": "abc",
+                "ConfigName": "nginx.conf",
+                "File": {"Name": "/etc/nginx/nginx.conf", "UID": "0", "GID": "0", "Mode": 288}
+            }])
+        );
+    }
+
+    #[test]
+    fn update_and_rollback_config() {
+        let update = UpdateConfigBuilder::new()
+            .parallelism(2)
+            .delay(Duration::from_secs(10))
+            .failure_action(
+                models::ServiceSpecUpdateConfigInlineItemFailureActionInlineItem::Rollback,
+            )
+            .monitor(Duration::from_millis(500))
+            .max_failure_ratio(0.25)
+            .order(models::ServiceSpecUpdateConfigInlineItemOrderInlineItem::StartFirst)
+            .build();
+        let rollback = RollbackConfigBuilder::new()
+            .parallelism(0)
+            .failure_action(
+                models::ServiceSpecRollbackConfigInlineItemFailureActionInlineItem::Pause,
+            )
+            .build();
+        let opts = ServiceOpts::builder()
+            .update_config(&update)
+            .rollback_config(&rollback)
+            .build()
+            .unwrap();
+
+        let value: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "UpdateConfig": {
+                    "Parallelism": 2,
+                    "Delay": 10_000_000_000i64,
+                    "FailureAction": "rollback",
+                    "Monitor": 500_000_000,
+                    "MaxFailureRatio": 0.25,
+                    "Order": "start-first"
+                },
+                "RollbackConfig": {"Parallelism": 0, "FailureAction": "pause"}
+            })
+        );
+    }
+
+    #[test]
+    fn service_mode() {
+        let mode: models::ServiceSpecModeInlineItem = ServiceMode::Global.into();
+        assert_eq!(serde_json::to_value(mode).unwrap(), json!({"Global": {}}));
+        let mode: models::ServiceSpecModeInlineItem = ServiceMode::ReplicatedJob {
+            max_concurrent: Some(2),
+            total_completions: None,
+        }
+        .into();
+        assert_eq!(
+            serde_json::to_value(mode).unwrap(),
+            json!({"ReplicatedJob": {"MaxConcurrent": 2}})
+        );
+    }
+}
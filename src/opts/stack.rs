@@ -0,0 +1,1021 @@
+//! Subset of the docker-compose v3 file format used to deploy stacks with
+//! [`Stack::deploy`](crate::Stack::deploy).
+use crate::models;
+use crate::opts::{
+    ByteSize, ConfigReferenceBuilder, ContainerSpecBuilder, EndpointSpecBuilder, PlacementBuilder,
+    ResourcesBuilder, RestartPolicyBuilder, RollbackConfigBuilder, ServiceMode, TaskSpecBuilder,
+    UpdateConfigBuilder,
+};
+use crate::{Error, Result};
+
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Label added to every object created by a stack deployment.
+pub const STACK_NAMESPACE_LABEL: &str = "com.docker.stack.namespace";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+/// A parsed docker-compose v3 file. Keys unsupported by swarm services are ignored.
+pub struct ComposeFile {
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub services: BTreeMap<String, ComposeService>,
+    #[serde(default)]
+    pub networks: BTreeMap<String, Option<ComposeNetwork>>,
+    #[serde(default)]
+    pub volumes: BTreeMap<String, Option<serde_yaml::Value>>,
+    #[serde(default)]
+    pub secrets: BTreeMap<String, ComposeFileObject>,
+    #[serde(default)]
+    pub configs: BTreeMap<String, ComposeFileObject>,
+}
+
+impl FromStr for ComposeFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        serde_yaml::from_str(s).map_err(|e| Error::InvalidCompose(e.to_string()))
+    }
+}
+
+impl ComposeFile {
+    /// Reads and parses the compose file at `path`. Relative paths of secret and config files
+    /// are resolved against the directory of the compose file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut compose: ComposeFile = std::fs::read_to_string(path)?.parse()?;
+        if let Some(dir) = path.parent() {
+            for object in compose
+                .secrets
+                .values_mut()
+                .chain(compose.configs.values_mut())
+            {
+                if let Some(file) = object.file.as_mut().filter(|file| file.is_relative()) {
+                    *file = dir.join(&*file);
+                }
+            }
+        }
+        Ok(compose)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+/// A top-level network of a compose file.
+pub struct ComposeNetwork {
+    pub name: Option<String>,
+    pub driver: Option<String>,
+    #[serde(default)]
+    pub driver_opts: BTreeMap<String, String>,
+    #[serde(default)]
+    pub external: bool,
+    pub attachable: Option<bool>,
+    #[serde(default)]
+    pub labels: ListOrMap,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+/// A top-level secret or config of a compose file.
+pub struct ComposeFileObject {
+    pub name: Option<String>,
+    /// Path of the file holding the data.
+    pub file: Option<PathBuf>,
+    #[serde(default)]
+    pub external: bool,
+    #[serde(default)]
+    pub labels: ListOrMap,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+/// A service of a compose file.
+pub struct ComposeService {
+    pub image: String,
+    pub command: Option<StringOrList>,
+    pub entrypoint: Option<StringOrList>,
+    #[serde(default)]
+    pub environment: ListOrMap,
+    #[serde(default)]
+    pub labels: ListOrMap,
+    #[serde(default)]
+    pub ports: Vec<ComposePort>,
+    #[serde(default)]
+    pub networks: ServiceNetworks,
+    #[serde(default)]
+    pub secrets: Vec<ComposeFileReference>,
+    #[serde(default)]
+    pub configs: Vec<ComposeFileReference>,
+    #[serde(default)]
+    pub volumes: Vec<ComposeVolume>,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub working_dir: Option<String>,
+    pub tty: Option<bool>,
+    pub read_only: Option<bool>,
+    pub init: Option<bool>,
+    pub stop_signal: Option<String>,
+    pub stop_grace_period: Option<String>,
+    #[serde(default)]
+    pub deploy: ComposeDeploy,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+/// The `deploy` section of a compose service.
+pub struct ComposeDeploy {
+    pub mode: Option<String>,
+    pub replicas: Option<u64>,
+    #[serde(default)]
+    pub labels: ListOrMap,
+    pub endpoint_mode: Option<String>,
+    #[serde(default)]
+    pub resources: ComposeResources,
+    pub restart_policy: Option<ComposeRestartPolicy>,
+    #[serde(default)]
+    pub placement: ComposePlacement,
+    pub update_config: Option<ComposeUpdateConfig>,
+    pub rollback_config: Option<ComposeUpdateConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ComposeResources {
+    pub limits: Option<ComposeResourceSpec>,
+    pub reservations: Option<ComposeResourceSpec>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ComposeResourceSpec {
+    pub cpus: Option<Scalar>,
+    pub memory: Option<Scalar>,
+    pub pids: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ComposeRestartPolicy {
+    pub condition: Option<String>,
+    pub delay: Option<String>,
+    pub max_attempts: Option<u64>,
+    pub window: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ComposePlacement {
+    #[serde(default)]
+    pub constraints: Vec<String>,
+    #[serde(default)]
+    pub preferences: Vec<ComposePlacementPreference>,
+    pub max_replicas_per_node: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ComposePlacementPreference {
+    pub spread: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ComposeUpdateConfig {
+    pub parallelism: Option<u64>,
+    pub delay: Option<String>,
+    pub failure_action: Option<String>,
+    pub monitor: Option<String>,
+    pub max_failure_ratio: Option<f64>,
+    pub order: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+/// A scalar YAML value which may be written as a string, a number or a boolean.
+pub enum Scalar {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl std::fmt::Display for Scalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scalar::Bool(b) => b.fmt(f),
+            Scalar::Int(i) => i.fmt(f),
+            Scalar::Float(n) => n.fmt(f),
+            Scalar::String(s) => s.fmt(f),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+/// A value that can be written either as a list of `KEY=VALUE` strings or as a mapping.
+pub enum ListOrMap {
+    List(Vec<String>),
+    Map(BTreeMap<String, Option<Scalar>>),
+}
+
+impl Default for ListOrMap {
+    fn default() -> Self {
+        ListOrMap::List(Vec::new())
+    }
+}
+
+impl ListOrMap {
+    /// Returns the entries as key/value pairs. Entries without a value map to an empty string.
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        match self {
+            ListOrMap::List(list) => list
+                .iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((key, val)) => (key.to_string(), val.to_string()),
+                    None => (entry.clone(), String::new()),
+                })
+                .collect(),
+            ListOrMap::Map(map) => map
+                .iter()
+                .map(|(key, val)| {
+                    (
+                        key.clone(),
+                        val.as_ref().map(ToString::to_string).unwrap_or_default(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the entries as a list of `KEY=VALUE` strings.
+    pub fn to_list(&self) -> Vec<String> {
+        match self {
+            ListOrMap::List(list) => list.clone(),
+            ListOrMap::Map(map) => map
+                .iter()
+                .map(|(key, val)| match val {
+                    Some(val) => format!("{key}={val}"),
+                    None => key.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+/// A command written either as a single string split like a shell would or as a list.
+pub enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+impl StringOrList {
+    pub fn to_list(&self) -> Vec<String> {
+        match self {
+            StringOrList::List(list) => list.clone(),
+            StringOrList::String(s) => split_command(s),
+        }
+    }
+}
+
+/// Splits a command on whitespace, keeping quoted parts together.
+fn split_command(command: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_part = false;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_part = true;
+            }
+            (q, '\\') if q != Some('\'') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_part = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_part {
+                    parts.push(std::mem::take(&mut current));
+                    in_part = false;
+                }
+            }
+            (_, c) => {
+                current.push(c);
+                in_part = true;
+            }
+        }
+    }
+    if in_part {
+        parts.push(current);
+    }
+    parts
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+/// A published port in the short (`"8080:80/udp"`) or long syntax.
+pub enum ComposePort {
+    Short(Scalar),
+    Long {
+        target: u16,
+        published: Option<u16>,
+        protocol: Option<String>,
+        mode: Option<String>,
+    },
+}
+
+impl ComposePort {
+    fn to_port_config(&self) -> Result<models::EndpointPortConfig> {
+        let (target, published, protocol, mode) = match self {
+            ComposePort::Short(port) => {
+                let port = port.to_string();
+                let (ports, protocol) = match port.split_once('/') {
+                    Some((ports, protocol)) => (ports, Some(protocol.to_string())),
+                    None => (port.as_str(), None),
+                };
+                // an optional host IP may precede the ports, it has no effect in swarm mode
+                let mut parts = ports.rsplit(':');
+                let target = parts.next().unwrap_or_default();
+                let published = parts.next();
+                let parse = |port: &str| {
+                    port.parse::<u16>()
+                        .map_err(|_| Error::InvalidCompose(format!("invalid port `{port}`")))
+                };
+                (
+                    parse(target)?,
+                    published.map(parse).transpose()?,
+                    protocol,
+                    None,
+                )
+            }
+            ComposePort::Long {
+                target,
+                published,
+                protocol,
+                mode,
+            } => (*target, *published, protocol.clone(), mode.clone()),
+        };
+        Ok(models::EndpointPortConfig {
+            name: None,
+            protocol: Some(protocol.unwrap_or_else(|| "tcp".into())),
+            publish_mode: mode,
+            published_port: published.map(|p| p as isize),
+            target_port: Some(target as isize),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+/// Networks of a service, either as a list of names or as a mapping with aliases.
+pub enum ServiceNetworks {
+    List(Vec<String>),
+    Map(BTreeMap<String, Option<ServiceNetwork>>),
+}
+
+impl Default for ServiceNetworks {
+    fn default() -> Self {
+        ServiceNetworks::List(Vec::new())
+    }
+}
+
+impl ServiceNetworks {
+    /// Returns the names of the networks together with the aliases of the service on them.
+    pub fn to_list(&self) -> Vec<(String, Vec<String>)> {
+        match self {
+            ServiceNetworks::List(list) => list.iter().map(|n| (n.clone(), Vec::new())).collect(),
+            ServiceNetworks::Map(map) => map
+                .iter()
+                .map(|(name, network)| {
+                    (
+                        name.clone(),
+                        network
+                            .as_ref()
+                            .map(|n| n.aliases.clone())
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ServiceNetwork {
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+/// A reference of a service to a secret or config in the short or long syntax.
+pub enum ComposeFileReference {
+    Short(String),
+    Long {
+        source: String,
+        target: Option<String>,
+        uid: Option<String>,
+        gid: Option<String>,
+        /// File mode, octal values like `0440` are supported.
+        mode: Option<Scalar>,
+    },
+}
+
+impl ComposeFileReference {
+    /// Name of the referenced top-level secret or config.
+    pub fn source(&self) -> &str {
+        match self {
+            ComposeFileReference::Short(source) => source,
+            ComposeFileReference::Long { source, .. } => source,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+/// A volume of a service in the short (`"data:/var/lib/data:ro"`) or long syntax.
+pub enum ComposeVolume {
+    Short(String),
+    Long {
+        #[serde(rename = "type")]
+        type_: Option<String>,
+        source: Option<String>,
+        target: String,
+        #[serde(default)]
+        read_only: bool,
+    },
+}
+
+/// Parses a duration in the format used by compose files like `1m30s`, `500ms` or `10s`.
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let invalid = || Error::InvalidCompose(format!("invalid duration `{duration}`"));
+    let mut total = Duration::ZERO;
+    let mut rest = duration.trim();
+    if rest == "0" {
+        return Ok(total);
+    }
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(invalid)?;
+        let (value, tail) = rest.split_at(split);
+        let value: f64 = value.parse().map_err(|_| invalid())?;
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let secs = match unit {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.,
+            "m" => 60.,
+            "h" => 3600.,
+            _ => return Err(invalid()),
+        };
+        total += Duration::from_secs_f64(value * secs);
+        rest = tail;
+    }
+    Ok(total)
+}
+
+/// Parses a size like `512m`, `1.5G` or `1024` into bytes.
+pub fn parse_bytes(size: &str) -> Result<u64> {
+    ByteSize::parse(size)
+        .map(|size| size.bytes())
+        .map_err(|_| Error::InvalidCompose(format!("invalid size `{size}`")))
+}
+
+fn parse_cpus(cpus: &Scalar) -> Result<f64> {
+    match cpus {
+        Scalar::Int(n) => Ok(*n as f64),
+        Scalar::Float(n) => Ok(*n),
+        other => other
+            .to_string()
+            .parse()
+            .map_err(|_| Error::InvalidCompose(format!("invalid cpus `{other}`"))),
+    }
+}
+
+fn parse_memory(memory: &Scalar) -> Result<u64> {
+    match memory {
+        Scalar::Int(n) => Ok(*n as u64),
+        other => parse_bytes(&other.to_string()),
+    }
+}
+
+fn parse_enum<T: serde::de::DeserializeOwned>(value: &str, what: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| Error::InvalidCompose(format!("invalid {what} `{value}`")))
+}
+
+/// IDs and names of the secrets or configs of a stack, keyed by their name in the compose file.
+pub(crate) type ObjectIds = BTreeMap<String, (String, String)>;
+
+impl ComposeService {
+    /// Converts this service into a spec of a service in the stack `namespace`.
+    pub(crate) fn to_service_opts(
+        &self,
+        namespace: &str,
+        name: &str,
+        networks: &BTreeMap<String, String>,
+        secrets: &ObjectIds,
+        configs: &ObjectIds,
+    ) -> Result<crate::opts::ServiceOpts> {
+        let mut container = ContainerSpecBuilder::new(self.image.clone())
+            .env(self.environment.to_list())
+            .labels(
+                self.labels
+                    .to_map()
+                    .into_iter()
+                    .chain([(STACK_NAMESPACE_LABEL.to_string(), namespace.to_string())]),
+            );
+        if let Some(entrypoint) = &self.entrypoint {
+            container = container.command(entrypoint.to_list());
+        }
+        if let Some(command) = &self.command {
+            container = container.args(command.to_list());
+        }
+        if let Some(hostname) = &self.hostname {
+            container = container.hostname(hostname.clone());
+        }
+        if let Some(user) = &self.user {
+            container = container.user(user.clone());
+        }
+        if let Some(dir) = &self.working_dir {
+            container = container.working_dir(dir.clone());
+        }
+        if let Some(tty) = self.tty {
+            container = container.tty(tty);
+        }
+        if let Some(read_only) = self.read_only {
+            container = container.read_only(read_only);
+        }
+        if let Some(init) = self.init {
+            container = container.init(init);
+        }
+        if let Some(signal) = &self.stop_signal {
+            container = container.stop_signal(signal.clone());
+        }
+        if let Some(period) = &self.stop_grace_period {
+            container = container.stop_grace_period(parse_duration(period)?);
+        }
+        if !self.volumes.is_empty() {
+            container = container.mounts(
+                self.volumes
+                    .iter()
+                    .map(|volume| volume.to_mount(namespace))
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+        if !self.secrets.is_empty() {
+            let refs = self
+                .secrets
+                .iter()
+                .map(|secret| {
+                    let (id, name) = lookup(secrets, secret.source(), "secret")?;
+                    let (target, uid, gid, mode) = secret.file_options()?;
+                    Ok(models::TaskSpecContainerSpecInlineItemSecretsInlineItem {
+                        file: Some(
+                            models::TaskSpecContainerSpecInlineItemSecretsInlineItemFileInlineItem {
+                                gid: Some(gid),
+                                mode: Some(mode),
+                                name: Some(target.unwrap_or_else(|| secret.source().to_string())),
+                                uid: Some(uid),
+                            },
+                        ),
+                        secret_id: Some(id),
+                        secret_name: Some(name),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            container = container.secrets(refs);
+        }
+        for config in &self.configs {
+            let (id, name) = lookup(configs, config.source(), "config")?;
+            let (target, uid, gid, mode) = config.file_options()?;
+            container = container.config(
+                ConfigReferenceBuilder::new(id, name)
+                    .file_name(target.unwrap_or_else(|| format!("/{}", config.source())))
+                    .uid(uid)
+                    .gid(gid)
+                    .mode(mode),
+            );
+        }
+
+        let mut task = TaskSpecBuilder::new().container_spec(container);
+        let mut attachments = self.networks.to_list();
+        if attachments.is_empty() {
+            attachments.push(("default".to_string(), Vec::new()));
+        }
+        for (network, mut aliases) in attachments {
+            let target = networks
+                .get(&network)
+                .ok_or_else(|| Error::InvalidCompose(format!("undefined network `{network}`")))?;
+            aliases.push(name.to_string());
+            task = task.network_attachment(models::NetworkAttachmentConfig {
+                aliases: Some(aliases),
+                driver_opts: None,
+                target: Some(target.clone()),
+            });
+        }
+
+        let deploy = &self.deploy;
+        let resources = &deploy.resources;
+        if resources.limits.is_some() || resources.reservations.is_some() {
+            let mut builder = ResourcesBuilder::new();
+            if let Some(limits) = &resources.limits {
+                if let Some(cpus) = &limits.cpus {
+                    builder = builder.cpu_limit(parse_cpus(cpus)?);
+                }
+                if let Some(memory) = &limits.memory {
+                    builder = builder.memory_limit(parse_memory(memory)?);
+                }
+                if let Some(pids) = limits.pids {
+                    builder = builder.pids_limit(pids);
+                }
+            }
+            if let Some(reservations) = &resources.reservations {
+                if let Some(cpus) = &reservations.cpus {
+                    builder = builder.cpu_reservation(parse_cpus(cpus)?);
+                }
+                if let Some(memory) = &reservations.memory {
+                    builder = builder.memory_reservation(parse_memory(memory)?);
+                }
+            }
+            task = task.resources(builder);
+        }
+        if let Some(policy) = &deploy.restart_policy {
+            let condition = policy.condition.as_deref().unwrap_or("any");
+            let mut builder =
+                RestartPolicyBuilder::new(parse_enum(condition, "restart condition")?);
+            if let Some(delay) = &policy.delay {
+                builder = builder.delay(parse_duration(delay)?);
+            }
+            if let Some(attempts) = policy.max_attempts {
+                builder = builder.max_attempts(attempts);
+            }
+            if let Some(window) = &policy.window {
+                builder = builder.window(parse_duration(window)?);
+            }
+            task = task.restart_policy(builder);
+        }
+        let placement = &deploy.placement;
+        if !placement.constraints.is_empty()
+            || !placement.preferences.is_empty()
+            || placement.max_replicas_per_node.is_some()
+        {
+            let mut builder = PlacementBuilder::new();
+            for constraint in &placement.constraints {
+                builder = builder.constraint(constraint.clone());
+            }
+            for preference in &placement.preferences {
+                builder = builder.spread(preference.spread.clone());
+            }
+            if let Some(max) = placement.max_replicas_per_node {
+                builder = builder.max_replicas_per_node(max);
+            }
+            task = task.placement(builder.build()?);
+        }
+
+        let mode = match deploy.mode.as_deref() {
+            None | Some("replicated") => ServiceMode::Replicated(deploy.replicas.unwrap_or(1)),
+            Some("global") => ServiceMode::Global,
+            Some(other) => {
+                return Err(Error::InvalidCompose(format!(
+                    "invalid deploy mode `{other}`"
+                )))
+            }
+        };
+
+        let mut endpoint = EndpointSpecBuilder::new();
+        if let Some(mode) = &deploy.endpoint_mode {
+            endpoint = endpoint.mode(parse_enum(mode, "endpoint mode")?);
+        }
+        for port in &self.ports {
+            endpoint = endpoint.port_config(port.to_port_config()?);
+        }
+
+        let mut labels = deploy.labels.to_map();
+        labels.insert(STACK_NAMESPACE_LABEL.to_string(), namespace.to_string());
+        labels.insert("com.docker.stack.image".to_string(), self.image.clone());
+
+        let mut opts = crate::opts::ServiceOpts::builder()
+            .name(format!("{namespace}_{name}"))
+            .labels(labels)
+            .service_mode(mode)
+            .task_template(&task.build())
+            .endpoint_spec(&endpoint.build());
+        if let Some(update) = &deploy.update_config {
+            let mut builder = UpdateConfigBuilder::new();
+            if let Some(parallelism) = update.parallelism {
+                builder = builder.parallelism(parallelism);
+            }
+            if let Some(delay) = &update.delay {
+                builder = builder.delay(parse_duration(delay)?);
+            }
+            if let Some(action) = &update.failure_action {
+                builder = builder.failure_action(parse_enum(action, "failure action")?);
+            }
+            if let Some(monitor) = &update.monitor {
+                builder = builder.monitor(parse_duration(monitor)?);
+            }
+            if let Some(ratio) = update.max_failure_ratio {
+                builder = builder.max_failure_ratio(ratio);
+            }
+            if let Some(order) = &update.order {
+                builder = builder.order(parse_enum(order, "update order")?);
+            }
+            opts = opts.update_config(&builder.build());
+        }
+        if let Some(rollback) = &deploy.rollback_config {
+            let mut builder = RollbackConfigBuilder::new();
+            if let Some(parallelism) = rollback.parallelism {
+                builder = builder.parallelism(parallelism);
+            }
+            if let Some(delay) = &rollback.delay {
+                builder = builder.delay(parse_duration(delay)?);
+            }
+            if let Some(action) = &rollback.failure_action {
+                builder = builder.failure_action(parse_enum(action, "failure action")?);
+            }
+            if let Some(monitor) = &rollback.monitor {
+                builder = builder.monitor(parse_duration(monitor)?);
+            }
+            if let Some(ratio) = rollback.max_failure_ratio {
+                builder = builder.max_failure_ratio(ratio);
+            }
+            if let Some(order) = &rollback.order {
+                builder = builder.order(parse_enum(order, "rollback order")?);
+            }
+            opts = opts.rollback_config(&builder.build());
+        }
+        opts.build()
+    }
+}
+
+fn lookup(objects: &ObjectIds, source: &str, kind: &str) -> Result<(String, String)> {
+    objects
+        .get(source)
+        .cloned()
+        .ok_or_else(|| Error::InvalidCompose(format!("undefined {kind} `{source}`")))
+}
+
+impl ComposeFileReference {
+    fn file_options(&self) -> Result<(Option<String>, String, String, u32)> {
+        match self {
+            ComposeFileReference::Short(_) => Ok((None, "0".into(), "0".into(), 0o444)),
+            ComposeFileReference::Long {
+                target,
+                uid,
+                gid,
+                mode,
+                ..
+            } => Ok((
+                target.clone(),
+                uid.clone().unwrap_or_else(|| "0".into()),
+                gid.clone().unwrap_or_else(|| "0".into()),
+                mode.as_ref().map(parse_mode).transpose()?.unwrap_or(0o444),
+            )),
+        }
+    }
+}
+
+/// Parses a file mode. YAML doesn't treat numbers with a leading zero as octal, so these are
+/// received as strings.
+fn parse_mode(mode: &Scalar) -> Result<u32> {
+    match mode {
+        Scalar::Int(n) => u32::try_from(*n).ok(),
+        Scalar::String(s) => match s.trim_start_matches("0o").trim_start_matches('0') {
+            "" => Some(0),
+            digits => u32::from_str_radix(digits, 8).ok(),
+        },
+        _ => None,
+    }
+    .ok_or_else(|| Error::InvalidCompose(format!("invalid file mode `{mode}`")))
+}
+
+impl ComposeVolume {
+    fn to_mount(&self, namespace: &str) -> Result<models::Mount> {
+        let (type_, source, target, read_only) = match self {
+            ComposeVolume::Short(volume) => {
+                let parts: Vec<_> = volume.split(':').collect();
+                match parts.as_slice() {
+                    [target] => (None, None, target.to_string(), false),
+                    [source, target] => (None, Some(source.to_string()), target.to_string(), false),
+                    [source, target, mode] => (
+                        None,
+                        Some(source.to_string()),
+                        target.to_string(),
+                        mode.split(',').any(|m| m == "ro"),
+                    ),
+                    _ => return Err(Error::InvalidCompose(format!("invalid volume `{volume}`"))),
+                }
+            }
+            ComposeVolume::Long {
+                type_,
+                source,
+                target,
+                read_only,
+            } => (type_.clone(), source.clone(), target.clone(), *read_only),
+        };
+        let is_path = |s: &str| s.starts_with('/') || s.starts_with('.') || s.starts_with('~');
+        let type_ = type_.unwrap_or_else(|| match &source {
+            Some(source) if is_path(source) => "bind".into(),
+            _ => "volume".into(),
+        });
+        let source = match (type_.as_str(), source) {
+            ("volume", Some(source)) => Some(format!("{namespace}_{source}")),
+            (_, source) => source,
+        };
+        Ok(models::Mount {
+            bind_options: None,
+            consistency: None,
+            read_only: Some(read_only),
+            source,
+            target: Some(target),
+            tmpfs_options: None,
+            type_: Some(type_),
+            volume_options: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const COMPOSE: &str = r#"
+version: "3.8"
+services:
+  web:
+    image: nginx:alpine
+    command: nginx -g "daemon off;"
+    environment:
+      MODE: production
+      WORKERS: 4
+    ports:
+      - "8080:80"
+      - target: 443
+        published: 8443
+        protocol: tcp
+        mode: host
+    networks:
+      - frontend
+    secrets:
+      - source: tls_key
+        target: /run/secrets/key
+        mode: 0400
+    volumes:
+      - data:/usr/share/nginx/html:ro
+      - /var/log/nginx:/var/log/nginx
+    stop_grace_period: 1m30s
+    deploy:
+      replicas: 2
+      resources:
+        limits:
+          cpus: "0.5"
+          memory: 128M
+      restart_policy:
+        condition: on-failure
+        delay: 5s
+      placement:
+        constraints: [node.role == worker]
+        preferences:
+          - spread: node.labels.zone
+      update_config:
+        parallelism: 1
+        order: start-first
+networks:
+  frontend:
+    driver: overlay
+secrets:
+  tls_key:
+    file: ./key.pem
+volumes:
+  data:
+"#;
+
+    #[test]
+    fn parse_compose_file() {
+        let compose: ComposeFile = COMPOSE.parse().unwrap();
+        assert_eq!(compose.version.as_deref(), Some("3.8"));
+        let web = &compose.services["web"];
+        assert_eq!(
+            web.command.as_ref().unwrap().to_list(),
+            vec!["nginx", "-g", "daemon off;"]
+        );
+        assert_eq!(
+            web.environment.to_list(),
+            vec!["MODE=production", "WORKERS=4"]
+        );
+        assert_eq!(
+            compose.networks["frontend"]
+                .as_ref()
+                .unwrap()
+                .driver
+                .as_deref(),
+            Some("overlay")
+        );
+        assert!(compose.volumes.contains_key("data"));
+
+        let networks = [("frontend".to_string(), "app_frontend".to_string())].into();
+        let secrets: ObjectIds = [(
+            "tls_key".to_string(),
+            ("s1".to_string(), "app_tls_key".to_string()),
+        )]
+        .into();
+        let opts = web
+            .to_service_opts("app", "web", &networks, &secrets, &ObjectIds::new())
+            .unwrap();
+        let value: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+        assert_eq!(value["Name"], json!("app_web"));
+        assert_eq!(value["Labels"][STACK_NAMESPACE_LABEL], json!("app"));
+        assert_eq!(value["Mode"], json!({"Replicated": {"Replicas": 2}}));
+        let task = &value["TaskTemplate"];
+        assert_eq!(
+            task["ContainerSpec"]["Args"],
+            json!(["nginx", "-g", "daemon off;"])
+        );
+        assert_eq!(
+            task["ContainerSpec"]["StopGracePeriod"],
+            json!(90_000_000_000i64)
+        );
+        assert_eq!(
+            task["ContainerSpec"]["Secrets"],
+            json!([{
+                "File": {"Name": "/run/secrets/key", "UID": "0", "GID": "0", "Mode": 0o400},
+                "SecretID": "s1",
+                "SecretName": "app_tls_key"
+            }])
+        );
+        assert_eq!(
+            task["ContainerSpec"]["Mounts"][0]["Source"],
+            json!("app_data")
+        );
+        assert_eq!(task["ContainerSpec"]["Mounts"][0]["Type"], json!("volume"));
+        assert_eq!(task["ContainerSpec"]["Mounts"][0]["ReadOnly"], json!(true));
+        assert_eq!(task["ContainerSpec"]["Mounts"][1]["Type"], json!("bind"));
+        assert_eq!(task["Networks"][0]["Target"], json!("app_frontend"));
+        assert_eq!(task["Resources"]["Limits"]["NanoCPUs"], json!(500_000_000));
+        assert_eq!(
+            task["Resources"]["Limits"]["MemoryBytes"],
+            json!(128 * 1024 * 1024)
+        );
+        assert_eq!(task["RestartPolicy"]["Condition"], json!("on-failure"));
+        assert_eq!(
+            task["Placement"]["Constraints"],
+            json!(["node.role == worker"])
+        );
+        assert_eq!(
+            value["EndpointSpec"]["Ports"],
+            json!([
+                {"Protocol": "tcp", "PublishedPort": 8080, "TargetPort": 80},
+                {"Protocol": "tcp", "PublishMode": "host", "PublishedPort": 8443, "TargetPort": 443}
+            ])
+        );
+        assert_eq!(
+            value["UpdateConfig"],
+            json!({"Parallelism": 1, "Order": "start-first"})
+        );
+    }
+
+    #[test]
+    fn undefined_secret() {
+        let compose: ComposeFile = COMPOSE.parse().unwrap();
+        assert!(matches!(
+            compose.services["web"].to_service_opts(
+                "app",
+                "web",
+                &[("frontend".to_string(), "app_frontend".to_string())].into(),
+                &ObjectIds::new(),
+                &ObjectIds::new()
+            ),
+            Err(Error::InvalidCompose(_))
+        ));
+    }
+
+    #[test]
+    fn durations_and_sizes() {
+        assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("0").unwrap(), Duration::ZERO);
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("5x").is_err());
+
+        assert_eq!(parse_bytes("1024").unwrap(), 1024);
+        assert_eq!(parse_bytes("512m").unwrap(), 512 << 20);
+        assert_eq!(parse_bytes("1.5GB").unwrap(), 3 << 29);
+        assert!(parse_bytes("12q").is_err());
+
+        assert_eq!(parse_mode(&Scalar::String("0440".into())).unwrap(), 0o440);
+        assert_eq!(parse_mode(&Scalar::Int(0o400)).unwrap(), 0o400);
+        assert!(parse_mode(&Scalar::String("0999".into())).is_err());
+    }
+}
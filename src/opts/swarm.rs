@@ -1,5 +1,68 @@
 use crate::models::SwarmSpec;
-use containers_api::{impl_field, impl_opts_builder, impl_str_field, impl_vec_field};
+use containers_api::{impl_field, impl_str_field, impl_url_bool_field, impl_vec_field};
+
+use serde::{Serialize, Serializer};
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+/// Address of a swarm node used as a listen, advertise or data path address.
+///
+/// The host part can be an IP address, a hostname or the name of a network interface like `eth0`.
+/// Values of this type can be passed to any of the address fields of
+/// [`SwarmInitOptsBuilder`](SwarmInitOptsBuilder) and [`SwarmJoinOptsBuilder`](SwarmJoinOptsBuilder).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwarmAddr {
+    host: String,
+    port: Option<u16>,
+}
+
+impl SwarmAddr {
+    /// Default port used by swarm managers for cluster management traffic.
+    pub const DEFAULT_PORT: u16 = 2377;
+
+    /// Creates a new address from an IP address, a hostname or an interface name without a port.
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+        }
+    }
+
+    /// Sets the port of this address.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+}
+
+impl From<IpAddr> for SwarmAddr {
+    fn from(ip: IpAddr) -> Self {
+        Self::new(ip.to_string())
+    }
+}
+
+impl From<SocketAddr> for SwarmAddr {
+    fn from(addr: SocketAddr) -> Self {
+        Self::from(addr.ip()).port(addr.port())
+    }
+}
+
+impl fmt::Display for SwarmAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) if self.host.contains(':') => write!(f, "[{}]:{port}", self.host),
+            Some(port) => write!(f, "{}:{port}", self.host),
+            None => f.write_str(&self.host),
+        }
+    }
+}
+
+impl Serialize for SwarmAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
 
 impl_opts_builder!(json => SwarmJoin);
 
@@ -11,10 +74,10 @@ impl SwarmJoinOptsBuilder {
     );
 
     impl_str_field!(
-        // Externally reachable address advertised to other nodes. This can either be an address/port
-        // combination in the form 192.168.1.1:4567, or an interface followed by a port number, like eth0:4567.
-        // If the port number is omitted, the port number from the listen address is used. If AdvertiseAddr is
-        // not specified, it will be automatically detected when possible.
+        /// Externally reachable address advertised to other nodes. This can either be an address/port
+        /// combination in the form 192.168.1.1:4567, or an interface followed by a port number, like eth0:4567.
+        /// If the port number is omitted, the port number from the listen address is used. If AdvertiseAddr is
+        /// not specified, it will be automatically detected when possible.
         advertise_addr => "AdvertiseAddr"
     );
 
@@ -38,16 +101,16 @@ impl_opts_builder!(json => SwarmInit);
 
 impl SwarmInitOptsBuilder {
     impl_str_field!(
-        // Listen address used for inter-manager communication if the node gets promoted to manager,
-        // as well as determining the networking interface used for the VXLAN Tunnel Endpoint (VTEP).
+        /// Listen address used for inter-manager communication, as well as determining the networking
+        /// interface used for the VXLAN Tunnel Endpoint (VTEP).
         listen_addr => "ListenAddr"
     );
 
     impl_str_field!(
-        // Externally reachable address advertised to other nodes. This can either be an address/port
-        // combination in the form 192.168.1.1:4567, or an interface followed by a port number, like eth0:4567.
-        // If the port number is omitted, the port number from the listen address is used. If AdvertiseAddr is
-        // not specified, it will be automatically detected when possible.
+        /// Externally reachable address advertised to other nodes. This can either be an address/port
+        /// combination in the form 192.168.1.1:4567, or an interface followed by a port number, like eth0:4567.
+        /// If the port number is omitted, the port number from the listen address is used. If AdvertiseAddr is
+        /// not specified, it will be automatically detected when possible.
         advertise_addr => "AdvertiseAddr"
     );
 
@@ -57,13 +120,13 @@ impl SwarmInitOptsBuilder {
     );
 
     impl_field!(
-        // Specifies the data path port number for data traffic. Acceptable port range is 1024 to 49151.
-        // If no port is set or is set to 0, default port 4789 will be used.
+        /// Specifies the data path port number for data traffic. Acceptable port range is 1024 to 49151.
+        /// If no port is set or is set to 0, default port 4789 will be used.
         data_path_port: u32 => "DataPathPort"
     );
 
     impl_vec_field!(
-        // Default Address Pool specifies default subnet pools for global scope networks.
+        /// Default Address Pool specifies default subnet pools for global scope networks.
         default_addr_pool => "DefaultAddrPool"
     );
 
@@ -73,7 +136,7 @@ impl SwarmInitOptsBuilder {
     );
 
     impl_field!(
-        // SubnetSize specifies the subnet size of the networks created from the default subnet pool.
+        /// SubnetSize specifies the subnet size of the networks created from the default subnet pool.
         subnet_size: u32 => "SubnetSize"
     );
 
@@ -82,3 +145,80 @@ impl SwarmInitOptsBuilder {
         spec: SwarmSpec => "Spec"
     );
 }
+
+impl_opts_builder!(url => SwarmUpdate);
+
+impl SwarmUpdateOptsBuilder {
+    impl_url_bool_field!(
+        /// Rotate the worker join token.
+        rotate_worker_token => "rotateWorkerToken"
+    );
+
+    impl_url_bool_field!(
+        /// Rotate the manager join token.
+        rotate_manager_token => "rotateManagerToken"
+    );
+
+    impl_url_bool_field!(
+        /// Rotate the manager unlock key.
+        rotate_manager_unlock_key => "rotateManagerUnlockKey"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swarm_addr_display() {
+        assert_eq!(SwarmAddr::new("eth0").to_string(), "eth0");
+        assert_eq!(
+            SwarmAddr::new("eth0")
+                .port(SwarmAddr::DEFAULT_PORT)
+                .to_string(),
+            "eth0:2377"
+        );
+        let addr: SocketAddr = "192.168.1.1:4567".parse().unwrap();
+        assert_eq!(SwarmAddr::from(addr).to_string(), "192.168.1.1:4567");
+        let addr: SocketAddr = "[::1]:2377".parse().unwrap();
+        assert_eq!(SwarmAddr::from(addr).to_string(), "[::1]:2377");
+    }
+
+    #[test]
+    fn swarm_init_opts() {
+        let opts = SwarmInitOpts::builder()
+            .listen_addr(SwarmAddr::new("0.0.0.0").port(SwarmAddr::DEFAULT_PORT))
+            .advertise_addr(SwarmAddr::new("eth0"))
+            .force_new_cluster(true)
+            .build();
+        assert_eq!(
+            opts.serialize().unwrap(),
+            r#"{"AdvertiseAddr":"eth0","ForceNewCluster":true,"ListenAddr":"0.0.0.0:2377"}"#
+        );
+    }
+
+    #[test]
+    fn swarm_join_opts() {
+        let opts = SwarmJoinOpts::builder()
+            .remote_addrs([SwarmAddr::new("10.0.0.1").port(2377)])
+            .join_token("SWMTKN-1-abc")
+            .build();
+        assert_eq!(
+            opts.serialize().unwrap(),
+            r#"{"JoinToken":"SWMTKN-1-abc","RemoteAddrs":["10.0.0.1:2377"]}"#
+        );
+    }
+
+    #[test]
+    fn swarm_update_opts() {
+        let opts = SwarmUpdateOpts::builder()
+            .rotate_worker_token(true)
+            .rotate_manager_unlock_key(true)
+            .build();
+        assert_eq!(
+            opts.serialize().unwrap(),
+            "rotateManagerUnlockKey=true&rotateWorkerToken=true"
+        );
+        assert!(SwarmUpdateOpts::default().serialize().is_none());
+    }
+}
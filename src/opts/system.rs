@@ -1,73 +1,83 @@
-use containers_api::impl_opts_builder;
-use std::collections::HashMap;
+use containers_api::conn::Headers;
+use containers_api::opts::{Filter, FilterItem};
 
-/// Opts for filtering streams of Docker events
-#[derive(Default, Debug)]
-pub struct EventsOpts {
-    params: HashMap<&'static str, String>,
-}
+use crate::opts::{api_v1, ParamVersions};
 
-impl EventsOpts {
-    pub fn builder() -> EventsOptsBuilder {
-        EventsOptsBuilder::default()
-    }
+use std::time::Duration;
 
-    /// serialize Opts as a string. returns None if no Opts are defined
-    pub fn serialize(&self) -> Option<String> {
-        if self.params.is_empty() {
-            None
-        } else {
-            Some(containers_api::url::encoded_pairs(&self.params))
-        }
-    }
-}
+impl_opts_builder!(url =>
+    /// Opts for filtering streams of Docker events
+    Events
+);
 
 #[derive(Copy, Clone)]
+/// Types of objects reporting events, used with [`EventFilter::Type`](EventFilter::Type).
 pub enum EventFilterType {
+    Builder,
+    Config,
     Container,
+    Daemon,
     Image,
-    Volume,
     Network,
-    Daemon,
+    Node,
+    Plugin,
+    Secret,
+    Service,
+    Volume,
 }
 
 impl AsRef<str> for EventFilterType {
     fn as_ref(&self) -> &str {
         match &self {
+            EventFilterType::Builder => "builder",
+            EventFilterType::Config => "config",
             EventFilterType::Container => "container",
+            EventFilterType::Daemon => "daemon",
             EventFilterType::Image => "image",
-            EventFilterType::Volume => "volume",
             EventFilterType::Network => "network",
-            EventFilterType::Daemon => "daemon",
+            EventFilterType::Node => "node",
+            EventFilterType::Plugin => "plugin",
+            EventFilterType::Secret => "secret",
+            EventFilterType::Service => "service",
+            EventFilterType::Volume => "volume",
         }
     }
 }
 
 /// An enumartion used to filter system events.
 pub enum EventFilter {
-    // TODO: use the Filter trait for this enum
+    /// Container name or ID.
     Container(String),
+    /// Event action, for example `start` or `die`.
     Event(String),
+    /// Image name or ID.
     Image(String),
+    /// Label in the form of `key` or `key=val`.
     Label(String),
+    /// Type of the object reporting the event.
     Type(EventFilterType),
+    /// Volume name.
     Volume(String),
+    /// Network name or ID.
     Network(String),
+    /// Daemon name or ID.
     Daemon(String),
 }
 
-#[derive(Default)]
-/// Builder interface for [`EventOpts`](EventOpts).
-pub struct EventsOptsBuilder {
-    params: HashMap<&'static str, String>,
-    events: Vec<String>,
-    containers: Vec<String>,
-    images: Vec<String>,
-    labels: Vec<String>,
-    volumes: Vec<String>,
-    networks: Vec<String>,
-    daemons: Vec<String>,
-    types: Vec<String>,
+impl Filter for EventFilter {
+    fn query_item(&self) -> FilterItem {
+        use EventFilter::*;
+        match &self {
+            Container(container) => FilterItem::new("container", container.to_owned()),
+            Event(event) => FilterItem::new("event", event.to_owned()),
+            Image(image) => FilterItem::new("image", image.to_owned()),
+            Label(label) => FilterItem::new("label", label.to_owned()),
+            Type(type_) => FilterItem::new("type", type_.as_ref().to_owned()),
+            Volume(volume) => FilterItem::new("volume", volume.to_owned()),
+            Network(network) => FilterItem::new("network", network.to_owned()),
+            Daemon(daemon) => FilterItem::new("daemon", daemon.to_owned()),
+        }
+    }
 }
 
 impl EventsOptsBuilder {
@@ -107,57 +117,119 @@ impl EventsOptsBuilder {
         self
     }
 
-    /// Filter the events by a list of event filters.
-    pub fn filter(mut self, filters: Vec<EventFilter>) -> Self {
-        let mut params = HashMap::new();
-        for f in filters {
-            match f {
-                EventFilter::Container(n) => {
-                    self.containers.push(n);
-                    params.insert("container", self.containers.clone())
-                }
-                EventFilter::Event(n) => {
-                    self.events.push(n);
-                    params.insert("event", self.events.clone())
-                }
-                EventFilter::Image(n) => {
-                    self.images.push(n);
-                    params.insert("image", self.images.clone())
-                }
-                EventFilter::Label(n) => {
-                    self.labels.push(n);
-                    params.insert("label", self.labels.clone())
-                }
-                EventFilter::Volume(n) => {
-                    self.volumes.push(n);
-                    params.insert("volume", self.volumes.clone())
-                }
-                EventFilter::Network(n) => {
-                    self.networks.push(n);
-                    params.insert("network", self.networks.clone())
-                }
-                EventFilter::Daemon(n) => {
-                    self.daemons.push(n);
-                    params.insert("daemon", self.daemons.clone())
-                }
-                EventFilter::Type(n) => {
-                    self.types.push(n.as_ref().to_string());
-                    params.insert("type", self.types.clone())
-                }
-            };
-        }
-        self.params.insert(
-            "filters",
-            serde_json::to_string(&params).unwrap_or_default(),
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    /// Only return events since this time.
+    pub fn since_time(mut self, timestamp: &time::OffsetDateTime) -> Self {
+        self.params
+            .insert("since", timestamp.unix_timestamp().to_string());
+        self
+    }
+
+    #[cfg(feature = "time")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+    /// Only return events before this time.
+    pub fn until_time(mut self, timestamp: &time::OffsetDateTime) -> Self {
+        self.params
+            .insert("until", timestamp.unix_timestamp().to_string());
+        self
+    }
+
+    /// Only return events since this UNIX timestamp. Available regardless of the `chrono`
+    /// feature.
+    pub fn since_timestamp(mut self, timestamp: i64) -> Self {
+        self.params.insert("since", timestamp.to_string());
+        self
+    }
+
+    /// Only return events before this UNIX timestamp. Available regardless of the `chrono`
+    /// feature.
+    pub fn until_timestamp(mut self, timestamp: i64) -> Self {
+        self.params.insert("until", timestamp.to_string());
+        self
+    }
+
+    impl_filter_func!(
+        /// Filter the events by a list of event filters.
+        EventFilter
+    );
+}
+
+impl EventsOpts {
+    pub(crate) fn has_until(&self) -> bool {
+        self.params.contains_key("until")
+    }
+
+    /// Returns a copy of these options that only returns events after the event with the given
+    /// timestamp in nanoseconds.
+    pub(crate) fn resume_after(&self, time_nano: i64) -> Self {
+        let mut opts = self.clone();
+        let next = time_nano + 1;
+        opts.params.insert(
+            "since",
+            format!("{}.{:09}", next / 1_000_000_000, next % 1_000_000_000),
         );
+        opts
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Policy used by [`Docker::events_resumable`](crate::Docker::events_resumable) to decide
+/// whether and when to re-subscribe to the events after a disconnect.
+pub struct EventsRetryPolicy {
+    max_retries: Option<usize>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for EventsRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: None,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl EventsRetryPolicy {
+    /// Create a new policy that retries forever with an exponential backoff starting at 500ms
+    /// and capped at 30s.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of consecutive reconnection attempts. The counter is reset every time an
+    /// event is received.
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = Some(max_retries);
         self
     }
 
-    /// Build the final event options.
-    pub fn build(self) -> EventsOpts {
-        EventsOpts {
-            params: self.params,
+    /// Delay before the first reconnection attempt. Each following attempt doubles the delay.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Upper bound of the delay between reconnection attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Returns the delay before the given attempt, starting at 0, or `None` if no more attempts
+    /// should be made.
+    pub(crate) fn backoff(&self, attempt: usize) -> Option<Duration> {
+        if self.max_retries.is_some_and(|max| attempt >= max) {
+            return None;
         }
+        let factor = 2u32.saturating_pow(attempt.min(31) as u32);
+        Some(
+            self.initial_backoff
+                .saturating_mul(factor)
+                .min(self.max_backoff),
+        )
     }
 }
 
@@ -181,7 +253,12 @@ impl AsRef<str> for DataUsageType {
 }
 
 impl_opts_builder!(url => SystemDataUsage);
+impl SystemDataUsageOpts {
+    pub(crate) const PARAM_VERSIONS: ParamVersions = &[("type", api_v1(42))];
+}
+
 impl SystemDataUsageOptsBuilder {
+    /// Only calculate the usage of the given object types. Requires API version 1.42.
     pub fn types(mut self, types: impl IntoIterator<Item = DataUsageType>) -> Self {
         self.vec_params.insert(
             "type",
@@ -190,3 +267,200 @@ impl SystemDataUsageOptsBuilder {
         self
     }
 }
+
+#[derive(Clone, Debug)]
+/// Options for starting an interactive session with [`Docker::session`](crate::Docker::session).
+pub struct SessionOpts {
+    id: String,
+    name: Option<String>,
+    shared_key: Option<String>,
+    grpc_methods: Vec<String>,
+}
+
+impl SessionOpts {
+    /// Start a session with a unique `id`, referenced by builds using the session.
+    pub fn builder<I>(id: I) -> SessionOptsBuilder
+    where
+        I: Into<String>,
+    {
+        SessionOptsBuilder {
+            opts: SessionOpts {
+                id: id.into(),
+                name: None,
+                shared_key: None,
+                grpc_methods: Vec::new(),
+            },
+        }
+    }
+
+    pub(crate) fn headers(&self) -> Headers {
+        let mut headers = Headers::single("X-Docker-Expose-Session-Uuid", self.id.as_str());
+        if let Some(name) = &self.name {
+            headers.add("X-Docker-Expose-Session-Name", name.as_str());
+        }
+        if let Some(key) = &self.shared_key {
+            headers.add("X-Docker-Expose-Session-Sharedkey", key.as_str());
+        }
+        for method in &self.grpc_methods {
+            headers.add("X-Docker-Expose-Session-Grpc-Method", method.as_str());
+        }
+        headers
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionOptsBuilder {
+    opts: SessionOpts,
+}
+
+impl SessionOptsBuilder {
+    /// Human readable name of the session.
+    pub fn name<N>(mut self, name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.opts.name = Some(name.into());
+        self
+    }
+
+    /// Key shared with the daemon, used for example to scope the build cache of a client.
+    pub fn shared_key<K>(mut self, key: K) -> Self
+    where
+        K: Into<String>,
+    {
+        self.opts.shared_key = Some(key.into());
+        self
+    }
+
+    /// gRPC methods exposed by the client over the session, like
+    /// `/moby.filesync.v1.FileSync/DiffCopy`.
+    pub fn grpc_methods<M>(mut self, methods: impl IntoIterator<Item = M>) -> Self
+    where
+        M: Into<String>,
+    {
+        self.opts
+            .grpc_methods
+            .extend(methods.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn build(self) -> SessionOpts {
+        self.opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_headers() {
+        let opts = SessionOpts::builder("abc")
+            .name("build")
+            .grpc_methods(["/a.A/One", "/a.A/Two"])
+            .build();
+        assert_eq!(
+            opts.headers().into_iter().collect::<Vec<_>>(),
+            vec![
+                ("X-Docker-Expose-Session-Uuid", "abc".to_string()),
+                ("X-Docker-Expose-Session-Name", "build".to_string()),
+                (
+                    "X-Docker-Expose-Session-Grpc-Method",
+                    "/a.A/One".to_string()
+                ),
+                (
+                    "X-Docker-Expose-Session-Grpc-Method",
+                    "/a.A/Two".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn events_time_window() {
+        let since = chrono::DateTime::<chrono::Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let until = chrono::DateTime::<chrono::Utc>::from_timestamp(1_700_000_600, 0).unwrap();
+        let opts = EventsOpts::builder().since(&since).until(&until).build();
+        assert_eq!(
+            Some("since=1700000000&until=1700000600".to_string()),
+            opts.serialize()
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn events_time_window_time() {
+        let since = time::OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let until = time::OffsetDateTime::from_unix_timestamp(1_700_000_600).unwrap();
+        let opts = EventsOpts::builder()
+            .since_time(&since)
+            .until_time(&until)
+            .build();
+        assert_eq!(
+            Some("since=1700000000&until=1700000600".to_string()),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn events_time_window_timestamps() {
+        let opts = EventsOpts::builder()
+            .since_timestamp(1_700_000_000)
+            .until_timestamp(1_700_000_600)
+            .build();
+        assert_eq!(
+            Some("since=1700000000&until=1700000600".to_string()),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn events_resume_after() {
+        let opts = EventsOpts::builder()
+            .since_timestamp(1_700_000_000)
+            .build()
+            .resume_after(1_700_000_100_999_999_999);
+        assert_eq!(
+            Some("since=1700000101.000000000".to_string()),
+            opts.serialize()
+        );
+        assert!(!opts.has_until());
+    }
+
+    #[test]
+    fn events_retry_policy() {
+        let policy = EventsRetryPolicy::new()
+            .max_retries(5)
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(5));
+        assert_eq!(policy.backoff(0), Some(Duration::from_secs(1)));
+        assert_eq!(policy.backoff(1), Some(Duration::from_secs(2)));
+        assert_eq!(policy.backoff(2), Some(Duration::from_secs(4)));
+        assert_eq!(policy.backoff(3), Some(Duration::from_secs(5)));
+        assert_eq!(policy.backoff(5), None);
+        assert_eq!(
+            EventsRetryPolicy::new().backoff(100),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn events_filters() {
+        let opts = EventsOpts::builder()
+            .filter([
+                EventFilter::Type(EventFilterType::Container),
+                EventFilter::Event("start".into()),
+                EventFilter::Event("die".into()),
+                EventFilter::Label("env=test".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22event%22%3A%5B%22start%22%2C%22die%22%5D%2C%22label%22%3A%5B%22env%3Dtest%22%5D%2C%22type%22%3A%5B%22container%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+}
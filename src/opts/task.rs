@@ -1,9 +1,9 @@
 use containers_api::opts::{Filter, FilterItem};
-use containers_api::{impl_filter_func, impl_opts_builder};
 
 impl_opts_builder!(url => TaskList);
 
 #[derive(Clone, Copy, Debug)]
+/// Desired state of a task used for filtering.
 pub enum TaskStateFilter {
     Running,
     Shutdown,
@@ -20,20 +20,22 @@ impl AsRef<str> for TaskStateFilter {
     }
 }
 
+#[derive(Clone, Debug)]
+/// Filter for listing tasks.
 pub enum TaskFilter {
     /// The state that the task should be in.
     DesiredState(TaskStateFilter),
-    /// The ID of the config.
+    /// The ID of the task.
     Id(String),
     /// Label in the form of `label=key`
     LabelKey(String),
     /// Label in the form of `label=key=val`
     Label(String, String),
-    /// The name of the config.
+    /// The name of the task.
     Name(String),
-    /// Name of the node.
+    /// ID or name of the node.
     Node(String),
-    /// Name of the service.
+    /// ID or name of the service.
     Service(String),
 }
 
@@ -58,3 +60,26 @@ impl TaskListOptsBuilder {
         TaskFilter
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_list_filters() {
+        let opts = TaskListOpts::builder()
+            .filter([
+                TaskFilter::DesiredState(TaskStateFilter::Running),
+                TaskFilter::Service("web".into()),
+                TaskFilter::Label("env".into(), "prod".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22desired-state%22%3A%5B%22running%22%5D%2C%22label%22%3A%5B%22env%3Dprod%22%5D%2C%22service%22%3A%5B%22web%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+}
@@ -1,8 +1,5 @@
 use containers_api::opts::{Filter, FilterItem};
-use containers_api::{
-    impl_field, impl_filter_func, impl_map_field, impl_opts_builder, impl_opts_required_builder,
-    impl_str_field,
-};
+use containers_api::{impl_field, impl_map_field, impl_opts_required_builder, impl_str_field};
 
 impl_opts_builder!(json => VolumeCreate);
 
@@ -59,16 +56,19 @@ impl Filter for VolumeFilter {
             Dangling(dangling) => FilterItem::new("dangling", dangling.to_string()),
             Driver(driver) => FilterItem::new("driver", driver.to_owned()),
             LabelKey(label) => FilterItem::new("label", label.to_owned()),
-            Label { key, val } => FilterItem::new("label", format!("{key}:{val}")),
+            Label { key, val } => FilterItem::new("label", format!("{key}={val}")),
             Name(name) => FilterItem::new("name", name.to_owned()),
         }
     }
 }
 
+/// Filter used to filter pruned volumes, see [`PruneFilter`](crate::opts::PruneFilter).
+pub type VolumePruneFilter = crate::opts::PruneFilter;
+
 impl VolumePruneOptsBuilder {
     impl_filter_func!(
         /// Filter pruned volumes by one of the variants of the filter enum.
-        VolumeFilter
+        VolumePruneFilter
     );
 }
 
@@ -99,3 +99,79 @@ impl ClusterVolumeUpdateOptsBuilder {
         access_mode: serde_json::Value => "AccessMode"
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn volume_list_filters() {
+        let opts = VolumeListOpts::builder()
+            .filter([
+                VolumeFilter::Dangling(false),
+                VolumeFilter::Driver("local".into()),
+                VolumeFilter::LabelKey("a".into()),
+                VolumeFilter::Label {
+                    key: "b".into(),
+                    val: "c".into(),
+                },
+                VolumeFilter::Name("vol1".into()),
+                VolumeFilter::Name("vol2".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22dangling%22%3A%5B%22false%22%5D%2C%22driver%22%3A%5B%22local%22%5D%2C%22label%22%3A%5B%22a%22%2C%22b%3Dc%22%5D%2C%22name%22%3A%5B%22vol1%22%2C%22vol2%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn volume_prune_opts() {
+        let opts = VolumePruneOpts::builder()
+            .filter([
+                VolumePruneFilter::All(true),
+                VolumePruneFilter::Label("env".into(), "test".into()),
+            ])
+            .build();
+        assert_eq!(
+            Some(
+                "filters=%7B%22all%22%3A%5B%22true%22%5D%2C%22label%22%3A%5B%22env%3Dtest%22%5D%7D"
+                    .to_string()
+            ),
+            opts.serialize()
+        );
+    }
+
+    #[test]
+    fn volume_create_opts() {
+        let opts = VolumeCreateOpts::builder()
+            .name("nfs-volume")
+            .driver("local")
+            .driver_opts([
+                ("type", "nfs"),
+                ("o", "addr=10.0.0.1,rw"),
+                ("device", ":/exports/data"),
+            ])
+            .labels([("env", "test")])
+            .build();
+        let serialized: Value = serde_json::from_str(&opts.serialize().unwrap()).unwrap();
+
+        assert_eq!(
+            json!({
+                "Name": "nfs-volume",
+                "Driver": "local",
+                "DriverOpts": {
+                    "type": "nfs",
+                    "o": "addr=10.0.0.1,rw",
+                    "device": ":/exports/data"
+                },
+                "Labels": {"env": "test"}
+            }),
+            serialized
+        );
+    }
+}
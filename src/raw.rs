@@ -0,0 +1,253 @@
+//! Inspect responses kept as raw JSON and deserialized on demand.
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::{Error, Result};
+
+/// Inspect response of a docker object kept as the raw JSON returned by the daemon.
+///
+/// The typed model `T` is only deserialized once [`model`](RawInspect::model) is called, so
+/// callers that need a few fields of a large payload can read them with
+/// [`pointer`](RawInspect::pointer) instead, which skips over everything else without building
+/// it.
+#[derive(Clone, Debug)]
+pub struct RawInspect<T> {
+    raw: Box<RawValue>,
+    model: OnceLock<T>,
+}
+
+impl<T> RawInspect<T> {
+    pub(crate) fn new(raw: Box<RawValue>) -> Self {
+        Self {
+            raw,
+            model: OnceLock::new(),
+        }
+    }
+
+    /// The JSON response as returned by the daemon.
+    pub fn json(&self) -> &str {
+        self.raw.get()
+    }
+
+    /// Returns the raw JSON of the value at `pointer`, a JSON pointer like `/State/Status` as
+    /// defined in RFC 6901, or `None` if there is no such value.
+    pub fn pointer_raw(&self, pointer: &str) -> Result<Option<&RawValue>> {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Ok(None);
+        }
+        let mut value = &*self.raw;
+        for token in pointer.split('/').skip(1) {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            let mut de = serde_json::Deserializer::from_str(value.get());
+            match Lookup(&token).deserialize(&mut de)? {
+                Some(next) => value = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+
+    /// Deserializes the value at `pointer`, see [`pointer_raw`](RawInspect::pointer_raw).
+    /// Returns `None` if there is no such value.
+    pub fn pointer<'a, V>(&'a self, pointer: &str) -> Result<Option<V>>
+    where
+        V: Deserialize<'a>,
+    {
+        self.pointer_raw(pointer)?
+            .map(|value| serde_json::from_str(value.get()).map_err(Error::from))
+            .transpose()
+    }
+}
+
+impl<T: DeserializeOwned> RawInspect<T> {
+    /// Returns the typed model, deserializing it on the first call.
+    pub fn model(&self) -> Result<&T> {
+        if let Some(model) = self.model.get() {
+            return Ok(model);
+        }
+        let model = serde_json::from_str(self.raw.get())?;
+        Ok(self.model.get_or_init(|| model))
+    }
+
+    /// Consumes the response returning the typed model.
+    pub fn into_model(self) -> Result<T> {
+        match self.model.into_inner() {
+            Some(model) => Ok(model),
+            None => serde_json::from_str(self.raw.get()).map_err(Error::from),
+        }
+    }
+}
+
+/// Finds the member named `token` of an object or the element at index `token` of an array,
+/// skipping over all other values.
+struct Lookup<'t>(&'t str);
+
+impl<'de> DeserializeSeed<'de> for Lookup<'_> {
+    type Value = Option<&'de RawValue>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Lookup<'_> {
+    type Value = Option<&'de RawValue>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut found = None;
+        while let Some(matches) = map.next_key_seed(KeyMatches(self.0))? {
+            if matches && found.is_none() {
+                found = Some(map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(found)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let index = self.0.parse::<usize>().ok();
+        let mut found = None;
+        let mut i = 0;
+        loop {
+            if Some(i) == index {
+                match seq.next_element()? {
+                    Some(value) => found = Some(value),
+                    None => break,
+                }
+            } else if seq.next_element::<IgnoredAny>()?.is_none() {
+                break;
+            }
+            i += 1;
+        }
+        Ok(found)
+    }
+
+    fn visit_bool<E>(self, _: bool) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_str<E>(self, _: &str) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+}
+
+/// Compares an object key with the looked up token without allocating it.
+struct KeyMatches<'t>(&'t str);
+
+impl<'de> DeserializeSeed<'de> for KeyMatches<'_> {
+    type Value = bool;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<bool, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl Visitor<'_> for KeyMatches<'_> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object key")
+    }
+
+    fn visit_str<E>(self, key: &str) -> std::result::Result<bool, E> {
+        Ok(key == self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawInspect;
+    use serde_json::value::RawValue;
+
+    const JSON: &str = r#"{
+        "Id": "abc",
+        "State": {"Status": "running", "Pid": 42, "Health": null},
+        "Mounts": [{"Source": "/a"}, {"Source": "/b"}],
+        "Config": {"Labels": {"a/b": "1", "c~d": "2", "e\"f": "3"}}
+    }"#;
+
+    fn inspect() -> RawInspect<crate::models::ContainerInspect200Response> {
+        RawInspect::new(RawValue::from_string(JSON.to_string()).unwrap())
+    }
+
+    #[test]
+    fn pointer() {
+        let inspect = inspect();
+        assert_eq!(inspect.pointer::<&str>("/Id").unwrap(), Some("abc"));
+        assert_eq!(inspect.pointer::<u32>("/State/Pid").unwrap(), Some(42));
+        assert_eq!(
+            inspect.pointer::<Option<String>>("/State/Health").unwrap(),
+            Some(None)
+        );
+        assert_eq!(
+            inspect.pointer::<String>("/Mounts/1/Source").unwrap(),
+            Some("/b".into())
+        );
+        assert_eq!(inspect.pointer::<String>("/Mounts/2/Source").unwrap(), None);
+        assert_eq!(inspect.pointer::<String>("/State/Missing").unwrap(), None);
+        assert_eq!(inspect.pointer::<String>("/Id/Nested").unwrap(), None);
+        assert_eq!(inspect.pointer::<String>("Id").unwrap(), None);
+        assert_eq!(
+            inspect.pointer::<&str>("/Config/Labels/a~1b").unwrap(),
+            Some("1")
+        );
+        assert_eq!(
+            inspect.pointer::<&str>("/Config/Labels/c~0d").unwrap(),
+            Some("2")
+        );
+        assert_eq!(
+            inspect.pointer::<&str>("/Config/Labels/e\"f").unwrap(),
+            Some("3")
+        );
+        assert_eq!(inspect.pointer_raw("").unwrap().unwrap().get(), JSON);
+        assert!(inspect.pointer::<u32>("/Id").is_err());
+    }
+
+    #[test]
+    fn model() {
+        let inspect = inspect();
+        let model = inspect.model().unwrap();
+        assert_eq!(model.id.as_deref(), Some("abc"));
+        assert!(std::ptr::eq(model, inspect.model().unwrap()));
+        assert_eq!(inspect.into_model().unwrap().state.unwrap().pid, Some(42));
+    }
+}
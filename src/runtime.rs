@@ -0,0 +1,50 @@
+//! Async runtime used by the client to drive connections and timers.
+
+use std::{fmt::Debug, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+/// A boxed future run by a [`Runtime`](Runtime).
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Spawns the tasks and creates the timers needed by a client, set with
+/// [`DockerBuilder::runtime`](crate::docker::DockerBuilder::runtime). Clients use
+/// [`TokioRuntime`](TokioRuntime) by default.
+///
+/// Implementing this trait moves the tasks and timers of the client to another runtime like
+/// async-std or smol. Only tokio is provided by this crate and the IO isn't abstracted: the
+/// built-in connectors rely on tokio and a connector passed to
+/// [`DockerBuilder::connector`](crate::docker::DockerBuilder::connector) has to return
+/// connections implementing the IO traits of tokio, as required by hyper.
+pub trait Runtime: Debug + Send + Sync + 'static {
+    /// Runs `future` in the background, used to drive the connections to the daemon.
+    fn spawn(&self, future: BoxFuture);
+
+    /// Returns a future completing after `duration`, used for timeouts and delays.
+    fn sleep(&self, duration: Duration) -> BoxFuture;
+}
+
+/// The [tokio](https://tokio.rs) runtime, used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture) {
+        tokio::spawn(future);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Spawns the background tasks of hyper on a [`Runtime`](Runtime).
+#[derive(Clone)]
+pub(crate) struct Executor(pub(crate) Arc<dyn Runtime>);
+
+impl<F> hyper::rt::Executor<F> for Executor
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    fn execute(&self, future: F) {
+        self.0.spawn(Box::pin(future))
+    }
+}
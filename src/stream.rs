@@ -1,9 +1,382 @@
+use bytes::{Bytes, BytesMut};
 use containers_api::conn::tty;
 use containers_api::conn::Payload;
-use futures_util::{AsyncRead, AsyncWrite};
+use flate2::{write::GzEncoder, Compression};
+use futures_util::{AsyncRead, AsyncReadExt, AsyncWrite};
 use hyper::Body;
 
-use crate::{Docker, Result};
+use crate::{
+    cancel::Waiter,
+    conn::{BoxFuture, CancellationToken, Runtime},
+    Docker, Error, Result,
+};
+
+use futures_util::future::{abortable, AbortHandle};
+use futures_util::{ready, Future, FutureExt, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Liveness check of a long-lived stream. Once no data was received for the idle period the
+/// daemon is pinged, the stream is considered stalled if it doesn't answer within another idle
+/// period.
+pub(crate) struct Liveness {
+    docker: Docker,
+    idle: Duration,
+    timer: BoxFuture,
+    ping: Option<Pin<Box<dyn Future<Output = bool> + Send>>>,
+}
+
+impl Liveness {
+    pub(crate) fn new(docker: Docker, idle: Duration) -> Self {
+        let timer = docker.runtime().sleep(idle);
+        Liveness {
+            docker,
+            idle,
+            timer,
+            ping: None,
+        }
+    }
+
+    /// Restarts the idle period after data was received.
+    fn reset(&mut self) {
+        self.timer = self.docker.runtime().sleep(self.idle);
+        self.ping = None;
+    }
+
+    /// Polled while the stream has no data, completes with an error once it stalled.
+    fn poll_stalled(&mut self, cx: &mut Context<'_>) -> Poll<Error> {
+        loop {
+            if let Some(ping) = &mut self.ping {
+                if ready!(ping.as_mut().poll(cx)) {
+                    self.reset();
+                } else {
+                    return Poll::Ready(Error::StreamStalled(self.idle));
+                }
+            }
+            ready!(self.timer.as_mut().poll(cx));
+            let docker = self.docker.clone().with_timeout(self.idle);
+            self.ping = Some(Box::pin(async move { docker.get("/_ping").await.is_ok() }));
+        }
+    }
+}
+
+/// A stream ending with [`Error::StreamStalled`](Error::StreamStalled) if its [`Liveness`]
+/// check fails or with [`Error::Cancelled`](Error::Cancelled) once its cancellation token is
+/// cancelled. The inner stream, and with it the connection, is dropped as soon as it ends.
+pub(crate) struct Watched<S> {
+    stream: Option<Pin<Box<S>>>,
+    liveness: Option<Liveness>,
+    cancellation: Option<Waiter>,
+}
+
+impl<S> Watched<S> {
+    pub(crate) fn new(
+        stream: S,
+        liveness: Option<Liveness>,
+        cancellation: Option<CancellationToken>,
+    ) -> Self {
+        Watched {
+            stream: Some(Box::pin(stream)),
+            liveness,
+            cancellation: cancellation.as_ref().map(CancellationToken::waiter),
+        }
+    }
+}
+
+impl<S, T> Stream for Watched<S>
+where
+    S: Stream<Item = Result<T>>,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let Some(stream) = &mut this.stream else {
+            return Poll::Ready(None);
+        };
+        if let Some(waiter) = &mut this.cancellation {
+            if waiter.poll_cancelled(cx) {
+                this.stream = None;
+                return Poll::Ready(Some(Err(Error::Cancelled)));
+            }
+        }
+        match stream.as_mut().poll_next(cx) {
+            Poll::Ready(item) => {
+                if let Some(liveness) = &mut this.liveness {
+                    liveness.reset();
+                }
+                Poll::Ready(item)
+            }
+            Poll::Pending => match &mut this.liveness {
+                Some(liveness) => {
+                    let e = ready!(liveness.poll_stalled(cx));
+                    this.stream = None;
+                    Poll::Ready(Some(Err(e)))
+                }
+                None => Poll::Pending,
+            },
+        }
+    }
+}
+
+/// A connection upgraded by the daemon, adapted to the `futures` IO traits. The connection is
+/// closed once its cancellation token is cancelled.
+pub(crate) struct Upgraded {
+    io: Option<hyper::upgrade::Upgraded>,
+    liveness: Option<Liveness>,
+    cancellation: Option<Waiter>,
+}
+
+impl Upgraded {
+    pub(crate) fn new(
+        io: hyper::upgrade::Upgraded,
+        liveness: Option<Liveness>,
+        cancellation: Option<CancellationToken>,
+    ) -> Self {
+        Upgraded {
+            io: Some(io),
+            liveness,
+            cancellation: cancellation.as_ref().map(CancellationToken::waiter),
+        }
+    }
+
+    /// Returns the connection unless it was cancelled.
+    fn io(&mut self, cx: &mut Context<'_>) -> io::Result<&mut hyper::upgrade::Upgraded> {
+        if let Some(waiter) = &mut self.cancellation {
+            if waiter.poll_cancelled(cx) {
+                self.io = None;
+            }
+        }
+        self.io
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::ConnectionAborted, Error::Cancelled))
+    }
+}
+
+impl AsyncRead for Upgraded {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        let io = self.io(cx)?;
+        match tokio::io::AsyncRead::poll_read(Pin::new(io), cx, &mut buf) {
+            Poll::Ready(Ok(())) => {
+                if let Some(liveness) = &mut self.liveness {
+                    liveness.reset();
+                }
+                Poll::Ready(Ok(buf.filled().len()))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => match &mut self.liveness {
+                Some(liveness) => {
+                    let e = ready!(liveness.poll_stalled(cx));
+                    Poll::Ready(Err(io::Error::new(io::ErrorKind::TimedOut, e)))
+                }
+                None => Poll::Pending,
+            },
+        }
+    }
+}
+
+impl AsyncWrite for Upgraded {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(self.io(cx)?), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(Pin::new(self.io(cx)?), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match &mut self.io {
+            Some(io) => tokio::io::AsyncWrite::poll_shutdown(Pin::new(io), cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+/// Item of a [`BoundedStream`](BoundedStream).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Buffered<T> {
+    Item(T),
+    /// The consumer didn't keep up with the daemon and this many of the oldest items were
+    /// dropped from the buffer.
+    Lagged(u64),
+}
+
+struct Shared<T> {
+    items: VecDeque<Result<T>>,
+    lagged: u64,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+/// A stream read from the daemon in the background into a buffer of bounded capacity.
+///
+/// Reading never waits for the consumer. Once the buffer is full the oldest items are dropped
+/// and the consumer receives [`Buffered::Lagged`](Buffered::Lagged) with their number before
+/// the next item. Dropping the stream stops reading.
+pub struct BoundedStream<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    abort: AbortHandle,
+}
+
+/// Producing half of a [`BoundedStream`](BoundedStream), ends the stream when dropped.
+pub(crate) struct BoundedSender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    capacity: usize,
+}
+
+impl<T> BoundedSender<T> {
+    pub(crate) fn push(&self, item: Result<T>) {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if shared.items.len() >= self.capacity {
+            shared.items.pop_front();
+            shared.lagged += 1;
+        }
+        shared.items.push_back(item);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        shared.done = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: Send + 'static> BoundedStream<T> {
+    /// Runs `produce` on `runtime` with a sender holding up to `capacity` items.
+    pub(crate) fn spawn<F, Fut>(runtime: &dyn Runtime, capacity: usize, produce: F) -> Self
+    where
+        F: FnOnce(BoundedSender<T>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            items: VecDeque::new(),
+            lagged: 0,
+            done: false,
+            waker: None,
+        }));
+        let sender = BoundedSender {
+            shared: shared.clone(),
+            capacity: capacity.max(1),
+        };
+        let (future, abort) = abortable(produce(sender));
+        runtime.spawn(Box::pin(future.map(|_| ())));
+        BoundedStream { shared, abort }
+    }
+
+    /// Reads `stream` on `runtime` into a buffer of `capacity` items.
+    pub(crate) fn forward<S>(runtime: &dyn Runtime, capacity: usize, stream: S) -> Self
+    where
+        S: Stream<Item = Result<T>> + Send + 'static,
+    {
+        Self::spawn(runtime, capacity, |sender| async move {
+            futures_util::pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                sender.push(item);
+            }
+        })
+    }
+}
+
+impl<T> Stream for BoundedStream<T> {
+    type Item = Result<Buffered<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if shared.lagged > 0 {
+            let lagged = std::mem::take(&mut shared.lagged);
+            return Poll::Ready(Some(Ok(Buffered::Lagged(lagged))));
+        }
+        match shared.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item.map(Buffered::Item))),
+            None if shared.done => Poll::Ready(None),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for BoundedStream<T> {
+    fn drop(&mut self) {
+        self.abort.abort();
+    }
+}
+
+/// Size of the chunks read from readers sent as request bodies.
+const READ_CHUNK_LEN: usize = 64 * 1024;
+
+/// Reads `reader` in chunks so it can be sent as a request body without buffering it whole. The
+/// stream ends after the first error.
+pub(crate) fn read_chunks<R>(reader: R) -> impl Stream<Item = Result<Bytes>> + Send
+where
+    R: AsyncRead + Send + 'static,
+{
+    futures_util::stream::unfold(Some(Box::pin(reader)), |reader| async move {
+        let mut reader = reader?;
+        let mut buf = BytesMut::zeroed(READ_CHUNK_LEN);
+        match reader.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(buf.freeze()), Some(reader)))
+            }
+            Err(e) => Some((Err(Error::IO(e)), None)),
+        }
+    })
+}
+
+/// Compresses `chunks` with gzip as they are produced, emitting the compressed output whenever
+/// the encoder flushes a block. The stream ends after the first error.
+pub(crate) fn gzip<S>(chunks: S) -> impl Stream<Item = Result<Bytes>> + Send
+where
+    S: Stream<Item = Result<Bytes>> + Send + 'static,
+{
+    let encoder = GzEncoder::new(Vec::new(), Compression::default());
+    futures_util::stream::unfold(Some((Box::pin(chunks), encoder)), |state| async move {
+        let (mut chunks, mut encoder) = state?;
+        loop {
+            match chunks.next().await {
+                Some(Ok(chunk)) => {
+                    if let Err(e) = encoder.write_all(&chunk) {
+                        return Some((Err(Error::IO(e)), None));
+                    }
+                    if !encoder.get_ref().is_empty() {
+                        let compressed = Bytes::from(std::mem::take(encoder.get_mut()));
+                        return Some((Ok(compressed), Some((chunks, encoder))));
+                    }
+                }
+                Some(Err(e)) => return Some((Err(e), None)),
+                None => {
+                    return match encoder.finish() {
+                        Ok(rest) => Some((Ok(Bytes::from(rest)), None)),
+                        Err(e) => Some((Err(Error::IO(e)), None)),
+                    }
+                }
+            }
+        }
+    })
+}
 
 /// Attaches a multiplexed TCP stream to the container that can be used to read Stdout, Stderr and write Stdin.
 async fn attach_raw(
@@ -28,3 +401,90 @@ pub async fn attach(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{gzip, read_chunks, BoundedStream, Buffered, READ_CHUNK_LEN};
+    use crate::conn::TokioRuntime;
+    use futures_util::TryStreamExt;
+
+    #[test]
+    fn read_in_chunks() {
+        let data = (0..READ_CHUNK_LEN * 2 + 100)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let chunks = futures::executor::block_on(
+            read_chunks(futures_util::io::Cursor::new(data.clone())).try_collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(
+            chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            [READ_CHUNK_LEN, READ_CHUNK_LEN, 100]
+        );
+        assert_eq!(chunks.concat(), data);
+    }
+
+    #[test]
+    fn gzip_chunks() {
+        use std::io::Read;
+
+        let data = (0..READ_CHUNK_LEN * 3)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        let chunks = data
+            .chunks(1000)
+            .map(|chunk| Ok(bytes::Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let compressed = futures::executor::block_on(
+            gzip(futures_util::stream::iter(chunks)).try_collect::<Vec<_>>(),
+        )
+        .unwrap()
+        .concat();
+        assert!(compressed.len() < data.len());
+
+        let mut decompressed = vec![];
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[tokio::test]
+    async fn bounded() {
+        let source = futures_util::stream::iter((0..10).map(Ok));
+        let bounded = BoundedStream::forward(&TokioRuntime, 3, source);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let items = bounded.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(
+            items,
+            [
+                Buffered::Lagged(7),
+                Buffered::Item(7),
+                Buffered::Item(8),
+                Buffered::Item(9)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_stalled() {
+        use crate::conn::{Method, MockResponse, MockTransport};
+        use crate::{Docker, Error};
+        use futures_util::StreamExt;
+
+        let mock = MockTransport::new();
+        mock.on(Method::GET, "/events", MockResponse::pending()).on(
+            Method::GET,
+            "/_ping",
+            MockResponse::pending(),
+        );
+        let idle = std::time::Duration::from_millis(50);
+        let docker = Docker::from_mock(mock).with_stream_idle_timeout(idle);
+        let mut events = docker.events(&Default::default());
+        match events.next().await {
+            Some(Err(Error::StreamStalled(d))) => assert_eq!(d, idle),
+            other => panic!("expected Error::StreamStalled, got {other:?}"),
+        }
+        assert!(events.next().await.is_none());
+    }
+}
@@ -0,0 +1,222 @@
+//! Instrumentation of requests with [tracing](https://docs.rs/tracing) spans and events,
+//! enabled with the `tracing` feature.
+//!
+//! Every request is sent within a `docker_request` span at debug level recording the method,
+//! path and API version of the request, and once the response headers are received its status
+//! and the duration in milliseconds, or the error. JSON request bodies and the bodies of
+//! responses read whole are emitted as debug events within the span. Values of keys holding
+//! credentials or secret data and the values of environment variables are redacted from them.
+use hyper::{body::HttpBody, header, Body, Request, Response};
+use serde_json::Value;
+use tracing::{field, Instrument, Level, Span};
+
+use std::time::Instant;
+
+use crate::{conn::Transport, ApiVersion, Error, Result};
+
+/// Replaces the redacted values of bodies.
+const REDACTED: &str = "<redacted>";
+
+/// Bodies are truncated to this many bytes in events.
+const MAX_BODY_LEN: usize = 16 * 1024;
+
+/// Keys, compared case insensitively, whose values are redacted from bodies.
+const SECRET_KEYS: &[&str] = &[
+    "auth",
+    "password",
+    "identitytoken",
+    "registrytoken",
+    "data",
+    "jointokens",
+    "jointoken",
+    "unlockkey",
+    "signingcakey",
+];
+
+/// Sends `request` over `transport` within a span of the request. The span is stored in the
+/// extensions of the response so events about its body are emitted within it.
+pub(crate) async fn send(
+    transport: &Transport,
+    version: Option<ApiVersion>,
+    request: Request<Body>,
+) -> Result<Response<Body>> {
+    let span = tracing::debug_span!(
+        "docker_request",
+        method = %request.method(),
+        path = request.uri().path(),
+        api_version = field::Empty,
+        status = field::Empty,
+        duration_ms = field::Empty,
+        error = field::Empty,
+    );
+    if let Some(version) = version {
+        span.record("api_version", field::display(version));
+    }
+
+    async move {
+        let request = request_body(request).await?;
+        let start = Instant::now();
+        let result = transport.request(request).await;
+        let span = Span::current();
+        span.record("duration_ms", start.elapsed().as_secs_f64() * 1000.0);
+        match result {
+            Ok(mut response) => {
+                span.record("status", response.status().as_u16());
+                tracing::debug!(status = response.status().as_u16(), "received response");
+                response.extensions_mut().insert(span);
+                Ok(response)
+            }
+            Err(e) => {
+                span.record("error", field::display(&e));
+                tracing::debug!(error = %e, "request failed");
+                Err(e)
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Emits the body of `request` if it is JSON held in memory, leaving streamed bodies untouched.
+async fn request_body(request: Request<Body>) -> Result<Request<Body>> {
+    let is_json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !is_json || request.body().size_hint().exact().is_none() || !tracing::enabled!(Level::DEBUG)
+    {
+        return Ok(request);
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = hyper::body::to_bytes(body).await.map_err(Error::from)?;
+    tracing::debug!(body = %redacted(&bytes), "request body");
+    Ok(Request::from_parts(parts, Body::from(bytes)))
+}
+
+/// Returns the span of the request `response` belongs to.
+pub(crate) fn span_of(response: &Response<Body>) -> Option<Span> {
+    response.extensions().get::<Span>().cloned()
+}
+
+/// Emits the body of a response read whole within the span of its request.
+pub(crate) fn response_body(span: Option<Span>, body: &[u8]) {
+    if let Some(span) = span {
+        span.in_scope(|| tracing::debug!(body = %redacted(body), "response body"));
+    }
+}
+
+/// Returns `body` with secrets redacted, truncated to [`MAX_BODY_LEN`](MAX_BODY_LEN).
+fn redacted(body: &[u8]) -> String {
+    let body = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(body).into_owned(),
+    };
+    if body.len() <= MAX_BODY_LEN {
+        return body;
+    }
+    let mut end = MAX_BODY_LEN;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &body[..end], body.len())
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(members) => {
+            for (key, value) in members {
+                if SECRET_KEYS.iter().any(|k| key.eq_ignore_ascii_case(k)) {
+                    *value = Value::String(REDACTED.into());
+                } else if key.eq_ignore_ascii_case("env") {
+                    redact_env(value);
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Redacts the values of `NAME=value` environment variables, keeping their names.
+fn redact_env(env: &mut Value) {
+    if let Value::Array(vars) = env {
+        for var in vars {
+            if let Value::String(var) = var {
+                if let Some((name, _)) = var.split_once('=') {
+                    *var = format!("{name}={REDACTED}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redaction() {
+        let body = br#"{
+            "Image": "postgres",
+            "Env": ["POSTGRES_PASSWORD=hunter2", "DEBUG"],
+            "HostConfig": {"Binds": ["/data:/data"]},
+            "Spec": {"Name": "db", "Data": "aHVudGVyMg=="},
+            "auths": [{"username": "me", "password": "hunter2", "auth": "bWU6aHVudGVyMg=="}],
+            "IdentityToken": "token"
+        }"#;
+        let value: Value = serde_json::from_str(&redacted(body)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "Image": "postgres",
+                "Env": ["POSTGRES_PASSWORD=<redacted>", "DEBUG"],
+                "HostConfig": {"Binds": ["/data:/data"]},
+                "Spec": {"Name": "db", "Data": "<redacted>"},
+                "auths": [{"username": "me", "password": "<redacted>", "auth": "<redacted>"}],
+                "IdentityToken": "<redacted>"
+            })
+        );
+
+        let swarm = br#"{
+            "ID": "abc",
+            "JoinTokens": {"Worker": "SWMTKN-1-worker", "Manager": "SWMTKN-1-manager"},
+            "Spec": {"CAConfig": {"SigningCACert": "cert", "SigningCAKey": "key"}}
+        }"#;
+        let value: Value = serde_json::from_str(&redacted(swarm)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "ID": "abc",
+                "JoinTokens": "<redacted>",
+                "Spec": {"CAConfig": {"SigningCACert": "cert", "SigningCAKey": "<redacted>"}}
+            })
+        );
+        let init = br#"{
+            "ListenAddr": "0.0.0.0:2377",
+            "JoinToken": "SWMTKN-1-worker",
+            "UnlockKey": "SWMKEY-1-key",
+            "Spec": {"CAConfig": {"SigningCAKey": "key"}}
+        }"#;
+        let value: Value = serde_json::from_str(&redacted(init)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "ListenAddr": "0.0.0.0:2377",
+                "JoinToken": "<redacted>",
+                "UnlockKey": "<redacted>",
+                "Spec": {"CAConfig": {"SigningCAKey": "<redacted>"}}
+            })
+        );
+
+        assert_eq!(redacted(b"OK"), "OK");
+        let long = "é".repeat(MAX_BODY_LEN);
+        let truncated = redacted(long.as_bytes());
+        assert!(truncated.ends_with(&format!("... ({} bytes)", long.len())));
+        assert!(truncated.len() < long.len());
+    }
+}
@@ -0,0 +1,272 @@
+//! Decoding of the multiplexed stdout and stderr streams of containers without a TTY.
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::{stream, Stream, StreamExt};
+
+use crate::{conn::TtyChunk, Error, Result};
+
+/// Length of the header preceding every frame, the stream type followed by 3 padding bytes and
+/// the big endian length of the payload.
+const HEADER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+/// A frame of a multiplexed stream of a container.
+///
+/// The payload is a slice of the buffer received from the daemon so decoding doesn't allocate or
+/// copy frames. For convenience this type dereferences to the contained [`Bytes`](Bytes).
+pub enum TtyFrame {
+    StdIn(Bytes),
+    StdOut(Bytes),
+    StdErr(Bytes),
+}
+
+impl From<TtyFrame> for Bytes {
+    fn from(frame: TtyFrame) -> Self {
+        match frame {
+            TtyFrame::StdIn(bytes) | TtyFrame::StdOut(bytes) | TtyFrame::StdErr(bytes) => bytes,
+        }
+    }
+}
+
+impl From<TtyFrame> for TtyChunk {
+    fn from(frame: TtyFrame) -> Self {
+        match frame {
+            TtyFrame::StdIn(bytes) => TtyChunk::StdIn(bytes.to_vec()),
+            TtyFrame::StdOut(bytes) => TtyChunk::StdOut(bytes.to_vec()),
+            TtyFrame::StdErr(bytes) => TtyChunk::StdErr(bytes.to_vec()),
+        }
+    }
+}
+
+impl AsRef<[u8]> for TtyFrame {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl std::ops::Deref for TtyFrame {
+    type Target = Bytes;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            TtyFrame::StdIn(bytes) | TtyFrame::StdOut(bytes) | TtyFrame::StdErr(bytes) => bytes,
+        }
+    }
+}
+
+/// Decoder of the frames of a multiplexed stream received in chunks of arbitrary size.
+///
+/// Frames contained in a chunk are sliced out of it. Only a frame split across chunks is copied,
+/// once, into a buffer of its full size.
+#[derive(Debug, Default)]
+pub struct TtyFrameDecoder {
+    chunk: Bytes,
+    partial: BytesMut,
+}
+
+impl TtyFrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the next chunk of the stream.
+    pub fn push(&mut self, chunk: Bytes) {
+        if !self.chunk.is_empty() {
+            self.move_to_partial();
+        }
+        self.chunk = chunk;
+    }
+
+    /// Returns `true` if no data is left to decode. Data left at the end of the stream means
+    /// that the last frame was truncated.
+    pub fn is_empty(&self) -> bool {
+        self.chunk.is_empty() && self.partial.is_empty()
+    }
+
+    /// Decodes the next frame, returns `None` if more data has to be pushed first.
+    pub fn next_frame(&mut self) -> Result<Option<TtyFrame>> {
+        if self.partial.is_empty() {
+            match frame_len(&self.chunk) {
+                Some(len) if self.chunk.len() >= len => {
+                    let frame = self.chunk.split_to(len);
+                    return to_frame(frame).map(Some);
+                }
+                _ => self.move_to_partial(),
+            }
+        } else {
+            // complete the header first to know how much more of the chunk belongs to the frame
+            if self.partial.len() < HEADER_LEN {
+                let take = (HEADER_LEN - self.partial.len()).min(self.chunk.len());
+                self.partial.extend_from_slice(&self.chunk[..take]);
+                self.chunk.advance(take);
+            }
+            if let Some(len) = frame_len(&self.partial) {
+                // chunks pushed without decoding their frames first can leave several frames in
+                // the buffer
+                if self.partial.len() < len {
+                    self.partial.reserve(len - self.partial.len());
+                    let take = (len - self.partial.len()).min(self.chunk.len());
+                    self.partial.extend_from_slice(&self.chunk[..take]);
+                    self.chunk.advance(take);
+                }
+                if self.partial.len() >= len {
+                    let frame = self.partial.split_to(len).freeze();
+                    return to_frame(frame).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn move_to_partial(&mut self) {
+        if self.partial.is_empty() {
+            if let Some(len) = frame_len(&self.chunk) {
+                self.partial.reserve(len);
+            }
+        }
+        self.partial.extend_from_slice(&self.chunk);
+        self.chunk.clear();
+    }
+}
+
+/// Returns the length of the frame including the header or `None` if the header is incomplete.
+fn frame_len(buf: &[u8]) -> Option<usize> {
+    let header = buf.get(..HEADER_LEN)?;
+    let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    Some(HEADER_LEN + len as usize)
+}
+
+fn to_frame(mut frame: Bytes) -> Result<TtyFrame> {
+    let stream = frame[0];
+    frame.advance(HEADER_LEN);
+    match stream {
+        0 => Ok(TtyFrame::StdIn(frame)),
+        1 => Ok(TtyFrame::StdOut(frame)),
+        2 => Ok(TtyFrame::StdErr(frame)),
+        n => Err(Error::InvalidResponse(format!(
+            "invalid stream type `{n}` of a multiplexed frame"
+        ))),
+    }
+}
+
+/// Decodes a multiplexed stream into frames. The stream ends after the first error.
+pub(crate) fn decode<S>(chunks: S) -> impl Stream<Item = Result<TtyFrame>>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    stream::unfold(Some((chunks, TtyFrameDecoder::new())), |state| async move {
+        let (mut chunks, mut decoder) = state?;
+        loop {
+            match decoder.next_frame() {
+                Ok(Some(frame)) => return Some((Ok(frame), Some((chunks, decoder)))),
+                Ok(None) => {}
+                Err(e) => return Some((Err(e), None)),
+            }
+            match chunks.next().await {
+                Some(Ok(chunk)) => decoder.push(chunk),
+                Some(Err(e)) => return Some((Err(e), None)),
+                None if decoder.is_empty() => return None,
+                None => {
+                    return Some((
+                        Err(Error::InvalidResponse(
+                            "multiplexed stream ended with a truncated frame".into(),
+                        )),
+                        None,
+                    ))
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(stream: u8, data: &[u8]) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    #[test]
+    fn decode_frames() {
+        let data = [
+            frame(1, b"hello\n"),
+            frame(2, b"oops\n"),
+            frame(1, b""),
+            frame(1, &[b'x'; 100]),
+        ]
+        .concat();
+        let expected = vec![
+            TtyFrame::StdOut(Bytes::from_static(b"hello\n")),
+            TtyFrame::StdErr(Bytes::from_static(b"oops\n")),
+            TtyFrame::StdOut(Bytes::new()),
+            TtyFrame::StdOut(Bytes::from(vec![b'x'; 100])),
+        ];
+
+        for size in [1, 3, 8, 13, data.len()] {
+            let chunks = data
+                .chunks(size)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>();
+            let frames =
+                futures::executor::block_on(decode(stream::iter(chunks)).collect::<Vec<_>>());
+            let frames = frames.into_iter().collect::<Result<Vec<_>>>().unwrap();
+            assert_eq!(frames, expected, "chunk size {size}");
+        }
+    }
+
+    #[test]
+    fn zero_copy() {
+        let data = Bytes::from([frame(1, b"abc"), frame(2, b"def")].concat());
+        let mut decoder = TtyFrameDecoder::new();
+        decoder.push(data.clone());
+        let frame = decoder.next_frame().unwrap().unwrap();
+        assert_eq!(frame.as_ptr(), data[HEADER_LEN..].as_ptr());
+        assert!(matches!(
+            decoder.next_frame(),
+            Ok(Some(TtyFrame::StdErr(_)))
+        ));
+        assert!(decoder.next_frame().unwrap().is_none());
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn push_without_decoding() {
+        let mut decoder = TtyFrameDecoder::new();
+        decoder.push(Bytes::from([frame(1, b"abc"), frame(2, b"de")].concat()));
+        let second = frame(1, b"fghij");
+        decoder.push(Bytes::copy_from_slice(&second[..10]));
+        decoder.push(Bytes::copy_from_slice(&second[10..]));
+
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.next_frame().unwrap() {
+            frames.push(frame);
+        }
+        assert_eq!(
+            frames,
+            [
+                TtyFrame::StdOut(Bytes::from_static(b"abc")),
+                TtyFrame::StdErr(Bytes::from_static(b"de")),
+                TtyFrame::StdOut(Bytes::from_static(b"fghij")),
+            ]
+        );
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let mut decoder = TtyFrameDecoder::new();
+        decoder.push(Bytes::from(frame(7, b"abc")));
+        assert!(matches!(
+            decoder.next_frame(),
+            Err(Error::InvalidResponse(_))
+        ));
+
+        let truncated = frame(1, b"abc");
+        let chunks = vec![Ok(Bytes::copy_from_slice(&truncated[..9]))];
+        let frames = futures::executor::block_on(decode(stream::iter(chunks)).collect::<Vec<_>>());
+        assert!(matches!(frames[..], [Err(Error::InvalidResponse(_))]));
+    }
+}
@@ -3,12 +3,12 @@
 use std::env;
 use std::path::PathBuf;
 
-#[allow(unused_imports)]
-pub use stackify_docker_api::conn;
-pub use stackify_docker_api::{api, models, models::ImageBuildChunk, opts, Docker};
 pub use futures_util::StreamExt;
 #[allow(unused_imports)]
 pub use futures_util::TryStreamExt;
+#[allow(unused_imports)]
+pub use stackify_docker_api::conn;
+pub use stackify_docker_api::{api, models, models::ImageBuildChunk, opts, Docker};
 pub use tempfile::TempDir;
 
 pub const DEFAULT_IMAGE: &str = "ubuntu:latest";
@@ -2,7 +2,7 @@ mod common;
 
 use common::{
     cleanup_container,
-    conn::TtyChunk,
+    conn::{TtyChunk, TtyFrame},
     create_base_container, get_container_full_id, init_runtime,
     opts::{ContainerCreateOpts, ExecCreateOpts},
     StreamExt, TryStreamExt, DEFAULT_CMD, DEFAULT_CMD_ARRAY, DEFAULT_IMAGE,
@@ -459,7 +459,7 @@ async fn container_logs() {
     let mut logs_stream = container.logs(&LogsOpts::builder().stdout(true).stderr(true).build());
     let chunk = logs_stream.next().await;
     match chunk {
-        Some(Ok(TtyChunk::StdOut(chunk))) => {
+        Some(Ok(TtyFrame::StdOut(chunk))) => {
             let logs = String::from_utf8_lossy(&chunk);
             assert_eq!(logs, "123456\n");
         }
@@ -677,12 +677,11 @@ async fn container_attach() {
     let _ = container.start().await;
 
     let mut multiplexer = container.attach().await.unwrap();
-    while let Some(chunk) = multiplexer.next().await {
+    if let Some(chunk) = multiplexer.next().await {
         match chunk {
             Ok(TtyChunk::StdOut(chunk)) => {
                 let logs = String::from_utf8_lossy(&chunk);
                 assert_eq!(logs, "123456\r\n");
-                break;
             }
             chunk => {
                 eprintln!("invalid chunk {chunk:?}");
@@ -713,12 +712,11 @@ async fn container_attach() {
     let _ = container.start().await;
 
     let mut multiplexer = container.attach().await.unwrap();
-    while let Some(chunk) = multiplexer.next().await {
+    if let Some(chunk) = multiplexer.next().await {
         match chunk {
             Ok(TtyChunk::StdOut(chunk)) => {
                 let logs = String::from_utf8_lossy(&chunk);
                 assert_eq!(logs, "123456\n");
-                break;
             }
             chunk => {
                 eprintln!("invalid chunk {chunk:?}");
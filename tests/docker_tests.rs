@@ -1,6 +1,8 @@
 mod common;
 
-use common::init_runtime;
+#[cfg(feature = "chrono")]
+use common::{create_base_container, TryStreamExt};
+use common::{init_runtime, opts};
 use stackify_docker_api::opts::{DataUsageType, SystemDataUsageOpts};
 
 #[tokio::test]
@@ -16,6 +18,26 @@ async fn docker_info() {
     );
 }
 
+#[tokio::test]
+async fn docker_info_capabilities() {
+    let docker = init_runtime();
+
+    let info = docker.info().await.expect("system info");
+    assert!(info.driver.is_some_and(|driver| !driver.is_empty()));
+    assert!(info
+        .cgroup_version
+        .is_some_and(|version| version == "1" || version == "2"));
+    let runtimes = info.runtimes.expect("runtimes");
+    assert!(runtimes.contains_key(&info.default_runtime.expect("default runtime")));
+    assert!(info
+        .swarm
+        .and_then(|swarm| swarm.local_node_state)
+        .is_some());
+    assert!(info.ncpu.is_some_and(|ncpu| ncpu > 0));
+    assert!(info.mem_total.is_some_and(|mem| mem > 0));
+    assert!(info.registry_config.is_some());
+}
+
 #[tokio::test]
 async fn docker_ping() {
     let docker = init_runtime();
@@ -26,6 +48,17 @@ async fn docker_ping() {
     assert!(!ping_data.api_version.is_empty());
 }
 
+#[tokio::test]
+async fn docker_ping_head() {
+    let docker = init_runtime();
+
+    let get = docker.ping().await.expect("ping");
+    let head = docker.ping_head().await.expect("ping head");
+    assert_eq!(get.api_version, head.api_version);
+    assert_eq!(get.docker_experimental, head.docker_experimental);
+    assert_eq!(get.os_type, head.os_type);
+}
+
 #[tokio::test]
 async fn docker_version() {
     let docker = init_runtime();
@@ -41,6 +74,42 @@ async fn docker_version() {
     assert_eq!(ping_data.api_version, version_data.api_version.unwrap());
 }
 
+#[tokio::test]
+async fn docker_negotiate_version() {
+    let mut docker = init_runtime();
+
+    let version = docker
+        .negotiate_version()
+        .await
+        .expect("negotiated version");
+    assert!(version <= stackify_docker_api::LATEST_API_VERSION);
+    assert_eq!(docker.api_version(), Some(version));
+
+    let server_version: stackify_docker_api::ApiVersion = docker
+        .version()
+        .await
+        .unwrap()
+        .api_version
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(version <= server_version);
+}
+
+#[tokio::test]
+async fn docker_build_cache_prune() {
+    let docker = init_runtime();
+
+    let opts = opts::ClearCacheOpts::builder()
+        .filter([opts::CacheFilter::Until("87600h".into())])
+        .build();
+    let pruned = docker
+        .build_cache_prune(&opts)
+        .await
+        .expect("pruned build cache");
+    assert!(pruned.space_reclaimed.is_some());
+}
+
 #[tokio::test]
 async fn docker_data_usage() {
     let docker = init_runtime();
@@ -59,3 +128,68 @@ async fn docker_data_usage() {
     assert!(du_result.is_ok());
     let _du_data = du_result.unwrap();
 }
+
+#[cfg(feature = "chrono")]
+#[tokio::test]
+async fn docker_events() {
+    let docker = init_runtime();
+
+    let container_name = "test-events-container";
+    let since = chrono::Utc::now() - chrono::Duration::seconds(1);
+    let container = create_base_container(&docker, container_name, None).await;
+    let until = chrono::Utc::now() + chrono::Duration::seconds(1);
+
+    let opts = opts::EventsOpts::builder()
+        .since(&since)
+        .until(&until)
+        .filter([
+            opts::EventFilter::Type(opts::EventFilterType::Container),
+            opts::EventFilter::Container(container_name.into()),
+            opts::EventFilter::Event("create".into()),
+        ])
+        .build();
+    let events = docker
+        .events(&opts)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("events");
+    assert!(events.iter().any(|event| {
+        event.action.as_deref() == Some("create")
+            && event
+                .actor
+                .as_ref()
+                .and_then(|actor| actor.attributes.as_ref())
+                .and_then(|attributes| attributes.get("name"))
+                .is_some_and(|name| name == container_name)
+    }));
+
+    let _ = container.delete().await;
+}
+
+#[cfg(feature = "chrono")]
+#[tokio::test]
+async fn docker_events_resumable() {
+    let docker = init_runtime();
+
+    let container_name = "test-events-resumable-container";
+    let since = chrono::Utc::now() - chrono::Duration::seconds(1);
+    let container = create_base_container(&docker, container_name, None).await;
+    let until = chrono::Utc::now() + chrono::Duration::seconds(1);
+
+    let opts = opts::EventsOpts::builder()
+        .since(&since)
+        .until(&until)
+        .filter([opts::EventFilter::Container(container_name.into())])
+        .build();
+    let policy = opts::EventsRetryPolicy::new().max_retries(3);
+    let events = docker
+        .events_resumable(&opts, policy)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("events");
+    assert!(events
+        .iter()
+        .any(|event| event.action.as_deref() == Some("create")));
+
+    let _ = container.delete().await;
+}
@@ -48,6 +48,62 @@ async fn image_create_with_build_args() {
     assert!(image.delete().await.is_ok());
 }
 
+#[tokio::test]
+async fn image_build_with_output() {
+    let docker = init_runtime();
+    let images = docker.images();
+
+    let image_name = "test-build-output-image";
+    let tmp = tempdir_with_dockerfile(None);
+    let opts = opts::ImageBuildOpts::builder(tmp.path())
+        .tag(image_name)
+        .build();
+
+    let mut output = Vec::new();
+    let chunks = images
+        .build_with_output(&opts, |chunk| output.extend_from_slice(chunk))
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("build chunks");
+
+    assert!(!chunks.is_empty());
+    let output = String::from_utf8(output).expect("utf8 build output");
+    let lines = output.lines().filter(|l| !l.trim().is_empty()).count();
+    assert_eq!(lines, chunks.len());
+
+    let _ = images.get(image_name).delete().await;
+}
+
+#[tokio::test]
+async fn image_remove_dangling() {
+    let docker = init_runtime();
+    let images = docker.images();
+
+    let image_name = "test-remove-dangling-image";
+    create_base_image(&docker, image_name, None).await;
+    let image = images.get(image_name);
+    let id = image.inspect().await.expect("image inspect").id.unwrap();
+
+    // building another image with the same tag leaves the previous one dangling
+    let tmp = tempdir_with_dockerfile(Some(&format!("FROM {DEFAULT_IMAGE}\nRUN echo dangling")));
+    let opts = opts::ImageBuildOpts::builder(tmp.path())
+        .tag(image_name)
+        .build();
+    images
+        .build(&opts)
+        .try_collect::<Vec<_>>()
+        .await
+        .expect("rebuilt image");
+
+    let removed = images.remove_dangling().await.expect("remove dangling");
+    let deleted = removed.images_deleted.unwrap_or_default();
+    assert!(deleted
+        .iter()
+        .any(|item| item.deleted.as_deref() == Some(id.as_str())));
+    assert!(images.get(&id).inspect().await.is_err());
+    let _ = images.get(image_name).delete().await;
+}
+
 #[tokio::test]
 async fn image_inspect() {
     let docker = init_runtime();
@@ -1,7 +1,7 @@
 #![cfg(unix)] //temporary
 mod common;
 
-use common::{create_base_container, create_base_network, init_runtime, opts};
+use common::{create_base_container, create_base_network, init_runtime, models, opts};
 
 #[tokio::test]
 async fn network_create_inspect_delete() {
@@ -35,6 +35,26 @@ async fn network_inspect() {
     assert!(network.delete().await.is_ok());
 }
 
+#[tokio::test]
+async fn network_inspect_details() {
+    let docker = init_runtime();
+
+    let network_name = "test-inspect-details-network";
+    let network = create_base_network(&docker, network_name, None).await;
+
+    let opts = opts::NetworkInspectOpts::builder()
+        .verbose(true)
+        .scope(opts::Scope::Local)
+        .build();
+    let details = network
+        .inspect_details(&opts)
+        .await
+        .expect("network details");
+    assert_eq!(details.network.name.as_deref(), Some(network_name));
+    assert_eq!(details.network.scope.as_deref(), Some("local"));
+    assert!(network.delete().await.is_ok());
+}
+
 #[tokio::test]
 async fn network_prune() {
     let docker = init_runtime();
@@ -187,9 +207,113 @@ async fn network_connect_disconnect() {
         .unwrap()
         .networks
         .unwrap()
-        .get(network_name)
-        .is_some());
+        .contains_key(network_name));
 
     let _ = network.delete().await;
     let _ = container.delete().await;
 }
+
+#[tokio::test]
+async fn network_connect_disconnect_container() {
+    let docker = init_runtime();
+
+    let network_name = "test-connect-container-network";
+    let container_name = "test-connect-container-network-container";
+    let opts = opts::NetworkCreateOpts::builder(network_name)
+        .ipam(
+            opts::IpamBuilder::new()
+                .config(opts::IpamConfigBuilder::new().subnet("172.31.250.0/24"))
+                .build(),
+        )
+        .build();
+    let network = create_base_network(&docker, network_name, Some(opts)).await;
+    let container = create_base_container(&docker, container_name, None).await;
+
+    let settings = models::EndpointSettings {
+        aliases: Some(vec!["test-alias".into()]),
+        driver_opts: None,
+        endpoint_id: None,
+        gateway: None,
+        global_i_pv_6_address: None,
+        global_i_pv_6_prefix_len: None,
+        ipam_config: Some(models::EndpointIpamConfig {
+            i_pv_4_address: Some("172.31.250.10".into()),
+            i_pv_6_address: None,
+            link_local_i_ps: None,
+        }),
+        ip_address: None,
+        ip_prefix_len: None,
+        i_pv_6_gateway: None,
+        links: None,
+        mac_address: None,
+        network_id: None,
+    };
+    network
+        .connect_container(container_name, &settings)
+        .await
+        .expect("connected container");
+
+    let networks = container
+        .inspect()
+        .await
+        .unwrap()
+        .network_settings
+        .unwrap()
+        .networks
+        .unwrap();
+    let endpoint = networks.get(network_name).expect("network endpoint");
+    assert!(endpoint
+        .aliases
+        .as_ref()
+        .unwrap()
+        .contains(&"test-alias".to_string()));
+    assert_eq!(
+        endpoint
+            .ipam_config
+            .as_ref()
+            .unwrap()
+            .i_pv_4_address
+            .as_deref(),
+        Some("172.31.250.10")
+    );
+
+    network
+        .disconnect_container(container_name, true)
+        .await
+        .expect("disconnected container");
+    let networks = container
+        .inspect()
+        .await
+        .unwrap()
+        .network_settings
+        .unwrap()
+        .networks
+        .unwrap_or_default();
+    assert!(!networks.contains_key(network_name));
+
+    let _ = network.delete().await;
+    let _ = container.delete().await;
+}
+
+#[tokio::test]
+async fn network_get_by_name() {
+    let docker = init_runtime();
+    let networks = docker.networks();
+
+    let name = "test-get-by-name-network";
+    let similar_name = "test-get-by-name-network-similar";
+    let network = create_base_network(&docker, name, None).await;
+    let similar = create_base_network(&docker, similar_name, None).await;
+
+    let id = network.inspect().await.unwrap().id.unwrap();
+    let found = networks.get_by_name(name).await.expect("network by name");
+    assert_eq!(found.id().as_ref(), id);
+
+    assert!(matches!(
+        networks.get_by_name("test-get-by-name").await,
+        Err(stackify_docker_api::Error::NoSuchObject(_))
+    ));
+
+    let _ = network.delete().await;
+    let _ = similar.delete().await;
+}
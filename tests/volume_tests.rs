@@ -0,0 +1,183 @@
+#![cfg(unix)] //temporary
+mod common;
+
+use common::{
+    create_base_container, create_base_volume, init_runtime, opts, TempDir, TryStreamExt,
+    DEFAULT_IMAGE,
+};
+
+#[tokio::test]
+async fn volume_create_inspect_delete() {
+    let docker = init_runtime();
+
+    let volume_name = "test-create-volume";
+    let opts = opts::VolumeCreateOpts::builder()
+        .name(volume_name)
+        .driver("local")
+        .driver_opts([("type", "tmpfs"), ("device", "tmpfs"), ("o", "size=1m")])
+        .labels([("test-create", "value")])
+        .build();
+    let created = docker
+        .volumes()
+        .create(&opts)
+        .await
+        .expect("created volume");
+    assert_eq!(created.name, volume_name);
+    assert_eq!(created.driver, "local");
+    assert_eq!(
+        created.labels.get("test-create").map(String::as_str),
+        Some("value")
+    );
+    assert_eq!(
+        created.options.get("type").map(String::as_str),
+        Some("tmpfs")
+    );
+
+    let volume = docker.volumes().get(volume_name);
+    assert!(volume.inspect().await.is_ok());
+    assert!(volume.delete().await.is_ok());
+    assert!(volume.inspect().await.is_err());
+
+    let volume = create_base_volume(&docker, volume_name, None).await;
+    assert!(volume.inspect().await.is_ok());
+    assert!(volume.delete().await.is_ok());
+}
+
+#[tokio::test]
+async fn volume_prune() {
+    let docker = init_runtime();
+    let volumes = docker.volumes();
+
+    let name_a = "test-prune-volume";
+    let name_b = "test-prune-volume2";
+    let label_key = "test-prune";
+    for (name, value) in [(name_a, "value_a"), (name_b, "value_b")] {
+        let opts = opts::VolumeCreateOpts::builder()
+            .name(name)
+            .labels([(label_key, value)])
+            .build();
+        create_base_volume(&docker, name, Some(opts)).await;
+    }
+
+    let opts = opts::VolumePruneOpts::builder()
+        .filter([
+            opts::VolumePruneFilter::All(true),
+            opts::VolumePruneFilter::Label(label_key.into(), "value_a".into()),
+        ])
+        .build();
+    let pruned = volumes.prune(&opts).await.expect("pruned volumes");
+    let deleted = pruned.volumes_deleted.unwrap_or_default();
+    assert!(deleted.iter().any(|name| name == name_a));
+    assert!(!deleted.iter().any(|name| name == name_b));
+    assert!(volumes.get(name_a).inspect().await.is_err());
+    assert!(volumes.get(name_b).inspect().await.is_ok());
+
+    let _ = volumes.get(name_b).delete().await;
+}
+
+#[tokio::test]
+async fn volume_inspect_list() {
+    let docker = init_runtime();
+    let volumes = docker.volumes();
+
+    let volume_name = "test-inspect-volume";
+    let opts = opts::VolumeCreateOpts::builder()
+        .name(volume_name)
+        .labels([("test-inspect", "value")])
+        .build();
+    let volume = create_base_volume(&docker, volume_name, Some(opts)).await;
+
+    let inspect = volume.inspect().await.expect("volume inspect");
+    assert_eq!(inspect.name, volume_name);
+    assert_eq!(inspect.scope, "local");
+    assert!(!inspect.mountpoint.is_empty());
+    assert_eq!(
+        inspect.labels.get("test-inspect").map(String::as_str),
+        Some("value")
+    );
+
+    let opts = opts::VolumeListOpts::builder()
+        .filter([opts::VolumeFilter::LabelKey("test-inspect".into())])
+        .build();
+    let list = volumes.list(&opts).await.expect("volume list");
+    let listed = list.volumes.unwrap_or_default();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, volume_name);
+
+    let usage = volume
+        .usage_data()
+        .await
+        .expect("volume usage data")
+        .expect("usage data of local volume");
+    assert_eq!(usage.ref_count, 0);
+
+    let _ = volume.delete().await;
+}
+
+#[tokio::test]
+async fn volume_populate_from_dir() {
+    let docker = init_runtime();
+
+    let volume_name = "test-populate-volume";
+    let volume = create_base_volume(&docker, volume_name, None).await;
+
+    let tmp = TempDir::new().expect("temp dir");
+    std::fs::write(tmp.path().join("seed.txt"), "seeded").expect("seed file");
+    volume
+        .populate_from_dir(DEFAULT_IMAGE, tmp.path())
+        .await
+        .expect("populated volume");
+
+    let container_name = "test-populate-volume-container";
+    let opts = opts::ContainerCreateOpts::builder()
+        .image(DEFAULT_IMAGE)
+        .name(container_name)
+        .command(["sleep", "inf"])
+        .volumes([format!("{volume_name}:/data")])
+        .build();
+    let container = create_base_container(&docker, container_name, Some(opts)).await;
+    let archive = container
+        .copy_from("/data/seed.txt")
+        .try_concat()
+        .await
+        .expect("copied seed file");
+    let mut archive = tar::Archive::new(&archive[..]);
+    let mut entry = archive.entries().unwrap().next().unwrap().unwrap();
+    let mut content = String::new();
+    std::io::Read::read_to_string(&mut entry, &mut content).unwrap();
+    assert_eq!(content, "seeded");
+
+    let _ = container.delete().await;
+    let _ = volume.delete().await;
+}
+
+#[tokio::test]
+async fn volume_dangling() {
+    let docker = init_runtime();
+
+    let unused_name = "test-dangling-volume";
+    let used_name = "test-dangling-volume-used";
+    let unused = create_base_volume(&docker, unused_name, None).await;
+    let used = create_base_volume(&docker, used_name, None).await;
+
+    let container_name = "test-dangling-volume-container";
+    let opts = opts::ContainerCreateOpts::builder()
+        .image(DEFAULT_IMAGE)
+        .name(container_name)
+        .command(["sleep", "inf"])
+        .volumes([format!("{used_name}:/data")])
+        .build();
+    let container = create_base_container(&docker, container_name, Some(opts)).await;
+
+    let dangling = docker.volumes().dangling().await.expect("dangling volumes");
+    let volume = dangling
+        .iter()
+        .find(|volume| volume.name == unused_name)
+        .expect("unused volume is dangling");
+    assert!(volume.usage_data.is_some());
+    assert!(!dangling.iter().any(|volume| volume.name == used_name));
+
+    let _ = container.delete().await;
+    let _ = unused.delete().await;
+    let _ = used.delete().await;
+}